@@ -1,3 +1,197 @@
+//! Generates the parser and compiles the runtime library the native backends link.
+//!
+//! The operator levels of the LALRPOP grammar are written from the operator table
+//! before LALRPOP reads it, so the grammar cannot disagree with the other front ends.
+//! The runtime is built here, once per change to its sources, and embedded in the
+//! compiler, so building an executable never needs the runtime's sources or `rustc`.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+include!("src/lexer_parser/operator_table.rs");
+
+/// An entry of the operator table: spelling, `OperatorToken` variant, level and level
+/// name, and associativity, empty for prefix operators.
+type Entry = (
+    &'static str,
+    &'static str,
+    u8,
+    &'static str,
+    &'static [&'static str],
+);
+
+macro_rules! entries {
+    ($(
+        $spelling:literal => $operator:ident, $level:ident,
+        $fixity:ident$(($associativity:ident))?, $kind:ident;
+    )*) => {
+        &[$((
+            $spelling,
+            stringify!($operator),
+            $level,
+            stringify!($level),
+            &[$(stringify!($associativity))?],
+        ),)*]
+    };
+}
+
+const OPERATORS: &[Entry] = operator_table!(entries);
+
+/// Grammar template, whose placeholder line is replaced by the operator levels.
+const GRAMMAR: &str = "src/lexer_parser/parser.lalrpop";
+const PLACEHOLDER: &str = "// {operator levels}";
+
 fn main() {
-    lalrpop::process_root().unwrap();
+    generate_parser();
+    build_runtime();
+}
+
+/// Writes the complete grammar to `OUT_DIR/grammar` and has LALRPOP turn it into
+/// `OUT_DIR/lexer_parser/parser.rs`, where `lalrpop_mod!` looks for it.
+fn generate_parser() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let grammar_dir = Path::new(&out_dir).join("grammar");
+    let template = fs::read_to_string(GRAMMAR).unwrap();
+    assert!(
+        template.contains(PLACEHOLDER),
+        "{} has no {}",
+        GRAMMAR,
+        PLACEHOLDER
+    );
+    let grammar = template.replace(PLACEHOLDER, &operator_levels());
+    fs::create_dir_all(grammar_dir.join("lexer_parser")).unwrap();
+    fs::write(grammar_dir.join("lexer_parser/parser.lalrpop"), grammar).unwrap();
+    lalrpop::Configuration::new()
+        .set_in_dir(&grammar_dir)
+        .set_out_dir(&out_dir)
+        .force_build(true)
+        .process()
+        .unwrap();
+    println!("cargo:rerun-if-changed={}", GRAMMAR);
+    println!("cargo:rerun-if-changed=src/lexer_parser/operator_table.rs");
+}
+
+/// `Expr` and one pair of nonterminals per level, `<Level>Expr` for the expressions
+/// and `<Level>Op` for the operators. Each level's operands are expressions of the
+/// next level, the last one's are `CompositeExpr`, and `:=` assigns to a
+/// `PrimaryExpr`.
+fn operator_levels() -> String {
+    let mut levels: Vec<u8> = OPERATORS.iter().map(|entry| entry.2).collect();
+    levels.dedup();
+    let level_name = |level: u8| {
+        let entry = OPERATORS.iter().find(|entry| entry.2 == level).unwrap();
+        let (first, rest) = entry.3.split_at(1);
+        format!("{}{}", first, rest.to_lowercase())
+    };
+
+    let mut grammar = format!(
+        "Expr: Expression = {{\n    {}Expr\n}};\n",
+        level_name(levels[0])
+    );
+    for (index, &level) in levels.iter().enumerate() {
+        let name = level_name(level);
+        let next = match levels.get(index + 1) {
+            Some(&next) => format!("{}Expr", level_name(next)),
+            None => "CompositeExpr".to_string(),
+        };
+        let entries: Vec<&Entry> = OPERATORS.iter().filter(|entry| entry.2 == level).collect();
+        let associativity = entries[0].4.first().copied();
+        assert!(
+            entries
+                .iter()
+                .all(|entry| entry.4.first().copied() == associativity),
+            "the operators of level {} group differently",
+            name
+        );
+        let this = format!("{}Expr", name);
+        let rule = match associativity {
+            None => format!(
+                "<s: @L> <op:{name}Op> <expr:{this}> <e: @R> =>\n        \
+                 Expression::new_unary_op(op.0, expr, Span::new(s, e))"
+            ),
+            Some(associativity) => {
+                let assignment = entries.iter().all(|entry| entry.1 == "DASSIGN");
+                let (left, right) = match associativity {
+                    _ if assignment => ("PrimaryExpr", this.as_str()),
+                    "Left" => (this.as_str(), next.as_str()),
+                    _ => (next.as_str(), this.as_str()),
+                };
+                let node = if assignment {
+                    "Expression::new_destructive_assign(left, right, Span::new(s, e))"
+                } else {
+                    "Expression::new_binary_op(left, op.0, right, Span::new(s, e))"
+                };
+                format!(
+                    "<s: @L> <left:{left}> <op:{name}Op> <right:{right}> <e: @R> =>\n        {node}"
+                )
+            }
+        };
+        writeln!(
+            grammar,
+            "\n{this}: Expression = {{\n    {rule},\n    {next} => <>\n}};\n"
+        )
+        .unwrap();
+        writeln!(grammar, "{name}Op: (OperatorToken, Span) = {{").unwrap();
+        for entry in &entries {
+            writeln!(
+                grammar,
+                "    <s: @L> \"{}\" <e: @R> => (OperatorToken::{}, Span::new(s, e)),",
+                entry.0, entry.1
+            )
+            .unwrap();
+        }
+        grammar.push_str("};\n");
+    }
+    grammar
+}
+
+/// Compiles `common` and then `runtime` on top of it into `OUT_DIR/libhulk_runtime.a`.
+fn build_runtime() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let out_dir = Path::new(&out_dir);
+    let target = env::var("TARGET").unwrap();
+    let common = out_dir.join("libhulk_common.rlib");
+    rustc(
+        &target,
+        "hulk_common",
+        "rlib",
+        "common/src/lib.rs",
+        &[],
+        &common,
+    );
+    let extern_common = format!("hulk_common={}", common.display());
+    rustc(
+        &target,
+        "hulk_runtime",
+        "staticlib",
+        "runtime/src/lib.rs",
+        &["--extern", &extern_common],
+        &out_dir.join("libhulk_runtime.a"),
+    );
+    for sources in ["common/src", "runtime/src"] {
+        println!("cargo:rerun-if-changed={}", sources);
+    }
+}
+
+fn rustc(target: &str, name: &str, crate_type: &str, root: &str, args: &[&str], output: &Path) {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let status = Command::new(rustc)
+        .args(["--edition", "2021", "--target", target])
+        .args(["--crate-name", name, "--crate-type", crate_type])
+        .args(["-C", "opt-level=2"])
+        .args(args)
+        .arg(root)
+        .arg("-o")
+        .arg(output)
+        .status()
+        .expect("rustc runs");
+    assert!(
+        status.success(),
+        "compiling {} failed with {}",
+        name,
+        status
+    );
 }
@@ -0,0 +1,7 @@
+//! Code shared by the compiler and the runtime library.
+//!
+//! Both print numbers and draw random numbers, and every execution mode must do it the
+//! same way, so the runtime links this crate instead of carrying its own copy.
+
+pub mod number_format;
+pub mod random;
@@ -0,0 +1,48 @@
+//! Conversion of HULK numbers to text.
+//!
+//! Every execution mode prints numbers the same way the native runtimes do with the C
+//! format `%.15g`: up to 15 significant digits without trailing zeros, in scientific
+//! notation when the exponent is below -4 or at least 15.
+
+/// Significant digits kept when printing a number.
+const PRECISION: i32 = 15;
+
+/// Formats `value` like C's `printf("%.15g", value)`.
+pub fn format_number(value: f64) -> String {
+    if value.is_nan() {
+        return if value.is_sign_negative() {
+            "-nan"
+        } else {
+            "nan"
+        }
+        .to_string();
+    }
+    if value.is_infinite() {
+        return if value < 0.0 { "-inf" } else { "inf" }.to_string();
+    }
+    if value == 0.0 {
+        return if value.is_sign_negative() { "-0" } else { "0" }.to_string();
+    }
+
+    // Rounding to the precision first gives the exponent the C library would use.
+    let scientific = format!("{:.*e}", (PRECISION - 1) as usize, value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+
+    if !(-4..PRECISION).contains(&exponent) {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", trim_zeros(mantissa), sign, exponent.abs())
+    } else {
+        let decimals = (PRECISION - 1 - exponent) as usize;
+        trim_zeros(&format!("{:.*}", decimals, value)).to_string()
+    }
+}
+
+/// Removes the trailing zeros of the fractional part, and the point if nothing is left.
+fn trim_zeros(number: &str) -> &str {
+    if number.contains('.') {
+        number.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        number
+    }
+}
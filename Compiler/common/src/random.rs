@@ -0,0 +1,37 @@
+//! The pseudo-random generator behind the `rand()` builtin.
+//!
+//! Every execution mode uses this SplitMix64 generator, so a program run with the same
+//! seed gets the same numbers whether it is interpreted or compiled. The C backend
+//! carries a copy of it in its runtime.
+
+/// Seed used when none is given on the command line.
+pub const DEFAULT_SEED: u64 = 0;
+
+/// A SplitMix64 generator.
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub const fn new(seed: u64) -> Self {
+        Random { state: seed }
+    }
+
+    /// Returns the next number, uniformly distributed in `[0, 1)`.
+    pub fn next_number(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        // The top 53 bits fill the mantissa of a double exactly.
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Random::new(DEFAULT_SEED)
+    }
+}
@@ -1,8 +0,0 @@
-mod lexer_parser;
-
-use lexer_parser::lexer::Lexer;
-use lexer_parser::tokens::{Token, TokenKind};
-
-fn main() {
-    // Your main function code here
-}
\ No newline at end of file
@@ -0,0 +1,249 @@
+//! Precise, non-moving mark-and-sweep garbage collector.
+//!
+//! Every allocation of the program goes through [`allocate`], which records its
+//! address, size and whether it is an object or a plain buffer such as a string.
+//!
+//! Roots come from a shadow stack. On entry every generated function that holds
+//! strings or objects links a [`GcFrame`] into [`hulk_gc_top`], listing the addresses
+//! of the stack slots of those variables, and unlinks it before returning. An entry
+//! with its lowest bit set is the address of an object allocated in the frame itself,
+//! whose fields are roots. Generated code keeps the slots up to date before every call.
+//!
+//! Objects are traced with the pointer map of their
+//! [`TypeDescriptor`](crate::object::TypeDescriptor). Any word that is not the start of
+//! a live allocation, like a string literal or a stack object, is ignored, so the
+//! collector never frees memory it does not own.
+//!
+//! The collector only runs at safepoints, calls to [`hulk_gc_safepoint`] that generated
+//! code makes right before allocating, once enough memory has been allocated since the
+//! last collection. Two environment variables tune it:
+//!
+//! * `HULK_GC_STRESS=1` collects at every safepoint;
+//! * `HULK_GC_STATS=1` writes the statistics to standard error when the program ends.
+
+use std::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::io::Write;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use crate::object::ObjectHeader;
+
+/// Bytes allocated between collections while little memory is live.
+const INITIAL_THRESHOLD: usize = 1 << 20;
+
+/// The record a generated function links into the shadow stack.
+#[repr(C)]
+pub struct GcFrame {
+    /// The frame of the caller, null for the outermost one.
+    pub prev: *mut GcFrame,
+    /// The number of entries following the record.
+    pub count: i64,
+    /// Addresses of the root slots, tagged with 1 for objects in the frame.
+    pub roots: [*mut c_void; 0],
+}
+
+/// The innermost frame of the shadow stack. Generated code reads and writes it directly.
+#[no_mangle]
+pub static hulk_gc_top: AtomicPtr<GcFrame> = AtomicPtr::new(ptr::null_mut());
+
+/// What the collector knows about a block of memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockKind {
+    /// Bytes without pointers, like strings.
+    Data,
+    /// An object, traced with the pointer map of its descriptor.
+    Object,
+}
+
+struct Block {
+    size: usize,
+    kind: BlockKind,
+    marked: bool,
+}
+
+/// Counters of the collector.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    pub collections: usize,
+    pub allocated_bytes: usize,
+    pub freed_bytes: usize,
+    pub live_bytes: usize,
+    pub peak_bytes: usize,
+}
+
+struct Heap {
+    blocks: HashMap<usize, Block>,
+    /// Bytes allocated since the last collection.
+    pending: usize,
+    threshold: usize,
+    stress: bool,
+    report: bool,
+    stats: GcStats,
+}
+
+fn enabled(variable: &str) -> bool {
+    std::env::var(variable).is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+fn heap() -> MutexGuard<'static, Heap> {
+    static HEAP: OnceLock<Mutex<Heap>> = OnceLock::new();
+    HEAP.get_or_init(|| {
+        Mutex::new(Heap {
+            blocks: HashMap::new(),
+            pending: 0,
+            threshold: INITIAL_THRESHOLD,
+            stress: enabled("HULK_GC_STRESS"),
+            report: enabled("HULK_GC_STATS"),
+            stats: GcStats::default(),
+        })
+    })
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn layout(size: usize) -> Layout {
+    Layout::from_size_align(size.max(1), 16).unwrap()
+}
+
+/// Allocates `size` zeroed bytes, aligned for any HULK value, owned by the collector.
+/// Allocating never collects.
+pub fn allocate(size: usize, kind: BlockKind) -> *mut u8 {
+    let layout = layout(size);
+    // SAFETY: the layout has a non-zero size.
+    let memory = unsafe { alloc_zeroed(layout) };
+    if memory.is_null() {
+        handle_alloc_error(layout);
+    }
+
+    let mut heap = heap();
+    heap.blocks.insert(
+        memory as usize,
+        Block {
+            size,
+            kind,
+            marked: false,
+        },
+    );
+    heap.pending += size;
+    heap.stats.allocated_bytes += size;
+    heap.stats.live_bytes += size;
+    heap.stats.peak_bytes = heap.stats.peak_bytes.max(heap.stats.live_bytes);
+    memory
+}
+
+/// Pushes the words `object` holds at the offsets of the pointer map of its type.
+///
+/// # Safety
+/// `object` must point to an object whose descriptor is null or valid.
+unsafe fn trace_fields(object: *const ObjectHeader, pending: &mut Vec<usize>) {
+    let descriptor = (*object).descriptor;
+    if descriptor.is_null() {
+        return;
+    }
+    let map = (*descriptor).pointers;
+    for index in 0..*map {
+        let offset = *map.add(index as usize + 1);
+        pending.push(*object.cast::<u8>().add(offset as usize).cast::<usize>());
+    }
+}
+
+/// Collects every allocation unreachable from the shadow stack.
+///
+/// # Safety
+/// Every frame of the shadow stack must list valid slots and stack objects.
+unsafe fn collect(heap: &mut Heap) {
+    let mut pending = Vec::new();
+    let mut frame = hulk_gc_top.load(Ordering::Relaxed);
+    while !frame.is_null() {
+        let roots = ptr::addr_of!((*frame).roots).cast::<usize>();
+        for index in 0..(*frame).count as usize {
+            let entry = *roots.add(index);
+            if entry & 1 == 1 {
+                trace_fields((entry & !1) as *const ObjectHeader, &mut pending);
+            } else {
+                pending.push(*(entry as *const usize));
+            }
+        }
+        frame = (*frame).prev;
+    }
+
+    while let Some(address) = pending.pop() {
+        let Some(block) = heap.blocks.get_mut(&address) else {
+            continue;
+        };
+        if block.marked {
+            continue;
+        }
+        block.marked = true;
+        if block.kind == BlockKind::Object {
+            trace_fields(address as *const ObjectHeader, &mut pending);
+        }
+    }
+
+    let mut freed = 0;
+    heap.blocks.retain(|&address, block| {
+        if block.marked {
+            block.marked = false;
+            return true;
+        }
+        freed += block.size;
+        // SAFETY: the block was allocated by `allocate` with this layout.
+        unsafe { dealloc(address as *mut u8, layout(block.size)) };
+        false
+    });
+
+    heap.stats.collections += 1;
+    heap.stats.freed_bytes += freed;
+    heap.stats.live_bytes -= freed;
+    heap.pending = 0;
+    heap.threshold = INITIAL_THRESHOLD.max(heap.stats.live_bytes);
+}
+
+/// Collects if enough memory was allocated since the last collection, or always in
+/// stress mode.
+///
+/// # Safety
+/// Every pointer the caller and its callers still use must be in a slot of the shadow
+/// stack.
+#[no_mangle]
+pub unsafe extern "C" fn hulk_gc_safepoint() {
+    let mut heap = heap();
+    if heap.stress || heap.pending >= heap.threshold {
+        collect(&mut heap);
+    }
+}
+
+/// Collects unconditionally.
+///
+/// # Safety
+/// The same as [`hulk_gc_safepoint`].
+#[no_mangle]
+pub unsafe extern "C" fn hulk_gc_collect() {
+    collect(&mut heap());
+}
+
+/// Called when the program ends. Writes the statistics if `HULK_GC_STATS` is set.
+#[no_mangle]
+pub extern "C" fn hulk_gc_finish() {
+    let heap = heap();
+    if heap.report {
+        let stats = heap.stats;
+        let _ = writeln!(
+            std::io::stderr(),
+            "gc: {} collections, {} bytes allocated, {} bytes freed, {} bytes live, {} bytes peak",
+            stats.collections,
+            stats.allocated_bytes,
+            stats.freed_bytes,
+            stats.live_bytes,
+            stats.peak_bytes
+        );
+    }
+}
+
+/// Returns the counters of the collector.
+pub fn stats() -> GcStats {
+    heap().stats
+}
@@ -0,0 +1,22 @@
+//! Runtime support for compiled HULK programs.
+//!
+//! The crate is built as a static library that the executables produced by the
+//! x86-64, LLVM and C backends link against. Generated code only uses its C ABI:
+//!
+//! * every object starts with an [`ObjectHeader`] pointing to the
+//!   [`TypeDescriptor`] of its type, which doubles as its vtable and holds the
+//!   pointer map of its objects;
+//! * numbers are `double`, booleans `bool` and strings immutable NUL-terminated
+//!   buffers;
+//! * numbers, strings and booleans used as objects are boxed with the builtin
+//!   descriptors.
+//!
+//! Memory is managed by the tracing collector in [`gc`].
+
+pub mod gc;
+pub mod math;
+pub mod object;
+pub mod print;
+pub mod string;
+
+use hulk_common::{number_format, random};
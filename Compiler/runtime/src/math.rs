@@ -0,0 +1,51 @@
+//! The builtin math functions and `rand()`.
+
+use std::sync::Mutex;
+
+use crate::random::{Random, DEFAULT_SEED};
+
+static RANDOM: Mutex<Random> = Mutex::new(Random::new(DEFAULT_SEED));
+
+#[no_mangle]
+pub extern "C" fn hulk_sqrt(x: f64) -> f64 {
+    x.sqrt()
+}
+
+#[no_mangle]
+pub extern "C" fn hulk_sin(x: f64) -> f64 {
+    x.sin()
+}
+
+#[no_mangle]
+pub extern "C" fn hulk_cos(x: f64) -> f64 {
+    x.cos()
+}
+
+#[no_mangle]
+pub extern "C" fn hulk_exp(x: f64) -> f64 {
+    x.exp()
+}
+
+/// The logarithm of `x` in `base`.
+#[no_mangle]
+pub extern "C" fn hulk_log(base: f64, x: f64) -> f64 {
+    x.ln() / base.ln()
+}
+
+/// Restarts the sequence `hulk_rand` returns. Generated `main` functions call it with
+/// the seed given to the compiler.
+#[no_mangle]
+pub extern "C" fn hulk_rand_seed(seed: u64) {
+    *RANDOM
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Random::new(seed);
+}
+
+/// Returns the next pseudo-random number in `[0, 1)`.
+#[no_mangle]
+pub extern "C" fn hulk_rand() -> f64 {
+    RANDOM
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .next_number()
+}
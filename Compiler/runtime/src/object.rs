@@ -0,0 +1,203 @@
+//! Object allocation, type descriptors and boxing.
+
+use std::ffi::{c_char, c_void};
+use std::mem::offset_of;
+use std::ptr;
+
+use crate::gc::{allocate, BlockKind};
+use crate::string::{hulk_bool_to_string, hulk_concat, hulk_number_to_string, hulk_string_eq};
+
+/// Describes a type. Generated code emits one per user type, followed by the
+/// pointers to the methods of the type, so the descriptor is also its vtable.
+#[repr(C)]
+pub struct TypeDescriptor {
+    /// NUL-terminated type name.
+    pub name: *const c_char,
+    /// Descriptor of the parent type, null for `Object`.
+    pub parent: *const TypeDescriptor,
+    /// Pointer map of the objects of the type: the number of fields holding strings
+    /// or objects, followed by their byte offsets.
+    pub pointers: *const i64,
+}
+
+// Descriptors are immutable, so they can be shared between threads.
+unsafe impl Sync for TypeDescriptor {}
+
+/// The first field of every object.
+#[repr(C)]
+pub struct ObjectHeader {
+    pub descriptor: *const TypeDescriptor,
+}
+
+/// A number, string or boolean used as an object.
+#[repr(C)]
+pub struct BoxedValue {
+    pub header: ObjectHeader,
+    pub value: BoxedPayload,
+}
+
+#[repr(C)]
+pub union BoxedPayload {
+    pub number: f64,
+    pub string: *const c_char,
+    pub boolean: bool,
+}
+
+static NO_POINTERS: [i64; 1] = [0];
+
+static STRING_POINTERS: [i64; 2] = [1, offset_of!(BoxedValue, value) as i64];
+
+#[no_mangle]
+pub static hulk_object_vtable: TypeDescriptor = TypeDescriptor {
+    name: c"Object".as_ptr(),
+    parent: ptr::null(),
+    pointers: NO_POINTERS.as_ptr(),
+};
+
+#[no_mangle]
+pub static hulk_number_vtable: TypeDescriptor = TypeDescriptor {
+    name: c"Number".as_ptr(),
+    parent: &hulk_object_vtable,
+    pointers: NO_POINTERS.as_ptr(),
+};
+
+#[no_mangle]
+pub static hulk_string_vtable: TypeDescriptor = TypeDescriptor {
+    name: c"String".as_ptr(),
+    parent: &hulk_object_vtable,
+    pointers: STRING_POINTERS.as_ptr(),
+};
+
+#[no_mangle]
+pub static hulk_boolean_vtable: TypeDescriptor = TypeDescriptor {
+    name: c"Boolean".as_ptr(),
+    parent: &hulk_object_vtable,
+    pointers: NO_POINTERS.as_ptr(),
+};
+
+/// Allocates `size` zeroed bytes without pointers, aligned for any HULK value.
+#[no_mangle]
+pub extern "C" fn hulk_alloc(size: i64) -> *mut c_void {
+    allocate(size as usize, BlockKind::Data).cast()
+}
+
+/// Allocates an object of `size` bytes, header included, whose fields are zeroed
+/// and whose header points to `descriptor`.
+#[no_mangle]
+pub extern "C" fn hulk_alloc_object(
+    descriptor: *const TypeDescriptor,
+    size: i64,
+) -> *mut ObjectHeader {
+    let size = size.max(std::mem::size_of::<ObjectHeader>() as i64);
+    let object = allocate(size as usize, BlockKind::Object).cast::<ObjectHeader>();
+    // SAFETY: the allocation is large enough for the header.
+    unsafe { (*object).descriptor = descriptor };
+    object
+}
+
+/// Returns whether the type of `object` is `descriptor` or one of its descendants.
+/// `null` belongs to no type.
+///
+/// # Safety
+/// `object` must be null or point to an object, and `descriptor` to a descriptor.
+#[no_mangle]
+pub unsafe extern "C" fn hulk_is(
+    object: *const ObjectHeader,
+    descriptor: *const TypeDescriptor,
+) -> bool {
+    if object.is_null() {
+        return false;
+    }
+    let mut current = (*object).descriptor;
+    while !current.is_null() {
+        if ptr::eq(current, descriptor) {
+            return true;
+        }
+        current = (*current).parent;
+    }
+    false
+}
+
+/// Returns the name of the type of `object`.
+///
+/// # Safety
+/// `object` must point to an object.
+#[no_mangle]
+pub unsafe extern "C" fn hulk_type_name(object: *const ObjectHeader) -> *const c_char {
+    (*(*object).descriptor).name
+}
+
+fn hulk_box(descriptor: &'static TypeDescriptor, value: BoxedPayload) -> *mut c_void {
+    let size = std::mem::size_of::<BoxedValue>() as i64;
+    let object = hulk_alloc_object(descriptor, size).cast::<BoxedValue>();
+    // SAFETY: the allocation has the size of a box.
+    unsafe { (*object).value = value };
+    object.cast()
+}
+
+#[no_mangle]
+pub extern "C" fn hulk_box_number(value: f64) -> *mut c_void {
+    hulk_box(&hulk_number_vtable, BoxedPayload { number: value })
+}
+
+#[no_mangle]
+pub extern "C" fn hulk_box_string(value: *const c_char) -> *mut c_void {
+    hulk_box(&hulk_string_vtable, BoxedPayload { string: value })
+}
+
+#[no_mangle]
+pub extern "C" fn hulk_box_bool(value: bool) -> *mut c_void {
+    hulk_box(&hulk_boolean_vtable, BoxedPayload { boolean: value })
+}
+
+/// Returns the text of an object: boxed values print as the value they hold, other
+/// objects as `<TypeName>` and null as `null`.
+///
+/// # Safety
+/// `object` must be null or point to an object.
+#[no_mangle]
+pub unsafe extern "C" fn hulk_object_to_string(object: *const BoxedValue) -> *const c_char {
+    if object.is_null() {
+        return c"null".as_ptr();
+    }
+    let descriptor = (*object).header.descriptor;
+    if ptr::eq(descriptor, &hulk_number_vtable) {
+        hulk_number_to_string((*object).value.number)
+    } else if ptr::eq(descriptor, &hulk_string_vtable) {
+        (*object).value.string
+    } else if ptr::eq(descriptor, &hulk_boolean_vtable) {
+        hulk_bool_to_string((*object).value.boolean)
+    } else {
+        hulk_concat(
+            hulk_concat(c"<".as_ptr(), (*descriptor).name),
+            c">".as_ptr(),
+        )
+    }
+}
+
+/// Returns whether two objects are the same object or boxes holding equal values.
+///
+/// # Safety
+/// Both arguments must be null or point to objects.
+#[no_mangle]
+pub unsafe extern "C" fn hulk_object_eq(left: *const BoxedValue, right: *const BoxedValue) -> bool {
+    if ptr::eq(left, right) {
+        return true;
+    }
+    if left.is_null() || right.is_null() {
+        return false;
+    }
+    let descriptor = (*left).header.descriptor;
+    if !ptr::eq(descriptor, (*right).header.descriptor) {
+        return false;
+    }
+    if ptr::eq(descriptor, &hulk_number_vtable) {
+        (*left).value.number == (*right).value.number
+    } else if ptr::eq(descriptor, &hulk_string_vtable) {
+        hulk_string_eq((*left).value.string, (*right).value.string)
+    } else if ptr::eq(descriptor, &hulk_boolean_vtable) {
+        (*left).value.boolean == (*right).value.boolean
+    } else {
+        false
+    }
+}
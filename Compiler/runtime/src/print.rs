@@ -0,0 +1,36 @@
+//! The `print` builtin, which writes a value and a newline to the standard output.
+
+use std::ffi::{c_char, CStr};
+use std::io::Write;
+
+use crate::object::{hulk_object_to_string, BoxedValue};
+use crate::string::{hulk_bool_to_string, hulk_number_to_string};
+
+/// # Safety
+/// `value` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hulk_print_string(value: *const c_char) {
+    let mut out = std::io::stdout().lock();
+    // A closed output is not an error of the program, so failures are ignored.
+    let _ = out.write_all(CStr::from_ptr(value).to_bytes());
+    let _ = out.write_all(b"\n");
+}
+
+#[no_mangle]
+pub extern "C" fn hulk_print_number(value: f64) {
+    // SAFETY: numbers convert to NUL-terminated strings.
+    unsafe { hulk_print_string(hulk_number_to_string(value)) }
+}
+
+#[no_mangle]
+pub extern "C" fn hulk_print_bool(value: bool) {
+    // SAFETY: booleans convert to NUL-terminated strings.
+    unsafe { hulk_print_string(hulk_bool_to_string(value)) }
+}
+
+/// # Safety
+/// `object` must be null or point to an object.
+#[no_mangle]
+pub unsafe extern "C" fn hulk_print_object(object: *const BoxedValue) {
+    hulk_print_string(hulk_object_to_string(object))
+}
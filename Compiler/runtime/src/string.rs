@@ -0,0 +1,49 @@
+//! Immutable NUL-terminated strings.
+
+use std::ffi::{c_char, CStr};
+
+use crate::number_format::format_number;
+use crate::object::hulk_alloc;
+
+/// Copies `bytes` into a new NUL-terminated string.
+fn new_string(bytes: &[u8]) -> *mut c_char {
+    let buffer = hulk_alloc(bytes.len() as i64 + 1).cast::<u8>();
+    // SAFETY: the buffer has room for the bytes and the zeroed terminator.
+    unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer, bytes.len()) };
+    buffer.cast()
+}
+
+/// Returns the text of a number, as C's `printf("%.15g", value)` writes it.
+#[no_mangle]
+pub extern "C" fn hulk_number_to_string(value: f64) -> *const c_char {
+    new_string(format_number(value).as_bytes())
+}
+
+#[no_mangle]
+pub extern "C" fn hulk_bool_to_string(value: bool) -> *const c_char {
+    if value {
+        c"true".as_ptr()
+    } else {
+        c"false".as_ptr()
+    }
+}
+
+/// Returns a new string with `right` appended to `left`, the `@` operator.
+///
+/// # Safety
+/// Both arguments must be NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn hulk_concat(left: *const c_char, right: *const c_char) -> *const c_char {
+    let left = CStr::from_ptr(left).to_bytes();
+    let right = CStr::from_ptr(right).to_bytes();
+    new_string(&[left, right].concat())
+}
+
+/// Returns whether two strings have the same contents.
+///
+/// # Safety
+/// Both arguments must be NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn hulk_string_eq(left: *const c_char, right: *const c_char) -> bool {
+    CStr::from_ptr(left) == CStr::from_ptr(right)
+}
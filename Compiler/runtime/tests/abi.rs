@@ -0,0 +1,119 @@
+//! Calls the C ABI of the runtime the way generated code does.
+
+use std::ffi::{c_char, CStr};
+use std::ptr;
+
+use hulk_runtime::object::*;
+use hulk_runtime::string::*;
+
+fn text(string: *const c_char) -> String {
+    unsafe { CStr::from_ptr(string) }
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+static NO_POINTERS: [i64; 1] = [0];
+
+/// A user type `Point`, whose descriptor is followed by one method slot.
+#[repr(C)]
+struct PointVtable {
+    descriptor: TypeDescriptor,
+    norm: extern "C" fn() -> f64,
+}
+
+unsafe impl Sync for PointVtable {}
+
+extern "C" fn norm() -> f64 {
+    5.0
+}
+
+static POINT: PointVtable = PointVtable {
+    descriptor: TypeDescriptor {
+        name: c"Point".as_ptr(),
+        parent: &hulk_object_vtable,
+        pointers: NO_POINTERS.as_ptr(),
+    },
+    norm,
+};
+
+static POINT3: TypeDescriptor = TypeDescriptor {
+    name: c"Point3".as_ptr(),
+    parent: &POINT.descriptor,
+    pointers: NO_POINTERS.as_ptr(),
+};
+
+#[test]
+fn objects_know_their_type_and_ancestors() {
+    let point = hulk_alloc_object(&POINT3, 24);
+    unsafe {
+        assert!(hulk_is(point, &POINT3));
+        assert!(hulk_is(point, &POINT.descriptor));
+        assert!(hulk_is(point, &hulk_object_vtable));
+        assert!(!hulk_is(point, &hulk_number_vtable));
+        assert!(!hulk_is(ptr::null(), &hulk_object_vtable));
+        assert_eq!(text(hulk_type_name(point)), "Point3");
+        assert_eq!(text(hulk_object_to_string(point.cast())), "<Point3>");
+        assert_eq!(*point.cast::<u8>().add(8).cast::<f64>(), 0.0);
+    }
+}
+
+#[test]
+fn vtable_slots_follow_the_descriptor() {
+    let point = hulk_alloc_object(&POINT.descriptor, 8);
+    unsafe {
+        let vtable = (*point).descriptor.cast::<PointVtable>();
+        assert_eq!(((*vtable).norm)(), 5.0);
+    }
+}
+
+#[test]
+fn numbers_convert_like_printf() {
+    assert_eq!(text(hulk_number_to_string(1.0 / 3.0)), "0.333333333333333");
+    assert_eq!(text(hulk_number_to_string(5000050000.0)), "5000050000");
+    assert_eq!(text(hulk_number_to_string(1e20)), "1e+20");
+    assert_eq!(text(hulk_number_to_string(-0.0001)), "-0.0001");
+    assert_eq!(text(hulk_number_to_string(0.00001)), "1e-05");
+    assert_eq!(text(hulk_bool_to_string(true)), "true");
+}
+
+#[test]
+fn strings_concatenate_and_compare_by_contents() {
+    unsafe {
+        let joined = hulk_concat(c"abc".as_ptr(), c"def".as_ptr());
+        assert_eq!(text(joined), "abcdef");
+        let again = hulk_concat(c"abcd".as_ptr(), c"ef".as_ptr());
+        assert!(hulk_string_eq(joined, again));
+        assert!(!hulk_string_eq(joined, c"abc".as_ptr()));
+    }
+}
+
+#[test]
+fn boxes_compare_by_value() {
+    unsafe {
+        let one = hulk_box_number(1.0).cast();
+        assert!(hulk_object_eq(one, hulk_box_number(1.0).cast()));
+        assert!(!hulk_object_eq(one, hulk_box_number(2.0).cast()));
+        assert!(!hulk_object_eq(one, hulk_box_bool(true).cast()));
+        let string = hulk_box_string(c"hi".as_ptr()).cast();
+        assert!(hulk_object_eq(
+            string,
+            hulk_box_string(c"hi".as_ptr()).cast()
+        ));
+        assert_eq!(text(hulk_object_to_string(string)), "hi");
+        assert_eq!(text(hulk_object_to_string(ptr::null())), "null");
+        assert!(hulk_is(one.cast(), &hulk_number_vtable));
+    }
+}
+
+#[test]
+fn rand_restarts_with_its_seed() {
+    use hulk_runtime::math::*;
+
+    hulk_rand_seed(42);
+    let first = [hulk_rand(), hulk_rand()];
+    hulk_rand_seed(42);
+    assert_eq!([hulk_rand(), hulk_rand()], first);
+    assert!(first.iter().all(|number| (0.0..1.0).contains(number)));
+    assert_eq!(hulk_log(2.0, 8.0), 3.0);
+}
@@ -0,0 +1,140 @@
+//! Drives the collector the way generated code does: roots are slots listed in frames
+//! linked into the shadow stack.
+
+use std::ffi::{c_char, c_void, CStr};
+use std::ptr;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+use hulk_runtime::gc::*;
+use hulk_runtime::object::*;
+use hulk_runtime::string::*;
+
+/// The shadow stack is global, so tests that collect run one at a time.
+static SHADOW_STACK: Mutex<()> = Mutex::new(());
+
+/// A frame with room for `N` roots, laid out like [`GcFrame`].
+#[repr(C)]
+struct Frame<const N: usize> {
+    prev: *mut GcFrame,
+    count: i64,
+    roots: [*mut c_void; N],
+}
+
+impl<const N: usize> Frame<N> {
+    fn new(roots: [*mut c_void; N]) -> Self {
+        Frame {
+            prev: ptr::null_mut(),
+            count: N as i64,
+            roots,
+        }
+    }
+
+    /// Links the frame into the shadow stack for the duration of `body`.
+    fn with<T>(&mut self, body: impl FnOnce() -> T) -> T {
+        self.prev = hulk_gc_top.load(Ordering::Relaxed);
+        hulk_gc_top.store((self as *mut Self).cast(), Ordering::Relaxed);
+        let result = body();
+        hulk_gc_top.store(self.prev, Ordering::Relaxed);
+        result
+    }
+}
+
+/// A user type `Pair(left: Object, right: Object)`.
+static PAIR_POINTERS: [i64; 3] = [2, 8, 16];
+
+static PAIR: TypeDescriptor = TypeDescriptor {
+    name: c"Pair".as_ptr(),
+    parent: &hulk_object_vtable,
+    pointers: PAIR_POINTERS.as_ptr(),
+};
+
+#[repr(C)]
+struct Pair {
+    header: ObjectHeader,
+    left: *mut c_void,
+    right: *mut c_void,
+}
+
+fn new_pair(left: *mut c_void, right: *mut c_void) -> *mut Pair {
+    let pair = hulk_alloc_object(&PAIR, std::mem::size_of::<Pair>() as i64).cast::<Pair>();
+    unsafe {
+        (*pair).left = left;
+        (*pair).right = right;
+    }
+    pair
+}
+
+fn text(string: *const c_char) -> String {
+    unsafe { CStr::from_ptr(string) }
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+fn slot<T>(value: &mut *mut T) -> *mut c_void {
+    (value as *mut *mut T).cast()
+}
+
+#[test]
+fn unreachable_memory_is_freed_and_reachable_memory_survives() {
+    let _guard = SHADOW_STACK.lock().unwrap();
+    let mut kept = new_pair(
+        hulk_box_string(hulk_number_to_string(1.5)),
+        new_pair(hulk_box_number(2.0), ptr::null_mut()).cast(),
+    );
+    new_pair(hulk_box_number(3.0), hulk_box_number(4.0));
+    let mut literal = c"literal".as_ptr().cast_mut();
+
+    let before = stats();
+    let mut frame = Frame::new([slot(&mut kept), slot(&mut literal)]);
+    frame.with(|| unsafe { hulk_gc_collect() });
+    let after = stats();
+
+    assert_eq!(after.collections, before.collections + 1);
+    assert!(after.freed_bytes >= before.freed_bytes + 3 * std::mem::size_of::<BoxedValue>());
+    unsafe {
+        assert_eq!(text(hulk_object_to_string((*kept).left.cast())), "1.5");
+        let inner = (*kept).right.cast::<Pair>();
+        assert_eq!(text(hulk_object_to_string((*inner).left.cast())), "2");
+    }
+    assert_eq!(text(literal), "literal");
+}
+
+#[test]
+fn fields_of_objects_in_frames_are_roots() {
+    let _guard = SHADOW_STACK.lock().unwrap();
+    let mut on_stack = Pair {
+        header: ObjectHeader { descriptor: &PAIR },
+        left: hulk_box_number(7.0),
+        right: unsafe { hulk_concat(c"a".as_ptr(), c"b".as_ptr()) }
+            .cast_mut()
+            .cast(),
+    };
+    let tagged = (ptr::addr_of_mut!(on_stack) as usize | 1) as *mut c_void;
+
+    let mut frame = Frame::new([tagged]);
+    frame.with(|| unsafe { hulk_gc_collect() });
+
+    assert_eq!(
+        text(unsafe { hulk_object_to_string(on_stack.left.cast()) }),
+        "7"
+    );
+    assert_eq!(text(on_stack.right.cast()), "ab");
+}
+
+#[test]
+fn nested_frames_are_all_scanned() {
+    let _guard = SHADOW_STACK.lock().unwrap();
+    let mut outer = hulk_box_number(1.0);
+    let mut inner = hulk_box_number(2.0);
+
+    let mut outer_frame = Frame::new([slot(&mut outer)]);
+    outer_frame.with(|| {
+        let mut inner_frame = Frame::new([slot(&mut inner)]);
+        inner_frame.with(|| unsafe { hulk_gc_collect() });
+    });
+
+    assert_eq!(text(unsafe { hulk_object_to_string(outer.cast()) }), "1");
+    assert_eq!(text(unsafe { hulk_object_to_string(inner.cast()) }), "2");
+}
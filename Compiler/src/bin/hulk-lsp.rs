@@ -0,0 +1,18 @@
+//! `hulk-lsp`: the HULK language server, speaking LSP over standard input and output.
+
+use std::io;
+use std::process::ExitCode;
+
+use compilador::lsp::server::Server;
+
+fn main() -> ExitCode {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    match Server::new().run(stdin.lock(), stdout.lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("hulk-lsp: {}", error);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Builtin global functions and constants of HULK.
+//!
+//! The registry is shared by every phase: the semantic checker takes the signatures
+//! from it, the interpreter and the virtual machine call [`BuiltinFunction::call`],
+//! and the native backends call the runtime function named by
+//! [`BuiltinFunction::symbol`]. Builtins take and return numbers.
+//!
+//! A program may define a global function with the name of a builtin, which then
+//! hides it; a variable named like a constant hides the constant the same way.
+
+use std::f64::consts;
+
+use crate::ast_nodes::function_def::FunctionParams;
+use crate::random::Random;
+use crate::tokens::Span;
+use crate::types_tree::tree_node::FunctionSignature;
+use crate::types_tree::type_tree::NUMBER;
+
+/// A builtin global function.
+pub struct BuiltinFunction {
+    pub name: &'static str,
+    /// Names of the parameters, all of type `Number`.
+    pub params: &'static [&'static str],
+    /// Whether calling it twice with the same arguments gives the same result.
+    pub pure: bool,
+    /// Name of the function implementing it in the native runtimes.
+    pub symbol: &'static str,
+    implementation: fn(&[f64], &mut Random) -> f64,
+}
+
+impl BuiltinFunction {
+    /// Returns the signature the semantic checker gives calls to the builtin.
+    pub fn signature(&self) -> FunctionSignature {
+        let params = self
+            .params
+            .iter()
+            .map(|param| {
+                FunctionParams::new(param.to_string(), NUMBER.to_string(), Span::new(0, 0))
+            })
+            .collect();
+        FunctionSignature::new(self.name.to_string(), params, NUMBER.to_string())
+    }
+
+    /// Computes the builtin. `args` must have one number per parameter.
+    pub fn call(&self, args: &[f64], random: &mut Random) -> f64 {
+        (self.implementation)(args, random)
+    }
+}
+
+pub static FUNCTIONS: [BuiltinFunction; 6] = [
+    BuiltinFunction {
+        name: "sqrt",
+        params: &["x"],
+        pure: true,
+        symbol: "hulk_sqrt",
+        implementation: |args, _| args[0].sqrt(),
+    },
+    BuiltinFunction {
+        name: "sin",
+        params: &["x"],
+        pure: true,
+        symbol: "hulk_sin",
+        implementation: |args, _| args[0].sin(),
+    },
+    BuiltinFunction {
+        name: "cos",
+        params: &["x"],
+        pure: true,
+        symbol: "hulk_cos",
+        implementation: |args, _| args[0].cos(),
+    },
+    BuiltinFunction {
+        name: "exp",
+        params: &["x"],
+        pure: true,
+        symbol: "hulk_exp",
+        implementation: |args, _| args[0].exp(),
+    },
+    BuiltinFunction {
+        name: "log",
+        params: &["base", "x"],
+        pure: true,
+        symbol: "hulk_log",
+        implementation: |args, _| log(args[0], args[1]),
+    },
+    BuiltinFunction {
+        name: "rand",
+        params: &[],
+        pure: false,
+        symbol: "hulk_rand",
+        implementation: |_, random| random.next_number(),
+    },
+];
+
+/// Builtin constants, by name.
+pub const CONSTANTS: [(&str, f64); 2] = [("PI", consts::PI), ("E", consts::E)];
+
+/// Returns the builtin function called `name`.
+pub fn function(name: &str) -> Option<&'static BuiltinFunction> {
+    FUNCTIONS.iter().find(|function| function.name == name)
+}
+
+/// Returns the value of the builtin constant called `name`.
+pub fn constant(name: &str) -> Option<f64> {
+    CONSTANTS
+        .iter()
+        .find(|(constant, _)| *constant == name)
+        .map(|(_, value)| *value)
+}
+
+/// The logarithm of `x` in `base`, as every runtime computes it.
+pub fn log(base: f64, x: f64) -> f64 {
+    x.ln() / base.ln()
+}
@@ -0,0 +1,247 @@
+//! The bytecode format: instructions, constants, functions and classes.
+//!
+//! A [`Chunk`] holds a whole compiled program. Functions refer to constants, classes
+//! and other functions by their index in the chunk. Every instruction works on the
+//! operand stack of the current call frame; the comment of each [`Op`] shows its
+//! effect, with the top of the stack on the right.
+
+use std::fmt;
+
+/// A value in the constant pool.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Number(f64),
+    Str(String),
+}
+
+/// Where a closure finds one of its upvalues when it is created.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpvalueDescriptor {
+    /// Whether the upvalue is a local of the enclosing function, or one of its upvalues.
+    pub is_local: bool,
+    pub index: u16,
+}
+
+/// A bytecode instruction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    /// `-> constant`
+    Constant(u32),
+    /// `-> null`
+    Null,
+    /// `-> true`
+    True,
+    /// `-> false`
+    False,
+    /// `value ->`
+    Pop,
+    /// `locals... value -> value`: drops the `n` values below the top.
+    PopBelow(u16),
+    /// `-> local`
+    GetLocal(u16),
+    /// `value -> value`: stores the top into a local.
+    SetLocal(u16),
+    /// `-> upvalue`
+    GetUpvalue(u16),
+    /// `value -> value`: stores the top into an upvalue.
+    SetUpvalue(u16),
+    /// `-> closure` of the given function, capturing its upvalues.
+    Closure(u32),
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    /// Logical and of two booleans, both already evaluated.
+    And,
+    /// Logical or of two booleans, both already evaluated.
+    Or,
+    /// `left right -> string`: concatenates the text of both values.
+    Concat,
+    Neg,
+    Not,
+    /// Continues at the given instruction.
+    Jump(u32),
+    /// `condition ->`: continues at the given instruction if the condition is false.
+    JumpIfFalse(u32),
+    /// `args... -> result`: calls the given function.
+    Call(u32, u8),
+    /// `closure args... -> result`: calls a closure.
+    CallClosure(u8),
+    /// `args... -> result`: restarts the current function with new arguments.
+    TailCallSelf(u8),
+    /// `args... -> result`: replaces the current frame with a call to the given
+    /// function, which returns to the caller of the current one.
+    TailCall(u32, u8),
+    /// `args... -> result`: calls the builtin at the given index of
+    /// [`builtins::FUNCTIONS`](crate::builtins::FUNCTIONS).
+    CallBuiltin(u8, u8),
+    /// `receiver args... -> result`: calls the method in the given vtable slot of the
+    /// class of the receiver.
+    InvokeVirtual(u16, u8),
+    /// `receiver args... -> result`: like [`Op::InvokeVirtual`], replacing the current
+    /// frame as [`Op::TailCall`] does.
+    TailInvokeVirtual(u16, u8),
+    /// `value ->`: returns from the current function.
+    Return,
+    /// `-> object` of the given class, with every field null.
+    New(u32),
+    /// `object -> value` of the field in the given slot.
+    GetField(u16),
+    /// `object value -> value`: stores into the field in the given slot.
+    SetField(u16),
+    /// `value -> value`: writes the value and a newline to the output.
+    Print,
+}
+
+impl Op {
+    /// Returns how many values the instruction adds to the stack, negative if it
+    /// removes them.
+    pub fn stack_effect(&self) -> i32 {
+        match self {
+            Op::Constant(_) | Op::Null | Op::True | Op::False => 1,
+            Op::GetLocal(_) | Op::GetUpvalue(_) | Op::Closure(_) | Op::New(_) => 1,
+            Op::Pop | Op::JumpIfFalse(_) | Op::Return => -1,
+            Op::PopBelow(n) => -(*n as i32),
+            Op::SetLocal(_) | Op::SetUpvalue(_) | Op::Jump(_) => 0,
+            Op::Neg | Op::Not | Op::GetField(_) | Op::Print => 0,
+            Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Mod | Op::Pow => -1,
+            Op::Lt | Op::Le | Op::Gt | Op::Ge | Op::Eq | Op::Ne => -1,
+            Op::And | Op::Or | Op::Concat | Op::SetField(_) => -1,
+            Op::Call(_, argc)
+            | Op::TailCallSelf(argc)
+            | Op::TailCall(_, argc)
+            | Op::CallBuiltin(_, argc) => 1 - *argc as i32,
+            Op::CallClosure(argc) | Op::InvokeVirtual(_, argc) | Op::TailInvokeVirtual(_, argc) => {
+                -(*argc as i32)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Op::Constant(index) => write!(f, "CONST {}", index),
+            Op::Null => write!(f, "NULL"),
+            Op::True => write!(f, "TRUE"),
+            Op::False => write!(f, "FALSE"),
+            Op::Pop => write!(f, "POP"),
+            Op::PopBelow(n) => write!(f, "POPBELOW {}", n),
+            Op::GetLocal(slot) => write!(f, "GETLOCAL {}", slot),
+            Op::SetLocal(slot) => write!(f, "SETLOCAL {}", slot),
+            Op::GetUpvalue(slot) => write!(f, "GETUPVAL {}", slot),
+            Op::SetUpvalue(slot) => write!(f, "SETUPVAL {}", slot),
+            Op::Closure(function) => write!(f, "CLOSURE {}", function),
+            Op::Add => write!(f, "ADD"),
+            Op::Sub => write!(f, "SUB"),
+            Op::Mul => write!(f, "MUL"),
+            Op::Div => write!(f, "DIV"),
+            Op::Mod => write!(f, "MOD"),
+            Op::Pow => write!(f, "POW"),
+            Op::Lt => write!(f, "LT"),
+            Op::Le => write!(f, "LE"),
+            Op::Gt => write!(f, "GT"),
+            Op::Ge => write!(f, "GE"),
+            Op::Eq => write!(f, "EQ"),
+            Op::Ne => write!(f, "NE"),
+            Op::And => write!(f, "AND"),
+            Op::Or => write!(f, "OR"),
+            Op::Concat => write!(f, "CONCAT"),
+            Op::Neg => write!(f, "NEG"),
+            Op::Not => write!(f, "NOT"),
+            Op::Jump(target) => write!(f, "JUMP {}", target),
+            Op::JumpIfFalse(target) => write!(f, "JUMPIFFALSE {}", target),
+            Op::Call(function, argc) => write!(f, "CALL {} {}", function, argc),
+            Op::CallClosure(argc) => write!(f, "CALLCLOSURE {}", argc),
+            Op::TailCallSelf(argc) => write!(f, "TAILCALLSELF {}", argc),
+            Op::CallBuiltin(builtin, argc) => write!(f, "CALLBUILTIN {} {}", builtin, argc),
+            Op::InvokeVirtual(slot, argc) => write!(f, "INVOKEVIRTUAL {} {}", slot, argc),
+            Op::TailCall(function, argc) => write!(f, "TAILCALL {} {}", function, argc),
+            Op::TailInvokeVirtual(slot, argc) => {
+                write!(f, "TAILINVOKEVIRTUAL {} {}", slot, argc)
+            }
+            Op::Return => write!(f, "RETURN"),
+            Op::New(class) => write!(f, "NEW {}", class),
+            Op::GetField(slot) => write!(f, "GETFIELD {}", slot),
+            Op::SetField(slot) => write!(f, "SETFIELD {}", slot),
+            Op::Print => write!(f, "PRINT"),
+        }
+    }
+}
+
+/// A compiled function. Its parameters are its first locals; methods get the
+/// receiver as parameter 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionProto {
+    pub name: String,
+    pub arity: u8,
+    pub upvalues: Vec<UpvalueDescriptor>,
+    pub code: Vec<Op>,
+}
+
+/// A compiled type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassProto {
+    pub name: String,
+    pub parent: Option<u32>,
+    /// Field names, inherited ones first, so a field has the same slot in subclasses.
+    pub fields: Vec<String>,
+    /// Method names and the function implementing each one, by vtable slot.
+    pub vtable: Vec<(String, u32)>,
+}
+
+/// A compiled program.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Chunk {
+    pub constants: Vec<Constant>,
+    pub classes: Vec<ClassProto>,
+    pub functions: Vec<FunctionProto>,
+    /// The function holding the top-level expressions.
+    pub entry: u32,
+}
+
+impl fmt::Display for Chunk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "constants:")?;
+        for (index, constant) in self.constants.iter().enumerate() {
+            match constant {
+                Constant::Number(value) => writeln!(f, "  {:>4}  {}", index, value)?,
+                Constant::Str(value) => writeln!(f, "  {:>4}  {:?}", index, value)?,
+            }
+        }
+        for (index, class) in self.classes.iter().enumerate() {
+            let parent = class.parent.map_or(String::new(), |parent| {
+                format!(" inherits {}", self.classes[parent as usize].name)
+            });
+            writeln!(f, "\nclass {} {}{}", index, class.name, parent)?;
+            writeln!(f, "  fields: {}", class.fields.join(", "))?;
+            for (slot, (method, function)) in class.vtable.iter().enumerate() {
+                writeln!(f, "  {:>4}  {} -> {}", slot, method, function)?;
+            }
+        }
+        for (index, function) in self.functions.iter().enumerate() {
+            let entry = if index as u32 == self.entry {
+                " (entry)"
+            } else {
+                ""
+            };
+            writeln!(
+                f,
+                "\nfunction {} {}/{}{}",
+                index, function.name, function.arity, entry
+            )?;
+            for (offset, op) in function.code.iter().enumerate() {
+                writeln!(f, "  {:>4}  {}", offset, op)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,760 @@
+//! `BytecodeCompiler` implements the [`Visitor`] trait to compile a type-checked program
+//! into a [`Chunk`].
+//!
+//! Every expression compiles to code that leaves exactly one value on the stack. The
+//! compiler tracks how many values the current function has on its stack, so `let`
+//! variables and the hidden variables of loops become locals in the slot where their
+//! value was pushed, and leaving a scope drops them from below its result.
+//!
+//! Each type gets a class, an initializer `T.init(self, ...)` that runs the
+//! constructors of the ancestors and sets the attributes, and `T.new(...)`, which
+//! allocates the object and initializes it. Global functions, methods and the
+//! top-level expressions, compiled into `main`, become functions of the chunk.
+//!
+//! Names are resolved among the locals of the function being compiled and then, as
+//! upvalues, among those of the functions enclosing it. Functions are only declared at
+//! the top level today, so they never capture anything, but closures compiled from
+//! nested functions would use the same resolution.
+
+use std::collections::HashMap;
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::BlockNode;
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::{FunctionCallNode, TailCallKind};
+use crate::ast_nodes::function_def::{FunctionDefNode, FunctionParams};
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::LetInNode;
+use crate::ast_nodes::literals::{
+    BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode,
+};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::{Program, Statement};
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+use crate::builtins;
+use crate::intermediate::ir_generator::{constructor_name, method_name, MAIN_FUNCTION};
+use crate::tokens::OperatorToken;
+use crate::types_tree::type_tree::{TypeTree, BOOLEAN, NUMBER, OBJECT, STRING};
+use crate::visitor::accept::Accept;
+use crate::visitor::visitor_trait::Visitor;
+
+use super::chunk::{Chunk, ClassProto, Constant, FunctionProto, Op, UpvalueDescriptor};
+
+/// Returns the name of the initializer of `type_name`.
+fn init_name(type_name: &str) -> String {
+    format!("{}.init", type_name)
+}
+
+/// Returns the static type the semantic phase gave `expr`.
+fn type_of(expr: &Expression) -> String {
+    expr.node_type()
+        .map(|node| node.type_name.clone())
+        .unwrap_or_else(|| OBJECT.to_string())
+}
+
+struct Local {
+    name: String,
+    slot: u16,
+}
+
+/// A function being compiled.
+struct FunctionState {
+    index: u32,
+    arity: u8,
+    code: Vec<Op>,
+    /// Variables in scope, innermost last.
+    locals: Vec<Local>,
+    upvalues: Vec<(String, UpvalueDescriptor)>,
+    /// Number of values on the stack of the function.
+    depth: i32,
+    /// Name of the global function being compiled, if any.
+    global: Option<String>,
+}
+
+/// A visitor that compiles a type-checked program into bytecode.
+pub struct BytecodeCompiler<'a> {
+    type_tree: &'a TypeTree,
+    chunk: Chunk,
+    function_ids: HashMap<String, u32>,
+    class_ids: HashMap<String, u32>,
+    numbers: HashMap<u64, u32>,
+    strings: HashMap<String, u32>,
+    /// The function being compiled last, after the functions enclosing it.
+    states: Vec<FunctionState>,
+}
+
+impl<'a> BytecodeCompiler<'a> {
+    pub fn new(type_tree: &'a TypeTree) -> Self {
+        BytecodeCompiler {
+            type_tree,
+            chunk: Chunk::default(),
+            function_ids: HashMap::new(),
+            class_ids: HashMap::new(),
+            numbers: HashMap::new(),
+            strings: HashMap::new(),
+            states: Vec::new(),
+        }
+    }
+
+    /// Compiles a program that passed the semantic phase.
+    ///
+    /// # Arguments
+    /// * `node` - The root `Program` node, annotated with static types.
+    ///
+    /// # Returns
+    /// The chunk with every class and function of the program, whose entry runs the
+    /// top-level expressions.
+    pub fn compile_program(mut self, node: &mut Program) -> Chunk {
+        let mut types: Vec<String> = node
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::StatementTypeDef(def) => Some(def.identifier.clone()),
+                _ => None,
+            })
+            .collect();
+        types.sort_by_key(|name| self.type_tree.get_type(name).map_or(0, |node| node.depth));
+
+        for type_name in types.iter() {
+            self.declare_function(&init_name(type_name));
+            self.declare_function(&constructor_name(type_name));
+            for method in self.type_tree.type_node(type_name).methods.iter() {
+                self.declare_function(&method_name(type_name, &method.name));
+            }
+        }
+        for statement in node.statements.iter() {
+            if let Statement::StatementFunctionDef(def) = statement {
+                self.declare_function(&def.name);
+            }
+        }
+        self.chunk.entry = self.declare_function(MAIN_FUNCTION);
+        for type_name in types.iter() {
+            let class = self.class_proto(type_name);
+            self.class_ids
+                .insert(type_name.clone(), self.chunk.classes.len() as u32);
+            self.chunk.classes.push(class);
+        }
+
+        for statement in node.statements.iter_mut() {
+            match statement {
+                Statement::StatementFunctionDef(def) => {
+                    self.visit_function_def(def);
+                }
+                Statement::StatementTypeDef(def) => {
+                    self.visit_type_def(def);
+                }
+                Statement::StatementExpression(_) => {}
+            }
+        }
+
+        self.begin_function(MAIN_FUNCTION, None, &[]);
+        self.emit(Op::Null);
+        for statement in node.statements.iter_mut() {
+            if let Statement::StatementExpression(expr) = statement {
+                self.emit(Op::Pop);
+                expr.accept(&mut self);
+            }
+        }
+        self.finish_function();
+        self.chunk
+    }
+
+    fn declare_function(&mut self, name: &str) -> u32 {
+        let index = self.chunk.functions.len() as u32;
+        self.chunk.functions.push(FunctionProto {
+            name: name.to_string(),
+            arity: 0,
+            upvalues: Vec::new(),
+            code: Vec::new(),
+        });
+        self.function_ids.insert(name.to_string(), index);
+        index
+    }
+
+    fn function_id(&self, name: &str) -> u32 {
+        *self
+            .function_ids
+            .get(name)
+            .unwrap_or_else(|| panic!("function `{}` was not declared", name))
+    }
+
+    fn class_id(&self, type_name: &str) -> u32 {
+        *self
+            .class_ids
+            .get(type_name)
+            .unwrap_or_else(|| panic!("no class for type `{}`", type_name))
+    }
+
+    /// Builds the class of `type_name`, whose parent class must already exist.
+    fn class_proto(&self, type_name: &str) -> ClassProto {
+        let node = self.type_tree.type_node(type_name);
+        let parent = node
+            .parent
+            .as_ref()
+            .and_then(|parent| self.class_ids.get(parent).copied());
+        let (mut fields, mut vtable) = match parent {
+            Some(parent) => {
+                let class = &self.chunk.classes[parent as usize];
+                (class.fields.clone(), class.vtable.clone())
+            }
+            None => (Vec::new(), Vec::new()),
+        };
+        fields.extend(node.attributes.iter().map(|(name, _)| name.clone()));
+        for method in node.methods.iter() {
+            let function = self.function_id(&method_name(type_name, &method.name));
+            match vtable.iter_mut().find(|(name, _)| *name == method.name) {
+                Some(slot) => slot.1 = function,
+                None => vtable.push((method.name.clone(), function)),
+            }
+        }
+        ClassProto {
+            name: type_name.to_string(),
+            parent,
+            fields,
+            vtable,
+        }
+    }
+
+    fn field_slot(&self, type_name: &str, field: &str) -> u16 {
+        let class = &self.chunk.classes[self.class_id(type_name) as usize];
+        class
+            .fields
+            .iter()
+            .position(|name| name == field)
+            .unwrap_or_else(|| panic!("type `{}` has no field `{}`", type_name, field))
+            as u16
+    }
+
+    fn method_slot(&self, type_name: &str, method: &str) -> u16 {
+        let class = &self.chunk.classes[self.class_id(type_name) as usize];
+        class
+            .vtable
+            .iter()
+            .position(|(name, _)| name == method)
+            .unwrap_or_else(|| panic!("type `{}` has no method `{}`", type_name, method))
+            as u16
+    }
+
+    fn number(&mut self, value: f64) -> u32 {
+        if let Some(&index) = self.numbers.get(&value.to_bits()) {
+            return index;
+        }
+        let index = self.chunk.constants.len() as u32;
+        self.chunk.constants.push(Constant::Number(value));
+        self.numbers.insert(value.to_bits(), index);
+        index
+    }
+
+    fn string(&mut self, value: &str) -> u32 {
+        if let Some(&index) = self.strings.get(value) {
+            return index;
+        }
+        let index = self.chunk.constants.len() as u32;
+        self.chunk.constants.push(Constant::Str(value.to_string()));
+        self.strings.insert(value.to_string(), index);
+        index
+    }
+
+    fn state(&mut self) -> &mut FunctionState {
+        self.states.last_mut().unwrap()
+    }
+
+    /// Starts compiling the function `name`, whose parameters are `params`.
+    fn begin_function(&mut self, name: &str, global: Option<String>, params: &[String]) {
+        let locals = params
+            .iter()
+            .enumerate()
+            .map(|(slot, name)| Local {
+                name: name.clone(),
+                slot: slot as u16,
+            })
+            .collect();
+        self.states.push(FunctionState {
+            index: self.function_id(name),
+            arity: params.len() as u8,
+            code: Vec::new(),
+            locals,
+            upvalues: Vec::new(),
+            depth: params.len() as i32,
+            global,
+        });
+    }
+
+    /// Returns the value on top of the stack and stores the function in the chunk.
+    fn finish_function(&mut self) {
+        self.emit(Op::Return);
+        let state = self.states.pop().unwrap();
+        let function = &mut self.chunk.functions[state.index as usize];
+        function.arity = state.arity;
+        function.code = state.code;
+        function.upvalues = state
+            .upvalues
+            .into_iter()
+            .map(|(_, upvalue)| upvalue)
+            .collect();
+    }
+
+    /// Appends `op` and returns its position.
+    fn emit(&mut self, op: Op) -> usize {
+        let state = self.state();
+        state.depth += op.stack_effect();
+        state.code.push(op);
+        state.code.len() - 1
+    }
+
+    fn position(&mut self) -> u32 {
+        self.state().code.len() as u32
+    }
+
+    /// Points the jump at `jump` to the next instruction emitted.
+    fn patch(&mut self, jump: usize) {
+        let target = self.position();
+        match &mut self.state().code[jump] {
+            Op::Jump(to) | Op::JumpIfFalse(to) => *to = target,
+            op => unreachable!("`{}` is not a jump", op),
+        }
+    }
+
+    /// Makes the value just pushed the local `name`.
+    fn declare_local(&mut self, name: &str) -> u16 {
+        let state = self.state();
+        let slot = (state.depth - 1) as u16;
+        state.locals.push(Local {
+            name: name.to_string(),
+            slot,
+        });
+        slot
+    }
+
+    /// Forgets the last `count` locals, whose values must be dropped separately.
+    fn forget_locals(&mut self, count: usize) {
+        let state = self.state();
+        let len = state.locals.len() - count;
+        state.locals.truncate(len);
+    }
+
+    fn resolve_local(state: &FunctionState, name: &str) -> Option<u16> {
+        state
+            .locals
+            .iter()
+            .rev()
+            .find(|local| local.name == name)
+            .map(|local| local.slot)
+    }
+
+    /// Finds `name` in the functions enclosing the one at `depth` of the state stack,
+    /// adding the upvalues needed to reach it.
+    fn resolve_upvalue(&mut self, depth: usize, name: &str) -> Option<u16> {
+        if depth == 0 {
+            return None;
+        }
+        if let Some(index) = self.states[depth]
+            .upvalues
+            .iter()
+            .position(|(upvalue, _)| upvalue == name)
+        {
+            return Some(index as u16);
+        }
+        let descriptor = match Self::resolve_local(&self.states[depth - 1], name) {
+            Some(slot) => UpvalueDescriptor {
+                is_local: true,
+                index: slot,
+            },
+            None => UpvalueDescriptor {
+                is_local: false,
+                index: self.resolve_upvalue(depth - 1, name)?,
+            },
+        };
+        let upvalues = &mut self.states[depth].upvalues;
+        upvalues.push((name.to_string(), descriptor));
+        Some(upvalues.len() as u16 - 1)
+    }
+
+    /// Pushes the default value of `type_name`, the value of loops that never run.
+    fn emit_default(&mut self, type_name: &str) {
+        match type_name {
+            NUMBER => {
+                let zero = self.number(0.0);
+                self.emit(Op::Constant(zero));
+            }
+            BOOLEAN => {
+                self.emit(Op::False);
+            }
+            STRING => {
+                let empty = self.string("");
+                self.emit(Op::Constant(empty));
+            }
+            _ => {
+                self.emit(Op::Null);
+            }
+        }
+    }
+
+    fn compile_function(&mut self, def: &mut FunctionDefNode, self_type: Option<&str>) {
+        let mut params: Vec<String> = Vec::new();
+        let (name, global) = match self_type {
+            Some(type_name) => {
+                params.push("self".to_string());
+                (method_name(type_name, &def.name), None)
+            }
+            None => (def.name.clone(), Some(def.name.clone())),
+        };
+        params.extend(def.params.iter().map(|param| param.name.clone()));
+        self.begin_function(&name, global, &params);
+        def.body.accept(self);
+        self.finish_function();
+    }
+
+    /// Compiles `T.init`, which runs the constructors of the ancestors of `def` and
+    /// sets its attributes, and `T.new`.
+    fn compile_constructor(&mut self, def: &mut TypeDefNode) {
+        let type_name = def.identifier.clone();
+        let inherits_params = def.params.is_empty() && def.parent_args.is_empty();
+        let own_params: Vec<FunctionParams> = if inherits_params {
+            self.type_tree.constructor_params(&type_name)
+        } else {
+            def.params.clone()
+        };
+        let mut params = vec!["self".to_string()];
+        params.extend(own_params.iter().map(|param| param.name.clone()));
+
+        self.begin_function(&init_name(&type_name), None, &params);
+        if let Some(parent) = def.parent.clone().filter(|parent| parent != OBJECT) {
+            self.emit(Op::GetLocal(0));
+            let argc = if inherits_params {
+                for slot in 1..params.len() {
+                    self.emit(Op::GetLocal(slot as u16));
+                }
+                own_params.len()
+            } else {
+                for arg in def.parent_args.iter_mut() {
+                    arg.accept(self);
+                }
+                def.parent_args.len()
+            };
+            let init = self.function_id(&init_name(&parent));
+            self.emit(Op::Call(init, argc as u8 + 1));
+            self.emit(Op::Pop);
+        }
+        for member in def.members.iter_mut() {
+            if let TypeMember::Property(assignment) = member {
+                let slot = self.field_slot(&type_name, &assignment.identifier);
+                self.emit(Op::GetLocal(0));
+                assignment.expression.accept(self);
+                self.emit(Op::SetField(slot));
+                self.emit(Op::Pop);
+            }
+        }
+        self.emit(Op::Null);
+        self.finish_function();
+
+        let params: Vec<String> = own_params.iter().map(|param| param.name.clone()).collect();
+        self.begin_function(&constructor_name(&type_name), None, &params);
+        let class = self.class_id(&type_name);
+        self.emit(Op::New(class));
+        let object = self.declare_local("");
+        self.emit(Op::GetLocal(object));
+        for slot in 0..params.len() {
+            self.emit(Op::GetLocal(slot as u16));
+        }
+        let init = self.function_id(&init_name(&type_name));
+        self.emit(Op::Call(init, params.len() as u8 + 1));
+        self.emit(Op::Pop);
+        self.finish_function();
+    }
+}
+
+impl Visitor<()> for BytecodeCompiler<'_> {
+    fn visit_function_def(&mut self, node: &mut FunctionDefNode) {
+        self.compile_function(node, None);
+    }
+
+    fn visit_literal_number(&mut self, node: &mut NumberLiteralNode) {
+        let index = self.number(node.value);
+        self.emit(Op::Constant(index));
+    }
+
+    fn visit_literal_boolean(&mut self, node: &mut BooleanLiteralNode) {
+        self.emit(if node.value { Op::True } else { Op::False });
+    }
+
+    fn visit_literal_string(&mut self, node: &mut StringLiteralNode) {
+        let index = self.string(&node.value);
+        self.emit(Op::Constant(index));
+    }
+
+    fn visit_identifier(&mut self, node: &mut IdentifierNode) {
+        let depth = self.states.len() - 1;
+        if let Some(slot) = Self::resolve_local(&self.states[depth], &node.value) {
+            self.emit(Op::GetLocal(slot));
+        } else if let Some(index) = self.resolve_upvalue(depth, &node.value) {
+            self.emit(Op::GetUpvalue(index));
+        } else if let Some(value) = builtins::constant(&node.value) {
+            let index = self.number(value);
+            self.emit(Op::Constant(index));
+        } else {
+            self.emit(Op::Null);
+        }
+    }
+
+    fn visit_function_call(&mut self, node: &mut FunctionCallNode) {
+        for arg in node.arguments.iter_mut() {
+            arg.accept(self);
+        }
+        let argc = node.arguments.len() as u8;
+        let recursive = node.tail_call == Some(TailCallKind::Recursive)
+            && self.state().global.as_deref() == Some(node.function_name.as_str());
+        if recursive {
+            self.emit(Op::TailCallSelf(argc));
+        } else if !self.function_ids.contains_key(&node.function_name) {
+            let builtin = builtins::FUNCTIONS
+                .iter()
+                .position(|builtin| builtin.name == node.function_name)
+                .unwrap_or_else(|| panic!("function `{}` was not declared", node.function_name));
+            self.emit(Op::CallBuiltin(builtin as u8, argc));
+        } else if node.tail_call.is_some() {
+            let function = self.function_id(&node.function_name);
+            self.emit(Op::TailCall(function, argc));
+        } else {
+            let function = self.function_id(&node.function_name);
+            self.emit(Op::Call(function, argc));
+        }
+    }
+
+    fn visit_while_loop(&mut self, node: &mut WhileNode) {
+        let ty = node
+            .node_type
+            .as_ref()
+            .map_or(OBJECT.to_string(), |node| node.type_name.clone());
+        self.emit_default(&ty);
+        let result = (self.state().depth - 1) as u16;
+
+        let header = self.position();
+        node.condition.accept(self);
+        let exit = self.emit(Op::JumpIfFalse(0));
+        node.body.accept(self);
+        self.emit(Op::SetLocal(result));
+        self.emit(Op::Pop);
+        self.emit(Op::Jump(header));
+        self.patch(exit);
+    }
+
+    fn visit_for_loop(&mut self, node: &mut ForNode) {
+        let ty = node
+            .node_type
+            .as_ref()
+            .map_or(OBJECT.to_string(), |node| node.type_name.clone());
+        self.emit_default(&ty);
+        let result = (self.state().depth - 1) as u16;
+        node.start.accept(self);
+        let counter = (self.state().depth - 1) as u16;
+        node.end.accept(self);
+        let end = (self.state().depth - 1) as u16;
+
+        let header = self.position();
+        self.emit(Op::GetLocal(counter));
+        self.emit(Op::GetLocal(end));
+        self.emit(Op::Lt);
+        let exit = self.emit(Op::JumpIfFalse(0));
+
+        // A copy of the counter per iteration, as `ForNode` documents.
+        self.emit(Op::GetLocal(counter));
+        self.declare_local(&node.variable);
+        node.body.accept(self);
+        self.emit(Op::SetLocal(result));
+        self.emit(Op::Pop);
+        self.emit(Op::Pop);
+        self.forget_locals(1);
+
+        self.emit(Op::GetLocal(counter));
+        let one = self.number(1.0);
+        self.emit(Op::Constant(one));
+        self.emit(Op::Add);
+        self.emit(Op::SetLocal(counter));
+        self.emit(Op::Pop);
+        self.emit(Op::Jump(header));
+        self.patch(exit);
+        self.emit(Op::Pop);
+        self.emit(Op::Pop);
+    }
+
+    fn visit_code_block(&mut self, node: &mut BlockNode) {
+        for (index, expr) in node.expression_list.expressions.iter_mut().enumerate() {
+            if index > 0 {
+                self.emit(Op::Pop);
+            }
+            expr.accept(self);
+        }
+    }
+
+    fn visit_binary_op(&mut self, node: &mut BinaryOpNode) {
+        node.left.accept(self);
+        node.right.accept(self);
+        let op = match node.operator {
+            OperatorToken::PLUS => Op::Add,
+            OperatorToken::MINUS => Op::Sub,
+            OperatorToken::MUL => Op::Mul,
+            OperatorToken::DIV => Op::Div,
+            OperatorToken::MOD => Op::Mod,
+            OperatorToken::POW => Op::Pow,
+            OperatorToken::LT => Op::Lt,
+            OperatorToken::LTE => Op::Le,
+            OperatorToken::GT => Op::Gt,
+            OperatorToken::GTE => Op::Ge,
+            OperatorToken::EQ => Op::Eq,
+            OperatorToken::NEQ => Op::Ne,
+            OperatorToken::AND => Op::And,
+            OperatorToken::OR => Op::Or,
+            OperatorToken::CONCAT => Op::Concat,
+            ref operator => unreachable!("`{}` is not a binary operator", operator),
+        };
+        self.emit(op);
+    }
+
+    fn visit_unary_op(&mut self, node: &mut UnaryOpNode) {
+        node.operand.accept(self);
+        match node.operator {
+            OperatorToken::NOT => self.emit(Op::Not),
+            _ => self.emit(Op::Neg),
+        };
+    }
+
+    fn visit_if_else(&mut self, node: &mut IfElseNode) {
+        let ty = node
+            .node_type
+            .as_ref()
+            .map_or(OBJECT.to_string(), |node| node.type_name.clone());
+        let depth = self.state().depth;
+        let mut exits = Vec::new();
+
+        node.condition.accept(self);
+        let mut next = self.emit(Op::JumpIfFalse(0));
+        node.if_expression.accept(self);
+        exits.push(self.emit(Op::Jump(0)));
+
+        let mut has_else = false;
+        for (condition, body) in node.elifs.iter_mut() {
+            self.patch(next);
+            self.state().depth = depth;
+            match condition {
+                Some(condition) => {
+                    condition.accept(self);
+                    next = self.emit(Op::JumpIfFalse(0));
+                    body.accept(self);
+                    exits.push(self.emit(Op::Jump(0)));
+                }
+                None => {
+                    body.accept(self);
+                    has_else = true;
+                    break;
+                }
+            }
+        }
+        if !has_else {
+            self.patch(next);
+            self.state().depth = depth;
+            self.emit_default(&ty);
+        }
+        for exit in exits {
+            self.patch(exit);
+        }
+    }
+
+    fn visit_let_in(&mut self, node: &mut LetInNode) {
+        for assignment in node.assignments.iter_mut() {
+            // Declared after compiling the value, which still sees outer variables.
+            assignment.expression.accept(self);
+            self.declare_local(&assignment.identifier);
+        }
+        node.body.accept(self);
+        let count = node.assignments.len();
+        self.emit(Op::PopBelow(count as u16));
+        self.forget_locals(count);
+    }
+
+    fn visit_destructive_assign(&mut self, node: &mut DestructiveAssignNode) {
+        match node.identifier.as_mut() {
+            Expression::Identifier(identifier) => {
+                node.expression.accept(self);
+                let depth = self.states.len() - 1;
+                if let Some(slot) = Self::resolve_local(&self.states[depth], &identifier.value) {
+                    self.emit(Op::SetLocal(slot));
+                } else if let Some(index) = self.resolve_upvalue(depth, &identifier.value) {
+                    self.emit(Op::SetUpvalue(index));
+                }
+            }
+            Expression::TypePropAccess(access) => {
+                access.object.accept(self);
+                node.expression.accept(self);
+                let slot = self.field_slot(&type_of(&access.object), &access.member);
+                self.emit(Op::SetField(slot));
+            }
+            _ => {
+                node.expression.accept(self);
+            }
+        }
+    }
+
+    fn visit_type_def(&mut self, node: &mut TypeDefNode) {
+        let type_name = node.identifier.clone();
+        self.compile_constructor(node);
+        for member in node.members.iter_mut() {
+            if let TypeMember::Method(method) = member {
+                self.compile_function(method, Some(&type_name));
+            }
+        }
+    }
+
+    fn visit_type_instance(&mut self, node: &mut TypeInstanceNode) {
+        for arg in node.arguments.iter_mut() {
+            arg.accept(self);
+        }
+        let constructor = self.function_id(&constructor_name(&node.type_name));
+        self.emit(Op::Call(constructor, node.arguments.len() as u8));
+    }
+
+    fn visit_type_function_access(&mut self, node: &mut TypeFunctionAccessNode) {
+        node.object.accept(self);
+        for arg in node.member.arguments.iter_mut() {
+            arg.accept(self);
+        }
+        let argc = node.member.arguments.len() as u8;
+        let method = node.member.function_name.clone();
+        let tail = node.member.tail_call.is_some();
+        match &node.direct_call {
+            Some(owner) => {
+                let function = self.function_id(&method_name(owner, &method));
+                self.emit(if tail {
+                    Op::TailCall(function, argc + 1)
+                } else {
+                    Op::Call(function, argc + 1)
+                });
+            }
+            None => {
+                let slot = self.method_slot(&type_of(&node.object), &method);
+                self.emit(if tail {
+                    Op::TailInvokeVirtual(slot, argc)
+                } else {
+                    Op::InvokeVirtual(slot, argc)
+                });
+            }
+        }
+    }
+
+    fn visit_type_prop_access(&mut self, node: &mut TypePropAccessNode) {
+        node.object.accept(self);
+        let slot = self.field_slot(&type_of(&node.object), &node.member);
+        self.emit(Op::GetField(slot));
+    }
+
+    fn visit_print(&mut self, node: &mut PrintNode) {
+        node.expression.accept(self);
+        self.emit(Op::Print);
+    }
+}
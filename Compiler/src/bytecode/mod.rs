@@ -0,0 +1,7 @@
+//! The bytecode backend: a compiler from the type-checked AST to a compact
+//! instruction set, a `.hbc` file format for it and a virtual machine that runs it.
+
+pub mod chunk;
+pub mod compiler;
+pub mod serialize;
+pub mod vm;
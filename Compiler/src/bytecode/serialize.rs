@@ -0,0 +1,493 @@
+//! Reading and writing chunks as `.hbc` files.
+//!
+//! The file starts with the magic bytes `HBC`, a format version byte and the index of
+//! the entry function, followed by the constant pool, the classes and the functions.
+//! Integers are little-endian; strings and lists are prefixed by a `u32` length.
+//! Every instruction is an opcode byte followed by its operands.
+//!
+//! Decoding rejects files whose instructions refer to constants, functions, classes or
+//! slots that are not there, so a damaged file is reported instead of crashing the VM.
+
+use std::fmt;
+
+use crate::builtins;
+
+use super::chunk::{Chunk, ClassProto, Constant, FunctionProto, Op, UpvalueDescriptor};
+
+const MAGIC: &[u8; 3] = b"HBC";
+const VERSION: u8 = 3;
+
+/// The reason a `.hbc` file could not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnexpectedEnd,
+    InvalidOpcode(u8),
+    InvalidConstant(u8),
+    InvalidString,
+    InvalidBuiltin(u8),
+    /// An instruction or a class refers to a constant, function, class, slot or
+    /// instruction the file does not have.
+    InvalidReference(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::BadMagic => write!(f, "not a HULK bytecode file"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported bytecode version {}", version)
+            }
+            DecodeError::UnexpectedEnd => write!(f, "bytecode file is truncated"),
+            DecodeError::InvalidOpcode(opcode) => write!(f, "invalid opcode {}", opcode),
+            DecodeError::InvalidConstant(tag) => write!(f, "invalid constant tag {}", tag),
+            DecodeError::InvalidString => write!(f, "string is not valid UTF-8"),
+            DecodeError::InvalidBuiltin(index) => write!(f, "invalid builtin {}", index),
+            DecodeError::InvalidReference(message) => write!(f, "invalid bytecode: {}", message),
+        }
+    }
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn len(&mut self, len: usize) {
+        self.u32(len as u32);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.len(value.len());
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn op(&mut self, op: &Op) {
+        match *op {
+            Op::Constant(index) => {
+                self.u8(0);
+                self.u32(index);
+            }
+            Op::Null => self.u8(1),
+            Op::True => self.u8(2),
+            Op::False => self.u8(3),
+            Op::Pop => self.u8(4),
+            Op::PopBelow(n) => {
+                self.u8(5);
+                self.u16(n);
+            }
+            Op::GetLocal(slot) => {
+                self.u8(6);
+                self.u16(slot);
+            }
+            Op::SetLocal(slot) => {
+                self.u8(7);
+                self.u16(slot);
+            }
+            Op::GetUpvalue(slot) => {
+                self.u8(8);
+                self.u16(slot);
+            }
+            Op::SetUpvalue(slot) => {
+                self.u8(9);
+                self.u16(slot);
+            }
+            Op::Closure(function) => {
+                self.u8(10);
+                self.u32(function);
+            }
+            Op::Add => self.u8(11),
+            Op::Sub => self.u8(12),
+            Op::Mul => self.u8(13),
+            Op::Div => self.u8(14),
+            Op::Mod => self.u8(15),
+            Op::Pow => self.u8(16),
+            Op::Lt => self.u8(17),
+            Op::Le => self.u8(18),
+            Op::Gt => self.u8(19),
+            Op::Ge => self.u8(20),
+            Op::Eq => self.u8(21),
+            Op::Ne => self.u8(22),
+            Op::And => self.u8(23),
+            Op::Or => self.u8(24),
+            Op::Concat => self.u8(25),
+            Op::Neg => self.u8(26),
+            Op::Not => self.u8(27),
+            Op::Jump(target) => {
+                self.u8(28);
+                self.u32(target);
+            }
+            Op::JumpIfFalse(target) => {
+                self.u8(29);
+                self.u32(target);
+            }
+            Op::Call(function, argc) => {
+                self.u8(30);
+                self.u32(function);
+                self.u8(argc);
+            }
+            Op::CallClosure(argc) => {
+                self.u8(31);
+                self.u8(argc);
+            }
+            Op::TailCallSelf(argc) => {
+                self.u8(32);
+                self.u8(argc);
+            }
+            Op::InvokeVirtual(slot, argc) => {
+                self.u8(33);
+                self.u16(slot);
+                self.u8(argc);
+            }
+            Op::Return => self.u8(34),
+            Op::New(class) => {
+                self.u8(35);
+                self.u32(class);
+            }
+            Op::GetField(slot) => {
+                self.u8(36);
+                self.u16(slot);
+            }
+            Op::SetField(slot) => {
+                self.u8(37);
+                self.u16(slot);
+            }
+            Op::Print => self.u8(38),
+            Op::CallBuiltin(builtin, argc) => {
+                self.u8(39);
+                self.u8(builtin);
+                self.u8(argc);
+            }
+            Op::TailCall(function, argc) => {
+                self.u8(40);
+                self.u32(function);
+                self.u8(argc);
+            }
+            Op::TailInvokeVirtual(slot, argc) => {
+                self.u8(41);
+                self.u16(slot);
+                self.u8(argc);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self.position + count;
+        let slice = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn len(&mut self) -> Result<usize, DecodeError> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn string(&mut self) -> Result<String, DecodeError> {
+        let len = self.len()?;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| DecodeError::InvalidString)
+    }
+
+    fn op(&mut self) -> Result<Op, DecodeError> {
+        let opcode = self.u8()?;
+        Ok(match opcode {
+            0 => Op::Constant(self.u32()?),
+            1 => Op::Null,
+            2 => Op::True,
+            3 => Op::False,
+            4 => Op::Pop,
+            5 => Op::PopBelow(self.u16()?),
+            6 => Op::GetLocal(self.u16()?),
+            7 => Op::SetLocal(self.u16()?),
+            8 => Op::GetUpvalue(self.u16()?),
+            9 => Op::SetUpvalue(self.u16()?),
+            10 => Op::Closure(self.u32()?),
+            11 => Op::Add,
+            12 => Op::Sub,
+            13 => Op::Mul,
+            14 => Op::Div,
+            15 => Op::Mod,
+            16 => Op::Pow,
+            17 => Op::Lt,
+            18 => Op::Le,
+            19 => Op::Gt,
+            20 => Op::Ge,
+            21 => Op::Eq,
+            22 => Op::Ne,
+            23 => Op::And,
+            24 => Op::Or,
+            25 => Op::Concat,
+            26 => Op::Neg,
+            27 => Op::Not,
+            28 => Op::Jump(self.u32()?),
+            29 => Op::JumpIfFalse(self.u32()?),
+            30 => Op::Call(self.u32()?, self.u8()?),
+            31 => Op::CallClosure(self.u8()?),
+            32 => Op::TailCallSelf(self.u8()?),
+            33 => Op::InvokeVirtual(self.u16()?, self.u8()?),
+            34 => Op::Return,
+            35 => Op::New(self.u32()?),
+            36 => Op::GetField(self.u16()?),
+            37 => Op::SetField(self.u16()?),
+            38 => Op::Print,
+            39 => {
+                let builtin = self.u8()?;
+                if builtin as usize >= builtins::FUNCTIONS.len() {
+                    return Err(DecodeError::InvalidBuiltin(builtin));
+                }
+                Op::CallBuiltin(builtin, self.u8()?)
+            }
+            40 => Op::TailCall(self.u32()?, self.u8()?),
+            41 => Op::TailInvokeVirtual(self.u16()?, self.u8()?),
+            _ => return Err(DecodeError::InvalidOpcode(opcode)),
+        })
+    }
+}
+
+impl Chunk {
+    /// Encodes the chunk as the contents of a `.hbc` file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer { bytes: Vec::new() };
+        w.bytes.extend_from_slice(MAGIC);
+        w.u8(VERSION);
+        w.u32(self.entry);
+
+        w.len(self.constants.len());
+        for constant in self.constants.iter() {
+            match constant {
+                Constant::Number(value) => {
+                    w.u8(0);
+                    w.bytes.extend_from_slice(&value.to_le_bytes());
+                }
+                Constant::Str(value) => {
+                    w.u8(1);
+                    w.string(value);
+                }
+            }
+        }
+
+        w.len(self.classes.len());
+        for class in self.classes.iter() {
+            w.string(&class.name);
+            w.u32(class.parent.map_or(u32::MAX, |parent| parent));
+            w.len(class.fields.len());
+            for field in class.fields.iter() {
+                w.string(field);
+            }
+            w.len(class.vtable.len());
+            for (method, function) in class.vtable.iter() {
+                w.string(method);
+                w.u32(*function);
+            }
+        }
+
+        w.len(self.functions.len());
+        for function in self.functions.iter() {
+            w.string(&function.name);
+            w.u8(function.arity);
+            w.len(function.upvalues.len());
+            for upvalue in function.upvalues.iter() {
+                w.u8(upvalue.is_local as u8);
+                w.u16(upvalue.index);
+            }
+            w.len(function.code.len());
+            for op in function.code.iter() {
+                w.op(op);
+            }
+        }
+        w.bytes
+    }
+
+    /// Decodes the contents of a `.hbc` file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Chunk, DecodeError> {
+        let mut r = Reader { bytes, position: 0 };
+        if r.take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(DecodeError::BadMagic);
+        }
+        let version = r.u8()?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let entry = r.u32()?;
+
+        let mut constants = Vec::new();
+        for _ in 0..r.len()? {
+            constants.push(match r.u8()? {
+                0 => Constant::Number(f64::from_le_bytes(r.take(8)?.try_into().unwrap())),
+                1 => Constant::Str(r.string()?),
+                tag => return Err(DecodeError::InvalidConstant(tag)),
+            });
+        }
+
+        let mut classes = Vec::new();
+        for _ in 0..r.len()? {
+            let name = r.string()?;
+            let parent = Some(r.u32()?).filter(|&parent| parent != u32::MAX);
+            let mut fields = Vec::new();
+            for _ in 0..r.len()? {
+                fields.push(r.string()?);
+            }
+            let mut vtable = Vec::new();
+            for _ in 0..r.len()? {
+                vtable.push((r.string()?, r.u32()?));
+            }
+            classes.push(ClassProto {
+                name,
+                parent,
+                fields,
+                vtable,
+            });
+        }
+
+        let mut functions = Vec::new();
+        for _ in 0..r.len()? {
+            let name = r.string()?;
+            let arity = r.u8()?;
+            let mut upvalues = Vec::new();
+            for _ in 0..r.len()? {
+                upvalues.push(UpvalueDescriptor {
+                    is_local: r.u8()? != 0,
+                    index: r.u16()?,
+                });
+            }
+            let mut code = Vec::new();
+            for _ in 0..r.len()? {
+                code.push(r.op()?);
+            }
+            functions.push(FunctionProto {
+                name,
+                arity,
+                upvalues,
+                code,
+            });
+        }
+
+        let chunk = Chunk {
+            constants,
+            classes,
+            functions,
+            entry,
+        };
+        validate(&chunk)?;
+        Ok(chunk)
+    }
+}
+
+/// Checks that every index in `chunk` refers to something it has, so the VM can
+/// follow them without checking.
+///
+/// Field and vtable slots depend on the class of the object at run time, so they are
+/// only checked against the largest class here; the VM checks them again.
+fn validate(chunk: &Chunk) -> Result<(), DecodeError> {
+    let check = |what: &str, index: usize, count: usize, owner: &str| {
+        if index < count {
+            Ok(())
+        } else {
+            Err(DecodeError::InvalidReference(format!(
+                "{} refers to {} {}, but there are {}",
+                owner, what, index, count
+            )))
+        }
+    };
+    let functions = chunk.functions.len();
+    let classes = chunk.classes.len();
+    let fields = chunk
+        .classes
+        .iter()
+        .map(|class| class.fields.len())
+        .max()
+        .unwrap_or(0);
+    let methods = chunk
+        .classes
+        .iter()
+        .map(|class| class.vtable.len())
+        .max()
+        .unwrap_or(0);
+
+    check(
+        "function",
+        chunk.entry as usize,
+        functions,
+        "the entry point",
+    )?;
+    for class in chunk.classes.iter() {
+        let owner = format!("class `{}`", class.name);
+        if let Some(parent) = class.parent {
+            check("class", parent as usize, classes, &owner)?;
+        }
+        for (_, function) in class.vtable.iter() {
+            check("function", *function as usize, functions, &owner)?;
+        }
+    }
+    for function in chunk.functions.iter() {
+        let owner = format!("function `{}`", function.name);
+        let upvalues = function.upvalues.len();
+        for op in function.code.iter() {
+            match *op {
+                Op::Constant(index) => {
+                    check("constant", index as usize, chunk.constants.len(), &owner)?
+                }
+                Op::Call(callee, _) | Op::TailCall(callee, _) => {
+                    check("function", callee as usize, functions, &owner)?
+                }
+                Op::Closure(callee) => {
+                    check("function", callee as usize, functions, &owner)?;
+                    for descriptor in chunk.functions[callee as usize].upvalues.iter() {
+                        if !descriptor.is_local {
+                            check("upvalue", descriptor.index as usize, upvalues, &owner)?;
+                        }
+                    }
+                }
+                Op::GetUpvalue(index) | Op::SetUpvalue(index) => {
+                    check("upvalue", index as usize, upvalues, &owner)?
+                }
+                Op::New(class) => check("class", class as usize, classes, &owner)?,
+                Op::GetField(slot) | Op::SetField(slot) => {
+                    check("field slot", slot as usize, fields, &owner)?
+                }
+                Op::InvokeVirtual(slot, _) | Op::TailInvokeVirtual(slot, _) => {
+                    check("method slot", slot as usize, methods, &owner)?
+                }
+                Op::Jump(target) | Op::JumpIfFalse(target) => {
+                    check("instruction", target as usize, function.code.len(), &owner)?
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,532 @@
+//! A stack-based virtual machine that runs a [`Chunk`].
+//!
+//! Every call pushes a frame whose locals start at its base in the shared value stack.
+//! Closures capture locals through upvalues, which stay open, pointing into the stack,
+//! until the local goes out of scope and its value is moved into the upvalue.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::io::Write;
+use std::rc::Rc;
+
+use crate::builtins;
+use crate::number_format::format_number;
+use crate::random::Random;
+
+use super::chunk::{Chunk, Constant, Op};
+
+/// Calls deeper than this stop the program instead of exhausting the memory.
+const MAX_FRAMES: usize = 100_000;
+
+/// An instance of a class.
+#[derive(Debug)]
+pub struct Object {
+    pub class: u32,
+    pub fields: Vec<Value>,
+}
+
+#[derive(Debug)]
+enum Upvalue {
+    /// The captured local, still on the stack.
+    Open(usize),
+    Closed(Value),
+}
+
+/// A function together with the variables it captured.
+#[derive(Debug)]
+pub struct Closure {
+    pub function: u32,
+    upvalues: Vec<Rc<RefCell<Upvalue>>>,
+}
+
+/// A value of the virtual machine.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Null,
+    Number(f64),
+    Boolean(bool),
+    Str(Rc<str>),
+    Object(Rc<RefCell<Object>>),
+    Closure(Rc<Closure>),
+}
+
+/// An error that stopped the program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VmError {
+    pub message: String,
+    /// Name of the function that was running.
+    pub function: String,
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "runtime error in `{}`: {}", self.function, self.message)
+    }
+}
+
+struct Frame {
+    function: u32,
+    ip: usize,
+    base: usize,
+    closure: Option<Rc<Closure>>,
+}
+
+/// Runs a chunk, writing what the program prints to `out`.
+pub struct Vm<'a, W: Write> {
+    chunk: &'a Chunk,
+    stack: Vec<Value>,
+    frames: Vec<Frame>,
+    /// Upvalues pointing into the stack, by increasing slot.
+    open_upvalues: Vec<Rc<RefCell<Upvalue>>>,
+    out: W,
+    random: Random,
+}
+
+impl<'a, W: Write> Vm<'a, W> {
+    pub fn new(chunk: &'a Chunk, out: W) -> Self {
+        Vm {
+            chunk,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            open_upvalues: Vec::new(),
+            out,
+            random: Random::default(),
+        }
+    }
+
+    /// Seeds the numbers `rand()` returns.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.random = Random::new(seed);
+        self
+    }
+
+    /// Runs the entry function of the chunk to completion.
+    pub fn run(&mut self) -> Result<(), VmError> {
+        self.stack.clear();
+        self.frames.clear();
+        self.open_upvalues.clear();
+        self.frames.push(Frame {
+            function: self.chunk.entry,
+            ip: 0,
+            base: 0,
+            closure: None,
+        });
+        while !self.frames.is_empty() {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    fn error(&self, message: impl Into<String>) -> VmError {
+        let function = self
+            .frames
+            .last()
+            .map(|frame| self.chunk.functions[frame.function as usize].name.clone())
+            .unwrap_or_default();
+        VmError {
+            message: message.into(),
+            function,
+        }
+    }
+
+    fn frame(&self) -> &Frame {
+        self.frames.last().unwrap()
+    }
+
+    fn pop(&mut self) -> Value {
+        let value = self.stack.pop().unwrap();
+        self.close_upvalues(self.stack.len());
+        value
+    }
+
+    fn peek(&self) -> &Value {
+        self.stack.last().unwrap()
+    }
+
+    /// Moves the values of the captured locals at `from` or above into their upvalues.
+    fn close_upvalues(&mut self, from: usize) {
+        while let Some(upvalue) = self.open_upvalues.last() {
+            let slot = match *upvalue.borrow() {
+                Upvalue::Open(slot) => slot,
+                Upvalue::Closed(_) => unreachable!("closed upvalue left open"),
+            };
+            if slot < from {
+                break;
+            }
+            *upvalue.borrow_mut() = Upvalue::Closed(self.stack[slot].clone());
+            self.open_upvalues.pop();
+        }
+    }
+
+    /// Drops every value from `len` up, closing the upvalues that capture them.
+    fn truncate(&mut self, len: usize) {
+        self.close_upvalues(len);
+        self.stack.truncate(len);
+    }
+
+    fn capture(&mut self, slot: usize) -> Rc<RefCell<Upvalue>> {
+        let position = self
+            .open_upvalues
+            .iter()
+            .position(|upvalue| matches!(*upvalue.borrow(), Upvalue::Open(open) if open >= slot));
+        if let Some(position) = position {
+            let upvalue = &self.open_upvalues[position];
+            if matches!(*upvalue.borrow(), Upvalue::Open(open) if open == slot) {
+                return upvalue.clone();
+            }
+        }
+        let upvalue = Rc::new(RefCell::new(Upvalue::Open(slot)));
+        let position = position.unwrap_or(self.open_upvalues.len());
+        self.open_upvalues.insert(position, upvalue.clone());
+        upvalue
+    }
+
+    fn upvalue(&self, index: u16) -> Rc<RefCell<Upvalue>> {
+        self.frame().closure.as_ref().unwrap().upvalues[index as usize].clone()
+    }
+
+    fn number(&self, value: &Value) -> Result<f64, VmError> {
+        match value {
+            Value::Number(value) => Ok(*value),
+            other => Err(self.error(format!("expected a number, found {}", self.text(other)))),
+        }
+    }
+
+    fn boolean(&self, value: &Value) -> Result<bool, VmError> {
+        match value {
+            Value::Boolean(value) => Ok(*value),
+            other => Err(self.error(format!("expected a boolean, found {}", self.text(other)))),
+        }
+    }
+
+    fn object(&self, value: &Value) -> Result<Rc<RefCell<Object>>, VmError> {
+        match value {
+            Value::Object(object) => Ok(object.clone()),
+            other => Err(self.error(format!("expected an object, found {}", self.text(other)))),
+        }
+    }
+
+    /// Returns the text `print` and `@` use for `value`.
+    fn text(&self, value: &Value) -> String {
+        match value {
+            Value::Null => "null".to_string(),
+            Value::Number(value) => format_number(*value),
+            Value::Boolean(value) => value.to_string(),
+            Value::Str(value) => value.to_string(),
+            Value::Object(object) => {
+                let class = &self.chunk.classes[object.borrow().class as usize];
+                format!("<{}>", class.name)
+            }
+            Value::Closure(closure) => {
+                format!(
+                    "<function {}>",
+                    self.chunk.functions[closure.function as usize].name
+                )
+            }
+        }
+    }
+
+    fn binary_numbers(&mut self) -> Result<(f64, f64), VmError> {
+        let right = self.pop();
+        let left = self.pop();
+        Ok((self.number(&left)?, self.number(&right)?))
+    }
+
+    fn call(
+        &mut self,
+        function: u32,
+        argc: u8,
+        closure: Option<Rc<Closure>>,
+    ) -> Result<(), VmError> {
+        if self.frames.len() >= MAX_FRAMES {
+            return Err(self.error("stack overflow"));
+        }
+        let arity = self.chunk.functions[function as usize].arity;
+        if arity != argc {
+            return Err(self.error(format!(
+                "`{}` expects {} arguments, got {}",
+                self.chunk.functions[function as usize].name, arity, argc
+            )));
+        }
+        self.frames.push(Frame {
+            function,
+            ip: 0,
+            base: self.stack.len() - argc as usize,
+            closure,
+        });
+        Ok(())
+    }
+
+    /// Calls `function` in place of the current frame, whose locals go, so chains of
+    /// tail calls run in constant space.
+    fn tail_call(&mut self, function: u32, argc: u8) -> Result<(), VmError> {
+        let base = self.frame().base;
+        let args = self.stack.split_off(self.stack.len() - argc as usize);
+        self.truncate(base);
+        self.stack.extend(args);
+        self.call(function, argc, None)?;
+        let callee = self.frames.pop().unwrap();
+        *self.frames.last_mut().unwrap() = callee;
+        Ok(())
+    }
+
+    /// Returns the stack slot of a local, which a damaged chunk may point past.
+    fn local(&mut self, base: usize, slot: u16) -> Result<&mut Value, VmError> {
+        if base + (slot as usize) < self.stack.len() {
+            Ok(&mut self.stack[base + slot as usize])
+        } else {
+            Err(self.error(format!("local slot {} is out of the frame", slot)))
+        }
+    }
+
+    /// Returns field `slot` of `object`, which a chunk may name for an object of the
+    /// wrong class.
+    fn field<'o>(&self, object: &'o mut Object, slot: u16) -> Result<&'o mut Value, VmError> {
+        let class = &self.chunk.classes[object.class as usize];
+        object
+            .fields
+            .get_mut(slot as usize)
+            .ok_or_else(|| self.error(format!("`{}` has no field slot {}", class.name, slot)))
+    }
+
+    /// Returns the method in vtable `slot` of the receiver below the `argc` arguments.
+    fn virtual_method(&self, slot: u16, argc: u8) -> Result<u32, VmError> {
+        let receiver = &self.stack[self.stack.len() - 1 - argc as usize];
+        let class = &self.chunk.classes[self.object(receiver)?.borrow().class as usize];
+        match class.vtable.get(slot as usize) {
+            Some((_, function)) => Ok(*function),
+            None => Err(self.error(format!("`{}` has no method slot {}", class.name, slot))),
+        }
+    }
+
+    fn step(&mut self) -> Result<(), VmError> {
+        let frame = self.frames.last_mut().unwrap();
+        let op = self.chunk.functions[frame.function as usize].code[frame.ip];
+        frame.ip += 1;
+        let base = frame.base;
+
+        match op {
+            Op::Constant(index) => {
+                let value = match &self.chunk.constants[index as usize] {
+                    Constant::Number(value) => Value::Number(*value),
+                    Constant::Str(value) => Value::Str(Rc::from(value.as_str())),
+                };
+                self.stack.push(value);
+            }
+            Op::Null => self.stack.push(Value::Null),
+            Op::True => self.stack.push(Value::Boolean(true)),
+            Op::False => self.stack.push(Value::Boolean(false)),
+            Op::Pop => {
+                self.pop();
+            }
+            Op::PopBelow(n) => {
+                let top = self.stack.pop().unwrap();
+                let len = self.stack.len() - n as usize;
+                self.truncate(len);
+                self.stack.push(top);
+            }
+            Op::GetLocal(slot) => {
+                let value = self.local(base, slot)?.clone();
+                self.stack.push(value);
+            }
+            Op::SetLocal(slot) => {
+                let value = self.peek().clone();
+                *self.local(base, slot)? = value;
+            }
+            Op::GetUpvalue(index) => {
+                let value = match &*self.upvalue(index).borrow() {
+                    Upvalue::Open(slot) => self.stack[*slot].clone(),
+                    Upvalue::Closed(value) => value.clone(),
+                };
+                self.stack.push(value);
+            }
+            Op::SetUpvalue(index) => {
+                let value = self.peek().clone();
+                let upvalue = self.upvalue(index);
+                let mut upvalue = upvalue.borrow_mut();
+                match &mut *upvalue {
+                    Upvalue::Open(slot) => self.stack[*slot] = value,
+                    Upvalue::Closed(closed) => *closed = value,
+                }
+            }
+            Op::Closure(function) => {
+                let descriptors = &self.chunk.functions[function as usize].upvalues;
+                let mut upvalues = Vec::with_capacity(descriptors.len());
+                for descriptor in descriptors.iter() {
+                    upvalues.push(if descriptor.is_local {
+                        self.capture(base + descriptor.index as usize)
+                    } else {
+                        self.upvalue(descriptor.index)
+                    });
+                }
+                self.stack
+                    .push(Value::Closure(Rc::new(Closure { function, upvalues })));
+            }
+            Op::Add => {
+                let (left, right) = self.binary_numbers()?;
+                self.stack.push(Value::Number(left + right));
+            }
+            Op::Sub => {
+                let (left, right) = self.binary_numbers()?;
+                self.stack.push(Value::Number(left - right));
+            }
+            Op::Mul => {
+                let (left, right) = self.binary_numbers()?;
+                self.stack.push(Value::Number(left * right));
+            }
+            Op::Div => {
+                let (left, right) = self.binary_numbers()?;
+                self.stack.push(Value::Number(left / right));
+            }
+            Op::Mod => {
+                let (left, right) = self.binary_numbers()?;
+                self.stack.push(Value::Number(left % right));
+            }
+            Op::Pow => {
+                let (left, right) = self.binary_numbers()?;
+                self.stack.push(Value::Number(left.powf(right)));
+            }
+            Op::Lt => {
+                let (left, right) = self.binary_numbers()?;
+                self.stack.push(Value::Boolean(left < right));
+            }
+            Op::Le => {
+                let (left, right) = self.binary_numbers()?;
+                self.stack.push(Value::Boolean(left <= right));
+            }
+            Op::Gt => {
+                let (left, right) = self.binary_numbers()?;
+                self.stack.push(Value::Boolean(left > right));
+            }
+            Op::Ge => {
+                let (left, right) = self.binary_numbers()?;
+                self.stack.push(Value::Boolean(left >= right));
+            }
+            Op::Eq | Op::Ne => {
+                let right = self.pop();
+                let left = self.pop();
+                let equal = values_equal(&left, &right);
+                self.stack.push(Value::Boolean(equal == (op == Op::Eq)));
+            }
+            Op::And | Op::Or => {
+                let right = self.pop();
+                let left = self.pop();
+                let (left, right) = (self.boolean(&left)?, self.boolean(&right)?);
+                let result = if op == Op::And {
+                    left && right
+                } else {
+                    left || right
+                };
+                self.stack.push(Value::Boolean(result));
+            }
+            Op::Concat => {
+                let right = self.pop();
+                let left = self.pop();
+                let text = self.text(&left) + &self.text(&right);
+                self.stack.push(Value::Str(Rc::from(text)));
+            }
+            Op::Neg => {
+                let value = self.pop();
+                let value = self.number(&value)?;
+                self.stack.push(Value::Number(-value));
+            }
+            Op::Not => {
+                let value = self.pop();
+                let value = self.boolean(&value)?;
+                self.stack.push(Value::Boolean(!value));
+            }
+            Op::Jump(target) => self.frames.last_mut().unwrap().ip = target as usize,
+            Op::JumpIfFalse(target) => {
+                let condition = self.pop();
+                if !self.boolean(&condition)? {
+                    self.frames.last_mut().unwrap().ip = target as usize;
+                }
+            }
+            Op::Call(function, argc) => self.call(function, argc, None)?,
+            Op::CallBuiltin(builtin, argc) => {
+                let first = self.stack.len() - argc as usize;
+                let args = self.stack.split_off(first);
+                let numbers = args
+                    .iter()
+                    .map(|value| self.number(value))
+                    .collect::<Result<Vec<f64>, VmError>>()?;
+                let result = builtins::FUNCTIONS[builtin as usize].call(&numbers, &mut self.random);
+                self.stack.push(Value::Number(result));
+            }
+            Op::CallClosure(argc) => {
+                let callee = self.stack[self.stack.len() - 1 - argc as usize].clone();
+                let closure = match callee {
+                    Value::Closure(closure) => closure,
+                    other => {
+                        return Err(self.error(format!("{} is not a function", self.text(&other))))
+                    }
+                };
+                // The closure is kept by the frame, so its slot below the arguments goes.
+                let callee_slot = self.stack.len() - 1 - argc as usize;
+                self.stack.remove(callee_slot);
+                self.call(closure.function, argc, Some(closure))?;
+            }
+            Op::TailCallSelf(argc) => {
+                let args = self.stack.split_off(self.stack.len() - argc as usize);
+                self.truncate(base);
+                self.stack.extend(args);
+                self.frames.last_mut().unwrap().ip = 0;
+            }
+            Op::InvokeVirtual(slot, argc) => {
+                let function = self.virtual_method(slot, argc)?;
+                self.call(function, argc + 1, None)?;
+            }
+            Op::TailCall(function, argc) => self.tail_call(function, argc)?,
+            Op::TailInvokeVirtual(slot, argc) => {
+                let function = self.virtual_method(slot, argc)?;
+                self.tail_call(function, argc + 1)?;
+            }
+            Op::Return => {
+                let result = self.stack.pop().unwrap();
+                self.truncate(base);
+                self.frames.pop();
+                self.stack.push(result);
+            }
+            Op::New(class) => {
+                let fields = vec![Value::Null; self.chunk.classes[class as usize].fields.len()];
+                self.stack.push(Value::Object(Rc::new(RefCell::new(Object {
+                    class,
+                    fields,
+                }))));
+            }
+            Op::GetField(slot) => {
+                let object = self.pop();
+                let object = self.object(&object)?;
+                let value = self.field(&mut object.borrow_mut(), slot)?.clone();
+                self.stack.push(value);
+            }
+            Op::SetField(slot) => {
+                let value = self.pop();
+                let object = self.pop();
+                let object = self.object(&object)?;
+                *self.field(&mut object.borrow_mut(), slot)? = value.clone();
+                self.stack.push(value);
+            }
+            Op::Print => {
+                let text = self.text(self.peek());
+                writeln!(self.out, "{}", text).map_err(|error| self.error(error.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares two values the way `==` does, by the rules the interpreter's
+/// [`Value`](crate::interpreter::value::Value) documents on its `PartialEq`. Closures,
+/// which only the VM has, compare by identity.
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Null, Value::Null) => true,
+        (Value::Number(left), Value::Number(right)) => left == right,
+        (Value::Boolean(left), Value::Boolean(right)) => left == right,
+        (Value::Str(left), Value::Str(right)) => left == right,
+        (Value::Object(left), Value::Object(right)) => Rc::ptr_eq(left, right),
+        (Value::Closure(left), Value::Closure(right)) => Rc::ptr_eq(left, right),
+        _ => false,
+    }
+}
@@ -0,0 +1,1239 @@
+//! `CGenerator` implements the [`Visitor`] trait to translate a type-checked program into
+//! a single C file that any `cc` understands:
+//!
+//! ```text
+//! cc -O2 -fno-strict-aliasing program.c libhulk_runtime.a -o program -lm
+//! ```
+//!
+//! Like the x86-64 and LLVM backends, the file is linked with the runtime library;
+//! `runtime.h`, copied into every file, declares its C ABI. Values are mapped to
+//! `double`, `bool`, `char *` and, for objects, `void *`. Every type becomes a struct
+//! whose first member points to the vtable of the type, a struct that starts like the
+//! runtime's type descriptors and extends the vtable of the parent type with function
+//! pointers. Inherited attributes are read through the struct of whichever type the
+//! code knows, so the file must be compiled without strict aliasing.
+//!
+//! Strings and objects are managed by the garbage collector of the runtime, through the
+//! shadow-stack frames and safepoints its `gc` module describes. Every variable or
+//! temporary holding them is declared at the start of its function, whose frame lists
+//! their addresses, and whenever an operand may run code, the others are bound to such
+//! temporaries first. Functions call `hulk_gc_safepoint` on entry and loops on every
+//! iteration, where nothing else is live, so only calls and loops collect, and any
+//! program that allocates without bound passes through a safepoint.
+//!
+//! Every HULK expression becomes a C expression. `let`, code blocks and loops use
+//! statement expressions (`({ ... })`, supported by GCC, Clang and TCC), and operands
+//! are bound to temporaries when needed to keep the left-to-right evaluation order.
+//! `#line` directives point every function and every expression of a block back to
+//! the `.hulk` source, so C compiler and debugger messages refer to it.
+//!
+//! Recursive tail calls jump back to the start of the function. Other calls in tail
+//! position jump to a `return` of the call after the body, which `-O2` turns into a
+//! jump.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::BlockNode;
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::{FunctionCallNode, TailCallKind};
+use crate::ast_nodes::function_def::{FunctionDefNode, FunctionParams};
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::LetInNode;
+use crate::ast_nodes::literals::{
+    BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode,
+};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::{Program, Statement};
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+use crate::builtins;
+use crate::random::DEFAULT_SEED;
+use crate::tokens::{OperatorToken, Span};
+use crate::types_tree::tree_node::FunctionSignature;
+use crate::types_tree::type_tree::{TypeTree, BOOLEAN, NUMBER, OBJECT, STRING};
+use crate::visitor::accept::Accept;
+use crate::visitor::visitor_trait::Visitor;
+
+/// Declarations of the runtime functions and builtin vtables copied into every
+/// generated file.
+pub const RUNTIME: &str = include_str!("runtime.h");
+
+/// Label at the start of every global function, where recursive tail calls jump.
+const BODY_LABEL: &str = "hulk_body";
+
+/// The shadow-stack frame of every function that holds strings or objects.
+const FRAME: &str = "hulk_frame";
+
+/// Stands before the jump to every tail call until the function is complete, when it
+/// becomes the statement unlinking the frame, if the function has one.
+const UNLINK_FRAME: &str = "/* unlink the frame */ ";
+
+/// Returns the C type of values of the HULK type `type_name`.
+fn c_type(type_name: &str) -> &'static str {
+    match type_name {
+        NUMBER => "double",
+        BOOLEAN => "bool",
+        STRING => "char *",
+        _ => "void *",
+    }
+}
+
+/// Returns the C declaration of `name` with the type of values of `type_name`.
+fn declaration(type_name: &str, name: &str) -> String {
+    let ty = c_type(type_name);
+    if ty.ends_with('*') {
+        format!("{}{}", ty, name)
+    } else {
+        format!("{} {}", ty, name)
+    }
+}
+
+/// Whether values of `type_name` are strings or objects, which the collector manages.
+fn is_pointer(type_name: &str) -> bool {
+    c_type(type_name).ends_with('*')
+}
+
+/// Returns the C expression of the default value of `type_name`.
+fn default_value(type_name: &str) -> &'static str {
+    match type_name {
+        NUMBER => "0.0",
+        BOOLEAN => "false",
+        STRING => "\"\"",
+        _ => "NULL",
+    }
+}
+
+fn function_symbol(name: &str) -> String {
+    format!("hulk_fn_{}", name)
+}
+
+fn method_symbol(type_name: &str, method: &str) -> String {
+    format!("hulk_method_{}__{}", type_name, method)
+}
+
+fn constructor_symbol(type_name: &str) -> String {
+    format!("hulk_new_{}", type_name)
+}
+
+fn init_symbol(type_name: &str) -> String {
+    format!("hulk_init_{}", type_name)
+}
+
+fn struct_name(type_name: &str) -> String {
+    format!("struct hulk_type_{}", type_name)
+}
+
+fn vtable_struct_name(type_name: &str) -> String {
+    format!("struct hulk_vtable_{}", type_name)
+}
+
+fn vtable_symbol(type_name: &str) -> String {
+    format!("hulk_vtable_{}", type_name)
+}
+
+fn pointers_symbol(type_name: &str) -> String {
+    format!("hulk_pointers_{}", type_name)
+}
+
+/// Writes `value` as a C string literal.
+fn string_literal(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for byte in value.bytes() {
+        match byte {
+            b'"' => literal.push_str("\\\""),
+            b'\\' => literal.push_str("\\\\"),
+            // Avoids trigraphs.
+            b'?' => literal.push_str("\\?"),
+            b' '..=b'~' => literal.push(byte as char),
+            _ => literal.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Indents every line of `code` one level.
+fn indent(code: &str) -> String {
+    code.lines()
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns a statement expression running `statements` and producing `value`.
+fn statement_expression(statements: &[String], value: &str) -> String {
+    let mut code = String::from("({\n");
+    for statement in statements {
+        code.push_str(&indent(statement));
+        code.push('\n');
+    }
+    code.push_str(&indent(&format!("{};", value)));
+    code.push_str("\n})");
+    code
+}
+
+/// Returns the static type the semantic phase gave `expr`.
+fn type_of(expr: &Expression) -> String {
+    expr.node_type()
+        .map(|node| node.type_name.clone())
+        .unwrap_or_else(|| OBJECT.to_string())
+}
+
+/// Returns whether `expr` can be evaluated in any order without changing the result.
+fn is_simple(expr: &Expression) -> bool {
+    matches!(
+        expr,
+        Expression::Number(_)
+            | Expression::Boolean(_)
+            | Expression::Str(_)
+            | Expression::Identifier(_)
+    )
+}
+
+/// A slot of a vtable: the method, the type whose implementation fills it and the
+/// signature shared by every implementation.
+struct VtableSlot {
+    method: String,
+    implementation: String,
+    signature: FunctionSignature,
+}
+
+/// A visitor that translates a type-checked program into a C source file.
+pub struct CGenerator<'a> {
+    type_tree: &'a TypeTree,
+    file_name: String,
+    /// Byte offset where every line of the source starts.
+    line_starts: Vec<usize>,
+    type_defs: HashMap<String, TypeDefNode>,
+    /// Signatures of the global functions.
+    functions: HashMap<String, FunctionSignature>,
+    prototypes: String,
+    definitions: String,
+    /// Name of the global function being translated, if any.
+    current_function: Option<String>,
+    /// Return type of the function or method being translated, `None` in constructors
+    /// and `main`.
+    return_type: Option<String>,
+    /// C names of the variables in scope, innermost scope last.
+    scopes: Vec<HashMap<String, String>>,
+    /// C names of the parameters of the function being translated.
+    params: Vec<String>,
+    /// Whether the function being translated jumps back to its start.
+    jumps_to_body: bool,
+    /// Declarations of the locals of the function being translated that hold strings
+    /// or objects, hoisted to its start.
+    hoisted: Vec<String>,
+    /// C names of the variables of the function being translated that its frame lists.
+    roots: Vec<String>,
+    /// Declarations of the arguments of the tail calls of the function being translated.
+    tail_args: Vec<String>,
+    /// The tail calls of the function being translated, each after its label.
+    tail_calls: Vec<String>,
+    next_name: usize,
+    /// Seed of the numbers `rand()` returns.
+    seed: u64,
+}
+
+impl<'a> CGenerator<'a> {
+    /// Creates a generator for a program read from `file_name`.
+    ///
+    /// # Arguments
+    /// * `type_tree` - The type tree built by the semantic phase.
+    /// * `file_name` - The path written in `#line` directives.
+    /// * `source` - The source text, used to turn spans into line numbers.
+    pub fn new(type_tree: &'a TypeTree, file_name: &str, source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        CGenerator {
+            type_tree,
+            file_name: file_name.to_string(),
+            line_starts,
+            type_defs: HashMap::new(),
+            functions: HashMap::new(),
+            prototypes: String::new(),
+            definitions: String::new(),
+            current_function: None,
+            return_type: None,
+            scopes: Vec::new(),
+            params: Vec::new(),
+            jumps_to_body: false,
+            hoisted: Vec::new(),
+            roots: Vec::new(),
+            tail_args: Vec::new(),
+            tail_calls: Vec::new(),
+            next_name: 0,
+            seed: DEFAULT_SEED,
+        }
+    }
+
+    /// Seeds the numbers `rand()` returns in the generated program.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Translates a program that passed the semantic phase.
+    ///
+    /// # Arguments
+    /// * `node` - The root `Program` node, annotated with static types.
+    ///
+    /// # Returns
+    /// The text of a C file with the runtime declarations, every type and function of the program
+    /// and a `main` that runs the top-level expressions.
+    pub fn generate_program(mut self, node: &mut Program) -> String {
+        for statement in node.statements.iter() {
+            match statement {
+                Statement::StatementTypeDef(def) => {
+                    self.type_defs
+                        .insert(def.identifier.clone(), def.as_ref().clone());
+                }
+                Statement::StatementFunctionDef(def) => {
+                    let signature = FunctionSignature::new(
+                        def.name.clone(),
+                        def.params.clone(),
+                        def.return_type.clone(),
+                    );
+                    self.functions.insert(def.name.clone(), signature);
+                }
+                Statement::StatementExpression(_) => {}
+            }
+        }
+
+        let mut types: Vec<String> = self.type_defs.keys().cloned().collect();
+        types.sort_by_key(|name| {
+            let depth = self.type_tree.get_type(name).map_or(0, |node| node.depth);
+            (depth, name.clone())
+        });
+
+        let mut out = String::new();
+        writeln!(
+            out,
+            "/* Generated by the HULK compiler from {}. */",
+            self.file_name
+        )
+        .unwrap();
+        writeln!(out, "#include <math.h>").unwrap();
+        out.push_str(RUNTIME);
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "struct hulk_object {{\n    const struct hulk_vtable *vtable;\n}};"
+        )
+        .unwrap();
+        for type_name in types.iter() {
+            out.push_str(&self.type_structs(type_name));
+        }
+
+        for statement in node.statements.iter_mut() {
+            match statement {
+                Statement::StatementFunctionDef(def) => {
+                    self.visit_function_def(def);
+                }
+                Statement::StatementTypeDef(def) => {
+                    self.visit_type_def(def);
+                }
+                Statement::StatementExpression(_) => {}
+            }
+        }
+
+        self.begin_function(None, None);
+        let mut statements = vec![format!("hulk_rand_seed({}ULL);", self.seed)];
+        for statement in node.statements.iter_mut() {
+            if let Statement::StatementExpression(expr) = statement {
+                let line = self.line_directive(expr.span());
+                let code = expr.accept(&mut self);
+                statements.push(format!("{}\n{};", line, code));
+            }
+        }
+        statements.push("hulk_gc_finish();".to_string());
+        let body = self.function_body(&statements, Some((NUMBER, "0".to_string())));
+        let main = format!("int main(void) {{\n{}\n}}\n", indent(&body));
+
+        writeln!(out).unwrap();
+        out.push_str(&self.prototypes);
+        writeln!(out).unwrap();
+        for type_name in types.iter() {
+            out.push_str(&self.vtable(type_name));
+        }
+        out.push_str(&self.definitions);
+        out.push_str(&main);
+        out
+    }
+
+    /// Returns a `#line` directive pointing at the start of `span`.
+    fn line_directive(&self, span: Span) -> String {
+        let line = match self.line_starts.binary_search(&span.start) {
+            Ok(index) => index + 1,
+            Err(index) => index,
+        };
+        format!("#line {} {}", line, string_literal(&self.file_name))
+    }
+
+    /// Returns a fresh C identifier derived from `name`.
+    fn fresh(&mut self, name: &str) -> String {
+        self.next_name += 1;
+        format!("{}_{}", name, self.next_name)
+    }
+
+    fn declare(&mut self, name: &str) -> String {
+        let c_name = self.fresh(name);
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), c_name.clone());
+        c_name
+    }
+
+    fn lookup(&self, name: &str) -> Option<&String> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn begin_function(&mut self, global: Option<String>, return_type: Option<String>) {
+        self.current_function = global;
+        self.return_type = return_type;
+        self.scopes = vec![HashMap::new()];
+        self.params.clear();
+        self.jumps_to_body = false;
+        self.hoisted.clear();
+        self.roots.clear();
+        self.tail_args.clear();
+        self.tail_calls.clear();
+    }
+
+    /// Returns the statement that stores `value` in a new local `name` of `type_name`.
+    /// A local holding strings or objects is declared at the start of the function
+    /// instead, so its frame can list it.
+    fn local(&mut self, type_name: &str, name: &str, value: &str) -> String {
+        if !is_pointer(type_name) {
+            return format!("{} = {};", declaration(type_name, name), value);
+        }
+        self.hoisted
+            .push(format!("{} = NULL;", declaration(type_name, name)));
+        self.roots.push(name.to_string());
+        format!("{} = {};", name, value)
+    }
+
+    /// Returns the body of the function being translated: its hoisted locals, the
+    /// frame linking them into the shadow stack, a safepoint and `statements`, with
+    /// the frame unlinked before the function returns `value`, of type `value_type`.
+    ///
+    /// The frame lives in a block the tail calls jump out of, as `cc` only turns a call
+    /// into a jump when no local whose address is taken is in scope.
+    fn function_body(&self, statements: &[String], value: Option<(&str, String)>) -> String {
+        let framed = !self.roots.is_empty();
+        let unlink = if framed {
+            format!("hulk_gc_top = {}.prev;", FRAME)
+        } else {
+            String::new()
+        };
+        let mut body = self.hoisted.clone();
+        if framed {
+            let roots: Vec<String> = self.roots.iter().map(|root| format!("&{}", root)).collect();
+            body.push(format!(
+                "struct {{ void *prev; long long count; void *roots[{}]; }} {} = {{\n{}\n}};",
+                roots.len(),
+                FRAME,
+                indent(&format!(
+                    "hulk_gc_top, {}, {{ {} }}",
+                    roots.len(),
+                    roots.join(", ")
+                ))
+            ));
+            body.push(format!("hulk_gc_top = &{};", FRAME));
+        }
+        if self.jumps_to_body {
+            body.push(format!("{}:", BODY_LABEL));
+        }
+        body.push("hulk_gc_safepoint();".to_string());
+        body.extend(statements.iter().cloned());
+        match value {
+            Some((value_type, value)) if framed => {
+                let result = declaration(value_type, "hulk_result");
+                body.push(format!("{} = {};", result, value));
+                body.push(unlink.clone());
+                body.push("return hulk_result;".to_string());
+            }
+            Some((_, value)) => body.push(format!("return {};", value)),
+            None => body.push(unlink.clone()),
+        }
+        body.retain(|statement| !statement.is_empty());
+        let unlink = if framed {
+            format!("{} ", unlink)
+        } else {
+            unlink
+        };
+        let body = body.join("\n").replace(UNLINK_FRAME, &unlink);
+        let mut function = self.tail_args.clone();
+        if framed {
+            function.push(format!("{{\n{}\n}}", indent(&body)));
+        } else {
+            function.push(body);
+        }
+        function.extend(self.tail_calls.iter().cloned());
+        function.join("\n")
+    }
+
+    /// Returns the declaration of the parameter `name` of `type_name`. A string or
+    /// object arrives in another parameter and is copied to a local the frame lists,
+    /// since `cc` never turns calls into jumps in functions that take the address of a
+    /// parameter.
+    fn param(&mut self, type_name: &str, name: &str) -> String {
+        if !is_pointer(type_name) {
+            return declaration(type_name, name);
+        }
+        let incoming = format!("{}_in", name);
+        self.hoisted.push(format!(
+            "{} = {};",
+            declaration(type_name, name),
+            incoming
+        ));
+        self.roots.push(name.to_string());
+        declaration(type_name, &incoming)
+    }
+
+    /// Declares the parameters of a function and returns its C parameter list.
+    fn declare_params(&mut self, self_param: bool, params: &[FunctionParams]) -> String {
+        let mut declarations = Vec::new();
+        if self_param {
+            let name = self.declare("self");
+            declarations.push(self.param(OBJECT, &name));
+        }
+        for param in params.iter() {
+            let name = self.declare(&param.name);
+            declarations.push(self.param(&param.signature, &name));
+            self.params.push(name);
+        }
+        if declarations.is_empty() {
+            "void".to_string()
+        } else {
+            declarations.join(", ")
+        }
+    }
+
+    /// Returns the attributes of `type_name`, inherited ones first.
+    fn fields(&self, type_name: &str) -> Vec<(String, String)> {
+        let mut ancestors = self.type_tree.ancestors(type_name);
+        ancestors.reverse();
+        ancestors
+            .iter()
+            .filter_map(|ancestor| self.type_tree.get_type(ancestor))
+            .flat_map(|node| node.attributes.iter().cloned())
+            .collect()
+    }
+
+    /// Returns the vtable slots of `type_name`, inherited ones first.
+    fn vtable_slots(&self, type_name: &str) -> Vec<VtableSlot> {
+        let mut ancestors = self.type_tree.ancestors(type_name);
+        ancestors.reverse();
+        let mut slots: Vec<VtableSlot> = Vec::new();
+        for ancestor in ancestors.iter() {
+            let node = match self.type_tree.get_type(ancestor) {
+                Some(node) => node,
+                None => continue,
+            };
+            for method in node.methods.iter() {
+                match slots.iter_mut().find(|slot| slot.method == method.name) {
+                    Some(slot) => slot.implementation = ancestor.clone(),
+                    None => slots.push(VtableSlot {
+                        method: method.name.clone(),
+                        implementation: ancestor.clone(),
+                        signature: method.clone(),
+                    }),
+                }
+            }
+        }
+        slots
+    }
+
+    /// Returns the type of a pointer to a method with `signature`, around `name`.
+    fn method_pointer(signature: &FunctionSignature, name: &str) -> String {
+        let mut params = vec!["void *".to_string()];
+        params.extend(
+            signature
+                .param_types()
+                .iter()
+                .map(|param| c_type(param).to_string()),
+        );
+        let pointer = format!("(*{})({})", name, params.join(", "));
+        declaration(&signature.return_type, &pointer)
+    }
+
+    /// Returns the object and vtable structs of `type_name`.
+    fn type_structs(&self, type_name: &str) -> String {
+        let mut out = String::new();
+        writeln!(out, "{} {{", struct_name(type_name)).unwrap();
+        writeln!(out, "    const struct hulk_vtable *vtable;").unwrap();
+        for (field, field_type) in self.fields(type_name) {
+            writeln!(
+                out,
+                "    {};",
+                declaration(&field_type, &format!("attr_{}", field))
+            )
+            .unwrap();
+        }
+        writeln!(out, "}};").unwrap();
+        writeln!(out, "{} {{", vtable_struct_name(type_name)).unwrap();
+        writeln!(out, "    const char *name;").unwrap();
+        writeln!(out, "    const struct hulk_vtable *parent;").unwrap();
+        writeln!(out, "    const long long *pointers;").unwrap();
+        for slot in self.vtable_slots(type_name) {
+            let member = format!("m_{}", slot.method);
+            writeln!(
+                out,
+                "    {};",
+                Self::method_pointer(&slot.signature, &member)
+            )
+            .unwrap();
+        }
+        writeln!(out, "}};").unwrap();
+        out
+    }
+
+    /// Returns the definitions of the pointer map and the vtable of `type_name`. The
+    /// pointer map lists the offsets of the attributes holding strings or objects.
+    fn vtable(&self, type_name: &str) -> String {
+        let offsets: Vec<String> = self
+            .fields(type_name)
+            .into_iter()
+            .filter(|(_, field_type)| !matches!(field_type.as_str(), NUMBER | BOOLEAN))
+            .map(|(field, _)| format!("offsetof({}, attr_{})", struct_name(type_name), field))
+            .collect();
+        let pointers = std::iter::once(offsets.len().to_string())
+            .chain(offsets)
+            .collect::<Vec<_>>();
+        let parent = match self
+            .type_tree
+            .get_type(type_name)
+            .and_then(|node| node.parent.clone())
+        {
+            Some(parent) if parent != OBJECT => {
+                format!("(const struct hulk_vtable *)&{}", vtable_symbol(&parent))
+            }
+            _ => "&hulk_object_vtable".to_string(),
+        };
+        let mut entries = vec![string_literal(type_name), parent, pointers_symbol(type_name)];
+        for slot in self.vtable_slots(type_name) {
+            let pointer = Self::method_pointer(&slot.signature, "");
+            entries.push(format!(
+                "({}){}",
+                pointer,
+                method_symbol(&slot.implementation, &slot.method)
+            ));
+        }
+        format!(
+            "static const long long {}[] = {{{}}};\nstatic const {} {} = {{\n{}\n}};\n",
+            pointers_symbol(type_name),
+            pointers.join(", "),
+            vtable_struct_name(type_name),
+            vtable_symbol(type_name),
+            indent(&entries.join(",\n"))
+        )
+    }
+
+    /// Adds a function with the given header and body to the file.
+    fn define(&mut self, span: Span, header: String, body: String) {
+        self.prototypes.push_str(&format!("static {};\n", header));
+        let line = self.line_directive(span);
+        self.definitions.push_str(&format!(
+            "{}\nstatic {} {{\n{}\n}}\n\n",
+            line,
+            header,
+            indent(&body)
+        ));
+    }
+
+    /// Boxes `code`, of type `from`, when a plain value is used where an object is expected.
+    fn coerce(&self, code: String, from: &str, to: &str) -> String {
+        if TypeTree::is_primitive(to) || !TypeTree::is_primitive(from) {
+            return code;
+        }
+        let boxer = match from {
+            NUMBER => "hulk_box_number",
+            BOOLEAN => "hulk_box_bool",
+            _ => "hulk_box_string",
+        };
+        format!("{}({})", boxer, code)
+    }
+
+    /// Converts `code`, of type `from`, to a string.
+    fn to_string(code: String, from: &str) -> String {
+        match from {
+            NUMBER => format!("hulk_number_to_string({})", code),
+            BOOLEAN => format!("hulk_bool_to_string({})", code),
+            STRING => code,
+            _ => format!("hulk_object_to_string({})", code),
+        }
+    }
+
+    /// Translates `exprs` so they run left to right whatever the C evaluation order,
+    /// and so no value of one is out of the frame while another may collect.
+    ///
+    /// # Returns
+    /// The declarations of the temporaries needed, and a C expression for the value of
+    /// every expression.
+    fn sequence(&mut self, exprs: Vec<&mut Expression>) -> (Vec<String>, Vec<String>) {
+        let ordered = exprs.len() > 1 && exprs.iter().any(|expr| !is_simple(expr));
+        let mut declarations = Vec::new();
+        let mut values = Vec::new();
+        for expr in exprs {
+            let code = expr.accept(self);
+            if ordered && !is_simple(expr) {
+                let temp = self.fresh("t");
+                declarations.push(self.local(&type_of(expr), &temp, &code));
+                values.push(temp);
+            } else {
+                values.push(code);
+            }
+        }
+        (declarations, values)
+    }
+
+    /// Translates call arguments, converting them to the types of the parameters.
+    fn arguments(
+        &mut self,
+        args: &mut [Expression],
+        param_types: &[String],
+    ) -> (Vec<String>, Vec<String>) {
+        let (declarations, values) = self.sequence(args.iter_mut().collect());
+        let values = values
+            .into_iter()
+            .zip(args.iter())
+            .zip(param_types.iter())
+            .map(|((value, arg), ty)| self.coerce(value, &type_of(arg), ty))
+            .collect();
+        (declarations, values)
+    }
+
+    /// Returns the statements that end the function with a call in tail position
+    /// returning `return_type`, if the function returns the same C type. They store
+    /// `args`, of types `types`, and jump to the call `call` builds from them, which
+    /// follows the body of the function. With nothing left to do after it, `cc -O2`
+    /// turns the call into a jump.
+    fn tail_return(
+        &mut self,
+        args: &[String],
+        types: &[String],
+        return_type: &str,
+        call: impl FnOnce(&[String]) -> String,
+    ) -> Option<Vec<String>> {
+        let current = self.return_type.as_deref()?;
+        if c_type(current) != c_type(return_type) {
+            return None;
+        }
+        let mut statements = Vec::new();
+        let mut temps = Vec::new();
+        for (arg, ty) in args.iter().zip(types) {
+            let temp = self.fresh("tail_arg");
+            self.tail_args.push(format!("{};", declaration(ty, &temp)));
+            statements.push(format!("{} = {};", temp, arg));
+            temps.push(temp);
+        }
+        let label = self.fresh("tail");
+        self.tail_calls
+            .push(format!("{}:\nreturn {};", label, call(&temps)));
+        statements.push(format!("{}goto {};", UNLINK_FRAME, label));
+        Some(statements)
+    }
+
+    /// Wraps `value` in a statement expression when it needs `declarations` first.
+    fn with_declarations(declarations: Vec<String>, value: String) -> String {
+        if declarations.is_empty() {
+            value
+        } else {
+            statement_expression(&declarations, &value)
+        }
+    }
+
+    fn translate_function(&mut self, def: &mut FunctionDefNode, self_type: Option<&str>) {
+        let (symbol, global) = match self_type {
+            Some(type_name) => (method_symbol(type_name, &def.name), None),
+            None => (function_symbol(&def.name), Some(def.name.clone())),
+        };
+        self.begin_function(global, Some(def.return_type.clone()));
+        let params = self.declare_params(self_type.is_some(), &def.params);
+        let header = format!("{}({})", declaration(&def.return_type, &symbol), params);
+        let value = def.body.accept(self);
+        let value = self.coerce(value, &type_of(&def.body), &def.return_type);
+        let body = self.function_body(&[], Some((&def.return_type, value)));
+        self.define(def.span, header, body);
+    }
+
+    /// Translates the constructor of `def` into an initializer, which runs the
+    /// constructors of the ancestors and initializes the attributes, and `new` function.
+    fn translate_constructor(&mut self, def: &mut TypeDefNode) {
+        let type_name = def.identifier.clone();
+        let inherits_params = def.params.is_empty() && def.parent_args.is_empty();
+        let own_params = if inherits_params {
+            self.type_tree.constructor_params(&type_name)
+        } else {
+            def.params.clone()
+        };
+
+        self.begin_function(None, None);
+        let params = self.declare_params(true, &own_params);
+        let object = self.lookup("self").unwrap().clone();
+        let mut body = Vec::new();
+        if let Some(parent) = def.parent.clone().filter(|parent| parent != OBJECT) {
+            let parent_types: Vec<String> = self
+                .type_tree
+                .constructor_params(&parent)
+                .iter()
+                .map(|param| param.signature.clone())
+                .collect();
+            let args = if inherits_params {
+                self.params.clone()
+            } else {
+                let (declarations, values) = self.arguments(&mut def.parent_args, &parent_types);
+                body.extend(declarations);
+                values
+            };
+            let args = std::iter::once(object.clone())
+                .chain(args)
+                .collect::<Vec<_>>();
+            body.push(format!("{}({});", init_symbol(&parent), args.join(", ")));
+        }
+        for member in def.members.iter_mut() {
+            if let TypeMember::Property(assignment) = member {
+                let field_type = self
+                    .type_tree
+                    .find_attribute(&type_name, &assignment.identifier)
+                    .cloned()
+                    .unwrap_or_else(|| OBJECT.to_string());
+                let value = assignment.expression.accept(self);
+                let value = self.coerce(value, &type_of(&assignment.expression), &field_type);
+                body.push(format!(
+                    "{}\n(({} *){})->attr_{} = {};",
+                    self.line_directive(assignment.span),
+                    struct_name(&type_name),
+                    object,
+                    assignment.identifier,
+                    value
+                ));
+            }
+        }
+        let header = format!("void {}({})", init_symbol(&type_name), params);
+        let body = self.function_body(&body, None);
+        self.define(def.span, header, body);
+
+        self.begin_function(None, None);
+        let params = self.declare_params(false, &own_params);
+        let object = self.fresh("object");
+        let args = std::iter::once(object.clone())
+            .chain(self.params.iter().cloned())
+            .collect::<Vec<_>>();
+        let allocation = format!(
+            "hulk_alloc_object(&{}, sizeof({}))",
+            vtable_symbol(&type_name),
+            struct_name(&type_name)
+        );
+        let body = [
+            self.local(&type_name, &object, &allocation),
+            format!("{}({});", init_symbol(&type_name), args.join(", ")),
+        ];
+        let header = format!("void *{}({})", constructor_symbol(&type_name), params);
+        let body = self.function_body(&body, Some((&type_name, object)));
+        self.define(def.span, header, body);
+    }
+}
+
+impl Visitor<String> for CGenerator<'_> {
+    fn visit_function_def(&mut self, node: &mut FunctionDefNode) -> String {
+        self.translate_function(node, None);
+        String::new()
+    }
+
+    fn visit_literal_number(&mut self, node: &mut NumberLiteralNode) -> String {
+        // The shortest representation that reads back as the same double.
+        format!("{:?}", node.value)
+    }
+
+    fn visit_literal_boolean(&mut self, node: &mut BooleanLiteralNode) -> String {
+        node.value.to_string()
+    }
+
+    fn visit_literal_string(&mut self, node: &mut StringLiteralNode) -> String {
+        string_literal(&node.value)
+    }
+
+    fn visit_identifier(&mut self, node: &mut IdentifierNode) -> String {
+        match self.lookup(&node.value) {
+            Some(name) => name.clone(),
+            None => match builtins::constant(&node.value) {
+                Some(value) => format!("{:?}", value),
+                None => "NULL".to_string(),
+            },
+        }
+    }
+
+    fn visit_function_call(&mut self, node: &mut FunctionCallNode) -> String {
+        let builtin = builtins::function(&node.function_name)
+            .filter(|_| !self.functions.contains_key(&node.function_name));
+        let signature = self
+            .functions
+            .get(&node.function_name)
+            .cloned()
+            .or_else(|| builtin.map(|builtin| builtin.signature()));
+        let (param_types, return_type) = match &signature {
+            Some(signature) => (signature.param_types(), signature.return_type.clone()),
+            None => (Vec::new(), OBJECT.to_string()),
+        };
+        let (mut declarations, args) = self.arguments(&mut node.arguments, &param_types);
+
+        let recursive = node.tail_call == Some(TailCallKind::Recursive)
+            && self.current_function.as_deref() == Some(node.function_name.as_str());
+        if recursive {
+            // Arguments may read the parameters, so they are saved before any is overwritten.
+            let mut saved = Vec::new();
+            for (arg, ty) in args.into_iter().zip(param_types.iter()) {
+                let temp = self.fresh("arg");
+                declarations.push(self.local(ty, &temp, &arg));
+                saved.push(temp);
+            }
+            for (param, temp) in self.params.iter().zip(saved) {
+                declarations.push(format!("{} = {};", param, temp));
+            }
+            declarations.push(format!("goto {};", BODY_LABEL));
+            self.jumps_to_body = true;
+            return statement_expression(&declarations, default_value(&return_type));
+        }
+
+        let callee = match builtin {
+            Some(builtin) => builtin.symbol.to_string(),
+            None => function_symbol(&node.function_name),
+        };
+        let call = |args: &[String]| format!("{}({})", callee, args.join(", "));
+        if node.tail_call.is_some() {
+            if let Some(statements) = self.tail_return(&args, &param_types, &return_type, call) {
+                declarations.extend(statements);
+                return statement_expression(&declarations, default_value(&return_type));
+            }
+        }
+        Self::with_declarations(declarations, call(&args))
+    }
+
+    fn visit_while_loop(&mut self, node: &mut WhileNode) -> String {
+        let ty = node
+            .node_type
+            .as_ref()
+            .map_or(OBJECT.to_string(), |node| node.type_name.clone());
+        let result = self.fresh("result");
+        let condition = node.condition.accept(self);
+        let body = node.body.accept(self);
+        let body = self.coerce(body, &type_of(&node.body), &ty);
+        let statements = [
+            self.local(&ty, &result, default_value(&ty)),
+            format!(
+                "while ({}) {{\n{}\n}}",
+                condition,
+                indent(&format!("hulk_gc_safepoint();\n{} = {};", result, body))
+            ),
+        ];
+        statement_expression(&statements, &result)
+    }
+
+    fn visit_for_loop(&mut self, node: &mut ForNode) -> String {
+        let ty = node
+            .node_type
+            .as_ref()
+            .map_or(OBJECT.to_string(), |node| node.type_name.clone());
+        let result = self.fresh("result");
+        let counter = self.fresh(&format!("{}_counter", node.variable));
+        let end = self.fresh("end");
+        let start_value = node.start.accept(self);
+        let end_value = node.end.accept(self);
+
+        // A copy of the counter per iteration, as `ForNode` documents.
+        self.scopes.push(HashMap::new());
+        let variable = self.declare(&node.variable);
+        let body = node.body.accept(self);
+        self.scopes.pop();
+        let body = self.coerce(body, &type_of(&node.body), &ty);
+
+        let statements = [
+            self.local(&ty, &result, default_value(&ty)),
+            format!("double {} = {};", counter, start_value),
+            format!("double {} = {};", end, end_value),
+            format!(
+                "for (; {} < {}; {} += 1) {{\n{}\n}}",
+                counter,
+                end,
+                counter,
+                indent(&format!(
+                    "hulk_gc_safepoint();\ndouble {} = {};\n{} = {};",
+                    variable, counter, result, body
+                ))
+            ),
+        ];
+        statement_expression(&statements, &result)
+    }
+
+    fn visit_code_block(&mut self, node: &mut BlockNode) -> String {
+        let mut statements = Vec::new();
+        let mut value = "NULL".to_string();
+        let count = node.expression_list.expressions.len();
+        for (index, expr) in node.expression_list.expressions.iter_mut().enumerate() {
+            let line = self.line_directive(expr.span());
+            let code = expr.accept(self);
+            if index + 1 == count {
+                value = format!("{}\n{}", line, code);
+            } else {
+                statements.push(format!("{}\n{};", line, code));
+            }
+        }
+        statement_expression(&statements, &value)
+    }
+
+    fn visit_binary_op(&mut self, node: &mut BinaryOpNode) -> String {
+        let left_type = type_of(&node.left);
+        let right_type = type_of(&node.right);
+        let (declarations, values) = self.sequence(vec![node.left.as_mut(), node.right.as_mut()]);
+        let (left, right) = (values[0].clone(), values[1].clone());
+
+        let code = match node.operator {
+            OperatorToken::PLUS => format!("({} + {})", left, right),
+            OperatorToken::MINUS => format!("({} - {})", left, right),
+            OperatorToken::MUL => format!("({} * {})", left, right),
+            OperatorToken::DIV => format!("({} / {})", left, right),
+            OperatorToken::MOD => format!("fmod({}, {})", left, right),
+            OperatorToken::POW => format!("pow({}, {})", left, right),
+            OperatorToken::LT => format!("({} < {})", left, right),
+            OperatorToken::LTE => format!("({} <= {})", left, right),
+            OperatorToken::GT => format!("({} > {})", left, right),
+            OperatorToken::GTE => format!("({} >= {})", left, right),
+            // Both operands are always evaluated.
+            OperatorToken::AND => format!("({} & {})", left, right),
+            OperatorToken::OR => format!("({} | {})", left, right),
+            OperatorToken::CONCAT => format!(
+                "hulk_concat({}, {})",
+                Self::to_string(left, &left_type),
+                Self::to_string(right, &right_type)
+            ),
+            OperatorToken::EQ | OperatorToken::NEQ => {
+                let negate = if node.operator == OperatorToken::NEQ {
+                    "!"
+                } else {
+                    ""
+                };
+                let primitive =
+                    TypeTree::is_primitive(&left_type) && TypeTree::is_primitive(&right_type);
+                if !primitive {
+                    let left = self.coerce(left, &left_type, OBJECT);
+                    let right = self.coerce(right, &right_type, OBJECT);
+                    format!("{}hulk_object_eq({}, {})", negate, left, right)
+                } else if left_type != right_type {
+                    // Values of different primitive types are never equal.
+                    (node.operator == OperatorToken::NEQ).to_string()
+                } else if left_type == STRING {
+                    format!("{}hulk_string_eq({}, {})", negate, left, right)
+                } else {
+                    let operator = if negate.is_empty() { "==" } else { "!=" };
+                    format!("({} {} {})", left, operator, right)
+                }
+            }
+            ref operator => unreachable!("`{}` is not a binary operator", operator),
+        };
+        Self::with_declarations(declarations, code)
+    }
+
+    fn visit_unary_op(&mut self, node: &mut UnaryOpNode) -> String {
+        let operand = node.operand.accept(self);
+        match node.operator {
+            OperatorToken::NOT => format!("(!{})", operand),
+            _ => format!("(-{})", operand),
+        }
+    }
+
+    fn visit_if_else(&mut self, node: &mut IfElseNode) -> String {
+        let ty = node
+            .node_type
+            .as_ref()
+            .map_or(OBJECT.to_string(), |node| node.type_name.clone());
+        let mut branches: Vec<(Option<String>, String)> = Vec::new();
+        let condition = node.condition.accept(self);
+        let value = node.if_expression.accept(self);
+        branches.push((
+            Some(condition),
+            self.coerce(value, &type_of(&node.if_expression), &ty),
+        ));
+        for (condition, body) in node.elifs.iter_mut() {
+            let condition = condition.as_mut().map(|condition| condition.accept(self));
+            let value = body.accept(self);
+            let value = self.coerce(value, &type_of(body), &ty);
+            let is_else = condition.is_none();
+            branches.push((condition, value));
+            if is_else {
+                break;
+            }
+        }
+
+        let mut code = match branches.last() {
+            Some((None, _)) => branches.pop().unwrap().1,
+            _ => default_value(&ty).to_string(),
+        };
+        for (condition, value) in branches.into_iter().rev() {
+            code = format!("({} ? {} : {})", condition.unwrap(), value, code);
+        }
+        code
+    }
+
+    fn visit_let_in(&mut self, node: &mut LetInNode) -> String {
+        self.scopes.push(HashMap::new());
+        let mut statements = Vec::new();
+        for assignment in node.assignments.iter_mut() {
+            let ty = assignment
+                .node_type
+                .as_ref()
+                .map_or(OBJECT.to_string(), |node| node.type_name.clone());
+            let value = assignment.expression.accept(self);
+            let value = self.coerce(value, &type_of(&assignment.expression), &ty);
+            // Declared after translating the value, which still sees outer variables.
+            let name = self.declare(&assignment.identifier);
+            statements.push(self.local(&ty, &name, &value));
+        }
+        let body = node.body.accept(self);
+        self.scopes.pop();
+        statement_expression(&statements, &body)
+    }
+
+    fn visit_destructive_assign(&mut self, node: &mut DestructiveAssignNode) -> String {
+        let value_type = type_of(&node.expression);
+        let value = self.fresh("value");
+        let code = node.expression.accept(self);
+        let mut statements = vec![self.local(&value_type, &value, &code)];
+        match node.identifier.as_mut() {
+            Expression::Identifier(identifier) => {
+                if let Some(name) = self.lookup(&identifier.value).cloned() {
+                    let ty = identifier
+                        .node_type
+                        .as_ref()
+                        .map_or(OBJECT.to_string(), |node| node.type_name.clone());
+                    let assigned = self.coerce(value.clone(), &value_type, &ty);
+                    statements.push(format!("{} = {};", name, assigned));
+                }
+            }
+            Expression::TypePropAccess(access) => {
+                let object = access.object.accept(self);
+                let type_name = type_of(&access.object);
+                let field_type = self
+                    .type_tree
+                    .find_attribute(&type_name, &access.member)
+                    .cloned()
+                    .unwrap_or_else(|| OBJECT.to_string());
+                let assigned = self.coerce(value.clone(), &value_type, &field_type);
+                statements.push(format!(
+                    "(({} *){})->attr_{} = {};",
+                    struct_name(&type_name),
+                    object,
+                    access.member,
+                    assigned
+                ));
+            }
+            _ => {}
+        }
+        statement_expression(&statements, &value)
+    }
+
+    fn visit_type_def(&mut self, node: &mut TypeDefNode) -> String {
+        let type_name = node.identifier.clone();
+        self.translate_constructor(node);
+        for member in node.members.iter_mut() {
+            if let TypeMember::Method(method) = member {
+                self.translate_function(method, Some(&type_name));
+            }
+        }
+        String::new()
+    }
+
+    fn visit_type_instance(&mut self, node: &mut TypeInstanceNode) -> String {
+        let param_types: Vec<String> = self
+            .type_tree
+            .constructor_params(&node.type_name)
+            .iter()
+            .map(|param| param.signature.clone())
+            .collect();
+        let (declarations, args) = self.arguments(&mut node.arguments, &param_types);
+        let call = format!(
+            "{}({})",
+            constructor_symbol(&node.type_name),
+            args.join(", ")
+        );
+        Self::with_declarations(declarations, call)
+    }
+
+    fn visit_type_function_access(&mut self, node: &mut TypeFunctionAccessNode) -> String {
+        let static_type = type_of(&node.object);
+        let method = node.member.function_name.clone();
+        let (param_types, return_type) = self
+            .type_tree
+            .find_method(&static_type, &method)
+            .map(|(_, signature)| (signature.param_types(), signature.return_type.clone()))
+            .unwrap_or_else(|| (Vec::new(), OBJECT.to_string()));
+
+        let receiver = self.fresh("receiver");
+        let object = node.object.accept(self);
+        let mut declarations = vec![self.local(&static_type, &receiver, &object)];
+        let (arg_declarations, args) = self.arguments(&mut node.member.arguments, &param_types);
+        declarations.extend(arg_declarations);
+        let args = std::iter::once(receiver)
+            .chain(args)
+            .collect::<Vec<_>>();
+        let types = std::iter::once(static_type.clone())
+            .chain(param_types)
+            .collect::<Vec<_>>();
+
+        let direct_call = &node.direct_call;
+        let call = |args: &[String]| match direct_call {
+            Some(owner) => format!("{}({})", method_symbol(owner, &method), args.join(", ")),
+            None => format!(
+                "((const {} *)((struct hulk_object *){})->vtable)->m_{}({})",
+                vtable_struct_name(&static_type),
+                args[0],
+                method,
+                args.join(", ")
+            ),
+        };
+        if node.member.tail_call.is_some() {
+            if let Some(statements) = self.tail_return(&args, &types, &return_type, call) {
+                declarations.extend(statements);
+                return statement_expression(&declarations, default_value(&return_type));
+            }
+        }
+        statement_expression(&declarations, &call(&args))
+    }
+
+    fn visit_type_prop_access(&mut self, node: &mut TypePropAccessNode) -> String {
+        let object = node.object.accept(self);
+        let type_name = type_of(&node.object);
+        format!(
+            "(({} *){})->attr_{}",
+            struct_name(&type_name),
+            object,
+            node.member
+        )
+    }
+
+    fn visit_print(&mut self, node: &mut PrintNode) -> String {
+        let ty = type_of(&node.expression);
+        let printer = match ty.as_str() {
+            NUMBER => "hulk_print_number",
+            BOOLEAN => "hulk_print_bool",
+            STRING => "hulk_print_string",
+            _ => "hulk_print_object",
+        };
+        let value = self.fresh("printed");
+        let code = node.expression.accept(self);
+        let statements = [
+            self.local(&ty, &value, &code),
+            format!("{}({});", printer, value),
+        ];
+        statement_expression(&statements, &value)
+    }
+}
@@ -0,0 +1,231 @@
+//! Linear-scan register allocation over the IR.
+//!
+//! Blocks are laid out in a fixed order and every instruction and terminator gets a
+//! position. The live interval of a variable goes from the first to the last position
+//! where it is live, computed from block-level liveness so values carried around a
+//! loop stay live through the whole loop. Intervals are then scanned by start: a
+//! variable gets a free register of its class, or the interval ending last is spilled
+//! to the stack (Poletto and Sarkar, 1999).
+
+use std::collections::HashSet;
+
+use crate::intermediate::ir::{BlockId, Function, IrType, Operand, Terminator, VarId};
+
+/// Registers for strings, booleans and objects. They are callee-saved, so values
+/// stay in them across calls.
+pub const GENERAL_REGISTERS: [&str; 5] = ["%rbx", "%r12", "%r13", "%r14", "%r15"];
+
+/// Registers for `Number` values. The System V ABI has no callee-saved SSE
+/// registers, so values live across a call are saved by the caller.
+pub const SSE_REGISTERS: [&str; 8] = [
+    "%xmm8", "%xmm9", "%xmm10", "%xmm11", "%xmm12", "%xmm13", "%xmm14", "%xmm15",
+];
+
+/// Returns whether values of `ty` go in SSE registers.
+pub fn is_sse(ty: &IrType) -> bool {
+    *ty == IrType::Number
+}
+
+/// The result of register allocation for one function.
+pub struct Allocation {
+    /// Register of every variable, or `None` if it lives in its stack slot.
+    pub registers: Vec<Option<&'static str>>,
+    /// Live interval of every variable that is used at all.
+    pub intervals: Vec<Option<(usize, usize)>>,
+    /// Position of the first instruction of every block laid out.
+    pub block_start: Vec<usize>,
+}
+
+impl Allocation {
+    /// Returns the variables kept in SSE registers that are live across `position`,
+    /// which have to be saved around a call made there.
+    pub fn sse_live_across(&self, position: usize) -> Vec<VarId> {
+        (0..self.registers.len())
+            .filter(|&var| match (self.registers[var], self.intervals[var]) {
+                (Some(register), Some((start, end))) => {
+                    register.starts_with("%xmm") && start < position && end > position
+                }
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Returns the variables kept in registers whose live interval contains `position`,
+    /// including the operands of the instruction there.
+    pub fn live_at(&self, position: usize) -> Vec<VarId> {
+        (0..self.registers.len())
+            .filter(|&var| match (self.registers[var], self.intervals[var]) {
+                (Some(_), Some((start, end))) => start <= position && end >= position,
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Returns the callee-saved general registers the function writes.
+    pub fn used_general_registers(&self) -> Vec<&'static str> {
+        GENERAL_REGISTERS
+            .iter()
+            .copied()
+            .filter(|register| self.registers.contains(&Some(register)))
+            .collect()
+    }
+}
+
+fn terminator_operand(terminator: &Terminator) -> Option<&Operand> {
+    match terminator {
+        Terminator::Branch { cond, .. } => Some(cond),
+        Terminator::Return(value) => Some(value),
+        Terminator::Jump(_) => None,
+    }
+}
+
+/// Computes the variables live on entry to every block.
+fn live_in(function: &Function, order: &[BlockId]) -> Vec<HashSet<VarId>> {
+    let count = function.blocks.len();
+    let mut uses = vec![HashSet::new(); count];
+    let mut defs = vec![HashSet::new(); count];
+    for &block in order {
+        let info = &function.blocks[block];
+        for instr in info.instrs.iter() {
+            for operand in instr.operands() {
+                if let Operand::Var(var) = operand {
+                    if !defs[block].contains(var) {
+                        uses[block].insert(*var);
+                    }
+                }
+            }
+            if let Some(dest) = instr.dest() {
+                defs[block].insert(dest);
+            }
+        }
+        if let Some(Operand::Var(var)) = terminator_operand(&info.terminator) {
+            if !defs[block].contains(var) {
+                uses[block].insert(*var);
+            }
+        }
+    }
+
+    let mut live_in: Vec<HashSet<VarId>> = vec![HashSet::new(); count];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &block in order.iter().rev() {
+            let mut live: HashSet<VarId> = HashSet::new();
+            for succ in function.blocks[block].terminator.successors() {
+                live.extend(live_in[succ].iter().copied());
+            }
+            live.retain(|var| !defs[block].contains(var));
+            live.extend(uses[block].iter().copied());
+            if live != live_in[block] {
+                live_in[block] = live;
+                changed = true;
+            }
+        }
+    }
+    live_in
+}
+
+/// Allocates registers for every variable of `function`.
+///
+/// # Arguments
+/// * `function` - The function to allocate.
+/// * `order` - The blocks in the order they are emitted.
+pub fn allocate(function: &Function, order: &[BlockId]) -> Allocation {
+    let live_in = live_in(function, order);
+    let mut intervals: Vec<Option<(usize, usize)>> = vec![None; function.vars.len()];
+    let mut extend = |var: VarId, position: usize| {
+        intervals[var] = Some(match intervals[var] {
+            Some((start, end)) => (start.min(position), end.max(position)),
+            None => (position, position),
+        });
+    };
+
+    for param in function.params.iter() {
+        extend(*param, 0);
+    }
+    let mut block_start = vec![0; function.blocks.len()];
+    let mut position = 0;
+    for &block in order {
+        block_start[block] = position;
+        let info = &function.blocks[block];
+        for var in live_in[block].iter() {
+            extend(*var, position);
+        }
+        for instr in info.instrs.iter() {
+            for operand in instr.operands() {
+                if let Operand::Var(var) = operand {
+                    extend(*var, position);
+                }
+            }
+            if let Some(dest) = instr.dest() {
+                extend(dest, position);
+            }
+            position += 1;
+        }
+        if let Some(Operand::Var(var)) = terminator_operand(&info.terminator) {
+            extend(*var, position);
+        }
+        // Whatever the successors need is live until the end of the block.
+        for succ in info.terminator.successors() {
+            for var in live_in[succ].iter() {
+                extend(*var, position);
+            }
+        }
+        position += 1;
+    }
+
+    let mut order_by_start: Vec<VarId> = (0..function.vars.len())
+        .filter(|&var| intervals[var].is_some())
+        .collect();
+    order_by_start.sort_by_key(|&var| (intervals[var].unwrap().0, var));
+
+    let mut registers: Vec<Option<&'static str>> = vec![None; function.vars.len()];
+    let mut active: Vec<VarId> = Vec::new();
+    for var in order_by_start {
+        let (start, end) = intervals[var].unwrap();
+        // Registers of intervals ending here can be reused: operands are read
+        // before the destination is written.
+        active.retain(|&other| intervals[other].unwrap().1 > start);
+
+        let sse = is_sse(&function.vars[var].ty);
+        let pool: &[&'static str] = if sse {
+            &SSE_REGISTERS
+        } else {
+            &GENERAL_REGISTERS
+        };
+        let same_class: Vec<VarId> = active
+            .iter()
+            .copied()
+            .filter(|&other| is_sse(&function.vars[other].ty) == sse)
+            .collect();
+        let free = pool.iter().find(|register| {
+            !same_class
+                .iter()
+                .any(|&other| registers[other] == Some(**register))
+        });
+        match free {
+            Some(register) => {
+                registers[var] = Some(register);
+                active.push(var);
+            }
+            None => {
+                let victim = same_class
+                    .iter()
+                    .copied()
+                    .max_by_key(|&other| intervals[other].unwrap().1)
+                    .unwrap();
+                if intervals[victim].unwrap().1 > end {
+                    registers[var] = registers[victim].take();
+                    active.retain(|&other| other != victim);
+                    active.push(var);
+                }
+            }
+        }
+    }
+
+    Allocation {
+        registers,
+        intervals,
+        block_start,
+    }
+}
@@ -0,0 +1,81 @@
+//! Providing the runtime library and linking executables against it.
+//!
+//! The native backends emit code that calls the C ABI of the `hulk_runtime` crate, so
+//! their executables link its static library and the system libraries the Rust
+//! standard library needs. The build script compiles the library and the compiler
+//! embeds it, so it only has to be written next to the program being linked. Assembly
+//! is linked and C compiled and linked with `cc`, and LLVM IR compiled and linked with
+//! `clang`.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// File name of the runtime library.
+pub const RUNTIME_LIBRARY: &str = "libhulk_runtime.a";
+
+/// The runtime library, compiled by the build script.
+const RUNTIME: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/libhulk_runtime.a"));
+
+/// System libraries the runtime library depends on, as linker flags.
+pub const NATIVE_LIBRARIES: [&str; 7] = [
+    "-lgcc_s",
+    "-lutil",
+    "-lrt",
+    "-lpthread",
+    "-lm",
+    "-ldl",
+    "-lc",
+];
+
+/// Writes the runtime library embedded in the compiler into `dir`.
+///
+/// # Returns
+/// The path of the static library.
+pub fn write_runtime(dir: &Path) -> io::Result<PathBuf> {
+    let library = dir.join(RUNTIME_LIBRARY);
+    fs::write(&library, RUNTIME)?;
+    Ok(library)
+}
+
+/// Returns the `cc` invocation that links `inputs` and the runtime `library` into
+/// `executable`.
+pub fn link_command(inputs: &[&Path], library: &Path, executable: &Path) -> Command {
+    let mut command = Command::new("cc");
+    command
+        .args(inputs)
+        .arg(library)
+        .arg("-o")
+        .arg(executable)
+        .args(NATIVE_LIBRARIES);
+    command
+}
+
+/// Returns the `cc` invocation that compiles the C file `c_file` and links it with the
+/// runtime `library` into `executable`.
+pub fn c_command(c_file: &Path, library: &Path, executable: &Path) -> Command {
+    let mut command = Command::new("cc");
+    command
+        .args(["-O2", "-fno-strict-aliasing"])
+        .arg(c_file)
+        .arg(library)
+        .arg("-o")
+        .arg(executable)
+        .args(NATIVE_LIBRARIES);
+    command
+}
+
+/// Returns the `clang` invocation that compiles the LLVM IR in `ir` and links it with the
+/// runtime `library` into `executable`.
+pub fn clang_command(ir: &Path, library: &Path, executable: &Path) -> Command {
+    let mut command = Command::new("clang");
+    command
+        .arg("-O2")
+        .arg(ir)
+        .arg(library)
+        .arg("-o")
+        .arg(executable)
+        .args(NATIVE_LIBRARIES);
+    command
+}
@@ -0,0 +1,709 @@
+//! Textual LLVM IR backend.
+//!
+//! `LlvmGenerator` translates an IR [`Module`] into a `.ll` file that a system `clang`
+//! turns into an executable linked with the runtime library, without linking against
+//! LLVM:
+//!
+//! ```text
+//! clang -O2 program.ll libhulk_runtime.a -o program -lm
+//! ```
+//!
+//! Numbers are `double`, booleans `i1`, strings and objects `ptr`. Every object is a
+//! struct whose first field points to the vtable of its type, followed by its fields.
+//! Every IR variable gets a stack slot, and `clang` promotes them to registers. The
+//! support functions for printing, strings and boxing come from the runtime library;
+//! `runtime.ll`, copied into every module, declares them.
+//!
+//! Strings and objects are managed by the garbage collector of the runtime, through the
+//! shadow-stack frames and safepoints its `gc` module describes. Listing the slots of
+//! those variables in the frame also keeps `clang` from promoting them to registers.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::builtins;
+use crate::intermediate::ir::{BinaryOp, Function, Instr, IrType, Module, Operand, Terminator, TypeLayout, UnaryOp, VarId};
+use crate::intermediate::ir_generator::MAIN_FUNCTION;
+use crate::random::DEFAULT_SEED;
+
+/// Declarations of the runtime functions and builtin vtables.
+pub const RUNTIME: &str = include_str!("runtime.ll");
+
+/// Returns the LLVM type used for values of `ty`.
+fn llvm_type(ty: &IrType) -> &'static str {
+    match ty {
+        IrType::Number => "double",
+        IrType::Boolean => "i1",
+        IrType::String | IrType::Object(_) => "ptr",
+    }
+}
+
+/// Returns the LLVM name of the function compiled from the IR function `name`.
+fn function_symbol(name: &str) -> String {
+    format!("@hulk.{}", name)
+}
+
+fn struct_type(type_name: &str) -> String {
+    format!("%{}", type_name)
+}
+
+fn vtable_type(type_name: &str) -> String {
+    format!("%{}.vtable", type_name)
+}
+
+fn vtable_symbol(type_name: &str) -> String {
+    format!("@{}.vtable", type_name)
+}
+
+fn pointers_symbol(type_name: &str) -> String {
+    format!("@{}.pointers", type_name)
+}
+
+/// Writes a string as an LLVM `c"..."` array body, NUL terminator included.
+fn escape_string(value: &str) -> String {
+    let mut escaped = String::new();
+    for byte in value.bytes().chain(std::iter::once(0)) {
+        if byte.is_ascii_graphic() && byte != b'"' && byte != b'\\' || byte == b' ' {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("\\{:02X}", byte));
+        }
+    }
+    escaped
+}
+
+/// A generator of textual LLVM IR for a whole module.
+pub struct LlvmGenerator<'a> {
+    module: &'a Module,
+    /// String constants, emitted after the functions as `@.str.N`.
+    strings: Vec<String>,
+    string_ids: HashMap<String, usize>,
+    /// Body of the function being emitted.
+    body: String,
+    next_temp: usize,
+    /// Whether the function being emitted links a shadow-stack frame.
+    gc_frame: bool,
+    /// Whether the block being emitted already returned, from a tail call.
+    returned: bool,
+    /// Seed of the numbers `rand()` returns.
+    seed: u64,
+}
+
+impl<'a> LlvmGenerator<'a> {
+    pub fn new(module: &'a Module) -> Self {
+        LlvmGenerator {
+            module,
+            strings: Vec::new(),
+            string_ids: HashMap::new(),
+            body: String::new(),
+            next_temp: 0,
+            gc_frame: false,
+            returned: false,
+            seed: DEFAULT_SEED,
+        }
+    }
+
+    /// Seeds the numbers `rand()` returns in the generated program.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Generates the `.ll` text of the module.
+    ///
+    /// # Returns
+    /// A complete LLVM module: type definitions, vtables, functions, runtime declarations and a
+    /// C `main` that runs the program's top-level expressions.
+    pub fn generate(&mut self) -> String {
+        let mut out = String::new();
+        writeln!(out, "; Generated by the HULK compiler.").unwrap();
+        writeln!(out).unwrap();
+
+        for layout in self.module.types.iter() {
+            self.emit_type(&mut out, layout);
+        }
+        for function in self.module.functions.iter() {
+            let text = self.emit_function(function);
+            out.push_str(&text);
+        }
+
+        writeln!(out, "define i32 @main() {{").unwrap();
+        writeln!(out, "  call void @hulk_rand_seed(i64 {})", self.seed as i64).unwrap();
+        writeln!(out, "  %result = call double {}()", function_symbol(MAIN_FUNCTION)).unwrap();
+        writeln!(out, "  call void @hulk_gc_finish()").unwrap();
+        writeln!(out, "  ret i32 0").unwrap();
+        writeln!(out, "}}").unwrap();
+        writeln!(out).unwrap();
+
+        for (id, value) in self.strings.iter().enumerate() {
+            writeln!(
+                out,
+                "@.str.{} = private unnamed_addr constant [{} x i8] c\"{}\"",
+                id,
+                value.len() + 1,
+                escape_string(value)
+            )
+            .unwrap();
+        }
+        writeln!(out).unwrap();
+        out.push_str(RUNTIME);
+        out
+    }
+
+    /// Emits the struct type, pointer map, vtable type and vtable of a user type.
+    fn emit_type(&mut self, out: &mut String, layout: &TypeLayout) {
+        let mut fields = vec!["ptr"];
+        fields.extend(layout.fields.iter().map(|(_, ty)| llvm_type(ty)));
+        writeln!(out, "{} = type {{ {} }}", struct_type(&layout.name), fields.join(", ")).unwrap();
+
+        let mut offsets: Vec<String> = layout
+            .fields
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, ty))| ty.is_pointer())
+            .map(|(index, _)| {
+                format!(
+                    "i64 ptrtoint (ptr getelementptr ({}, ptr null, i32 0, i32 {}) to i64)",
+                    struct_type(&layout.name),
+                    index + 1
+                )
+            })
+            .collect();
+        offsets.insert(0, format!("i64 {}", offsets.len()));
+        writeln!(
+            out,
+            "{} = constant [{} x i64] [{}]",
+            pointers_symbol(&layout.name),
+            offsets.len(),
+            offsets.join(", ")
+        )
+        .unwrap();
+
+        let slots = vec!["ptr"; layout.methods.len() + 3];
+        writeln!(out, "{} = type {{ {} }}", vtable_type(&layout.name), slots.join(", ")).unwrap();
+
+        let name = self.string(&layout.name);
+        let parent = match &layout.parent {
+            Some(parent) => vtable_symbol(parent),
+            None => "@hulk_object_vtable".to_string(),
+        };
+        let mut entries = vec![
+            format!("ptr {}", name),
+            format!("ptr {}", parent),
+            format!("ptr {}", pointers_symbol(&layout.name)),
+        ];
+        entries.extend(
+            layout
+                .methods
+                .iter()
+                .map(|(_, implementation)| format!("ptr {}", function_symbol(implementation))),
+        );
+        writeln!(
+            out,
+            "{} = constant {} {{ {} }}",
+            vtable_symbol(&layout.name),
+            vtable_type(&layout.name),
+            entries.join(", ")
+        )
+        .unwrap();
+        writeln!(out).unwrap();
+    }
+
+    /// Returns the symbol of the constant holding `value`.
+    fn string(&mut self, value: &str) -> String {
+        let id = match self.string_ids.get(value) {
+            Some(&id) => id,
+            None => {
+                self.strings.push(value.to_string());
+                self.string_ids.insert(value.to_string(), self.strings.len() - 1);
+                self.strings.len() - 1
+            }
+        };
+        format!("@.str.{}", id)
+    }
+
+    /// Returns the LLVM function a call to the IR function `name` calls: the function of
+    /// the module, or the runtime function of the builtin it names.
+    fn callee_symbol(&self, name: &str) -> String {
+        match builtins::function(name).filter(|_| self.module.get_function(name).is_none()) {
+            Some(builtin) => format!("@{}", builtin.symbol),
+            None => function_symbol(name),
+        }
+    }
+
+    fn layout(&self, type_name: &str) -> &'a TypeLayout {
+        self.module
+            .get_type(type_name)
+            .unwrap_or_else(|| panic!("no layout for type `{}`", type_name))
+    }
+
+    fn temp(&mut self) -> String {
+        self.next_temp += 1;
+        format!("%t{}", self.next_temp)
+    }
+
+    fn line(&mut self, text: String) {
+        self.body.push_str("  ");
+        self.body.push_str(&text);
+        self.body.push('\n');
+    }
+
+    /// Returns the stack slot of a variable.
+    fn slot(function: &Function, var: VarId) -> String {
+        match &function.vars[var].name {
+            Some(name) => format!("%{}.{}", name, var),
+            None => format!("%v{}", var),
+        }
+    }
+
+    /// Returns an LLVM value holding `operand`, loading it if it is a variable.
+    fn value(&mut self, function: &Function, operand: &Operand) -> String {
+        match operand {
+            Operand::Var(var) => {
+                let temp = self.temp();
+                let ty = llvm_type(&function.vars[*var].ty);
+                let slot = Self::slot(function, *var);
+                self.line(format!("{} = load {}, ptr {}", temp, ty, slot));
+                temp
+            }
+            // Hexadecimal literals are exact for every double.
+            Operand::Number(value) => format!("0x{:016X}", value.to_bits()),
+            Operand::Boolean(value) => value.to_string(),
+            Operand::Str(value) => self.string(value),
+            Operand::Null => "null".to_string(),
+        }
+    }
+
+    fn store(&mut self, function: &Function, dest: VarId, value: &str) {
+        let ty = llvm_type(&function.vars[dest].ty);
+        let slot = Self::slot(function, dest);
+        self.line(format!("store {} {}, ptr {}", ty, value, slot));
+    }
+
+    /// Returns `operand` converted to a string, for concatenation.
+    fn string_value(&mut self, function: &Function, operand: &Operand) -> String {
+        let value = self.value(function, operand);
+        let converter = match function.operand_type(operand) {
+            IrType::Number => "@hulk_number_to_string(double",
+            IrType::Boolean => "@hulk_bool_to_string(i1",
+            IrType::Object(_) => "@hulk_object_to_string(ptr",
+            IrType::String => return value,
+        };
+        let temp = self.temp();
+        self.line(format!("{} = call ptr {} {})", temp, converter, value));
+        temp
+    }
+
+    fn emit_function(&mut self, function: &Function) -> String {
+        self.body.clear();
+        self.next_temp = 0;
+
+        let params: Vec<String> = function
+            .params
+            .iter()
+            .enumerate()
+            .map(|(index, var)| format!("{} %arg{}", llvm_type(&function.vars[*var].ty), index))
+            .collect();
+        let mut text = format!(
+            "define {} {}({}) {{\n",
+            llvm_type(&function.return_type),
+            function_symbol(&function.name),
+            params.join(", ")
+        );
+
+        text.push_str("alloca:\n");
+        for (var, info) in function.vars.iter().enumerate() {
+            text.push_str(&format!("  {} = alloca {}\n", Self::slot(function, var), llvm_type(&info.ty)));
+        }
+        for (index, var) in function.params.iter().enumerate() {
+            text.push_str(&format!(
+                "  store {} %arg{}, ptr {}\n",
+                llvm_type(&function.vars[*var].ty),
+                index,
+                Self::slot(function, *var)
+            ));
+        }
+        let mut objects = Vec::new();
+        for instr in function.blocks.iter().flat_map(|block| block.instrs.iter()) {
+            if let Instr::Alloc {
+                dest,
+                type_name,
+                stack: true,
+            } = instr
+            {
+                text.push_str(&format!("  %object{} = alloca {}\n", dest, struct_type(type_name)));
+                objects.push(*dest);
+            }
+        }
+        self.gc_frame = false;
+        self.emit_gc_frame(&mut text, function, &objects);
+        text.push_str(&format!("  br label %bb{}\n", function.entry));
+
+        let mut blocks = function.reachable_blocks();
+        blocks.sort();
+        for block in blocks {
+            self.body.push_str(&format!("bb{}:\n", block));
+            for instr in function.blocks[block].instrs.iter() {
+                self.emit_instr(function, instr);
+            }
+            self.emit_terminator(function, &function.blocks[block].terminator);
+        }
+
+        text.push_str(&self.body);
+        text.push_str("}\n\n");
+        text
+    }
+
+    /// Links a shadow-stack frame listing the slots of the strings and objects of
+    /// `function` and the tagged addresses of its stack `objects`. Slots start as null
+    /// and stack objects without a vtable, so nothing stale is traced.
+    fn emit_gc_frame(&mut self, text: &mut String, function: &Function, objects: &[VarId]) {
+        let mut roots = Vec::new();
+        for (var, info) in function.vars.iter().enumerate() {
+            if info.ty.is_pointer() {
+                let slot = Self::slot(function, var);
+                if !function.params.contains(&var) {
+                    text.push_str(&format!("  store ptr null, ptr {}\n", slot));
+                }
+                roots.push(slot);
+            }
+        }
+        for object in objects {
+            text.push_str(&format!("  store ptr null, ptr %object{}\n", object));
+            text.push_str(&format!(
+                "  %gc.tag{} = getelementptr i8, ptr %object{}, i64 1\n",
+                object, object
+            ));
+            roots.push(format!("%gc.tag{}", object));
+        }
+        if roots.is_empty() {
+            return;
+        }
+        self.gc_frame = true;
+
+        let frame_type = format!("{{ ptr, i64, [{} x ptr] }}", roots.len());
+        text.push_str(&format!("  %gc.frame = alloca {}\n", frame_type));
+        text.push_str(&format!(
+            "  %gc.count = getelementptr {}, ptr %gc.frame, i32 0, i32 1\n",
+            frame_type
+        ));
+        text.push_str(&format!("  store i64 {}, ptr %gc.count\n", roots.len()));
+        for (index, root) in roots.iter().enumerate() {
+            text.push_str(&format!(
+                "  %gc.root{} = getelementptr {}, ptr %gc.frame, i32 0, i32 2, i32 {}\n",
+                index, frame_type, index
+            ));
+            text.push_str(&format!("  store ptr {}, ptr %gc.root{}\n", root, index));
+        }
+        text.push_str("  %gc.prev = load ptr, ptr @hulk_gc_top\n");
+        text.push_str("  store ptr %gc.prev, ptr %gc.frame\n");
+        text.push_str("  store ptr %gc.frame, ptr @hulk_gc_top\n");
+    }
+
+    /// Unlinks the shadow-stack frame of the function, if it has one.
+    fn emit_gc_pop(&mut self) {
+        if self.gc_frame {
+            let prev = self.temp();
+            self.line(format!("{} = load ptr, ptr %gc.frame", prev));
+            self.line(format!("store ptr {}, ptr @hulk_gc_top", prev));
+        }
+    }
+
+    fn emit_instr(&mut self, function: &Function, instr: &Instr) {
+        match instr {
+            Instr::Copy { dest, src } => {
+                let value = self.value(function, src);
+                self.store(function, *dest, &value);
+            }
+            Instr::Binary {
+                dest,
+                op,
+                left,
+                right,
+            } => {
+                let result = self.emit_binary(function, *op, left, right);
+                self.store(function, *dest, &result);
+            }
+            Instr::Unary { dest, op, operand } => {
+                let value = self.value(function, operand);
+                let temp = self.temp();
+                match op {
+                    UnaryOp::Neg => self.line(format!("{} = fneg double {}", temp, value)),
+                    UnaryOp::Not => self.line(format!("{} = xor i1 {}, true", temp, value)),
+                }
+                self.store(function, *dest, &temp);
+            }
+            Instr::Call {
+                dest,
+                function: callee,
+                args,
+                tail,
+            } => {
+                let args = self.arguments(function, args);
+                let ty = llvm_type(&function.vars[*dest].ty);
+                let temp = self.temp();
+                let call = self.call_kind(*tail);
+                let symbol = self.callee_symbol(callee);
+                self.line(format!("{} = {} {} {}({})", temp, call, ty, symbol, args));
+                self.finish_call(function, *dest, &temp, *tail);
+            }
+            Instr::CallMethod {
+                dest,
+                receiver,
+                static_type,
+                method,
+                args,
+                tail,
+            } => {
+                let layout = self.layout(static_type);
+                let slot = layout
+                    .method_index(method)
+                    .unwrap_or_else(|| panic!("type `{}` has no method `{}`", static_type, method));
+                let object = self.value(function, receiver);
+                let vtable = self.temp();
+                self.line(format!("{} = load ptr, ptr {}", vtable, object));
+                let entry = self.temp();
+                self.line(format!(
+                    "{} = getelementptr {}, ptr {}, i32 0, i32 {}",
+                    entry,
+                    vtable_type(static_type),
+                    vtable,
+                    slot + 3
+                ));
+                let callee = self.temp();
+                self.line(format!("{} = load ptr, ptr {}", callee, entry));
+
+                let mut all_args = format!("ptr {}", object);
+                let rest = self.arguments(function, args);
+                if !rest.is_empty() {
+                    all_args.push_str(", ");
+                    all_args.push_str(&rest);
+                }
+                let ty = llvm_type(&function.vars[*dest].ty);
+                let temp = self.temp();
+                let call = self.call_kind(*tail);
+                self.line(format!("{} = {} {} {}({})", temp, call, ty, callee, all_args));
+                self.finish_call(function, *dest, &temp, *tail);
+            }
+            Instr::Alloc {
+                dest,
+                type_name,
+                stack,
+            } => {
+                let ty = struct_type(type_name);
+                if !*stack {
+                    self.line("call void @hulk_gc_safepoint()".to_string());
+                }
+                let object = if *stack {
+                    let object = format!("%object{}", dest);
+                    self.line(format!("store {} zeroinitializer, ptr {}", ty, object));
+                    self.line(format!("store ptr {}, ptr {}", vtable_symbol(type_name), object));
+                    object
+                } else {
+                    let size = self.temp();
+                    self.line(format!(
+                        "{} = ptrtoint ptr getelementptr ({}, ptr null, i32 1) to i64",
+                        size, ty
+                    ));
+                    let object = self.temp();
+                    self.line(format!(
+                        "{} = call ptr @hulk_alloc_object(ptr {}, i64 {})",
+                        object,
+                        vtable_symbol(type_name),
+                        size
+                    ));
+                    object
+                };
+                self.store(function, *dest, &object);
+            }
+            Instr::GetField {
+                dest,
+                object,
+                type_name,
+                field,
+            } => {
+                let field_ptr = self.field_pointer(function, object, type_name, field);
+                let ty = llvm_type(&function.vars[*dest].ty);
+                let temp = self.temp();
+                self.line(format!("{} = load {}, ptr {}", temp, ty, field_ptr));
+                self.store(function, *dest, &temp);
+            }
+            Instr::SetField {
+                object,
+                type_name,
+                field,
+                value,
+            } => {
+                let field_ptr = self.field_pointer(function, object, type_name, field);
+                let ty = llvm_type(&function.operand_type(value));
+                let value = self.value(function, value);
+                self.line(format!("store {} {}, ptr {}", ty, value, field_ptr));
+            }
+            Instr::Box { dest, value } => {
+                let (boxer, ty) = match function.operand_type(value) {
+                    IrType::Number => ("@hulk_box_number", "double"),
+                    IrType::Boolean => ("@hulk_box_bool", "i1"),
+                    IrType::String => ("@hulk_box_string", "ptr"),
+                    IrType::Object(_) => {
+                        let value = self.value(function, value);
+                        self.store(function, *dest, &value);
+                        return;
+                    }
+                };
+                self.line("call void @hulk_gc_safepoint()".to_string());
+                let value = self.value(function, value);
+                let temp = self.temp();
+                self.line(format!("{} = call ptr {}({} {})", temp, boxer, ty, value));
+                self.store(function, *dest, &temp);
+            }
+            Instr::Print { value } => {
+                let (printer, ty) = match function.operand_type(value) {
+                    IrType::Number => ("@hulk_print_number", "double"),
+                    IrType::Boolean => ("@hulk_print_bool", "i1"),
+                    IrType::String => ("@hulk_print_string", "ptr"),
+                    IrType::Object(_) => ("@hulk_print_object", "ptr"),
+                };
+                let value = self.value(function, value);
+                self.line(format!("call void {}({} {})", printer, ty, value));
+            }
+        }
+    }
+
+    /// Returns the call instruction for a call, in tail position if `tail`. A tail call
+    /// unlinks the frame first: the callee may not reach the allocas of the caller.
+    fn call_kind(&mut self, tail: bool) -> &'static str {
+        if tail {
+            self.emit_gc_pop();
+            "tail call"
+        } else {
+            "call"
+        }
+    }
+
+    /// Stores the result of a call into `dest`. The result of a tail call is returned
+    /// right away instead, which ends the block: with nothing between the call and the
+    /// `ret`, LLVM turns the call into a jump even without optimizations.
+    fn finish_call(&mut self, function: &Function, dest: VarId, temp: &str, tail: bool) {
+        if tail {
+            self.line(format!("ret {} {}", llvm_type(&function.return_type), temp));
+            self.returned = true;
+        } else {
+            self.store(function, dest, temp);
+        }
+    }
+
+    fn arguments(&mut self, function: &Function, args: &[Operand]) -> String {
+        let mut values = Vec::new();
+        for arg in args {
+            let ty = llvm_type(&function.operand_type(arg));
+            let value = self.value(function, arg);
+            values.push(format!("{} {}", ty, value));
+        }
+        values.join(", ")
+    }
+
+    fn field_pointer(&mut self, function: &Function, object: &Operand, type_name: &str, field: &str) -> String {
+        let index = self
+            .layout(type_name)
+            .field_index(field)
+            .unwrap_or_else(|| panic!("type `{}` has no field `{}`", type_name, field));
+        let object = self.value(function, object);
+        let temp = self.temp();
+        self.line(format!(
+            "{} = getelementptr {}, ptr {}, i32 0, i32 {}",
+            temp,
+            struct_type(type_name),
+            object,
+            index + 1
+        ));
+        temp
+    }
+
+    fn emit_binary(&mut self, function: &Function, op: BinaryOp, left: &Operand, right: &Operand) -> String {
+        if op == BinaryOp::Concat {
+            self.line("call void @hulk_gc_safepoint()".to_string());
+            let left = self.string_value(function, left);
+            let right = self.string_value(function, right);
+            let temp = self.temp();
+            self.line(format!("{} = call ptr @hulk_concat(ptr {}, ptr {})", temp, left, right));
+            return temp;
+        }
+
+        let operand_type = function.operand_type(left);
+        let left = self.value(function, left);
+        let right = self.value(function, right);
+        let temp = self.temp();
+        let instruction = match op {
+            BinaryOp::Add => format!("fadd double {}, {}", left, right),
+            BinaryOp::Sub => format!("fsub double {}, {}", left, right),
+            BinaryOp::Mul => format!("fmul double {}, {}", left, right),
+            BinaryOp::Div => format!("fdiv double {}, {}", left, right),
+            BinaryOp::Mod => format!("frem double {}, {}", left, right),
+            BinaryOp::Pow => format!("call double @llvm.pow.f64(double {}, double {})", left, right),
+            BinaryOp::Lt => format!("fcmp olt double {}, {}", left, right),
+            BinaryOp::Le => format!("fcmp ole double {}, {}", left, right),
+            BinaryOp::Gt => format!("fcmp ogt double {}, {}", left, right),
+            BinaryOp::Ge => format!("fcmp oge double {}, {}", left, right),
+            BinaryOp::And => format!("and i1 {}, {}", left, right),
+            BinaryOp::Or => format!("or i1 {}, {}", left, right),
+            BinaryOp::Eq | BinaryOp::Ne => {
+                let equal = op == BinaryOp::Eq;
+                match operand_type {
+                    IrType::Number => {
+                        let predicate = if equal { "oeq" } else { "une" };
+                        format!("fcmp {} double {}, {}", predicate, left, right)
+                    }
+                    IrType::Boolean => {
+                        let predicate = if equal { "eq" } else { "ne" };
+                        format!("icmp {} i1 {}, {}", predicate, left, right)
+                    }
+                    IrType::String | IrType::Object(_) => {
+                        let comparer = match operand_type {
+                            IrType::String => "@hulk_string_eq",
+                            _ => "@hulk_object_eq",
+                        };
+                        let call = format!("call i1 {}(ptr {}, ptr {})", comparer, left, right);
+                        if equal {
+                            call
+                        } else {
+                            self.line(format!("{} = {}", temp, call));
+                            let negated = self.temp();
+                            self.line(format!("{} = xor i1 {}, true", negated, temp));
+                            return negated;
+                        }
+                    }
+                }
+            }
+            BinaryOp::Concat => unreachable!(),
+        };
+        self.line(format!("{} = {}", temp, instruction));
+        temp
+    }
+
+    fn emit_terminator(&mut self, function: &Function, terminator: &Terminator) {
+        // The `Return` after a tail call was emitted with it.
+        if std::mem::take(&mut self.returned) {
+            return;
+        }
+        match terminator {
+            Terminator::Jump(target) => self.line(format!("br label %bb{}", target)),
+            Terminator::Branch {
+                cond,
+                then_block,
+                else_block,
+            } => {
+                let cond = self.value(function, cond);
+                self.line(format!(
+                    "br i1 {}, label %bb{}, label %bb{}",
+                    cond, then_block, else_block
+                ));
+            }
+            Terminator::Return(value) => {
+                let ty = llvm_type(&function.return_type);
+                let value = self.value(function, value);
+                self.emit_gc_pop();
+                self.line(format!("ret {} {}", ty, value));
+            }
+        }
+    }
+}
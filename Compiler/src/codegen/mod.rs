@@ -1 +1,5 @@
-// ...implementación del generador de código final...
+pub mod c;
+pub mod linear_scan;
+pub mod linking;
+pub mod llvm;
+pub mod x86;
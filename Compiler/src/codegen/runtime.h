@@ -0,0 +1,55 @@
+/*
+ * Declarations of the runtime library every C file generated from a program links
+ * against, copied at the top of the file. The definitions live in the `hulk_runtime`
+ * crate.
+ *
+ * Numbers are doubles, booleans `bool`, strings NUL-terminated buffers and objects
+ * start with a pointer to the vtable of their type. A vtable starts with the type
+ * name, the vtable of the parent type and the pointer map of the type, followed by
+ * the methods. Numbers, strings and booleans used as objects are boxed with the
+ * builtin vtables below.
+ *
+ * Memory is managed by the garbage collector: functions link their frames into the
+ * shadow stack at `hulk_gc_top` and call `hulk_gc_safepoint` where it may collect.
+ */
+
+#include <stdbool.h>
+#include <stddef.h>
+
+struct hulk_vtable {
+    const char *name;
+    const struct hulk_vtable *parent;
+    const long long *pointers;
+};
+
+extern const struct hulk_vtable hulk_object_vtable;
+extern const struct hulk_vtable hulk_number_vtable;
+extern const struct hulk_vtable hulk_string_vtable;
+extern const struct hulk_vtable hulk_boolean_vtable;
+
+extern void *hulk_gc_top;
+
+void hulk_gc_safepoint(void);
+void hulk_gc_finish(void);
+void *hulk_alloc(long long size);
+void *hulk_alloc_object(const void *vtable, long long size);
+char *hulk_number_to_string(double value);
+char *hulk_bool_to_string(bool value);
+char *hulk_concat(const char *left, const char *right);
+bool hulk_string_eq(const char *left, const char *right);
+void *hulk_box_number(double value);
+void *hulk_box_string(const char *value);
+void *hulk_box_bool(bool value);
+char *hulk_object_to_string(const void *object);
+bool hulk_object_eq(const void *left, const void *right);
+void hulk_print_string(const char *value);
+void hulk_print_number(double value);
+void hulk_print_bool(bool value);
+void hulk_print_object(const void *object);
+double hulk_sqrt(double x);
+double hulk_sin(double x);
+double hulk_cos(double x);
+double hulk_exp(double x);
+double hulk_log(double base, double x);
+double hulk_rand(void);
+void hulk_rand_seed(unsigned long long seed);
@@ -0,0 +1,51 @@
+; Declarations of the runtime library every program compiled to LLVM IR links
+; against. The definitions live in the `hulk_runtime` crate.
+;
+; Values are represented as:
+;   Number  -> double
+;   Boolean -> i1
+;   String  -> ptr to a NUL-terminated buffer
+;   objects -> ptr to a struct whose first field points to the vtable of its type
+;
+; A vtable starts with the type name, the vtable of the parent type and the pointer
+; map of the type, followed by the methods. Numbers, strings and booleans used as
+; objects are boxed with the builtin vtables below.
+;
+; Memory is managed by the garbage collector: functions link their frames into the
+; shadow stack at @hulk_gc_top and call @hulk_gc_safepoint before allocating.
+
+declare double @llvm.pow.f64(double, double)
+
+@hulk_object_vtable = external constant { ptr, ptr, ptr }
+@hulk_number_vtable = external constant { ptr, ptr, ptr }
+@hulk_string_vtable = external constant { ptr, ptr, ptr }
+@hulk_boolean_vtable = external constant { ptr, ptr, ptr }
+
+@hulk_gc_top = external global ptr
+
+declare void @hulk_gc_safepoint()
+declare void @hulk_gc_finish()
+declare ptr @hulk_alloc(i64)
+declare ptr @hulk_alloc_object(ptr, i64)
+declare zeroext i1 @hulk_is(ptr, ptr)
+declare ptr @hulk_type_name(ptr)
+declare ptr @hulk_number_to_string(double)
+declare ptr @hulk_bool_to_string(i1 zeroext)
+declare ptr @hulk_concat(ptr, ptr)
+declare zeroext i1 @hulk_string_eq(ptr, ptr)
+declare ptr @hulk_box_number(double)
+declare ptr @hulk_box_string(ptr)
+declare ptr @hulk_box_bool(i1 zeroext)
+declare ptr @hulk_object_to_string(ptr)
+declare zeroext i1 @hulk_object_eq(ptr, ptr)
+declare void @hulk_print_string(ptr)
+declare void @hulk_print_number(double)
+declare void @hulk_print_bool(i1 zeroext)
+declare void @hulk_print_object(ptr)
+declare double @hulk_sqrt(double)
+declare double @hulk_sin(double)
+declare double @hulk_cos(double)
+declare double @hulk_exp(double)
+declare double @hulk_log(double, double)
+declare double @hulk_rand()
+declare void @hulk_rand_seed(i64)
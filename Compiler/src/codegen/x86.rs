@@ -0,0 +1,1065 @@
+//! Native x86-64 backend emitting GNU assembler syntax.
+//!
+//! `X86Generator` translates an IR [`Module`] into a `.s` file for the System V ABI.
+//! It is linked with the runtime library, so no LLVM toolchain is needed:
+//!
+//! ```text
+//! cc program.s libhulk_runtime.a -o program -lm
+//! ```
+//!
+//! Numbers live in SSE registers and every other value in a general register: strings
+//! and objects as pointers, booleans as 0 or 1. Registers come from
+//! [`linear_scan`](super::linear_scan). Every variable also has a stack slot, used
+//! when it is spilled and to save SSE registers around calls. Instructions load their
+//! operands into scratch registers (`%rax`, `%rcx`, `%xmm0`, `%xmm1`), compute the
+//! result and store it into the destination.
+//!
+//! Strings and objects are managed by the garbage collector of the runtime, through the
+//! shadow-stack frames and safepoints its `gc` module describes. The frame lists the
+//! slots of those variables, and the registers that hold them are written back to the
+//! slots before every call.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::builtins;
+use crate::intermediate::ir::{
+    BinaryOp, BlockId, Function, Instr, IrType, Module, Operand, Terminator, TypeLayout, UnaryOp,
+    VarId,
+};
+use crate::intermediate::ir_generator::MAIN_FUNCTION;
+use crate::random::DEFAULT_SEED;
+
+use super::linear_scan::{allocate, is_sse, Allocation, GENERAL_REGISTERS};
+
+const INTEGER_ARGUMENTS: [&str; 6] = ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"];
+const SSE_ARGUMENTS: [&str; 8] = [
+    "%xmm0", "%xmm1", "%xmm2", "%xmm3", "%xmm4", "%xmm5", "%xmm6", "%xmm7",
+];
+
+/// Bytes reserved at the top of every frame to save callee-saved registers.
+const SAVE_AREA: i64 = 8 * GENERAL_REGISTERS.len() as i64;
+/// Bytes reserved for intermediate values of concatenations.
+const SCRATCH_AREA: i64 = 16;
+
+/// Returns the assembler symbol of the function compiled from the IR function `name`.
+fn function_symbol(name: &str) -> String {
+    format!("hulk.{}", name)
+}
+
+fn vtable_symbol(type_name: &str) -> String {
+    format!("{}.vtable", type_name)
+}
+
+fn pointers_symbol(type_name: &str) -> String {
+    format!("{}.pointers", type_name)
+}
+
+/// Returns the size in bytes of an object of `layout`: the vtable pointer and one
+/// 8-byte slot per field.
+fn object_size(layout: &TypeLayout) -> i64 {
+    8 * (layout.fields.len() as i64 + 1)
+}
+
+/// Writes a string as the body of an `.asciz` directive.
+fn escape_string(value: &str) -> String {
+    let mut escaped = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'"' => escaped.push_str("\\\""),
+            b'\\' => escaped.push_str("\\\\"),
+            b' '..=b'~' => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    escaped
+}
+
+/// An argument of a call, in the order the callee declares them.
+enum Argument<'o> {
+    Value(&'o Operand),
+    /// A general-purpose value saved in the frame at the given offset from `%rbp`.
+    Frame(i64),
+    Immediate(i64),
+    /// The address of a symbol.
+    Address(String),
+}
+
+/// An argument passed in a register: the argument, whether it is a number and the
+/// register.
+type InRegister<'a, 'o> = (&'a Argument<'o>, bool, &'static str);
+
+/// Decides where each argument of a call goes, following the System V ABI: the ones
+/// that do not fit in registers are pushed, and returned apart.
+fn place_arguments<'a, 'o>(
+    args: &'a [(Argument<'o>, bool)],
+) -> (Vec<InRegister<'a, 'o>>, Vec<(&'a Argument<'o>, bool)>) {
+    let (mut integers, mut floats) = (0, 0);
+    let mut in_registers = Vec::new();
+    let mut on_stack = Vec::new();
+    for (arg, sse) in args.iter() {
+        if *sse && floats < SSE_ARGUMENTS.len() {
+            in_registers.push((arg, true, SSE_ARGUMENTS[floats]));
+            floats += 1;
+        } else if !*sse && integers < INTEGER_ARGUMENTS.len() {
+            in_registers.push((arg, false, INTEGER_ARGUMENTS[integers]));
+            integers += 1;
+        } else {
+            on_stack.push((arg, *sse));
+        }
+    }
+    (in_registers, on_stack)
+}
+
+/// Where the called code is.
+enum Callee<'o> {
+    Symbol(String),
+    /// A method found in the vtable of the receiver, at the given byte offset.
+    Virtual(&'o Operand, i64),
+}
+
+/// A generator of GNU-as x86-64 assembly for a whole module.
+pub struct X86Generator<'a> {
+    module: &'a Module,
+    text: String,
+    /// `Number` constants by bit pattern, emitted as `.LCN`.
+    numbers: Vec<u64>,
+    number_ids: HashMap<u64, usize>,
+    /// String constants, emitted as `.LSN`.
+    strings: Vec<String>,
+    string_ids: HashMap<String, usize>,
+    // State of the function being emitted.
+    function_index: usize,
+    allocation: Allocation,
+    variable_count: i64,
+    stack_objects: HashMap<VarId, i64>,
+    /// Offset from `%rbp` of the shadow-stack frame, if the function has roots.
+    gc_frame: Option<i64>,
+    frame_size: i64,
+    /// Seed of the numbers `rand()` returns.
+    seed: u64,
+}
+
+impl<'a> X86Generator<'a> {
+    pub fn new(module: &'a Module) -> Self {
+        X86Generator {
+            module,
+            text: String::new(),
+            numbers: Vec::new(),
+            number_ids: HashMap::new(),
+            strings: Vec::new(),
+            string_ids: HashMap::new(),
+            function_index: 0,
+            allocation: Allocation {
+                registers: Vec::new(),
+                intervals: Vec::new(),
+                block_start: Vec::new(),
+            },
+            variable_count: 0,
+            stack_objects: HashMap::new(),
+            gc_frame: None,
+            frame_size: 0,
+            seed: DEFAULT_SEED,
+        }
+    }
+
+    /// Seeds the numbers `rand()` returns in the generated program.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Generates the assembly of the module.
+    ///
+    /// # Returns
+    /// The text of a `.s` file with every function, the vtables, the constants and a
+    /// C `main` that runs the program's top-level expressions.
+    pub fn generate(&mut self) -> String {
+        self.text.clear();
+        for (index, function) in self.module.functions.iter().enumerate() {
+            self.function_index = index;
+            self.emit_function(function);
+        }
+
+        let mut out = String::new();
+        writeln!(out, "# Generated by the HULK compiler.").unwrap();
+        writeln!(out, "\t.text").unwrap();
+        writeln!(out, "\t.globl main").unwrap();
+        writeln!(out, "main:").unwrap();
+        writeln!(out, "\tpushq %rbp").unwrap();
+        writeln!(out, "\tmovq %rsp, %rbp").unwrap();
+        writeln!(out, "\tmovabsq $0x{:x}, %rdi", self.seed).unwrap();
+        writeln!(out, "\tcall hulk_rand_seed").unwrap();
+        writeln!(out, "\tcall {}", function_symbol(MAIN_FUNCTION)).unwrap();
+        writeln!(out, "\tcall hulk_gc_finish").unwrap();
+        writeln!(out, "\txorl %eax, %eax").unwrap();
+        writeln!(out, "\tpopq %rbp").unwrap();
+        writeln!(out, "\tret").unwrap();
+        writeln!(out).unwrap();
+        out.push_str(&self.text);
+
+        writeln!(out, "\t.section .data.rel.ro").unwrap();
+        writeln!(out, "\t.align 8").unwrap();
+        for layout in self.module.types.iter() {
+            let name = self.string(&layout.name);
+            let parent = match &layout.parent {
+                Some(parent) => vtable_symbol(parent),
+                None => "hulk_object_vtable".to_string(),
+            };
+            writeln!(out, "{}:", vtable_symbol(&layout.name)).unwrap();
+            writeln!(out, "\t.quad {}", name).unwrap();
+            writeln!(out, "\t.quad {}", parent).unwrap();
+            writeln!(out, "\t.quad {}", pointers_symbol(&layout.name)).unwrap();
+            for (_, implementation) in layout.methods.iter() {
+                writeln!(out, "\t.quad {}", function_symbol(implementation)).unwrap();
+            }
+        }
+
+        writeln!(out, "\t.section .rodata").unwrap();
+        writeln!(out, "\t.align 8").unwrap();
+        for layout in self.module.types.iter() {
+            let offsets: Vec<String> = layout
+                .fields
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, ty))| ty.is_pointer())
+                .map(|(index, _)| (8 * (index + 1)).to_string())
+                .collect();
+            writeln!(out, "{}:", pointers_symbol(&layout.name)).unwrap();
+            writeln!(out, "\t.quad {}", offsets.len()).unwrap();
+            if !offsets.is_empty() {
+                writeln!(out, "\t.quad {}", offsets.join(", ")).unwrap();
+            }
+        }
+        for (id, bits) in self.numbers.iter().enumerate() {
+            writeln!(out, ".LC{}:\n\t.quad 0x{:016x}", id, bits).unwrap();
+        }
+        for (id, value) in self.strings.iter().enumerate() {
+            writeln!(out, ".LS{}:\n\t.asciz \"{}\"", id, escape_string(value)).unwrap();
+        }
+        writeln!(out, "\t.section .note.GNU-stack,\"\",@progbits").unwrap();
+        out
+    }
+
+    fn number(&mut self, bits: u64) -> String {
+        let id = match self.number_ids.get(&bits) {
+            Some(&id) => id,
+            None => {
+                self.numbers.push(bits);
+                self.number_ids.insert(bits, self.numbers.len() - 1);
+                self.numbers.len() - 1
+            }
+        };
+        format!(".LC{}(%rip)", id)
+    }
+
+    /// Returns the label of the constant holding `value`.
+    fn string(&mut self, value: &str) -> String {
+        let id = match self.string_ids.get(value) {
+            Some(&id) => id,
+            None => {
+                self.strings.push(value.to_string());
+                self.string_ids
+                    .insert(value.to_string(), self.strings.len() - 1);
+                self.strings.len() - 1
+            }
+        };
+        format!(".LS{}", id)
+    }
+
+    /// Returns the symbol a call to the IR function `name` jumps to: the function of the
+    /// module, or the runtime function of the builtin it names.
+    fn callee_symbol(&self, name: &str) -> String {
+        match builtins::function(name).filter(|_| self.module.get_function(name).is_none()) {
+            Some(builtin) => builtin.symbol.to_string(),
+            None => function_symbol(name),
+        }
+    }
+
+    fn layout(&self, type_name: &str) -> &'a TypeLayout {
+        self.module
+            .get_type(type_name)
+            .unwrap_or_else(|| panic!("no layout for type `{}`", type_name))
+    }
+
+    fn line(&mut self, text: String) {
+        self.text.push('\t');
+        self.text.push_str(&text);
+        self.text.push('\n');
+    }
+
+    fn block_label(&self, block: BlockId) -> String {
+        format!(".L{}_bb{}", self.function_index, block)
+    }
+
+    /// Returns the offset from `%rbp` of the stack slot of `var`.
+    fn slot(&self, var: VarId) -> i64 {
+        -(SAVE_AREA + 8 * (var as i64 + 1))
+    }
+
+    fn scratch_slot(&self, index: i64) -> i64 {
+        -(SAVE_AREA + 8 * self.variable_count + 8 * (index + 1))
+    }
+
+    /// Returns the register or memory operand where `var` lives.
+    fn location(&self, var: VarId) -> String {
+        match self.allocation.registers.get(var).copied().flatten() {
+            Some(register) => register.to_string(),
+            None => format!("{}(%rbp)", self.slot(var)),
+        }
+    }
+
+    fn in_register(&self, var: VarId) -> bool {
+        self.allocation
+            .registers
+            .get(var)
+            .copied()
+            .flatten()
+            .is_some()
+    }
+
+    /// Loads a string, boolean or object operand into a general register.
+    fn load_general(&mut self, function: &Function, operand: &Operand, register: &str) {
+        match operand {
+            Operand::Var(var) => {
+                let location = self.location(*var);
+                if location != register {
+                    self.line(format!("movq {}, {}", location, register));
+                }
+            }
+            Operand::Boolean(value) => self.line(format!("movq ${}, {}", *value as i64, register)),
+            Operand::Str(value) => {
+                let label = self.string(value);
+                self.line(format!("leaq {}(%rip), {}", label, register));
+            }
+            Operand::Null => self.line(format!("movq $0, {}", register)),
+            Operand::Number(_) => {
+                unreachable!("`{}` is not a general value", function.fmt_operand(operand))
+            }
+        }
+    }
+
+    /// Loads a `Number` operand into an SSE register.
+    fn load_sse(&mut self, operand: &Operand, register: &str) {
+        match operand {
+            Operand::Var(var) => {
+                let location = self.location(*var);
+                if self.in_register(*var) {
+                    if location != register {
+                        self.line(format!("movapd {}, {}", location, register));
+                    }
+                } else {
+                    self.line(format!("movsd {}, {}", location, register));
+                }
+            }
+            Operand::Number(value) => {
+                let constant = self.number(value.to_bits());
+                self.line(format!("movsd {}, {}", constant, register));
+            }
+            _ => unreachable!("only numbers are loaded into SSE registers"),
+        }
+    }
+
+    /// Loads any operand into `%rax` or `%xmm0`, depending on its type.
+    fn load(&mut self, function: &Function, operand: &Operand) {
+        if is_sse(&function.operand_type(operand)) {
+            self.load_sse(operand, "%xmm0");
+        } else {
+            self.load_general(function, operand, "%rax");
+        }
+    }
+
+    /// Stores `%rax` or `%xmm0`, depending on the type of `dest`, into `dest`.
+    fn store(&mut self, function: &Function, dest: VarId) {
+        let location = self.location(dest);
+        if is_sse(&function.vars[dest].ty) {
+            if self.in_register(dest) {
+                self.line(format!("movapd %xmm0, {}", location));
+            } else {
+                self.line(format!("movsd %xmm0, {}", location));
+            }
+        } else {
+            self.line(format!("movq %rax, {}", location));
+        }
+    }
+
+    fn emit_function(&mut self, function: &Function) {
+        let mut order = vec![function.entry];
+        let mut rest = function.reachable_blocks();
+        rest.retain(|&block| block != function.entry);
+        rest.sort();
+        order.extend(rest);
+        self.allocation = allocate(function, &order);
+        self.variable_count = function.vars.len() as i64;
+
+        // Frame: saved registers, variable slots, scratch slots, stack objects, then the
+        // shadow-stack frame.
+        let mut size = SAVE_AREA + 8 * self.variable_count + SCRATCH_AREA;
+        self.stack_objects.clear();
+        for instr in function.blocks.iter().flat_map(|block| block.instrs.iter()) {
+            if let Instr::Alloc {
+                dest,
+                type_name,
+                stack: true,
+            } = instr
+            {
+                size += object_size(self.layout(type_name));
+                self.stack_objects.insert(*dest, -size);
+            }
+        }
+        let mut objects: Vec<i64> = self.stack_objects.values().copied().collect();
+        objects.sort();
+        let roots: Vec<i64> = (0..function.vars.len())
+            .filter(|&var| {
+                function.vars[var].ty.is_pointer() && self.allocation.intervals[var].is_some()
+            })
+            .map(|var| self.slot(var))
+            .chain(objects.iter().map(|offset| offset + 1))
+            .collect();
+        self.gc_frame = None;
+        if !roots.is_empty() {
+            size += 16 + 8 * roots.len() as i64;
+            self.gc_frame = Some(-size);
+        }
+        self.frame_size = (size + 15) / 16 * 16;
+
+        self.text.push_str(&format!("# {}\n", function.name));
+        self.text
+            .push_str(&format!("{}:\n", function_symbol(&function.name)));
+        self.line("pushq %rbp".to_string());
+        self.line("movq %rsp, %rbp".to_string());
+        self.line(format!("subq ${}, %rsp", self.frame_size));
+        for (index, register) in self
+            .allocation
+            .used_general_registers()
+            .into_iter()
+            .enumerate()
+        {
+            self.line(format!(
+                "movq {}, {}(%rbp)",
+                register,
+                -8 * (index as i64 + 1)
+            ));
+        }
+
+        if let Some(frame) = self.gc_frame {
+            self.emit_gc_frame(frame, &roots, &objects);
+        }
+
+        // Parameters arrive in registers, or on the stack past the return address.
+        let (mut integers, mut floats, mut stacked) = (0, 0, 0);
+        for &param in function.params.iter() {
+            let sse = is_sse(&function.vars[param].ty);
+            let source = if sse && floats < SSE_ARGUMENTS.len() {
+                floats += 1;
+                SSE_ARGUMENTS[floats - 1].to_string()
+            } else if !sse && integers < INTEGER_ARGUMENTS.len() {
+                integers += 1;
+                INTEGER_ARGUMENTS[integers - 1].to_string()
+            } else {
+                stacked += 1;
+                format!("{}(%rbp)", 16 + 8 * (stacked - 1))
+            };
+            if self.allocation.intervals[param].is_none() {
+                continue;
+            }
+            let location = self.location(param);
+            match (sse, source.starts_with('%')) {
+                (true, true) if self.in_register(param) => {
+                    self.line(format!("movapd {}, {}", source, location))
+                }
+                (true, true) => self.line(format!("movsd {}, {}", source, location)),
+                (true, false) => {
+                    self.line(format!("movsd {}, %xmm0", source));
+                    self.store(function, param);
+                }
+                (false, true) => self.line(format!("movq {}, {}", source, location)),
+                (false, false) => {
+                    self.line(format!("movq {}, %rax", source));
+                    self.store(function, param);
+                }
+            }
+        }
+        self.line(format!("jmp {}", self.block_label(function.entry)));
+
+        let mut position = 0;
+        for &block in order.iter() {
+            let label = self.block_label(block);
+            self.text.push_str(&format!("{}:\n", label));
+            for instr in function.blocks[block].instrs.iter() {
+                self.emit_instr(function, instr, position);
+                position += 1;
+            }
+            self.emit_terminator(function, &function.blocks[block].terminator);
+            position += 1;
+        }
+        self.text.push('\n');
+    }
+
+    /// Links the shadow-stack frame at `frame` listing `roots`, the slots of pointer
+    /// variables and the tagged addresses of stack objects. Slots start as null and
+    /// stack objects without a vtable, so nothing stale is traced. Only `%rax` is
+    /// written, leaving the parameters in place.
+    fn emit_gc_frame(&mut self, frame: i64, roots: &[i64], objects: &[i64]) {
+        for &root in roots.iter().filter(|root| *root & 1 == 0) {
+            self.line(format!("movq $0, {}(%rbp)", root));
+        }
+        for &object in objects {
+            self.line(format!("movq $0, {}(%rbp)", object));
+        }
+        self.line(format!("movq ${}, {}(%rbp)", roots.len(), frame + 8));
+        for (index, root) in roots.iter().enumerate() {
+            self.line(format!("leaq {}(%rbp), %rax", root));
+            self.line(format!(
+                "movq %rax, {}(%rbp)",
+                frame + 16 + 8 * index as i64
+            ));
+        }
+        self.line("movq hulk_gc_top(%rip), %rax".to_string());
+        self.line(format!("movq %rax, {}(%rbp)", frame));
+        self.line(format!("leaq {}(%rbp), %rax", frame));
+        self.line("movq %rax, hulk_gc_top(%rip)".to_string());
+    }
+
+    /// Unlinks the shadow-stack frame of the function, if it has one.
+    fn emit_gc_pop(&mut self) {
+        if let Some(frame) = self.gc_frame {
+            self.line(format!("movq {}(%rbp), %rcx", frame));
+            self.line("movq %rcx, hulk_gc_top(%rip)".to_string());
+        }
+    }
+
+    /// Restores the callee-saved registers and pops the frame, leaving `%rsp` at the
+    /// return address.
+    fn emit_frame_teardown(&mut self) {
+        for (index, register) in self
+            .allocation
+            .used_general_registers()
+            .into_iter()
+            .enumerate()
+        {
+            self.line(format!(
+                "movq {}(%rbp), {}",
+                -8 * (index as i64 + 1),
+                register
+            ));
+        }
+        self.line("leave".to_string());
+    }
+
+    fn emit_epilogue(&mut self) {
+        self.emit_gc_pop();
+        self.emit_frame_teardown();
+        self.line("ret".to_string());
+    }
+
+    fn emit_terminator(&mut self, function: &Function, terminator: &Terminator) {
+        match terminator {
+            Terminator::Jump(target) => {
+                let label = self.block_label(*target);
+                self.line(format!("jmp {}", label));
+            }
+            Terminator::Branch {
+                cond,
+                then_block,
+                else_block,
+            } => {
+                self.load_general(function, cond, "%rax");
+                self.line("testq %rax, %rax".to_string());
+                let then_label = self.block_label(*then_block);
+                let else_label = self.block_label(*else_block);
+                self.line(format!("jnz {}", then_label));
+                self.line(format!("jmp {}", else_label));
+            }
+            Terminator::Return(value) => {
+                if is_sse(&function.return_type) {
+                    self.load_sse(value, "%xmm0");
+                } else {
+                    self.load_general(function, value, "%rax");
+                }
+                self.emit_epilogue();
+            }
+        }
+    }
+
+    /// Emits a call following the System V ABI, leaving the result in `%rax` or `%xmm0`.
+    ///
+    /// # Arguments
+    /// * `function` - The function being emitted.
+    /// * `callee` - What to call.
+    /// * `args` - The arguments, with whether each one goes in an SSE register.
+    /// * `position` - The position of the call, to save the SSE registers live across it.
+    fn emit_call(
+        &mut self,
+        function: &Function,
+        callee: Callee,
+        args: &[(Argument, bool)],
+        position: usize,
+    ) {
+        self.write_back_pointers(function, position);
+        let saved = self.allocation.sse_live_across(position);
+        for &var in saved.iter() {
+            let location = self.location(var);
+            self.line(format!("movsd {}, {}(%rbp)", location, self.slot(var)));
+        }
+
+        let (in_registers, on_stack) = place_arguments(args);
+        let padding = if on_stack.len() % 2 == 1 { 8 } else { 0 };
+        if padding > 0 {
+            self.line("subq $8, %rsp".to_string());
+        }
+        for (arg, sse) in on_stack.iter().rev() {
+            if *sse {
+                self.load_argument(function, arg, true, "%xmm0");
+                self.line("subq $8, %rsp".to_string());
+                self.line("movsd %xmm0, (%rsp)".to_string());
+            } else {
+                self.load_argument(function, arg, false, "%rax");
+                self.line("pushq %rax".to_string());
+            }
+        }
+        for (arg, sse, register) in in_registers.iter() {
+            self.load_argument(function, arg, *sse, register);
+        }
+
+        match callee {
+            Callee::Symbol(symbol) => self.line(format!("call {}", symbol)),
+            Callee::Virtual(receiver, offset) => {
+                self.load_general(function, receiver, "%r11");
+                self.line("movq (%r11), %r11".to_string());
+                self.line(format!("movq {}(%r11), %r11", offset));
+                self.line("call *%r11".to_string());
+            }
+        }
+
+        let pushed = 8 * on_stack.len() as i64 + padding;
+        if pushed > 0 {
+            self.line(format!("addq ${}, %rsp", pushed));
+        }
+        for &var in saved.iter() {
+            let location = self.location(var);
+            self.line(format!("movsd {}(%rbp), {}", self.slot(var), location));
+        }
+    }
+
+    /// Emits a call in tail position as a jump: the frame is torn down first, so the
+    /// callee returns straight to the caller of the function and deep chains of tail
+    /// calls run in constant stack. Only calls whose arguments all go in registers
+    /// qualify, since the frame holding the stacked ones is gone by the time the
+    /// callee runs.
+    ///
+    /// # Returns
+    /// Whether the call was emitted; if not, it must be emitted as a plain call.
+    fn emit_tail_call(
+        &mut self,
+        function: &Function,
+        callee: &Callee,
+        args: &[(Argument, bool)],
+    ) -> bool {
+        let (in_registers, on_stack) = place_arguments(args);
+        if !on_stack.is_empty() {
+            return false;
+        }
+        // Nothing allocates from here to the jump, so the roots can go first. `%rcx` is
+        // loaded with its argument afterwards.
+        self.emit_gc_pop();
+        for (arg, sse, register) in in_registers.iter() {
+            self.load_argument(function, arg, *sse, register);
+        }
+        if let Callee::Virtual(receiver, offset) = callee {
+            self.load_general(function, receiver, "%r11");
+            self.line("movq (%r11), %r11".to_string());
+            self.line(format!("movq {}(%r11), %r11", offset));
+        }
+        self.emit_frame_teardown();
+        match callee {
+            Callee::Symbol(symbol) => self.line(format!("jmp {}", symbol)),
+            Callee::Virtual(..) => self.line("jmp *%r11".to_string()),
+        }
+        true
+    }
+
+    /// Writes the strings and objects kept in general registers at `position` to their
+    /// slots, where the garbage collector finds them. Objects never move, so the
+    /// registers stay valid.
+    fn write_back_pointers(&mut self, function: &Function, position: usize) {
+        for var in self.allocation.live_at(position) {
+            if function.vars[var].ty.is_pointer() {
+                let location = self.location(var);
+                self.line(format!("movq {}, {}(%rbp)", location, self.slot(var)));
+            }
+        }
+    }
+
+    /// Lets the garbage collector run before an allocation. The operands of the
+    /// instruction at `position` are used after the call, so the SSE registers live at
+    /// that point are saved too, not only the ones live across it.
+    fn emit_safepoint(&mut self, function: &Function, position: usize) {
+        self.write_back_pointers(function, position);
+        let saved: Vec<VarId> = self
+            .allocation
+            .live_at(position)
+            .into_iter()
+            .filter(|&var| is_sse(&function.vars[var].ty))
+            .collect();
+        for &var in saved.iter() {
+            let location = self.location(var);
+            self.line(format!("movsd {}, {}(%rbp)", location, self.slot(var)));
+        }
+        self.line("call hulk_gc_safepoint".to_string());
+        for &var in saved.iter() {
+            let location = self.location(var);
+            self.line(format!("movsd {}(%rbp), {}", self.slot(var), location));
+        }
+    }
+
+    fn load_argument(&mut self, function: &Function, arg: &Argument, sse: bool, register: &str) {
+        match arg {
+            Argument::Value(operand) if sse => self.load_sse(operand, register),
+            Argument::Value(operand) => self.load_general(function, operand, register),
+            Argument::Frame(offset) => self.line(format!("movq {}(%rbp), {}", offset, register)),
+            Argument::Immediate(value) => self.line(format!("movq ${}, {}", value, register)),
+            Argument::Address(symbol) => self.line(format!("leaq {}(%rip), {}", symbol, register)),
+        }
+    }
+
+    fn value_arguments<'o>(
+        function: &Function,
+        operands: &'o [Operand],
+    ) -> Vec<(Argument<'o>, bool)> {
+        operands
+            .iter()
+            .map(|operand| {
+                (
+                    Argument::Value(operand),
+                    is_sse(&function.operand_type(operand)),
+                )
+            })
+            .collect()
+    }
+
+    /// Calls a runtime function taking a single value.
+    fn call_runtime(
+        &mut self,
+        function: &Function,
+        symbol: &str,
+        operand: &Operand,
+        position: usize,
+    ) {
+        let args = Self::value_arguments(function, std::slice::from_ref(operand));
+        self.emit_call(
+            function,
+            Callee::Symbol(symbol.to_string()),
+            &args,
+            position,
+        );
+    }
+
+    /// Returns the byte offset of `field` in objects of `type_name`.
+    fn field_offset(&self, type_name: &str, field: &str) -> i64 {
+        let index = self
+            .layout(type_name)
+            .field_index(field)
+            .unwrap_or_else(|| panic!("type `{}` has no field `{}`", type_name, field));
+        8 * (index as i64 + 1)
+    }
+
+    fn emit_instr(&mut self, function: &Function, instr: &Instr, position: usize) {
+        match instr {
+            Instr::Copy { dest, src } => {
+                self.load(function, src);
+                self.store(function, *dest);
+            }
+            Instr::Binary {
+                dest,
+                op,
+                left,
+                right,
+            } => {
+                if *op == BinaryOp::Concat {
+                    self.emit_safepoint(function, position);
+                }
+                self.emit_binary(function, *op, left, right, position);
+                self.store(function, *dest);
+            }
+            Instr::Unary { dest, op, operand } => {
+                match op {
+                    UnaryOp::Neg => {
+                        self.load_sse(operand, "%xmm0");
+                        let mask = self.number(0x8000_0000_0000_0000);
+                        self.line(format!("movsd {}, %xmm1", mask));
+                        self.line("xorpd %xmm1, %xmm0".to_string());
+                    }
+                    UnaryOp::Not => {
+                        self.load_general(function, operand, "%rax");
+                        self.line("xorq $1, %rax".to_string());
+                    }
+                }
+                self.store(function, *dest);
+            }
+            Instr::Call {
+                dest,
+                function: callee,
+                args,
+                tail,
+            } => {
+                let arguments = Self::value_arguments(function, args);
+                let callee = Callee::Symbol(self.callee_symbol(callee));
+                if !(*tail && self.emit_tail_call(function, &callee, &arguments)) {
+                    self.emit_call(function, callee, &arguments, position);
+                    self.store(function, *dest);
+                }
+            }
+            Instr::CallMethod {
+                dest,
+                receiver,
+                static_type,
+                method,
+                args,
+                tail,
+            } => {
+                let slot = self
+                    .layout(static_type)
+                    .method_index(method)
+                    .unwrap_or_else(|| panic!("type `{}` has no method `{}`", static_type, method));
+                let mut arguments = vec![(Argument::Value(receiver), false)];
+                arguments.extend(Self::value_arguments(function, args));
+                let callee = Callee::Virtual(receiver, 8 * (slot as i64 + 3));
+                if !(*tail && self.emit_tail_call(function, &callee, &arguments)) {
+                    self.emit_call(function, callee, &arguments, position);
+                    self.store(function, *dest);
+                }
+            }
+            Instr::Alloc {
+                dest,
+                type_name,
+                stack,
+            } => {
+                let size = object_size(self.layout(type_name));
+                match self.stack_objects.get(dest).copied().filter(|_| *stack) {
+                    Some(offset) => {
+                        self.line(format!("leaq {}(%rbp), %rax", offset));
+                        for word in 0..size / 8 {
+                            self.line(format!("movq $0, {}(%rax)", 8 * word));
+                        }
+                        self.line(format!("leaq {}(%rip), %rcx", vtable_symbol(type_name)));
+                        self.line("movq %rcx, (%rax)".to_string());
+                    }
+                    None => {
+                        self.emit_safepoint(function, position);
+                        let args = [
+                            (Argument::Address(vtable_symbol(type_name)), false),
+                            (Argument::Immediate(size), false),
+                        ];
+                        self.emit_call(
+                            function,
+                            Callee::Symbol("hulk_alloc_object".to_string()),
+                            &args,
+                            position,
+                        );
+                    }
+                }
+                self.store(function, *dest);
+            }
+            Instr::GetField {
+                dest,
+                object,
+                type_name,
+                field,
+            } => {
+                let offset = self.field_offset(type_name, field);
+                self.load_general(function, object, "%rcx");
+                if is_sse(&function.vars[*dest].ty) {
+                    self.line(format!("movsd {}(%rcx), %xmm0", offset));
+                } else {
+                    self.line(format!("movq {}(%rcx), %rax", offset));
+                }
+                self.store(function, *dest);
+            }
+            Instr::SetField {
+                object,
+                type_name,
+                field,
+                value,
+            } => {
+                let offset = self.field_offset(type_name, field);
+                self.load_general(function, object, "%rcx");
+                if is_sse(&function.operand_type(value)) {
+                    self.load_sse(value, "%xmm0");
+                    self.line(format!("movsd %xmm0, {}(%rcx)", offset));
+                } else {
+                    self.load_general(function, value, "%rax");
+                    self.line(format!("movq %rax, {}(%rcx)", offset));
+                }
+            }
+            Instr::Box { dest, value } => {
+                let boxer = match function.operand_type(value) {
+                    IrType::Number => Some("hulk_box_number"),
+                    IrType::Boolean => Some("hulk_box_bool"),
+                    IrType::String => Some("hulk_box_string"),
+                    IrType::Object(_) => None,
+                };
+                match boxer {
+                    Some(boxer) => {
+                        self.emit_safepoint(function, position);
+                        self.call_runtime(function, boxer, value, position)
+                    }
+                    None => self.load_general(function, value, "%rax"),
+                }
+                self.store(function, *dest);
+            }
+            Instr::Print { value } => {
+                let printer = match function.operand_type(value) {
+                    IrType::Number => "hulk_print_number",
+                    IrType::Boolean => "hulk_print_bool",
+                    IrType::String => "hulk_print_string",
+                    IrType::Object(_) => "hulk_print_object",
+                };
+                self.call_runtime(function, printer, value, position);
+            }
+        }
+    }
+
+    /// Converts `operand` to a string and saves it in scratch slot `index`.
+    fn string_to_scratch(
+        &mut self,
+        function: &Function,
+        operand: &Operand,
+        index: i64,
+        position: usize,
+    ) {
+        let converter = match function.operand_type(operand) {
+            IrType::Number => Some("hulk_number_to_string"),
+            IrType::Boolean => Some("hulk_bool_to_string"),
+            IrType::Object(_) => Some("hulk_object_to_string"),
+            IrType::String => None,
+        };
+        match converter {
+            Some(converter) => self.call_runtime(function, converter, operand, position),
+            None => self.load_general(function, operand, "%rax"),
+        }
+        let slot = self.scratch_slot(index);
+        self.line(format!("movq %rax, {}(%rbp)", slot));
+    }
+
+    /// Computes a binary operation into `%rax` or `%xmm0`.
+    fn emit_binary(
+        &mut self,
+        function: &Function,
+        op: BinaryOp,
+        left: &Operand,
+        right: &Operand,
+        position: usize,
+    ) {
+        let operand_type = function.operand_type(left);
+        match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
+                self.load_sse(left, "%xmm0");
+                self.load_sse(right, "%xmm1");
+                let instruction = match op {
+                    BinaryOp::Add => "addsd",
+                    BinaryOp::Sub => "subsd",
+                    BinaryOp::Mul => "mulsd",
+                    _ => "divsd",
+                };
+                self.line(format!("{} %xmm1, %xmm0", instruction));
+            }
+            BinaryOp::Mod | BinaryOp::Pow => {
+                let symbol = if op == BinaryOp::Mod {
+                    "fmod@PLT"
+                } else {
+                    "pow@PLT"
+                };
+                let args = [
+                    (Argument::Value(left), true),
+                    (Argument::Value(right), true),
+                ];
+                self.emit_call(
+                    function,
+                    Callee::Symbol(symbol.to_string()),
+                    &args,
+                    position,
+                );
+            }
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+                self.load_sse(left, "%xmm0");
+                self.load_sse(right, "%xmm1");
+                // `ucomisd a, b` compares b with a; "above" is false for NaN.
+                let (compare, set) = match op {
+                    BinaryOp::Lt => ("%xmm0, %xmm1", "seta"),
+                    BinaryOp::Le => ("%xmm0, %xmm1", "setae"),
+                    BinaryOp::Gt => ("%xmm1, %xmm0", "seta"),
+                    _ => ("%xmm1, %xmm0", "setae"),
+                };
+                self.line(format!("ucomisd {}", compare));
+                self.line(format!("{} %al", set));
+                self.line("movzbq %al, %rax".to_string());
+            }
+            BinaryOp::And | BinaryOp::Or => {
+                self.load_general(function, left, "%rax");
+                self.load_general(function, right, "%rcx");
+                let instruction = if op == BinaryOp::And { "andq" } else { "orq" };
+                self.line(format!("{} %rcx, %rax", instruction));
+            }
+            BinaryOp::Eq | BinaryOp::Ne => {
+                let equal = op == BinaryOp::Eq;
+                match operand_type {
+                    IrType::Number => {
+                        self.load_sse(left, "%xmm0");
+                        self.load_sse(right, "%xmm1");
+                        self.line("ucomisd %xmm1, %xmm0".to_string());
+                        if equal {
+                            self.line("sete %al".to_string());
+                            self.line("setnp %cl".to_string());
+                            self.line("andb %cl, %al".to_string());
+                        } else {
+                            self.line("setne %al".to_string());
+                            self.line("setp %cl".to_string());
+                            self.line("orb %cl, %al".to_string());
+                        }
+                    }
+                    IrType::Boolean => {
+                        self.load_general(function, left, "%rax");
+                        self.load_general(function, right, "%rcx");
+                        self.line("cmpq %rcx, %rax".to_string());
+                        self.line(format!("{} %al", if equal { "sete" } else { "setne" }));
+                    }
+                    IrType::String | IrType::Object(_) => {
+                        let symbol = match operand_type {
+                            IrType::String => "hulk_string_eq",
+                            _ => "hulk_object_eq",
+                        };
+                        let args = [
+                            (Argument::Value(left), false),
+                            (Argument::Value(right), false),
+                        ];
+                        self.emit_call(
+                            function,
+                            Callee::Symbol(symbol.to_string()),
+                            &args,
+                            position,
+                        );
+                        if !equal {
+                            self.line("xorb $1, %al".to_string());
+                        }
+                    }
+                }
+                self.line("movzbq %al, %rax".to_string());
+            }
+            BinaryOp::Concat => {
+                self.string_to_scratch(function, left, 0, position);
+                self.string_to_scratch(function, right, 1, position);
+                let args = [
+                    (Argument::Frame(self.scratch_slot(0)), false),
+                    (Argument::Frame(self.scratch_slot(1)), false),
+                ];
+                self.emit_call(
+                    function,
+                    Callee::Symbol("hulk_concat".to_string()),
+                    &args,
+                    position,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Errors of every phase in one shape, so tools can report them the same way.
+//!
+//! A [`Diagnostic`] keeps the phase that found the problem, its message and, when the
+//! problem points at the source, the span it refers to. It can be rendered for people,
+//! with the offending line and a caret under it, or as one line of JSON for editors
+//! and scripts.
+
+use std::fmt;
+
+use lalrpop_util::ParseError;
+
+use crate::bytecode::vm::VmError;
+use crate::interpreter::runtime_error::RuntimeError;
+use crate::semantic::semantic_errors::SemanticError;
+use crate::tokens::Span;
+
+/// The phase of the compiler that reported a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Lexical,
+    Syntax,
+    Semantic,
+    Runtime,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Phase::Lexical => "lexical",
+            Phase::Syntax => "syntax",
+            Phase::Semantic => "semantic",
+            Phase::Runtime => "runtime",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An error found in a program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub phase: Phase,
+    pub message: String,
+    /// The region of the source the error refers to, if any.
+    pub span: Option<Span>,
+}
+
+/// How diagnostics are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// `file:line:column: phase error: message`, followed by the source line.
+    Human,
+    /// One JSON object per diagnostic and line.
+    Json,
+}
+
+impl Diagnostic {
+    pub fn new(phase: Phase, message: impl Into<String>, span: Option<Span>) -> Self {
+        Diagnostic {
+            phase,
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// Describes an error of the parser generated from `parser.lalrpop`.
+    pub fn from_parse_error<T: fmt::Display, E: fmt::Display>(
+        error: &ParseError<usize, T, E>,
+    ) -> Self {
+        match error {
+            ParseError::InvalidToken { location } => Diagnostic::new(
+                Phase::Lexical,
+                "unrecognized token",
+                Some(Span::new(*location, *location + 1)),
+            ),
+            ParseError::UnrecognizedEof { location, expected } => Diagnostic::new(
+                Phase::Syntax,
+                format!("unexpected end of input{}", expectation(expected)),
+                Some(Span::new(*location, *location)),
+            ),
+            ParseError::UnrecognizedToken {
+                token: (start, token, end),
+                expected,
+            } => Diagnostic::new(
+                Phase::Syntax,
+                format!("unexpected `{}`{}", token, expectation(expected)),
+                Some(Span::new(*start, *end)),
+            ),
+            ParseError::ExtraToken {
+                token: (start, token, end),
+            } => Diagnostic::new(
+                Phase::Syntax,
+                format!("unexpected `{}` after the end of the program", token),
+                Some(Span::new(*start, *end)),
+            ),
+            ParseError::User { error } => Diagnostic::new(Phase::Syntax, error.to_string(), None),
+        }
+    }
+
+    /// Renders the diagnostic for a program read from `file_name`.
+    pub fn render(&self, format: ErrorFormat, file_name: &str, source: &str) -> String {
+        match format {
+            ErrorFormat::Human => self.render_human(file_name, source),
+            ErrorFormat::Json => self.render_json(file_name, source),
+        }
+    }
+
+    fn render_human(&self, file_name: &str, source: &str) -> String {
+        let Some(span) = self.span else {
+            return format!("{}: {} error: {}", file_name, self.phase, self.message);
+        };
+        let (line, column) = position(source, span.start);
+        let text = source.lines().nth(line - 1).unwrap_or("");
+        let width = source[span.start..span.end.min(source.len())]
+            .lines()
+            .next()
+            .map_or(0, |first| first.chars().count());
+        let gutter = " ".repeat(line.to_string().len());
+        format!(
+            "{}:{}:{}: {} error: {}\n{} |\n{} | {}\n{} | {}{}",
+            file_name,
+            line,
+            column,
+            self.phase,
+            self.message,
+            gutter,
+            line,
+            text,
+            gutter,
+            " ".repeat(column - 1),
+            "^".repeat(width.max(1))
+        )
+    }
+
+    fn render_json(&self, file_name: &str, source: &str) -> String {
+        let location = match self.span {
+            Some(span) => {
+                let (line, column) = position(source, span.start);
+                format!(
+                    "\"line\":{},\"column\":{},\"start\":{},\"end\":{}",
+                    line, column, span.start, span.end
+                )
+            }
+            None => "\"line\":null,\"column\":null,\"start\":null,\"end\":null".to_string(),
+        };
+        format!(
+            "{{\"file\":{},\"phase\":\"{}\",\"message\":{},{}}}",
+            json_string(file_name),
+            self.phase,
+            json_string(&self.message),
+            location
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} error: {}", self.phase, self.message)
+    }
+}
+
+impl From<&SemanticError> for Diagnostic {
+    /// Statements out of order are syntax errors: the grammar rejects such programs, and
+    /// only the recovering CST parser gets far enough to say which statement is wrong.
+    fn from(error: &SemanticError) -> Self {
+        let phase = match error {
+            SemanticError::MissingEntryExpression { .. }
+            | SemanticError::ExtraEntryExpression { .. }
+            | SemanticError::DeclarationAfterEntry { .. } => Phase::Syntax,
+            _ => Phase::Semantic,
+        };
+        Diagnostic::new(phase, error.to_string(), Some(error.span()))
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(error: &RuntimeError) -> Self {
+        Diagnostic::new(Phase::Runtime, error.to_string(), Some(error.span()))
+    }
+}
+
+impl From<&VmError> for Diagnostic {
+    fn from(error: &VmError) -> Self {
+        Diagnostic::new(Phase::Runtime, error.to_string(), None)
+    }
+}
+
+/// Returns the 1-based line and column, counted in characters, of a byte offset.
+pub fn position(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+/// Lists the terminals the parser expected, as LALRPOP names them.
+fn expectation(expected: &[String]) -> String {
+    if expected.is_empty() {
+        return String::new();
+    }
+    let names: Vec<String> = expected.iter().map(|name| terminal_name(name)).collect();
+    format!(", expected one of {}", names.join(", "))
+}
+
+/// Turns a LALRPOP terminal, a quoted literal or a regular expression, into a name.
+fn terminal_name(terminal: &str) -> String {
+    match terminal.strip_prefix("r#\"") {
+        Some(pattern) if pattern.starts_with("[A-Za-z]") => "identifier".to_string(),
+        Some(pattern) if pattern.starts_with("[0-9]") => "number".to_string(),
+        Some(_) => "string".to_string(),
+        None => format!("`{}`", terminal.trim_matches('"')),
+    }
+}
+
+/// Quotes `text` as a JSON string.
+pub fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,272 @@
+//! `AstGraph` implements the [`Visitor`] trait to draw the AST.
+//!
+//! Every node is a box with its kind on the first line, the names or values it holds
+//! on the second and, after the semantic phase, its static type on the last. Edges go
+//! from a node to its children, labeled with their role when a node has children of
+//! different roles, such as the condition and body of a loop.
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::BlockNode;
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::FunctionCallNode;
+use crate::ast_nodes::function_def::{FunctionDefNode, FunctionParams};
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::{Assignment, LetInNode};
+use crate::ast_nodes::literals::{
+    BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode,
+};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::Program;
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+use crate::types_tree::tree_node::TypeNode;
+use crate::visitor::accept::Accept;
+use crate::visitor::visitor_trait::Visitor;
+
+use super::Graph;
+
+/// A visitor that adds one DOT node per AST node and returns its id.
+pub struct AstGraph {
+    graph: Graph,
+}
+
+impl Default for AstGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AstGraph {
+    pub fn new() -> Self {
+        AstGraph {
+            graph: Graph::new("ast", "shape=box, fontname=monospace"),
+        }
+    }
+
+    /// Draws the full program.
+    pub fn draw_program(mut self, node: &mut Program) -> String {
+        let root = self.graph.node("Program");
+        for statement in node.statements.iter_mut() {
+            let child = statement.accept(&mut self);
+            self.graph.edge(root, child, "");
+        }
+        self.graph.finish()
+    }
+
+    /// Adds the node of `kind`, with `value` and `node_type` below it when present.
+    fn node(&mut self, kind: &str, value: &str, node_type: Option<&TypeNode>) -> usize {
+        let mut label = kind.to_string();
+        if !value.is_empty() {
+            label.push('\n');
+            label.push_str(value);
+        }
+        if let Some(node_type) = node_type {
+            label.push_str(&format!("\n: {}", node_type.type_name));
+        }
+        self.graph.node(&label)
+    }
+
+    /// Visits `child` and links it to `parent`.
+    fn child(&mut self, parent: usize, child: &mut Expression, role: &str) {
+        let child = child.accept(self);
+        self.graph.edge(parent, child, role);
+    }
+
+    fn children(&mut self, parent: usize, children: &mut [Expression]) {
+        for child in children.iter_mut() {
+            self.child(parent, child, "");
+        }
+    }
+
+    fn assignment(&mut self, kind: &str, node: &mut Assignment) -> usize {
+        let id = self.node(kind, &node.identifier, node.node_type.as_ref());
+        self.child(id, &mut node.expression, "");
+        id
+    }
+}
+
+fn signature(params: &[FunctionParams], return_type: &str) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .map(|param| format!("{}: {}", param.name, param.signature))
+        .collect();
+    format!("({}): {}", params.join(", "), return_type)
+}
+
+impl Visitor<usize> for AstGraph {
+    fn visit_function_def(&mut self, node: &mut FunctionDefNode) -> usize {
+        let value = format!(
+            "{}{}",
+            node.name,
+            signature(&node.params, &node.return_type)
+        );
+        let id = self.node("FunctionDef", &value, node.node_type.as_ref());
+        self.child(id, &mut node.body, "");
+        id
+    }
+
+    fn visit_literal_number(&mut self, node: &mut NumberLiteralNode) -> usize {
+        self.node("Number", &node.value.to_string(), node.node_type.as_ref())
+    }
+
+    fn visit_literal_boolean(&mut self, node: &mut BooleanLiteralNode) -> usize {
+        self.node("Boolean", &node.value.to_string(), node.node_type.as_ref())
+    }
+
+    fn visit_literal_string(&mut self, node: &mut StringLiteralNode) -> usize {
+        let value = format!("\"{}\"", node.value);
+        self.node("String", &value, node.node_type.as_ref())
+    }
+
+    fn visit_identifier(&mut self, node: &mut IdentifierNode) -> usize {
+        self.node("Identifier", &node.value, node.node_type.as_ref())
+    }
+
+    fn visit_function_call(&mut self, node: &mut FunctionCallNode) -> usize {
+        let id = self.node("Call", &node.function_name, node.node_type.as_ref());
+        self.children(id, &mut node.arguments);
+        id
+    }
+
+    fn visit_while_loop(&mut self, node: &mut WhileNode) -> usize {
+        let id = self.node("While", "", node.node_type.as_ref());
+        self.child(id, &mut node.condition, "condition");
+        self.child(id, &mut node.body, "body");
+        id
+    }
+
+    fn visit_for_loop(&mut self, node: &mut ForNode) -> usize {
+        let id = self.node("For", &node.variable, node.node_type.as_ref());
+        self.child(id, &mut node.start, "start");
+        self.child(id, &mut node.end, "end");
+        self.child(id, &mut node.body, "body");
+        id
+    }
+
+    fn visit_code_block(&mut self, node: &mut BlockNode) -> usize {
+        let id = self.node("Block", "", node.node_type.as_ref());
+        self.children(id, &mut node.expression_list.expressions);
+        id
+    }
+
+    fn visit_binary_op(&mut self, node: &mut BinaryOpNode) -> usize {
+        let id = self.node(
+            "BinaryOp",
+            &node.operator.to_string(),
+            node.node_type.as_ref(),
+        );
+        self.child(id, &mut node.left, "");
+        self.child(id, &mut node.right, "");
+        id
+    }
+
+    fn visit_unary_op(&mut self, node: &mut UnaryOpNode) -> usize {
+        let id = self.node(
+            "UnaryOp",
+            &node.operator.to_string(),
+            node.node_type.as_ref(),
+        );
+        self.child(id, &mut node.operand, "");
+        id
+    }
+
+    fn visit_if_else(&mut self, node: &mut IfElseNode) -> usize {
+        let id = self.node("If", "", node.node_type.as_ref());
+        self.child(id, &mut node.condition, "condition");
+        self.child(id, &mut node.if_expression, "then");
+        for (condition, body) in node.elifs.iter_mut() {
+            match condition {
+                Some(condition) => {
+                    self.child(id, condition, "elif");
+                    self.child(id, body, "then");
+                }
+                None => self.child(id, body, "else"),
+            }
+        }
+        id
+    }
+
+    fn visit_let_in(&mut self, node: &mut LetInNode) -> usize {
+        let id = self.node("LetIn", "", node.node_type.as_ref());
+        for assignment in node.assignments.iter_mut() {
+            let binding = self.assignment("Binding", assignment);
+            self.graph.edge(id, binding, "");
+        }
+        self.child(id, &mut node.body, "body");
+        id
+    }
+
+    fn visit_destructive_assign(&mut self, node: &mut DestructiveAssignNode) -> usize {
+        let id = self.node("DestructiveAssign", ":=", node.node_type.as_ref());
+        self.child(id, &mut node.identifier, "target");
+        self.child(id, &mut node.expression, "value");
+        id
+    }
+
+    fn visit_type_def(&mut self, node: &mut TypeDefNode) -> usize {
+        let params: Vec<String> = node
+            .params
+            .iter()
+            .map(|param| format!("{}: {}", param.name, param.signature))
+            .collect();
+        let mut value = format!("{}({})", node.identifier, params.join(", "));
+        if let Some(parent) = &node.parent {
+            value.push_str(&format!(" inherits {}", parent));
+        }
+        let id = self.node("TypeDef", &value, node.node_type.as_ref());
+        for argument in node.parent_args.iter_mut() {
+            self.child(id, argument, "parent argument");
+        }
+        for member in node.members.iter_mut() {
+            let child = match member {
+                TypeMember::Property(assignment) => self.assignment("Attribute", assignment),
+                TypeMember::Method(method) => {
+                    let value = format!(
+                        "{}{}",
+                        method.name,
+                        signature(&method.params, &method.return_type)
+                    );
+                    let method_id = self.node("Method", &value, method.node_type.as_ref());
+                    self.child(method_id, &mut method.body, "");
+                    method_id
+                }
+            };
+            self.graph.edge(id, child, "");
+        }
+        id
+    }
+
+    fn visit_type_instance(&mut self, node: &mut TypeInstanceNode) -> usize {
+        let id = self.node("New", &node.type_name, node.node_type.as_ref());
+        self.children(id, &mut node.arguments);
+        id
+    }
+
+    fn visit_type_function_access(&mut self, node: &mut TypeFunctionAccessNode) -> usize {
+        let id = self.node(
+            "MethodCall",
+            &node.member.function_name,
+            node.node_type.as_ref(),
+        );
+        self.child(id, &mut node.object, "receiver");
+        self.children(id, &mut node.member.arguments);
+        id
+    }
+
+    fn visit_type_prop_access(&mut self, node: &mut TypePropAccessNode) -> usize {
+        let id = self.node("AttributeAccess", &node.member, node.node_type.as_ref());
+        self.child(id, &mut node.object, "");
+        id
+    }
+
+    fn visit_print(&mut self, node: &mut PrintNode) -> usize {
+        let id = self.node("Print", "", node.node_type.as_ref());
+        self.child(id, &mut node.expression, "");
+        id
+    }
+}
@@ -0,0 +1,74 @@
+//! Draws the control-flow graph of every function of a [`Module`].
+//!
+//! Each function is a cluster titled with its signature. Every basic block is a box
+//! with its name, its instructions and its terminator; branches label their edges
+//! `true` and `false`. Blocks the entry cannot reach are drawn too, dashed.
+
+use crate::intermediate::ir::{Function, Module, Terminator};
+
+use super::{quote, Graph};
+
+pub fn draw_module(module: &Module) -> String {
+    let mut graph = Graph::new("cfg", "shape=box, fontname=monospace");
+    for (index, function) in module.functions.iter().enumerate() {
+        graph.raw(&format!("subgraph cluster_{} {{", index));
+        graph.raw(&format!("label={};", quote(&title(function))));
+        draw_function(&mut graph, function);
+        graph.raw("}");
+    }
+    graph.finish()
+}
+
+fn title(function: &Function) -> String {
+    let params: Vec<String> = function
+        .params
+        .iter()
+        .map(|&param| format!("{}: {}", function.var_name(param), function.vars[param].ty))
+        .collect();
+    format!(
+        "{}({}): {}",
+        function.name,
+        params.join(", "),
+        function.return_type
+    )
+}
+
+fn draw_function(graph: &mut Graph, function: &Function) {
+    let reachable = function.reachable_blocks();
+    let ids: Vec<usize> = function
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(id, block)| {
+            let mut label = format!("bb{}:", id);
+            if id == function.entry {
+                label.push_str(" (entry)");
+            }
+            for instr in block.instrs.iter() {
+                label.push_str("\n    ");
+                label.push_str(&function.fmt_instr(instr));
+            }
+            label.push_str("\n    ");
+            label.push_str(&function.fmt_terminator(&block.terminator));
+            let node = graph.node(&label);
+            if !reachable.contains(&id) {
+                graph.raw(&format!("n{} [style=dashed];", node));
+            }
+            node
+        })
+        .collect();
+    for (id, block) in function.blocks.iter().enumerate() {
+        match &block.terminator {
+            Terminator::Jump(target) => graph.edge(ids[id], ids[*target], ""),
+            Terminator::Branch {
+                then_block,
+                else_block,
+                ..
+            } => {
+                graph.edge(ids[id], ids[*then_block], "true");
+                graph.edge(ids[id], ids[*else_block], "false");
+            }
+            Terminator::Return(_) => {}
+        }
+    }
+}
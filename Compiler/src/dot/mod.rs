@@ -0,0 +1,84 @@
+//! Graphviz DOT export of the phases of the compiler, for teaching and debugging.
+//!
+//! - [`ast`] draws the syntax tree, each node with its kind, value and static type.
+//! - [`scopes`] draws the tree of scopes the semantic phase opens, with their symbols.
+//! - [`cfg`] draws the control-flow graph of every function of the IR.
+//!
+//! The output is plain text that `dot -Tsvg` and other Graphviz tools render.
+
+pub mod ast;
+pub mod cfg;
+pub mod scopes;
+
+/// A directed graph being written as DOT.
+pub(crate) struct Graph {
+    out: String,
+    nodes: usize,
+}
+
+impl Graph {
+    /// Starts a graph named `name` whose nodes have the attributes `node_attributes`.
+    pub(crate) fn new(name: &str, node_attributes: &str) -> Self {
+        Graph {
+            out: format!(
+                "digraph {} {{\n    node [{}];\n",
+                quote(name),
+                node_attributes
+            ),
+            nodes: 0,
+        }
+    }
+
+    /// Adds a node with `label`, which may span several lines, and returns its id.
+    pub(crate) fn node(&mut self, label: &str) -> usize {
+        let id = self.nodes;
+        self.nodes += 1;
+        self.out
+            .push_str(&format!("    n{} [label={}];\n", id, quote(label)));
+        id
+    }
+
+    /// Adds an edge, with a label when `label` is not empty.
+    pub(crate) fn edge(&mut self, from: usize, to: usize, label: &str) {
+        if label.is_empty() {
+            self.out.push_str(&format!("    n{} -> n{};\n", from, to));
+        } else {
+            self.out.push_str(&format!(
+                "    n{} -> n{} [label={}];\n",
+                from,
+                to,
+                quote(label)
+            ));
+        }
+    }
+
+    /// Writes `line` as is, for subgraphs and attributes.
+    pub(crate) fn raw(&mut self, line: &str) {
+        self.out.push_str("    ");
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+
+    pub(crate) fn finish(mut self) -> String {
+        self.out.push_str("}\n");
+        self.out
+    }
+}
+
+/// Quotes `text` as a DOT string. Lines end in `\l`, so they are left-justified.
+pub(crate) fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\l"),
+            c => quoted.push(c),
+        }
+    }
+    if text.contains('\n') {
+        quoted.push_str("\\l");
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,37 @@
+//! Draws the tree of scopes of a [`SymbolTable`].
+//!
+//! Every scope is a box with its name on the first line and one line per symbol it
+//! declares, in the order they appear in the source. Edges go from a scope to the
+//! scopes nested in it.
+
+use crate::symbol_table::{SymbolTable, SymbolType};
+
+use super::Graph;
+
+pub fn draw_scopes(table: &SymbolTable) -> String {
+    let mut graph = Graph::new("scopes", "shape=box, fontname=monospace");
+    let mut ids = Vec::with_capacity(table.scopes.len());
+    for scope in table.scopes.iter() {
+        let mut symbols: Vec<_> = scope.symbols.values().collect();
+        symbols.sort_by_key(|symbol| (symbol.span.start, symbol.name.clone()));
+        let mut label = scope.name.clone();
+        for symbol in symbols {
+            let kind = match symbol.symbol_type {
+                SymbolType::Variable => "variable",
+                SymbolType::Parameter => "parameter",
+                SymbolType::Function => "function",
+            };
+            label.push_str(&format!(
+                "\n{}: {} ({})",
+                symbol.name, symbol.type_name, kind
+            ));
+        }
+        ids.push(graph.node(&label));
+    }
+    for (id, scope) in table.scopes.iter().enumerate() {
+        for &child in scope.children.iter() {
+            graph.edge(ids[id], ids[child], "");
+        }
+    }
+    graph.finish()
+}
@@ -0,0 +1,120 @@
+//! The phases of the compiler chained the way every tool runs them.
+//!
+//! Each function stops at the first phase that finds errors and returns them as
+//! [`Diagnostic`]s, so the command-line driver and other front ends report them alike.
+
+use crate::ast_nodes::program::Program;
+use crate::cst;
+use crate::diagnostics::{Diagnostic, Phase};
+use crate::intermediate::escape::EscapeAnalysis;
+use crate::intermediate::ir::Module;
+use crate::intermediate::ir_generator::IrGenerator;
+use crate::intermediate::loop_optimizations::LoopOptimizer;
+use crate::lexer::Lexer;
+use crate::parser::ProgramParser;
+use crate::semantic::desugar::Desugarer;
+use crate::semantic::devirtualization::DevirtualizationVisitor;
+use crate::semantic::semantic_visitor::{structure_errors, SemanticVisitor};
+use crate::semantic::tail_calls::TailCallVisitor;
+use crate::symbol_table::SymbolTable;
+use crate::tokens::{Span, Token};
+use crate::types_tree::type_tree::TypeTree;
+
+/// A program that passed the semantic phase, with the types it defines and the scopes
+/// it opens.
+pub struct CheckedProgram {
+    pub program: Program,
+    pub type_tree: TypeTree,
+    pub symbol_table: SymbolTable,
+    /// What each optimization pass that ran did, one summary per pass.
+    pub reports: Vec<String>,
+}
+
+/// Splits `source` into tokens, reporting the characters that start none.
+pub fn tokenize(source: &str) -> (Vec<(Token, Span)>, Vec<Diagnostic>) {
+    let mut lexer = Lexer::new(source);
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    while let Some((token, span)) = lexer.next_token() {
+        if let Token::Unknown(c) = token {
+            errors.push(Diagnostic::new(
+                Phase::Lexical,
+                format!("unexpected character `{}`", c),
+                Some(span),
+            ));
+        }
+        tokens.push((token, span));
+    }
+    (tokens, errors)
+}
+
+/// Parses `source` with the grammar, which only accepts declarations followed by one
+/// entry expression.
+///
+/// When the grammar rejects a program the recovering CST parser accepts, its
+/// statements are only out of order, and the errors say which ones.
+pub fn parse(source: &str) -> Result<Program, Vec<Diagnostic>> {
+    ProgramParser::new().parse(source).map_err(|error| {
+        let misplaced = match cst::parse(source).to_ast() {
+            Ok(program) => structure_errors(&program),
+            Err(_) => Vec::new(),
+        };
+        if misplaced.is_empty() {
+            vec![Diagnostic::from_parse_error(&error)]
+        } else {
+            misplaced.iter().map(Diagnostic::from).collect()
+        }
+    })
+}
+
+/// Parses `source` and runs the semantic phase, which annotates the program with types.
+pub fn check(source: &str) -> Result<CheckedProgram, Vec<Diagnostic>> {
+    let mut program = parse(source)?;
+    let mut semantic = SemanticVisitor::new();
+    semantic
+        .check_program(&mut program)
+        .map_err(|errors| errors.iter().map(Diagnostic::from).collect::<Vec<_>>())?;
+    Ok(CheckedProgram {
+        program,
+        type_tree: semantic.type_tree,
+        symbol_table: semantic.symbol_table,
+        reports: Vec::new(),
+    })
+}
+
+/// Checks `source`, desugars it into the core language and runs the passes that
+/// annotate the AST for the backends: tail calls and devirtualization.
+pub fn analyze(source: &str) -> Result<CheckedProgram, Vec<Diagnostic>> {
+    let CheckedProgram {
+        program,
+        type_tree,
+        symbol_table,
+        ..
+    } = check(source)?;
+    let mut program = Desugarer::new(&type_tree).desugar_program(program);
+    TailCallVisitor::new().mark_program(&mut program);
+    let mut devirtualization = DevirtualizationVisitor::new(&type_tree);
+    devirtualization.devirtualize_program(&mut program);
+    let reports = vec![devirtualization.report()];
+    Ok(CheckedProgram {
+        program,
+        type_tree,
+        symbol_table,
+        reports,
+    })
+}
+
+/// Translates an analyzed program into the optimized intermediate representation the
+/// x86-64 and LLVM backends take.
+///
+/// The reports of the IR passes are added to those of `checked`.
+pub fn lower(checked: &mut CheckedProgram) -> Module {
+    let mut module = IrGenerator::new(&checked.type_tree).generate_program(&mut checked.program);
+    let mut escape = EscapeAnalysis::new();
+    escape.analyze_module(&mut module);
+    let mut loops = LoopOptimizer::new();
+    loops.optimize_module(&mut module);
+    checked.reports.push(escape.report());
+    checked.reports.push(loops.report());
+    module
+}
@@ -0,0 +1,135 @@
+//! A document layout engine in the style of Wadler's "A prettier printer".
+//!
+//! A [`Doc`] describes text together with the places where it may be broken into
+//! lines. A [`Doc::Group`] is laid out flat, with its line breaks turned into spaces
+//! or nothing, when it fits in the rest of the line up to the next possible break;
+//! otherwise all the breaks directly inside it become newlines. Groups nested in a
+//! broken group decide again for themselves.
+
+/// A document to lay out.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Doc {
+    Nil,
+    /// Text without newlines.
+    Text(String),
+    /// A space when flat, a newline when broken.
+    Line,
+    /// Nothing when flat, a newline when broken.
+    SoftLine,
+    /// Always a newline. A group containing one never fits flat.
+    HardLine,
+    /// Indents the lines that start inside the document by that many more columns.
+    Nest(usize, Box<Doc>),
+    Concat(Vec<Doc>),
+    Group(Box<Doc>),
+}
+
+pub fn text(text: impl Into<String>) -> Doc {
+    Doc::Text(text.into())
+}
+
+pub fn nest(indent: usize, doc: Doc) -> Doc {
+    Doc::Nest(indent, Box::new(doc))
+}
+
+pub fn concat(docs: Vec<Doc>) -> Doc {
+    Doc::Concat(docs)
+}
+
+pub fn group(doc: Doc) -> Doc {
+    Doc::Group(Box::new(doc))
+}
+
+/// Puts `separator` between every two documents.
+pub fn join(docs: Vec<Doc>, separator: Doc) -> Doc {
+    let mut joined = Vec::with_capacity(docs.len() * 2);
+    for (index, doc) in docs.into_iter().enumerate() {
+        if index > 0 {
+            joined.push(separator.clone());
+        }
+        joined.push(doc);
+    }
+    Doc::Concat(joined)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// A document still to lay out, with the indentation and mode it is laid out in.
+type Command<'a> = (usize, Mode, &'a Doc);
+
+/// Returns whether `next`, followed by `rest` up to its first line break, fits in
+/// `width` columns.
+fn fits(width: usize, next: Command, rest: &[Command]) -> bool {
+    let mut remaining = width as isize;
+    let mut pending = vec![next];
+    let mut rest = rest.iter().rev();
+    while remaining >= 0 {
+        let (indent, mode, doc) = match pending.pop() {
+            Some(command) => command,
+            None => match rest.next() {
+                Some(&command) => command,
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Nil => {}
+            Doc::Text(text) => remaining -= text.chars().count() as isize,
+            Doc::Line if mode == Mode::Flat => remaining -= 1,
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine => return true,
+            Doc::HardLine => return mode == Mode::Break,
+            Doc::Nest(more, doc) => pending.push((indent + more, mode, doc)),
+            Doc::Concat(docs) => pending.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Group(doc) => pending.push((indent, mode, doc)),
+        }
+    }
+    false
+}
+
+/// Lays out `doc` in lines of at most `width` columns where its groups allow it.
+pub fn pretty(doc: &Doc, width: usize) -> String {
+    let mut out = String::new();
+    let mut column = 0;
+    let mut commands: Vec<Command> = vec![(0, Mode::Break, doc)];
+    while let Some((indent, mode, doc)) = commands.pop() {
+        match doc {
+            Doc::Nil => {}
+            Doc::Text(text) => {
+                out.push_str(text);
+                column = match text.rfind('\n') {
+                    Some(newline) => text[newline + 1..].chars().count(),
+                    None => column + text.chars().count(),
+                };
+            }
+            Doc::Line if mode == Mode::Flat => {
+                out.push(' ');
+                column += 1;
+            }
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                let trimmed = out.trim_end_matches(' ').len();
+                out.truncate(trimmed);
+                out.push('\n');
+                out.push_str(&" ".repeat(indent));
+                column = indent;
+            }
+            Doc::Nest(more, doc) => commands.push((indent + more, mode, doc)),
+            Doc::Concat(docs) => commands.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Group(doc) => {
+                let flat = (indent, Mode::Flat, doc.as_ref());
+                let fits_flat =
+                    mode == Mode::Flat || fits(width.saturating_sub(column), flat, &commands);
+                commands.push(if fits_flat {
+                    flat
+                } else {
+                    (indent, Mode::Break, doc)
+                });
+            }
+        }
+    }
+    out
+}
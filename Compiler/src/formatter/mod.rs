@@ -0,0 +1,490 @@
+//! The canonical layout of HULK source, as `hulk fmt` writes it.
+//!
+//! [`Formatter`] implements the [`Visitor`] trait to turn the AST into a [`Doc`], and
+//! [`doc::pretty`] lays it out in lines of the requested width. Since the text is built
+//! from the AST alone, formatting keeps the AST and formatting twice gives the same
+//! text.
+//!
+//! The layout rules:
+//! - Indentation is four spaces, and every expression of a block ends in `;`.
+//! - Blocks with one expression stay on one line when it fits, `{ x; }`; longer blocks
+//!   take one line per expression.
+//! - `elif` and `else` follow the `}` that closes the previous branch, and the
+//!   branches of an `if` are all broken or all flat.
+//! - Parentheses appear only where the grammar needs them: an operand whose operator
+//!   binds less tightly than the one around it, the right operand of a
+//!   left-associative operator at the same level, or a prefix operator applied to
+//!   itself, `-(-x)`.
+//! - Declarations are separated from what follows by a blank line.
+//! - [`format_source`] keeps the `//` comments between statements, each on its own
+//!   line or after the `;` of its statement.
+
+pub mod doc;
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::BlockNode;
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::FunctionCallNode;
+use crate::ast_nodes::function_def::{FunctionDefNode, FunctionParams};
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::{Assignment, LetInNode};
+use crate::ast_nodes::literals::{
+    BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode,
+};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::{Program, Statement};
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+use crate::cst::{self, kind::SyntaxKind};
+use crate::operators::{self, Associativity, ASSIGN, UNARY};
+use crate::tokens::{OperatorToken, Span};
+use crate::visitor::accept::Accept;
+use crate::visitor::visitor_trait::Visitor;
+
+use doc::{concat, group, join, nest, text, Doc};
+
+/// The line width `hulk fmt` uses unless told otherwise.
+pub const DEFAULT_WIDTH: usize = 80;
+
+const INDENT: usize = 4;
+
+/// `let`, `if`, `while` and `for`, which may be operands but not receivers.
+const COMPOSITE: u8 = UNARY + 1;
+const PRIMARY: u8 = UNARY + 2;
+
+/// Returns the precedence of a binary operator in the operator table.
+fn operator_level(operator: &OperatorToken) -> u8 {
+    operators::infix_operator(operator).map_or(PRIMARY, |entry| entry.precedence)
+}
+
+/// Returns how tightly `expr` binds, to decide whether it needs parentheses.
+fn level(expr: &Expression) -> u8 {
+    match expr {
+        Expression::DestructiveAssign(_) => ASSIGN,
+        Expression::BinaryOp(node) => operator_level(&node.operator),
+        Expression::UnaryOp(_) => UNARY,
+        Expression::LetIn(_)
+        | Expression::IfElse(_)
+        | Expression::WhileLoop(_)
+        | Expression::ForLoop(_) => COMPOSITE,
+        _ => PRIMARY,
+    }
+}
+
+/// Formats a program in lines of at most `width` columns where possible.
+pub fn format_program(program: &mut Program, width: usize) -> String {
+    let comments = Comments::new(program.statements.len());
+    layout(program, &comments, width)
+}
+
+/// Formats `program`, parsed from `source`, keeping the comments of `source` that sit
+/// between its statements. A comment inside a declaration or an expression has no
+/// place in the AST, so its span is returned instead.
+pub fn format_source(program: &mut Program, source: &str, width: usize) -> Result<String, Span> {
+    let parse = cst::parse(source);
+    let statements: Vec<Span> = parse
+        .program()
+        .statements()
+        .iter()
+        .map(|statement| statement.syntax().span())
+        .collect();
+    let mut comments = Comments::new(statements.len());
+    for token in parse.syntax().descendant_tokens() {
+        if token.kind() != SyntaxKind::Comment {
+            continue;
+        }
+        let span = token.span();
+        let index = statements
+            .iter()
+            .position(|statement| span.start < statement.end)
+            .unwrap_or(statements.len());
+        if statements.get(index).is_some_and(|statement| statement.start < span.start) {
+            return Err(span);
+        }
+        let comment = token.text().trim_end().to_string();
+        match index.checked_sub(1) {
+            Some(previous) if !source[statements[previous].end..span.start].contains('\n') => {
+                comments.trailing[previous] = Some(comment);
+            }
+            _ => comments.leading[index].push(comment),
+        }
+    }
+    Ok(layout(program, &comments, width))
+}
+
+/// The comments `hulk fmt` keeps, by the statement they go with.
+struct Comments {
+    /// The comments on the lines before each statement, and after the last one.
+    leading: Vec<Vec<String>>,
+    /// The comment at the end of the line of each statement.
+    trailing: Vec<Option<String>>,
+}
+
+impl Comments {
+    fn new(statements: usize) -> Self {
+        Comments {
+            leading: vec![Vec::new(); statements + 1],
+            trailing: vec![None; statements],
+        }
+    }
+}
+
+fn layout(program: &mut Program, comments: &Comments, width: usize) -> String {
+    let mut formatter = Formatter;
+    let declarations: Vec<bool> = program
+        .statements
+        .iter()
+        .map(|statement| !matches!(statement, Statement::StatementExpression(_)))
+        .collect();
+    let mut docs = Vec::new();
+    for (index, statement) in program.statements.iter_mut().enumerate() {
+        for comment in &comments.leading[index] {
+            docs.push(text(comment.as_str()));
+            docs.push(Doc::HardLine);
+        }
+        docs.push(statement.accept(&mut formatter));
+        docs.push(text(";"));
+        if let Some(comment) = &comments.trailing[index] {
+            docs.push(text(format!(" {}", comment)));
+        }
+        docs.push(Doc::HardLine);
+        if let Some(next) = declarations.get(index + 1) {
+            if declarations[index] || *next {
+                docs.push(Doc::HardLine);
+            }
+        }
+    }
+    for comment in &comments.leading[program.statements.len()] {
+        docs.push(text(comment.as_str()));
+        docs.push(Doc::HardLine);
+    }
+    doc::pretty(&concat(docs), width)
+}
+
+/// A visitor that builds the [`Doc`] of every node.
+pub struct Formatter;
+
+impl Formatter {
+    /// Formats `expr`, in parentheses when it binds less tightly than `min_level`.
+    fn operand(&mut self, expr: &mut Expression, min_level: u8) -> Doc {
+        let needs_parentheses = level(expr) < min_level;
+        let doc = expr.accept(self);
+        if needs_parentheses {
+            concat(vec![text("("), doc, text(")")])
+        } else {
+            doc
+        }
+    }
+
+    /// A parenthesized list that breaks one item per line when it does not fit.
+    fn list(&self, items: Vec<Doc>) -> Doc {
+        if items.is_empty() {
+            return text("()");
+        }
+        group(concat(vec![
+            text("("),
+            nest(
+                INDENT,
+                concat(vec![
+                    Doc::SoftLine,
+                    join(items, concat(vec![text(","), Doc::Line])),
+                ]),
+            ),
+            Doc::SoftLine,
+            text(")"),
+        ]))
+    }
+
+    fn arguments(&mut self, arguments: &mut [Expression]) -> Doc {
+        let items = arguments
+            .iter_mut()
+            .map(|argument| self.operand(argument, ASSIGN))
+            .collect();
+        self.list(items)
+    }
+
+    fn params(&self, params: &[FunctionParams]) -> Doc {
+        let items = params
+            .iter()
+            .map(|param| text(format!("{}: {}", param.name, param.signature)))
+            .collect();
+        self.list(items)
+    }
+
+    /// The braces and expressions of a block, without a group of its own so the
+    /// branches of an `if` break together.
+    fn block(&mut self, node: &mut BlockNode) -> Doc {
+        let expressions = &mut node.expression_list.expressions;
+        if expressions.is_empty() {
+            return text("{}");
+        }
+        let separator = if expressions.len() > 1 {
+            Doc::HardLine
+        } else {
+            Doc::Line
+        };
+        let lines = expressions
+            .iter_mut()
+            .map(|expr| concat(vec![self.operand(expr, ASSIGN), text(";")]))
+            .collect();
+        concat(vec![
+            text("{"),
+            nest(INDENT, concat(vec![Doc::Line, join(lines, separator)])),
+            Doc::Line,
+            text("}"),
+        ])
+    }
+
+    /// Formats an `if` branch, which the grammar requires to be a block.
+    fn branch(&mut self, body: &mut Expression) -> Doc {
+        match body {
+            Expression::CodeBlock(block) => self.block(block),
+            other => {
+                let doc = self.operand(other, ASSIGN);
+                concat(vec![
+                    text("{"),
+                    nest(INDENT, concat(vec![Doc::Line, doc, text(";")])),
+                    Doc::Line,
+                    text("}"),
+                ])
+            }
+        }
+    }
+
+    /// The signature and body of a function or method, after `function` if any.
+    fn function(&mut self, node: &mut FunctionDefNode) -> Doc {
+        let header = concat(vec![
+            text(node.name.clone()),
+            self.params(&node.params),
+            text(format!(": {}", node.return_type)),
+        ]);
+        match &mut node.body {
+            Expression::CodeBlock(block) => {
+                concat(vec![header, text(" "), group(self.block(block))])
+            }
+            body => {
+                let body = self.operand(body, ASSIGN);
+                concat(vec![
+                    header,
+                    text(" =>"),
+                    group(nest(INDENT, concat(vec![Doc::Line, body]))),
+                ])
+            }
+        }
+    }
+
+    fn assignment(&mut self, node: &mut Assignment) -> Doc {
+        let value = self.operand(&mut node.expression, ASSIGN);
+        concat(vec![text(format!("{} = ", node.identifier)), value])
+    }
+
+    /// Formats a chain of operators of the same level as one group, so it breaks
+    /// before every operand or before none.
+    fn chain(&mut self, node: &mut BinaryOpNode, parts: &mut Vec<Doc>) {
+        let level = operator_level(&node.operator);
+        let left_associative = operators::infix_operator(&node.operator)
+            .and_then(|entry| entry.associativity())
+            != Some(Associativity::Right);
+        let (left_min, right_min) = if left_associative {
+            (level, level + 1)
+        } else {
+            (level + 1, level)
+        };
+        match node.left.as_mut() {
+            Expression::BinaryOp(left)
+                if operator_level(&left.operator) == level && left_associative =>
+            {
+                self.chain(left, parts)
+            }
+            left => parts.push(self.operand(left, left_min)),
+        }
+        parts.push(text(format!(" {}", node.operator)));
+        parts.push(Doc::Line);
+        parts.push(self.operand(&mut node.right, right_min));
+    }
+}
+
+impl Visitor<Doc> for Formatter {
+    fn visit_function_def(&mut self, node: &mut FunctionDefNode) -> Doc {
+        concat(vec![text("function "), self.function(node)])
+    }
+
+    fn visit_literal_number(&mut self, node: &mut NumberLiteralNode) -> Doc {
+        text(node.value.to_string())
+    }
+
+    fn visit_literal_boolean(&mut self, node: &mut BooleanLiteralNode) -> Doc {
+        text(node.value.to_string())
+    }
+
+    fn visit_literal_string(&mut self, node: &mut StringLiteralNode) -> Doc {
+        text(format!("\"{}\"", node.value))
+    }
+
+    fn visit_identifier(&mut self, node: &mut IdentifierNode) -> Doc {
+        text(node.value.clone())
+    }
+
+    fn visit_function_call(&mut self, node: &mut FunctionCallNode) -> Doc {
+        concat(vec![
+            text(node.function_name.clone()),
+            self.arguments(&mut node.arguments),
+        ])
+    }
+
+    fn visit_while_loop(&mut self, node: &mut WhileNode) -> Doc {
+        let condition = self.operand(&mut node.condition, ASSIGN);
+        let body = self.operand(&mut node.body, COMPOSITE);
+        concat(vec![text("while ("), condition, text(") "), body])
+    }
+
+    fn visit_for_loop(&mut self, node: &mut ForNode) -> Doc {
+        let start = self.operand(&mut node.start, ASSIGN);
+        let end = self.operand(&mut node.end, ASSIGN);
+        let body = self.operand(&mut node.body, COMPOSITE);
+        concat(vec![
+            text(format!("for ({} in range(", node.variable)),
+            start,
+            text(", "),
+            end,
+            text(")) "),
+            body,
+        ])
+    }
+
+    fn visit_code_block(&mut self, node: &mut BlockNode) -> Doc {
+        group(self.block(node))
+    }
+
+    fn visit_binary_op(&mut self, node: &mut BinaryOpNode) -> Doc {
+        let mut parts = Vec::new();
+        self.chain(node, &mut parts);
+        let first = parts.remove(0);
+        group(concat(vec![first, nest(INDENT, concat(parts))]))
+    }
+
+    fn visit_unary_op(&mut self, node: &mut UnaryOpNode) -> Doc {
+        // `-(-x)` keeps its parentheses rather than run the operators together.
+        let repeated = matches!(
+            node.operand.as_ref(),
+            Expression::UnaryOp(inner) if inner.operator == node.operator
+        );
+        let operand = self.operand(&mut node.operand, if repeated { PRIMARY } else { UNARY });
+        concat(vec![text(node.operator.to_string()), operand])
+    }
+
+    fn visit_if_else(&mut self, node: &mut IfElseNode) -> Doc {
+        let condition = self.operand(&mut node.condition, ASSIGN);
+        let mut docs = vec![
+            text("if ("),
+            condition,
+            text(") "),
+            self.branch(&mut node.if_expression),
+        ];
+        for (condition, body) in node.elifs.iter_mut() {
+            match condition {
+                Some(condition) => {
+                    docs.push(text(" elif ("));
+                    docs.push(self.operand(condition, ASSIGN));
+                    docs.push(text(") "));
+                }
+                None => docs.push(text(" else ")),
+            }
+            docs.push(self.branch(body));
+        }
+        group(concat(docs))
+    }
+
+    fn visit_let_in(&mut self, node: &mut LetInNode) -> Doc {
+        let assignments = node
+            .assignments
+            .iter_mut()
+            .map(|assignment| self.assignment(assignment))
+            .collect();
+        let body = self.operand(&mut node.body, COMPOSITE);
+        concat(vec![
+            group(concat(vec![
+                text("let "),
+                nest(
+                    INDENT,
+                    join(assignments, concat(vec![text(","), Doc::Line])),
+                ),
+            ])),
+            text(" in "),
+            body,
+        ])
+    }
+
+    fn visit_destructive_assign(&mut self, node: &mut DestructiveAssignNode) -> Doc {
+        let target = self.operand(&mut node.identifier, PRIMARY);
+        let value = self.operand(&mut node.expression, ASSIGN);
+        concat(vec![target, text(" := "), value])
+    }
+
+    fn visit_type_def(&mut self, node: &mut TypeDefNode) -> Doc {
+        let mut header = vec![text(format!("type {}", node.identifier))];
+        if !node.params.is_empty() {
+            header.push(self.params(&node.params));
+        }
+        if let Some(parent) = &node.parent {
+            header.push(text(format!(" inherits {}", parent)));
+            if !node.parent_args.is_empty() {
+                header.push(self.arguments(&mut node.parent_args));
+            }
+        }
+        if node.members.is_empty() {
+            header.push(text(" {}"));
+            return concat(header);
+        }
+        let members = node
+            .members
+            .iter_mut()
+            .map(|member| {
+                let doc = match member {
+                    TypeMember::Property(assignment) => self.assignment(assignment),
+                    TypeMember::Method(method) => self.function(method),
+                };
+                concat(vec![doc, text(";")])
+            })
+            .collect();
+        header.push(text(" {"));
+        header.push(nest(
+            INDENT,
+            concat(vec![Doc::HardLine, join(members, Doc::HardLine)]),
+        ));
+        header.push(Doc::HardLine);
+        header.push(text("}"));
+        concat(header)
+    }
+
+    fn visit_type_instance(&mut self, node: &mut TypeInstanceNode) -> Doc {
+        concat(vec![
+            text(format!("new {}", node.type_name)),
+            self.arguments(&mut node.arguments),
+        ])
+    }
+
+    fn visit_type_function_access(&mut self, node: &mut TypeFunctionAccessNode) -> Doc {
+        let object = self.operand(&mut node.object, PRIMARY);
+        concat(vec![
+            object,
+            text(format!(".{}", node.member.function_name)),
+            self.arguments(&mut node.member.arguments),
+        ])
+    }
+
+    fn visit_type_prop_access(&mut self, node: &mut TypePropAccessNode) -> Doc {
+        let object = self.operand(&mut node.object, PRIMARY);
+        concat(vec![object, text(format!(".{}", node.member))])
+    }
+
+    fn visit_print(&mut self, node: &mut PrintNode) -> Doc {
+        let argument = self.operand(&mut node.expression, ASSIGN);
+        concat(vec![text("print"), self.list(vec![argument])])
+    }
+}
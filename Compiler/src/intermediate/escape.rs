@@ -0,0 +1,367 @@
+//! Escape analysis over the IR.
+//!
+//! An object escapes its function when a pointer to it may be used after the function
+//! returns: it is returned, passed to a call, boxed or stored into another object.
+//! Pointers are followed through copies, so `let p = new Point(1, 2) in p.x` tracks
+//! `p` and the temporary holding the `new`.
+//!
+//! Allocations that do not escape and do not run inside a loop are marked to live in
+//! the stack frame. When, besides, the object is only used to read and write its
+//! fields, the allocation is removed and every field becomes a local variable.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use super::ir::{BlockId, Function, Instr, Module, Operand, Terminator, TypeLayout, VarId};
+
+/// Why an allocation has to stay on the heap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EscapeReason {
+    Returned,
+    PassedToCall,
+    StoredInObject,
+    Boxed,
+    /// The object may be created many times per call, so the frame cannot hold it.
+    InLoop,
+}
+
+impl fmt::Display for EscapeReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EscapeReason::Returned => write!(f, "returned"),
+            EscapeReason::PassedToCall => write!(f, "passed to a call"),
+            EscapeReason::StoredInObject => write!(f, "stored in an object"),
+            EscapeReason::Boxed => write!(f, "boxed"),
+            EscapeReason::InLoop => write!(f, "allocated in a loop"),
+        }
+    }
+}
+
+/// Where an allocation ended up after the analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Placement {
+    Heap(EscapeReason),
+    Stack,
+    /// The object was replaced by one local variable per field.
+    Scalars,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllocationSite {
+    pub function: String,
+    pub type_name: String,
+    /// Name of the variable the allocation was written to.
+    pub variable: String,
+    pub placement: Placement,
+}
+
+impl fmt::Display for AllocationSite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: new {} in {} -> ",
+            self.function, self.type_name, self.variable
+        )?;
+        match &self.placement {
+            Placement::Heap(reason) => write!(f, "heap ({})", reason),
+            Placement::Stack => write!(f, "stack"),
+            Placement::Scalars => write!(f, "scalar-replaced"),
+        }
+    }
+}
+
+/// The escape analysis pass, keeping the decision taken for every allocation.
+#[derive(Default)]
+pub struct EscapeAnalysis {
+    pub allocations: Vec<AllocationSite>,
+}
+
+impl EscapeAnalysis {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Places every allocation of `module` on the heap, on the stack or in scalars.
+    ///
+    /// # Arguments
+    /// * `module` - The IR module, rewritten in place.
+    pub fn analyze_module(&mut self, module: &mut Module) {
+        self.allocations.clear();
+        let Module { types, functions } = module;
+        for function in functions.iter_mut() {
+            self.analyze_function(function, types);
+        }
+    }
+
+    /// Returns a summary line followed by one line per allocation.
+    pub fn report(&self) -> String {
+        let count = |wanted: fn(&Placement) -> bool| {
+            self.allocations
+                .iter()
+                .filter(|site| wanted(&site.placement))
+                .count()
+        };
+        let mut report = format!(
+            "escape analysis: {} allocation(s), {} on the stack, {} scalar-replaced, {} on the heap",
+            self.allocations.len(),
+            count(|placement| *placement == Placement::Stack),
+            count(|placement| *placement == Placement::Scalars),
+            count(|placement| matches!(placement, Placement::Heap(_))),
+        );
+        for site in self.allocations.iter() {
+            report.push_str(&format!("\n  {}", site));
+        }
+        report
+    }
+
+    fn analyze_function(&mut self, function: &mut Function, types: &[TypeLayout]) {
+        let allocations: Vec<(VarId, String)> = function
+            .blocks
+            .iter()
+            .flat_map(|block| block.instrs.iter())
+            .filter_map(|instr| match instr {
+                Instr::Alloc {
+                    dest, type_name, ..
+                } => Some((*dest, type_name.clone())),
+                _ => None,
+            })
+            .collect();
+
+        for (object, type_name) in allocations {
+            let variable = function.var_name(object);
+            let aliases = aliases_of(function, object);
+            let placement = match escape_reason(function, &aliases) {
+                Some(reason) => Placement::Heap(reason),
+                None if in_loop(function, alloc_block(function, object)) => {
+                    Placement::Heap(EscapeReason::InLoop)
+                }
+                None => match types.iter().find(|layout| layout.name == type_name) {
+                    Some(layout) if only_field_accesses(function, object, &aliases) => {
+                        replace_with_scalars(function, object, &aliases, layout);
+                        Placement::Scalars
+                    }
+                    _ => {
+                        mark_stack(function, object);
+                        Placement::Stack
+                    }
+                },
+            };
+            self.allocations.push(AllocationSite {
+                function: function.name.clone(),
+                type_name,
+                variable,
+                placement,
+            });
+        }
+    }
+}
+
+/// Returns the variables that may hold the pointer produced by the allocation into `object`.
+fn aliases_of(function: &Function, object: VarId) -> HashSet<VarId> {
+    let mut aliases = HashSet::from([object]);
+    loop {
+        let before = aliases.len();
+        for instr in function.blocks.iter().flat_map(|block| block.instrs.iter()) {
+            if let Instr::Copy {
+                dest,
+                src: Operand::Var(src),
+            } = instr
+            {
+                if aliases.contains(src) {
+                    aliases.insert(*dest);
+                }
+            }
+        }
+        if aliases.len() == before {
+            return aliases;
+        }
+    }
+}
+
+fn is_alias(operand: &Operand, aliases: &HashSet<VarId>) -> bool {
+    matches!(operand, Operand::Var(var) if aliases.contains(var))
+}
+
+/// Returns the first way the pointer leaves the function, if any.
+fn escape_reason(function: &Function, aliases: &HashSet<VarId>) -> Option<EscapeReason> {
+    for block in function.blocks.iter() {
+        for instr in block.instrs.iter() {
+            let reason = match instr {
+                Instr::Call { args, .. } if args.iter().any(|arg| is_alias(arg, aliases)) => {
+                    Some(EscapeReason::PassedToCall)
+                }
+                Instr::CallMethod { receiver, args, .. }
+                    if is_alias(receiver, aliases)
+                        || args.iter().any(|arg| is_alias(arg, aliases)) =>
+                {
+                    Some(EscapeReason::PassedToCall)
+                }
+                Instr::SetField { value, .. } if is_alias(value, aliases) => {
+                    Some(EscapeReason::StoredInObject)
+                }
+                Instr::Box { value, .. } if is_alias(value, aliases) => Some(EscapeReason::Boxed),
+                _ => None,
+            };
+            if reason.is_some() {
+                return reason;
+            }
+        }
+        if let Terminator::Return(value) = &block.terminator {
+            if is_alias(value, aliases) {
+                return Some(EscapeReason::Returned);
+            }
+        }
+    }
+    None
+}
+
+fn alloc_block(function: &Function, object: VarId) -> BlockId {
+    function
+        .blocks
+        .iter()
+        .position(|block| {
+            block
+                .instrs
+                .iter()
+                .any(|instr| matches!(instr, Instr::Alloc { dest, .. } if *dest == object))
+        })
+        .unwrap_or(function.entry)
+}
+
+/// Returns whether `block` can run more than once per call.
+fn in_loop(function: &Function, block: BlockId) -> bool {
+    let mut pending = function.blocks[block].terminator.successors();
+    let mut seen = HashSet::new();
+    while let Some(current) = pending.pop() {
+        if current == block {
+            return true;
+        }
+        if seen.insert(current) {
+            pending.extend(function.blocks[current].terminator.successors());
+        }
+    }
+    false
+}
+
+/// Returns whether the object is only used to read and write its own fields, so it
+/// does not need an address.
+fn only_field_accesses(function: &Function, object: VarId, aliases: &HashSet<VarId>) -> bool {
+    for block in function.blocks.iter() {
+        for instr in block.instrs.iter() {
+            let allowed = match instr {
+                Instr::Alloc { dest, .. } => *dest == object || !aliases.contains(dest),
+                Instr::Copy { dest, src } => {
+                    // Every alias must hold this object and nothing else.
+                    !aliases.contains(dest) || is_alias(src, aliases)
+                }
+                Instr::GetField { .. } | Instr::SetField { .. } => {
+                    instr.dest().is_none_or(|dest| !aliases.contains(&dest))
+                }
+                _ => {
+                    instr.dest().is_none_or(|dest| !aliases.contains(&dest))
+                        && !instr
+                            .operands()
+                            .iter()
+                            .any(|operand| is_alias(operand, aliases))
+                }
+            };
+            if !allowed {
+                return false;
+            }
+        }
+        if let Terminator::Branch { cond, .. } = &block.terminator {
+            if is_alias(cond, aliases) {
+                return false;
+            }
+        }
+    }
+    !function.params.iter().any(|param| aliases.contains(param))
+}
+
+fn mark_stack(function: &mut Function, object: VarId) {
+    for instr in function
+        .blocks
+        .iter_mut()
+        .flat_map(|block| block.instrs.iter_mut())
+    {
+        if let Instr::Alloc { dest, stack, .. } = instr {
+            if *dest == object {
+                *stack = true;
+            }
+        }
+    }
+}
+
+/// Replaces the allocation with one variable per field, initialized to its default.
+fn replace_with_scalars(
+    function: &mut Function,
+    object: VarId,
+    aliases: &HashSet<VarId>,
+    layout: &TypeLayout,
+) {
+    // Fields are named after the `let` binding holding the object, when there is one.
+    let mut named: Vec<VarId> = aliases
+        .iter()
+        .copied()
+        .filter(|var| function.vars[*var].name.is_some())
+        .collect();
+    named.sort();
+    let base = named
+        .first()
+        .and_then(|var| function.vars[*var].name.clone());
+    let fields: Vec<(String, VarId)> = layout
+        .fields
+        .iter()
+        .map(|(field, ty)| {
+            let name = match &base {
+                Some(base) => format!("{}.{}", base, field),
+                None => field.clone(),
+            };
+            (field.clone(), function.new_var(Some(&name), ty.clone()))
+        })
+        .collect();
+    let field_var = |field: &str| {
+        fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, var)| *var)
+            .expect("field of a scalar-replaced object")
+    };
+
+    for block in function.blocks.iter_mut() {
+        let mut instrs = Vec::with_capacity(block.instrs.len());
+        for instr in block.instrs.drain(..) {
+            match instr {
+                Instr::Alloc { dest, .. } if dest == object => {
+                    for (index, (_, var)) in fields.iter().enumerate() {
+                        instrs.push(Instr::Copy {
+                            dest: *var,
+                            src: layout.fields[index].1.default_value(),
+                        });
+                    }
+                }
+                Instr::Copy { dest, .. } if aliases.contains(&dest) => {}
+                Instr::GetField {
+                    dest,
+                    object: Operand::Var(var),
+                    field,
+                    ..
+                } if aliases.contains(&var) => instrs.push(Instr::Copy {
+                    dest,
+                    src: Operand::Var(field_var(&field)),
+                }),
+                Instr::SetField {
+                    object: Operand::Var(var),
+                    field,
+                    value,
+                    ..
+                } if aliases.contains(&var) => instrs.push(Instr::Copy {
+                    dest: field_var(&field),
+                    src: value,
+                }),
+                instr => instrs.push(instr),
+            }
+        }
+        block.instrs = instrs;
+    }
+}
@@ -0,0 +1,635 @@
+//! Intermediate representation shared by the optimization passes and the backends.
+//!
+//! Every function is a control-flow graph of [`BasicBlock`]s. Instructions read
+//! [`Operand`]s and write numbered variables; temporaries produced while lowering an
+//! expression are written exactly once, while variables coming from the source
+//! (parameters, `let` bindings, loop variables) may be assigned many times.
+
+use std::fmt;
+
+use crate::types_tree::type_tree::{BOOLEAN, NUMBER, OBJECT, STRING};
+
+pub type VarId = usize;
+pub type BlockId = usize;
+
+/// The machine-level view of a HULK type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrType {
+    Number,
+    Boolean,
+    String,
+    /// A pointer to an object; the name is the static type.
+    Object(String),
+}
+
+impl IrType {
+    /// Maps a HULK type name to its IR type.
+    pub fn from_type_name(type_name: &str) -> Self {
+        match type_name {
+            NUMBER => IrType::Number,
+            BOOLEAN => IrType::Boolean,
+            STRING => IrType::String,
+            name if name.starts_with('<') => IrType::Object(OBJECT.to_string()),
+            name => IrType::Object(name.to_string()),
+        }
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, IrType::Object(_))
+    }
+
+    /// Returns whether values of this type are pointers the garbage collector traces.
+    pub fn is_pointer(&self) -> bool {
+        matches!(self, IrType::String | IrType::Object(_))
+    }
+
+    /// Returns the value a variable of this type holds before being assigned.
+    pub fn default_value(&self) -> Operand {
+        match self {
+            IrType::Number => Operand::Number(0.0),
+            IrType::Boolean => Operand::Boolean(false),
+            IrType::String => Operand::Str(String::new()),
+            IrType::Object(_) => Operand::Null,
+        }
+    }
+}
+
+impl fmt::Display for IrType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IrType::Number => write!(f, "{}", NUMBER),
+            IrType::Boolean => write!(f, "{}", BOOLEAN),
+            IrType::String => write!(f, "{}", STRING),
+            IrType::Object(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Var {
+    /// Source name for parameters and bindings, `None` for temporaries.
+    pub name: Option<String>,
+    pub ty: IrType,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Var(VarId),
+    Number(f64),
+    Boolean(bool),
+    Str(String),
+    /// The value of an object variable that was never assigned.
+    Null,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+    Concat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instr {
+    Copy {
+        dest: VarId,
+        src: Operand,
+    },
+    Binary {
+        dest: VarId,
+        op: BinaryOp,
+        left: Operand,
+        right: Operand,
+    },
+    Unary {
+        dest: VarId,
+        op: UnaryOp,
+        operand: Operand,
+    },
+    /// Direct call to a global function or to a known method implementation.
+    /// Methods are named `Type.method` and take the receiver as first argument.
+    Call {
+        dest: VarId,
+        function: String,
+        args: Vec<Operand>,
+        /// Set when the call is immediately followed by a `Return` of its result.
+        tail: bool,
+    },
+    /// Call through the receiver's vtable.
+    CallMethod {
+        dest: VarId,
+        receiver: Operand,
+        static_type: String,
+        method: String,
+        args: Vec<Operand>,
+        tail: bool,
+    },
+    /// Allocates an object of `type_name` with its vtable set and every field zeroed.
+    Alloc {
+        dest: VarId,
+        type_name: String,
+        /// Set by escape analysis when the object cannot outlive the function.
+        stack: bool,
+    },
+    GetField {
+        dest: VarId,
+        object: Operand,
+        type_name: String,
+        field: String,
+    },
+    SetField {
+        object: Operand,
+        type_name: String,
+        field: String,
+        value: Operand,
+    },
+    /// Wraps a `Number`, `String` or `Boolean` so it can be used as an `Object`.
+    Box {
+        dest: VarId,
+        value: Operand,
+    },
+    Print {
+        value: Operand,
+    },
+}
+
+impl Instr {
+    /// Returns the variable written by the instruction, if any.
+    pub fn dest(&self) -> Option<VarId> {
+        match self {
+            Instr::Copy { dest, .. }
+            | Instr::Binary { dest, .. }
+            | Instr::Unary { dest, .. }
+            | Instr::Call { dest, .. }
+            | Instr::CallMethod { dest, .. }
+            | Instr::Alloc { dest, .. }
+            | Instr::GetField { dest, .. }
+            | Instr::Box { dest, .. } => Some(*dest),
+            Instr::SetField { .. } | Instr::Print { .. } => None,
+        }
+    }
+
+    /// Returns every operand read by the instruction.
+    pub fn operands(&self) -> Vec<&Operand> {
+        match self {
+            Instr::Copy { src, .. } => vec![src],
+            Instr::Binary { left, right, .. } => vec![left, right],
+            Instr::Unary { operand, .. } => vec![operand],
+            Instr::Call { args, .. } => args.iter().collect(),
+            Instr::CallMethod { receiver, args, .. } => {
+                std::iter::once(receiver).chain(args.iter()).collect()
+            }
+            Instr::Alloc { .. } => vec![],
+            Instr::GetField { object, .. } => vec![object],
+            Instr::SetField { object, value, .. } => vec![object, value],
+            Instr::Box { value, .. } => vec![value],
+            Instr::Print { value } => vec![value],
+        }
+    }
+
+    /// Returns whether the instruction only computes its destination from its operands.
+    pub fn is_pure(&self) -> bool {
+        matches!(
+            self,
+            Instr::Copy { .. } | Instr::Binary { .. } | Instr::Unary { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Jump(BlockId),
+    Branch {
+        cond: Operand,
+        then_block: BlockId,
+        else_block: BlockId,
+    },
+    Return(Operand),
+}
+
+impl Terminator {
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Jump(target) => vec![*target],
+            Terminator::Branch {
+                then_block,
+                else_block,
+                ..
+            } => vec![*then_block, *else_block],
+            Terminator::Return(_) => vec![],
+        }
+    }
+
+    /// Replaces every successor with the result of `f`.
+    pub fn map_successors(&mut self, f: impl Fn(BlockId) -> BlockId) {
+        match self {
+            Terminator::Jump(target) => *target = f(*target),
+            Terminator::Branch {
+                then_block,
+                else_block,
+                ..
+            } => {
+                *then_block = f(*then_block);
+                *else_block = f(*else_block);
+            }
+            Terminator::Return(_) => {}
+        }
+    }
+
+    /// Rewrites every successor equal to `from` into `to`.
+    pub fn retarget(&mut self, from: BlockId, to: BlockId) {
+        self.map_successors(|succ| if succ == from { to } else { succ });
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub instrs: Vec<Instr>,
+    pub terminator: Terminator,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<VarId>,
+    pub vars: Vec<Var>,
+    pub return_type: IrType,
+    pub blocks: Vec<BasicBlock>,
+    pub entry: BlockId,
+}
+
+impl Function {
+    pub fn new(name: &str, return_type: IrType) -> Self {
+        Function {
+            name: name.to_string(),
+            params: Vec::new(),
+            vars: Vec::new(),
+            return_type,
+            blocks: Vec::new(),
+            entry: 0,
+        }
+    }
+
+    pub fn new_var(&mut self, name: Option<&str>, ty: IrType) -> VarId {
+        self.vars.push(Var {
+            name: name.map(str::to_string),
+            ty,
+        });
+        self.vars.len() - 1
+    }
+
+    pub fn new_block(&mut self) -> BlockId {
+        self.blocks.push(BasicBlock {
+            instrs: Vec::new(),
+            terminator: Terminator::Return(Operand::Null),
+        });
+        self.blocks.len() - 1
+    }
+
+    pub fn operand_type(&self, operand: &Operand) -> IrType {
+        match operand {
+            Operand::Var(var) => self.vars[*var].ty.clone(),
+            Operand::Number(_) => IrType::Number,
+            Operand::Boolean(_) => IrType::Boolean,
+            Operand::Str(_) => IrType::String,
+            Operand::Null => IrType::Object(OBJECT.to_string()),
+        }
+    }
+
+    /// Returns the predecessors of every block.
+    pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+        let mut preds = vec![Vec::new(); self.blocks.len()];
+        for (id, block) in self.blocks.iter().enumerate() {
+            for succ in block.terminator.successors() {
+                if !preds[succ].contains(&id) {
+                    preds[succ].push(id);
+                }
+            }
+        }
+        preds
+    }
+
+    /// Returns the blocks reachable from the entry, in depth-first preorder.
+    pub fn reachable_blocks(&self) -> Vec<BlockId> {
+        let mut visited = vec![false; self.blocks.len()];
+        let mut order = Vec::new();
+        let mut stack = vec![self.entry];
+        while let Some(block) = stack.pop() {
+            if visited[block] {
+                continue;
+            }
+            visited[block] = true;
+            order.push(block);
+            for succ in self.blocks[block].terminator.successors().into_iter().rev() {
+                if !visited[succ] {
+                    stack.push(succ);
+                }
+            }
+        }
+        order
+    }
+
+    /// Drops unreachable blocks and renumbers the remaining ones, keeping their order.
+    pub fn remove_unreachable_blocks(&mut self) {
+        let mut kept = vec![false; self.blocks.len()];
+        for block in self.reachable_blocks() {
+            kept[block] = true;
+        }
+        let mut new_ids = vec![0; self.blocks.len()];
+        let mut next = 0;
+        for (old, keep) in kept.iter().enumerate() {
+            if *keep {
+                new_ids[old] = next;
+                next += 1;
+            }
+        }
+        let blocks = std::mem::take(&mut self.blocks);
+        for (old, mut block) in blocks.into_iter().enumerate() {
+            if kept[old] {
+                block.terminator.map_successors(|succ| new_ids[succ]);
+                self.blocks.push(block);
+            }
+        }
+        self.entry = new_ids[self.entry];
+    }
+}
+
+/// Object layout and dispatch table of a user type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeLayout {
+    pub name: String,
+    pub parent: Option<String>,
+    /// Every field, inherited ones first, so a field has the same index in all subtypes.
+    pub fields: Vec<(String, IrType)>,
+    /// Vtable slots as `(method, implementing function)`; overrides keep the parent's slot.
+    pub methods: Vec<(String, String)>,
+}
+
+impl TypeLayout {
+    pub fn field_index(&self, field: &str) -> Option<usize> {
+        self.fields.iter().position(|(name, _)| name == field)
+    }
+
+    pub fn method_index(&self, method: &str) -> Option<usize> {
+        self.methods.iter().position(|(name, _)| name == method)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Module {
+    pub types: Vec<TypeLayout>,
+    pub functions: Vec<Function>,
+}
+
+impl Module {
+    pub fn get_type(&self, name: &str) -> Option<&TypeLayout> {
+        self.types.iter().find(|layout| layout.name == name)
+    }
+
+    pub fn get_function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::Pow => "^",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::And => "&",
+            BinaryOp::Or => "|",
+            BinaryOp::Concat => "@",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl Function {
+    /// Formats a variable as `%name.id` for source variables and `%id` for temporaries.
+    pub fn var_name(&self, var: VarId) -> String {
+        match &self.vars[var].name {
+            Some(name) => format!("%{}.{}", name, var),
+            None => format!("%{}", var),
+        }
+    }
+
+    pub fn fmt_operand(&self, operand: &Operand) -> String {
+        match operand {
+            Operand::Var(var) => self.var_name(*var),
+            Operand::Number(value) => format!("{:?}", value),
+            Operand::Boolean(value) => value.to_string(),
+            Operand::Str(value) => format!("{:?}", value),
+            Operand::Null => "null".to_string(),
+        }
+    }
+
+    fn fmt_operands(&self, operands: &[Operand]) -> String {
+        operands
+            .iter()
+            .map(|operand| self.fmt_operand(operand))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn fmt_instr(&self, instr: &Instr) -> String {
+        let tail = |tail: &bool| if *tail { "tail " } else { "" };
+        match instr {
+            Instr::Copy { dest, src } => {
+                format!("{} = {}", self.var_name(*dest), self.fmt_operand(src))
+            }
+            Instr::Binary {
+                dest,
+                op,
+                left,
+                right,
+            } => format!(
+                "{} = {} {} {}",
+                self.var_name(*dest),
+                self.fmt_operand(left),
+                op,
+                self.fmt_operand(right)
+            ),
+            Instr::Unary { dest, op, operand } => {
+                let symbol = match op {
+                    UnaryOp::Neg => "-",
+                    UnaryOp::Not => "!",
+                };
+                format!(
+                    "{} = {}{}",
+                    self.var_name(*dest),
+                    symbol,
+                    self.fmt_operand(operand)
+                )
+            }
+            Instr::Call {
+                dest,
+                function,
+                args,
+                tail: is_tail,
+            } => format!(
+                "{} = {}call {}({})",
+                self.var_name(*dest),
+                tail(is_tail),
+                function,
+                self.fmt_operands(args)
+            ),
+            Instr::CallMethod {
+                dest,
+                receiver,
+                static_type,
+                method,
+                args,
+                tail: is_tail,
+            } => format!(
+                "{} = {}call {}.{}:{}({})",
+                self.var_name(*dest),
+                tail(is_tail),
+                self.fmt_operand(receiver),
+                static_type,
+                method,
+                self.fmt_operands(args)
+            ),
+            Instr::Alloc {
+                dest,
+                type_name,
+                stack,
+            } => format!(
+                "{} = {} {}",
+                self.var_name(*dest),
+                if *stack { "alloca" } else { "alloc" },
+                type_name
+            ),
+            Instr::GetField {
+                dest,
+                object,
+                type_name,
+                field,
+            } => format!(
+                "{} = {}.{}:{}",
+                self.var_name(*dest),
+                self.fmt_operand(object),
+                type_name,
+                field
+            ),
+            Instr::SetField {
+                object,
+                type_name,
+                field,
+                value,
+            } => format!(
+                "{}.{}:{} = {}",
+                self.fmt_operand(object),
+                type_name,
+                field,
+                self.fmt_operand(value)
+            ),
+            Instr::Box { dest, value } => {
+                format!("{} = box {}", self.var_name(*dest), self.fmt_operand(value))
+            }
+            Instr::Print { value } => format!("print {}", self.fmt_operand(value)),
+        }
+    }
+
+    pub fn fmt_terminator(&self, terminator: &Terminator) -> String {
+        match terminator {
+            Terminator::Jump(target) => format!("jump bb{}", target),
+            Terminator::Branch {
+                cond,
+                then_block,
+                else_block,
+            } => format!(
+                "branch {}, bb{}, bb{}",
+                self.fmt_operand(cond),
+                then_block,
+                else_block
+            ),
+            Terminator::Return(value) => format!("return {}", self.fmt_operand(value)),
+        }
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|&param| format!("{}: {}", self.var_name(param), self.vars[param].ty))
+            .collect();
+        writeln!(
+            f,
+            "function {}({}): {} {{",
+            self.name,
+            params.join(", "),
+            self.return_type
+        )?;
+        for (id, block) in self.blocks.iter().enumerate() {
+            writeln!(f, "bb{}:", id)?;
+            for instr in block.instrs.iter() {
+                writeln!(f, "    {}", self.fmt_instr(instr))?;
+            }
+            writeln!(f, "    {}", self.fmt_terminator(&block.terminator))?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for layout in self.types.iter() {
+            let fields: Vec<String> = layout
+                .fields
+                .iter()
+                .map(|(name, ty)| format!("{}: {}", name, ty))
+                .collect();
+            let methods: Vec<&str> = layout
+                .methods
+                .iter()
+                .map(|(_, function)| function.as_str())
+                .collect();
+            writeln!(
+                f,
+                "type {}{} {{ {} }} vtable [{}]",
+                layout.name,
+                layout
+                    .parent
+                    .as_ref()
+                    .map(|parent| format!(" inherits {}", parent))
+                    .unwrap_or_default(),
+                fields.join(", "),
+                methods.join(", ")
+            )?;
+        }
+        for function in self.functions.iter() {
+            writeln!(f, "\n{}", function)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,885 @@
+//! `IrGenerator` implements the [`Visitor`] trait to lower a type-checked program to IR.
+//!
+//! Each global function becomes an IR function with the same name, each method becomes
+//! `Type.method` with the receiver as first parameter, and the top-level expressions
+//! become `main`. Lowering relies on the annotations left by earlier passes:
+//! - `node_type` gives the IR type of every value;
+//! - calls marked [`TailCallKind::Recursive`] become a jump back to the start of the
+//!   function body after reassigning its parameters, so they run in constant stack;
+//! - other calls in tail position are emitted as tail calls followed by a `Return`;
+//! - method calls with a `direct_call` target become direct calls.
+//!
+//! Builtin functions the program does not redefine are called by name like any other
+//! function; no function of the module has that name, and the backends call the
+//! runtime instead. Builtin constants become number operands.
+//!
+//! `new T(...)` is expanded in place: the object is allocated and the constructors of
+//! `T` and its ancestors are inlined, so later passes see every field store. A type
+//! built again from its own constructor calls the out-of-line `T.new` function instead.
+
+use std::collections::HashMap;
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::BlockNode;
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::{FunctionCallNode, TailCallKind};
+use crate::ast_nodes::function_def::FunctionDefNode;
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::LetInNode;
+use crate::ast_nodes::literals::{
+    BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode,
+};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::{Program, Statement};
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+use crate::builtins;
+use crate::tokens::OperatorToken;
+use crate::types_tree::tree_node::TypeNode;
+use crate::types_tree::type_tree::{TypeTree, OBJECT};
+use crate::visitor::accept::Accept;
+use crate::visitor::visitor_trait::Visitor;
+
+use super::ir::{
+    BinaryOp, BlockId, Function, Instr, IrType, Module, Operand, Terminator, TypeLayout, UnaryOp,
+    VarId,
+};
+
+/// Name of the IR function holding the top-level expressions.
+pub const MAIN_FUNCTION: &str = "main";
+
+/// Returns the IR name of the implementation of `method` declared by `type_name`.
+pub fn method_name(type_name: &str, method: &str) -> String {
+    format!("{}.{}", type_name, method)
+}
+
+/// Returns the IR name of the out-of-line constructor of `type_name`.
+pub fn constructor_name(type_name: &str) -> String {
+    format!("{}.new", type_name)
+}
+
+/// A visitor that lowers the AST of a type-checked program into a [`Module`].
+pub struct IrGenerator<'a> {
+    type_tree: &'a TypeTree,
+    type_defs: HashMap<String, TypeDefNode>,
+    /// Parameter and return types of every global function.
+    functions: HashMap<String, (Vec<IrType>, IrType)>,
+    module: Module,
+    function: Function,
+    block: BlockId,
+    /// Block recursive tail calls jump back to.
+    body_block: BlockId,
+    /// Name of the global function being lowered, if any.
+    current_function: Option<String>,
+    scopes: Vec<HashMap<String, VarId>>,
+    /// Types whose constructors are being inlined, innermost last.
+    constructing: Vec<String>,
+}
+
+impl<'a> IrGenerator<'a> {
+    pub fn new(type_tree: &'a TypeTree) -> Self {
+        IrGenerator {
+            type_tree,
+            type_defs: HashMap::new(),
+            functions: HashMap::new(),
+            module: Module::default(),
+            function: Function::new(MAIN_FUNCTION, IrType::Number),
+            block: 0,
+            body_block: 0,
+            current_function: None,
+            scopes: Vec::new(),
+            constructing: Vec::new(),
+        }
+    }
+
+    /// Lowers a program that passed the semantic phase.
+    ///
+    /// # Arguments
+    /// * `node` - The root `Program` node, annotated with static types.
+    ///
+    /// # Returns
+    /// The IR module with the type layouts and every function of the program.
+    pub fn generate_program(mut self, node: &mut Program) -> Module {
+        for statement in node.statements.iter() {
+            match statement {
+                Statement::StatementTypeDef(def) => {
+                    self.type_defs
+                        .insert(def.identifier.clone(), def.as_ref().clone());
+                }
+                Statement::StatementFunctionDef(def) => {
+                    let params = def
+                        .params
+                        .iter()
+                        .map(|param| IrType::from_type_name(&param.signature))
+                        .collect();
+                    let return_type = IrType::from_type_name(&def.return_type);
+                    self.functions
+                        .insert(def.name.clone(), (params, return_type));
+                }
+                Statement::StatementExpression(_) => {}
+            }
+        }
+        self.module.types = self.build_layouts(node);
+
+        for statement in node.statements.iter_mut() {
+            match statement {
+                Statement::StatementFunctionDef(def) => {
+                    self.visit_function_def(def);
+                }
+                Statement::StatementTypeDef(def) => {
+                    self.visit_type_def(def);
+                }
+                Statement::StatementExpression(_) => {}
+            }
+        }
+
+        self.begin_function(MAIN_FUNCTION, IrType::Number, None);
+        for statement in node.statements.iter_mut() {
+            if let Statement::StatementExpression(expr) = statement {
+                expr.accept(&mut self);
+            }
+        }
+        self.finish_function(Operand::Number(0.0));
+        self.module
+    }
+
+    /// Computes the layout of every user type, parents before children.
+    fn build_layouts(&self, program: &Program) -> Vec<TypeLayout> {
+        let mut names: Vec<&String> = self.type_defs.keys().collect();
+        let order: Vec<&String> = program
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::StatementTypeDef(def) => Some(&def.identifier),
+                _ => None,
+            })
+            .collect();
+        names.sort_by_key(|name| {
+            let depth = self.type_tree.get_type(name).map_or(0, |node| node.depth);
+            (depth, order.iter().position(|other| other == name))
+        });
+
+        let mut layouts: Vec<TypeLayout> = Vec::new();
+        for name in names {
+            let node = match self.type_tree.get_type(name) {
+                Some(node) => node,
+                None => continue,
+            };
+            let parent = node.parent.clone().filter(|parent| parent != OBJECT);
+            let (mut fields, mut methods) = match parent
+                .as_ref()
+                .and_then(|parent| layouts.iter().find(|layout| &layout.name == parent))
+            {
+                Some(layout) => (layout.fields.clone(), layout.methods.clone()),
+                None => (Vec::new(), Vec::new()),
+            };
+            for (attribute, type_name) in node.attributes.iter() {
+                fields.push((attribute.clone(), IrType::from_type_name(type_name)));
+            }
+            for method in node.methods.iter() {
+                let implementation = method_name(name, &method.name);
+                match methods.iter_mut().find(|(slot, _)| *slot == method.name) {
+                    Some(slot) => slot.1 = implementation,
+                    None => methods.push((method.name.clone(), implementation)),
+                }
+            }
+            layouts.push(TypeLayout {
+                name: name.clone(),
+                parent,
+                fields,
+                methods,
+            });
+        }
+        layouts
+    }
+
+    /// Starts lowering a new function; its parameters must be declared right after.
+    fn begin_function(&mut self, name: &str, return_type: IrType, global: Option<String>) {
+        self.function = Function::new(name, return_type);
+        let entry = self.function.new_block();
+        self.body_block = self.function.new_block();
+        self.function.blocks[entry].terminator = Terminator::Jump(self.body_block);
+        self.function.entry = entry;
+        self.block = self.body_block;
+        self.current_function = global;
+        self.scopes = vec![HashMap::new()];
+    }
+
+    fn declare_param(&mut self, name: &str, ty: IrType) -> VarId {
+        let var = self.function.new_var(Some(name), ty);
+        self.function.params.push(var);
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), var);
+        var
+    }
+
+    /// Returns `value` from the current block and adds the function to the module.
+    fn finish_function(&mut self, value: Operand) {
+        let return_type = self.function.return_type.clone();
+        let value = self.coerce(value, &return_type);
+        self.terminate(Terminator::Return(value));
+        let mut function = std::mem::replace(&mut self.function, Function::new("", IrType::Number));
+        function.remove_unreachable_blocks();
+        self.module.functions.push(function);
+    }
+
+    fn emit(&mut self, instr: Instr) {
+        self.function.blocks[self.block].instrs.push(instr);
+    }
+
+    fn terminate(&mut self, terminator: Terminator) {
+        self.function.blocks[self.block].terminator = terminator;
+    }
+
+    fn switch_to(&mut self, block: BlockId) {
+        self.block = block;
+    }
+
+    /// Continues in a fresh block nobody jumps to, after control left the current one.
+    fn continue_unreachable(&mut self) {
+        let dead = self.function.new_block();
+        self.switch_to(dead);
+    }
+
+    fn temp(&mut self, ty: IrType) -> VarId {
+        self.function.new_var(None, ty)
+    }
+
+    fn lookup(&self, name: &str) -> Option<VarId> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    fn ir_type(node_type: Option<&TypeNode>) -> IrType {
+        node_type
+            .map(|node| IrType::from_type_name(&node.type_name))
+            .unwrap_or(IrType::Object(OBJECT.to_string()))
+    }
+
+    /// Boxes `value` when a plain value is used where an object is expected.
+    fn coerce(&mut self, value: Operand, target: &IrType) -> Operand {
+        let source = self.function.operand_type(&value);
+        if target.is_object() && !source.is_object() {
+            let boxed = self.temp(IrType::Object(OBJECT.to_string()));
+            self.emit(Instr::Box { dest: boxed, value });
+            Operand::Var(boxed)
+        } else {
+            value
+        }
+    }
+
+    fn lower_args(&mut self, args: &mut [Expression], param_types: &[IrType]) -> Vec<Operand> {
+        let mut operands = Vec::new();
+        for (arg, ty) in args.iter_mut().zip(param_types.iter()) {
+            let value = arg.accept(self);
+            operands.push(self.coerce(value, ty));
+        }
+        operands
+    }
+
+    /// Returns the IR types of the parameters and the return type of a global function.
+    fn function_types(&self, name: &str) -> (Vec<IrType>, IrType) {
+        if let Some(types) = self.functions.get(name) {
+            return types.clone();
+        }
+        match builtins::function(name) {
+            Some(builtin) => (vec![IrType::Number; builtin.params.len()], IrType::Number),
+            None => (Vec::new(), IrType::Object(OBJECT.to_string())),
+        }
+    }
+
+    /// Returns the IR types of the parameters and the return type of `method` on `type_name`.
+    fn method_types(&self, type_name: &str, method: &str) -> Option<(Vec<IrType>, IrType)> {
+        let (_, signature) = self.type_tree.find_method(type_name, method)?;
+        let params = signature
+            .param_types()
+            .iter()
+            .map(|name| IrType::from_type_name(name))
+            .collect();
+        Some((params, IrType::from_type_name(&signature.return_type)))
+    }
+
+    fn constructor_types(&self, type_name: &str) -> Vec<IrType> {
+        self.type_tree
+            .constructor_params(type_name)
+            .iter()
+            .map(|param| IrType::from_type_name(&param.signature))
+            .collect()
+    }
+
+    fn field_type(&self, type_name: &str, field: &str) -> IrType {
+        self.module
+            .get_type(type_name)
+            .and_then(|layout| layout.fields.iter().find(|(name, _)| name == field))
+            .map(|(_, ty)| ty.clone())
+            .unwrap_or(IrType::Object(OBJECT.to_string()))
+    }
+
+    /// Returns whether a call returning `return_type` can be emitted as a tail call of
+    /// the function being lowered.
+    fn can_tail_call(&self, return_type: &IrType) -> bool {
+        if !self.constructing.is_empty() {
+            return false;
+        }
+        match (&self.function.return_type, return_type) {
+            (IrType::Object(_), IrType::Object(_)) => true,
+            (current, callee) => current == callee,
+        }
+    }
+
+    /// Returns from the function with the result of the tail call just emitted.
+    fn finish_tail_call(&mut self, dest: VarId) {
+        self.terminate(Terminator::Return(Operand::Var(dest)));
+        self.continue_unreachable();
+    }
+
+    /// Stores `value` into the result variable of a branch and jumps to `merge`.
+    fn end_branch(&mut self, value: Operand, result: VarId, merge: BlockId) {
+        let ty = self.function.vars[result].ty.clone();
+        let value = self.coerce(value, &ty);
+        self.emit(Instr::Copy {
+            dest: result,
+            src: value,
+        });
+        self.terminate(Terminator::Jump(merge));
+    }
+
+    /// Lowers a global function, or a method when `self_type` is given.
+    fn lower_function(&mut self, def: &mut FunctionDefNode, self_type: Option<&str>) {
+        let name = match self_type {
+            Some(type_name) => method_name(type_name, &def.name),
+            None => def.name.clone(),
+        };
+        let global = match self_type {
+            Some(_) => None,
+            None => Some(def.name.clone()),
+        };
+        self.begin_function(&name, IrType::from_type_name(&def.return_type), global);
+        if let Some(type_name) = self_type {
+            self.declare_param("self", IrType::Object(type_name.to_string()));
+        }
+        for param in def.params.iter() {
+            self.declare_param(&param.name, IrType::from_type_name(&param.signature));
+        }
+        let value = def.body.accept(self);
+        self.finish_function(value);
+    }
+
+    /// Lowers `T.new`, the out-of-line constructor used by recursive constructions.
+    fn lower_constructor(&mut self, type_name: &str) {
+        let ty = IrType::Object(type_name.to_string());
+        self.begin_function(&constructor_name(type_name), ty.clone(), None);
+        let mut args = Vec::new();
+        for param in self.type_tree.constructor_params(type_name) {
+            let var = self.declare_param(&param.name, IrType::from_type_name(&param.signature));
+            args.push(Operand::Var(var));
+        }
+        let object = self.temp(ty);
+        self.emit(Instr::Alloc {
+            dest: object,
+            type_name: type_name.to_string(),
+            stack: false,
+        });
+        self.init_object(Operand::Var(object), type_name, args);
+        self.finish_function(Operand::Var(object));
+    }
+
+    /// Allocates an object of `type_name` and runs its constructor with `args`.
+    fn construct(&mut self, type_name: &str, args: Vec<Operand>) -> Operand {
+        let dest = self.temp(IrType::Object(type_name.to_string()));
+        if self.constructing.iter().any(|name| name == type_name) {
+            self.emit(Instr::Call {
+                dest,
+                function: constructor_name(type_name),
+                args,
+                tail: false,
+            });
+        } else {
+            self.emit(Instr::Alloc {
+                dest,
+                type_name: type_name.to_string(),
+                stack: false,
+            });
+            self.init_object(Operand::Var(dest), type_name, args);
+        }
+        Operand::Var(dest)
+    }
+
+    /// Inlines the constructor of `type_name`, and those of its ancestors, on `object`.
+    ///
+    /// # Arguments
+    /// * `object` - The freshly allocated object.
+    /// * `type_name` - The type whose constructor runs.
+    /// * `args` - The constructor arguments, already coerced to the parameter types.
+    fn init_object(&mut self, object: Operand, type_name: &str, args: Vec<Operand>) {
+        let mut def = match self.type_defs.get(type_name) {
+            Some(def) => def.clone(),
+            None => return,
+        };
+        self.constructing.push(type_name.to_string());
+        // Constructor code only sees the constructor parameters.
+        let saved_scopes = std::mem::replace(&mut self.scopes, vec![HashMap::new()]);
+
+        let inherits_params = def.params.is_empty() && def.parent_args.is_empty();
+        if !inherits_params {
+            for (param, arg) in def.params.iter().zip(args.iter()) {
+                let ty = IrType::from_type_name(&param.signature);
+                let var = self.function.new_var(Some(&param.name), ty);
+                self.emit(Instr::Copy {
+                    dest: var,
+                    src: arg.clone(),
+                });
+                self.scopes
+                    .last_mut()
+                    .unwrap()
+                    .insert(param.name.clone(), var);
+            }
+        }
+
+        if let Some(parent) = def.parent.clone() {
+            let parent_args = if inherits_params {
+                args
+            } else {
+                let param_types = self.constructor_types(&parent);
+                self.lower_args(&mut def.parent_args, &param_types)
+            };
+            self.init_object(object.clone(), &parent, parent_args);
+        }
+
+        for member in def.members.iter_mut() {
+            if let TypeMember::Property(assignment) = member {
+                let value = assignment.expression.accept(self);
+                let ty = self.field_type(type_name, &assignment.identifier);
+                let value = self.coerce(value, &ty);
+                self.emit(Instr::SetField {
+                    object: object.clone(),
+                    type_name: type_name.to_string(),
+                    field: assignment.identifier.clone(),
+                    value,
+                });
+            }
+        }
+
+        self.scopes = saved_scopes;
+        self.constructing.pop();
+    }
+}
+
+impl Visitor<Operand> for IrGenerator<'_> {
+    fn visit_function_def(&mut self, node: &mut FunctionDefNode) -> Operand {
+        self.lower_function(node, None);
+        Operand::Null
+    }
+
+    fn visit_literal_number(&mut self, node: &mut NumberLiteralNode) -> Operand {
+        Operand::Number(node.value)
+    }
+
+    fn visit_literal_boolean(&mut self, node: &mut BooleanLiteralNode) -> Operand {
+        Operand::Boolean(node.value)
+    }
+
+    fn visit_literal_string(&mut self, node: &mut StringLiteralNode) -> Operand {
+        Operand::Str(node.value.clone())
+    }
+
+    fn visit_identifier(&mut self, node: &mut IdentifierNode) -> Operand {
+        match self.lookup(&node.value) {
+            Some(var) => Operand::Var(var),
+            None => builtins::constant(&node.value).map_or(Operand::Null, Operand::Number),
+        }
+    }
+
+    fn visit_function_call(&mut self, node: &mut FunctionCallNode) -> Operand {
+        let (param_types, return_type) = self.function_types(&node.function_name);
+        let args = self.lower_args(&mut node.arguments, &param_types);
+
+        let recursive = node.tail_call == Some(TailCallKind::Recursive)
+            && self.constructing.is_empty()
+            && self.current_function.as_deref() == Some(node.function_name.as_str());
+        if recursive {
+            // Arguments may read the parameters, so they are saved before any is overwritten.
+            let saved: Vec<VarId> = args
+                .into_iter()
+                .map(|arg| {
+                    let ty = self.function.operand_type(&arg);
+                    let temp = self.temp(ty);
+                    self.emit(Instr::Copy {
+                        dest: temp,
+                        src: arg,
+                    });
+                    temp
+                })
+                .collect();
+            for (param, temp) in self.function.params.clone().into_iter().zip(saved) {
+                self.emit(Instr::Copy {
+                    dest: param,
+                    src: Operand::Var(temp),
+                });
+            }
+            self.terminate(Terminator::Jump(self.body_block));
+            self.continue_unreachable();
+            return return_type.default_value();
+        }
+
+        let tail = node.tail_call.is_some() && self.can_tail_call(&return_type);
+        let dest = self.temp(return_type);
+        self.emit(Instr::Call {
+            dest,
+            function: node.function_name.clone(),
+            args,
+            tail,
+        });
+        if tail {
+            self.finish_tail_call(dest);
+        }
+        Operand::Var(dest)
+    }
+
+    fn visit_while_loop(&mut self, node: &mut WhileNode) -> Operand {
+        let ty = Self::ir_type(node.node_type.as_ref());
+        let result = self.temp(ty.clone());
+        self.emit(Instr::Copy {
+            dest: result,
+            src: ty.default_value(),
+        });
+
+        let header = self.function.new_block();
+        let body = self.function.new_block();
+        let exit = self.function.new_block();
+        self.terminate(Terminator::Jump(header));
+
+        self.switch_to(header);
+        let cond = node.condition.accept(self);
+        self.terminate(Terminator::Branch {
+            cond,
+            then_block: body,
+            else_block: exit,
+        });
+
+        self.switch_to(body);
+        let value = node.body.accept(self);
+        self.end_branch(value, result, header);
+
+        self.switch_to(exit);
+        Operand::Var(result)
+    }
+
+    fn visit_for_loop(&mut self, node: &mut ForNode) -> Operand {
+        let ty = Self::ir_type(node.node_type.as_ref());
+        let result = self.temp(ty.clone());
+        self.emit(Instr::Copy {
+            dest: result,
+            src: ty.default_value(),
+        });
+
+        let counter_name = format!("{}.counter", node.variable);
+        let counter = self.function.new_var(Some(&counter_name), IrType::Number);
+        let start = node.start.accept(self);
+        self.emit(Instr::Copy {
+            dest: counter,
+            src: start,
+        });
+        let end = self.temp(IrType::Number);
+        let end_value = node.end.accept(self);
+        self.emit(Instr::Copy {
+            dest: end,
+            src: end_value,
+        });
+
+        let header = self.function.new_block();
+        let body = self.function.new_block();
+        let exit = self.function.new_block();
+        self.terminate(Terminator::Jump(header));
+
+        self.switch_to(header);
+        let cond = self.temp(IrType::Boolean);
+        self.emit(Instr::Binary {
+            dest: cond,
+            op: BinaryOp::Lt,
+            left: Operand::Var(counter),
+            right: Operand::Var(end),
+        });
+        self.terminate(Terminator::Branch {
+            cond: Operand::Var(cond),
+            then_block: body,
+            else_block: exit,
+        });
+
+        // A copy of the counter per iteration, as `ForNode` documents.
+        self.switch_to(body);
+        self.scopes.push(HashMap::new());
+        let variable = self.function.new_var(Some(&node.variable), IrType::Number);
+        self.emit(Instr::Copy {
+            dest: variable,
+            src: Operand::Var(counter),
+        });
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(node.variable.clone(), variable);
+        let value = node.body.accept(self);
+        self.scopes.pop();
+        let value = self.coerce(value, &ty);
+        self.emit(Instr::Copy {
+            dest: result,
+            src: value,
+        });
+        self.emit(Instr::Binary {
+            dest: counter,
+            op: BinaryOp::Add,
+            left: Operand::Var(counter),
+            right: Operand::Number(1.0),
+        });
+        self.terminate(Terminator::Jump(header));
+
+        self.switch_to(exit);
+        Operand::Var(result)
+    }
+
+    fn visit_code_block(&mut self, node: &mut BlockNode) -> Operand {
+        let mut value = Operand::Null;
+        for expr in node.expression_list.expressions.iter_mut() {
+            value = expr.accept(self);
+        }
+        value
+    }
+
+    fn visit_binary_op(&mut self, node: &mut BinaryOpNode) -> Operand {
+        let mut left = node.left.accept(self);
+        let mut right = node.right.accept(self);
+        let op = match node.operator {
+            OperatorToken::PLUS => BinaryOp::Add,
+            OperatorToken::MINUS => BinaryOp::Sub,
+            OperatorToken::MUL => BinaryOp::Mul,
+            OperatorToken::DIV => BinaryOp::Div,
+            OperatorToken::MOD => BinaryOp::Mod,
+            OperatorToken::POW => BinaryOp::Pow,
+            OperatorToken::LT => BinaryOp::Lt,
+            OperatorToken::LTE => BinaryOp::Le,
+            OperatorToken::GT => BinaryOp::Gt,
+            OperatorToken::GTE => BinaryOp::Ge,
+            OperatorToken::EQ => BinaryOp::Eq,
+            OperatorToken::NEQ => BinaryOp::Ne,
+            OperatorToken::AND => BinaryOp::And,
+            OperatorToken::OR => BinaryOp::Or,
+            OperatorToken::CONCAT => BinaryOp::Concat,
+            ref operator => unreachable!("`{}` is not a binary operator", operator),
+        };
+
+        if op == BinaryOp::Eq || op == BinaryOp::Ne {
+            let left_type = self.function.operand_type(&left);
+            let right_type = self.function.operand_type(&right);
+            if left_type.is_object() || right_type.is_object() {
+                let object = IrType::Object(OBJECT.to_string());
+                left = self.coerce(left, &object);
+                right = self.coerce(right, &object);
+            } else if left_type != right_type {
+                // Values of different primitive types are never equal.
+                return Operand::Boolean(op == BinaryOp::Ne);
+            }
+        }
+
+        let dest = self.temp(Self::ir_type(node.node_type.as_ref()));
+        self.emit(Instr::Binary {
+            dest,
+            op,
+            left,
+            right,
+        });
+        Operand::Var(dest)
+    }
+
+    fn visit_unary_op(&mut self, node: &mut UnaryOpNode) -> Operand {
+        let operand = node.operand.accept(self);
+        let op = match node.operator {
+            OperatorToken::NOT => UnaryOp::Not,
+            _ => UnaryOp::Neg,
+        };
+        let dest = self.temp(Self::ir_type(node.node_type.as_ref()));
+        self.emit(Instr::Unary { dest, op, operand });
+        Operand::Var(dest)
+    }
+
+    fn visit_if_else(&mut self, node: &mut IfElseNode) -> Operand {
+        let ty = Self::ir_type(node.node_type.as_ref());
+        let result = self.temp(ty.clone());
+        let merge = self.function.new_block();
+
+        let cond = node.condition.accept(self);
+        let then_block = self.function.new_block();
+        let mut next = self.function.new_block();
+        self.terminate(Terminator::Branch {
+            cond,
+            then_block,
+            else_block: next,
+        });
+        self.switch_to(then_block);
+        let value = node.if_expression.accept(self);
+        self.end_branch(value, result, merge);
+
+        let mut has_else = false;
+        for (condition, body) in node.elifs.iter_mut() {
+            self.switch_to(next);
+            match condition {
+                Some(condition) => {
+                    let cond = condition.accept(self);
+                    let then_block = self.function.new_block();
+                    next = self.function.new_block();
+                    self.terminate(Terminator::Branch {
+                        cond,
+                        then_block,
+                        else_block: next,
+                    });
+                    self.switch_to(then_block);
+                    let value = body.accept(self);
+                    self.end_branch(value, result, merge);
+                }
+                None => {
+                    let value = body.accept(self);
+                    self.end_branch(value, result, merge);
+                    has_else = true;
+                    break;
+                }
+            }
+        }
+        if !has_else {
+            self.switch_to(next);
+            self.end_branch(ty.default_value(), result, merge);
+        }
+
+        self.switch_to(merge);
+        Operand::Var(result)
+    }
+
+    fn visit_let_in(&mut self, node: &mut LetInNode) -> Operand {
+        self.scopes.push(HashMap::new());
+        for assignment in node.assignments.iter_mut() {
+            let value = assignment.expression.accept(self);
+            let ty = Self::ir_type(assignment.node_type.as_ref());
+            let value = self.coerce(value, &ty);
+            let var = self.function.new_var(Some(&assignment.identifier), ty);
+            self.emit(Instr::Copy {
+                dest: var,
+                src: value,
+            });
+            self.scopes
+                .last_mut()
+                .unwrap()
+                .insert(assignment.identifier.clone(), var);
+        }
+        let value = node.body.accept(self);
+        self.scopes.pop();
+        value
+    }
+
+    fn visit_destructive_assign(&mut self, node: &mut DestructiveAssignNode) -> Operand {
+        let value = node.expression.accept(self);
+        match node.identifier.as_mut() {
+            Expression::Identifier(identifier) => {
+                if let Some(var) = self.lookup(&identifier.value) {
+                    let ty = self.function.vars[var].ty.clone();
+                    let value = self.coerce(value.clone(), &ty);
+                    self.emit(Instr::Copy {
+                        dest: var,
+                        src: value,
+                    });
+                }
+            }
+            Expression::TypePropAccess(access) => {
+                let object = access.object.accept(self);
+                let type_name = self.function.operand_type(&object).to_string();
+                let ty = self.field_type(&type_name, &access.member);
+                let field_value = self.coerce(value.clone(), &ty);
+                self.emit(Instr::SetField {
+                    object,
+                    type_name,
+                    field: access.member.to_string(),
+                    value: field_value,
+                });
+            }
+            _ => {}
+        }
+        value
+    }
+
+    fn visit_type_def(&mut self, node: &mut TypeDefNode) -> Operand {
+        let type_name = node.identifier.clone();
+        self.lower_constructor(&type_name);
+        for member in node.members.iter_mut() {
+            if let TypeMember::Method(method) = member {
+                self.lower_function(method, Some(&type_name));
+            }
+        }
+        Operand::Null
+    }
+
+    fn visit_type_instance(&mut self, node: &mut TypeInstanceNode) -> Operand {
+        let param_types = self.constructor_types(&node.type_name);
+        let args = self.lower_args(&mut node.arguments, &param_types);
+        self.construct(&node.type_name, args)
+    }
+
+    fn visit_type_function_access(&mut self, node: &mut TypeFunctionAccessNode) -> Operand {
+        let receiver = node.object.accept(self);
+        let static_type = self.function.operand_type(&receiver).to_string();
+        let method = node.member.function_name.clone();
+        let (param_types, return_type) = self
+            .method_types(&static_type, &method)
+            .unwrap_or_else(|| (Vec::new(), Self::ir_type(node.node_type.as_ref())));
+        let args = self.lower_args(&mut node.member.arguments, &param_types);
+
+        let tail = node.member.tail_call.is_some() && self.can_tail_call(&return_type);
+        let dest = self.temp(return_type);
+        match &node.direct_call {
+            Some(owner) => self.emit(Instr::Call {
+                dest,
+                function: method_name(owner, &method),
+                args: std::iter::once(receiver).chain(args).collect(),
+                tail,
+            }),
+            None => self.emit(Instr::CallMethod {
+                dest,
+                receiver,
+                static_type,
+                method,
+                args,
+                tail,
+            }),
+        }
+        if tail {
+            self.finish_tail_call(dest);
+        }
+        Operand::Var(dest)
+    }
+
+    fn visit_type_prop_access(&mut self, node: &mut TypePropAccessNode) -> Operand {
+        let object = node.object.accept(self);
+        let type_name = self.function.operand_type(&object).to_string();
+        let dest = self.temp(self.field_type(&type_name, &node.member));
+        self.emit(Instr::GetField {
+            dest,
+            object,
+            type_name,
+            field: node.member.to_string(),
+        });
+        Operand::Var(dest)
+    }
+
+    fn visit_print(&mut self, node: &mut PrintNode) -> Operand {
+        let value = node.expression.accept(self);
+        self.emit(Instr::Print {
+            value: value.clone(),
+        });
+        value
+    }
+}
@@ -0,0 +1,369 @@
+//! Loop-invariant code motion and strength reduction over the IR.
+//!
+//! Both passes work on the natural loops found by [`find_loops`], innermost first, and
+//! place new code in the loop's preheader:
+//! - a pure instruction whose operands do not change inside the loop is computed once
+//!   in the preheader instead of on every iteration;
+//! - a multiplication `i * c` of an induction variable `i` (a variable only changed by
+//!   `i := i + k` inside the loop, directly or through a temporary) by a positive
+//!   integer `c` is replaced with a variable that starts at `i * c` and grows by
+//!   `k * c` every time `i` is incremented.
+//!
+//! Strength reduction only fires when `i`, `k` and `c` are integers, so the sums give
+//! exactly the same `Number`s as the products they replace.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::builtins;
+
+use super::ir::{BinaryOp, BlockId, Function, Instr, Module, Operand, VarId};
+use super::loops::{ensure_preheader, find_loops, Loop};
+
+/// Runs loop-invariant code motion and strength reduction, counting what was done.
+#[derive(Default)]
+pub struct LoopOptimizer {
+    pub loops: usize,
+    pub hoisted: usize,
+    pub reduced: usize,
+}
+
+impl LoopOptimizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Optimizes the loops of every function of `module` in place.
+    pub fn optimize_module(&mut self, module: &mut Module) {
+        let user_functions: HashSet<String> = module
+            .functions
+            .iter()
+            .map(|function| function.name.clone())
+            .collect();
+        for function in module.functions.iter_mut() {
+            self.optimize_function(function, &user_functions);
+        }
+    }
+
+    /// Returns a one-line summary of the transformations applied.
+    pub fn report(&self) -> String {
+        format!(
+            "found {} loop(s), hoisted {} invariant instruction(s), strength-reduced {} multiplication(s)",
+            self.loops, self.hoisted, self.reduced
+        )
+    }
+
+    fn optimize_function(&mut self, function: &mut Function, user_functions: &HashSet<String>) {
+        let headers: Vec<BlockId> = find_loops(function).iter().map(|lp| lp.header).collect();
+        self.loops += headers.len();
+        for header in headers {
+            // Preheaders of inner loops become part of the outer ones, so loops are
+            // computed again before each one is optimized.
+            let lp = match find_loops(function).into_iter().find(|lp| lp.header == header) {
+                Some(lp) => lp,
+                None => continue,
+            };
+            let preheader = ensure_preheader(function, &lp);
+            self.hoisted += hoist_invariants(function, &lp, preheader, user_functions);
+            self.reduced += reduce_strength(function, &lp, preheader);
+        }
+    }
+}
+
+/// Counts the instructions writing each variable, in the given blocks.
+fn count_definitions<'a>(blocks: impl Iterator<Item = &'a Vec<Instr>>) -> HashMap<VarId, usize> {
+    let mut counts = HashMap::new();
+    for instr in blocks.flat_map(|instrs| instrs.iter()) {
+        if let Some(dest) = instr.dest() {
+            *counts.entry(dest).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+fn loop_definitions(function: &Function, lp: &Loop) -> HashMap<VarId, usize> {
+    count_definitions(lp.blocks.iter().map(|&block| &function.blocks[block].instrs))
+}
+
+fn all_definitions(function: &Function) -> HashMap<VarId, usize> {
+    let mut counts = count_definitions(function.blocks.iter().map(|block| &block.instrs));
+    for param in function.params.iter() {
+        *counts.entry(*param).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Moves loop-invariant pure instructions to the preheader.
+///
+/// Only temporaries written once in the whole function are moved, so every use of
+/// their value still sees the same one. Pure instructions cannot fail, so computing
+/// them even when the loop body does not run is harmless.
+///
+/// # Returns
+/// The number of instructions moved.
+fn hoist_invariants(
+    function: &mut Function,
+    lp: &Loop,
+    preheader: BlockId,
+    user_functions: &HashSet<String>,
+) -> usize {
+    let mut hoisted = 0;
+    loop {
+        let in_loop = loop_definitions(function, lp);
+        let everywhere = all_definitions(function);
+        let invariant = |operand: &Operand| match operand {
+            Operand::Var(var) => !in_loop.contains_key(var),
+            _ => true,
+        };
+        let candidate = lp.blocks.iter().find_map(|&block| {
+            function.blocks[block]
+                .instrs
+                .iter()
+                .position(|instr| {
+                    let dest = match instr.dest() {
+                        Some(dest) => dest,
+                        None => return false,
+                    };
+                    is_pure(instr, user_functions)
+                        && function.vars[dest].name.is_none()
+                        && everywhere.get(&dest) == Some(&1)
+                        && instr.operands().into_iter().all(invariant)
+                })
+                .map(|index| (block, index))
+        });
+        match candidate {
+            Some((block, index)) => {
+                let instr = function.blocks[block].instrs.remove(index);
+                function.blocks[preheader].instrs.push(instr);
+                hoisted += 1;
+            }
+            None => return hoisted,
+        }
+    }
+}
+
+/// Returns whether `instr` computes its destination from its operands alone, counting
+/// calls to pure builtins the program does not redefine.
+fn is_pure(instr: &Instr, user_functions: &HashSet<String>) -> bool {
+    match instr {
+        Instr::Call { function, tail, .. } => {
+            !tail
+                && builtins::function(function).is_some_and(|builtin| builtin.pure)
+                && !user_functions.contains(function)
+        }
+        _ => instr.is_pure(),
+    }
+}
+
+fn integer(value: f64) -> bool {
+    value.is_finite() && value.fract() == 0.0
+}
+
+/// Returns the step of `var` if it is an induction variable of `lp` holding integers.
+fn induction_step(function: &Function, lp: &Loop, var: VarId) -> Option<f64> {
+    if function.params.contains(&var) {
+        return None;
+    }
+    let mut step = None;
+    let mut initialized = false;
+    for (block, instrs) in function.blocks.iter().enumerate().map(|(id, block)| (id, &block.instrs)) {
+        let definitions = instrs.iter().enumerate().filter(|(_, instr)| instr.dest() == Some(var));
+        for (index, instr) in definitions {
+            if !lp.contains(block) {
+                match instr {
+                    Instr::Copy {
+                        src: Operand::Number(value),
+                        ..
+                    } if integer(*value) => initialized = true,
+                    _ => return None,
+                }
+                continue;
+            }
+            let k = match increment(function, instrs, index) {
+                Instr::Binary {
+                    op: BinaryOp::Add,
+                    left: Operand::Var(left),
+                    right: Operand::Number(k),
+                    ..
+                }
+                | Instr::Binary {
+                    op: BinaryOp::Add,
+                    left: Operand::Number(k),
+                    right: Operand::Var(left),
+                    ..
+                } if *left == var => *k,
+                Instr::Binary {
+                    op: BinaryOp::Sub,
+                    left: Operand::Var(left),
+                    right: Operand::Number(k),
+                    ..
+                } if *left == var => -*k,
+                _ => return None,
+            };
+            if step.is_some() || !integer(k) {
+                return None;
+            }
+            step = Some(k);
+        }
+    }
+    step.filter(|_| initialized)
+}
+
+/// Returns the instruction that computes the value `instrs[index]` writes. `i := i + k`
+/// computes the sum into a temporary and copies it right after, which is how the
+/// counter of a desugared `for` loop is incremented.
+fn increment<'a>(function: &Function, instrs: &'a [Instr], index: usize) -> &'a Instr {
+    let instr = &instrs[index];
+    match (instr, index.checked_sub(1).map(|previous| &instrs[previous])) {
+        (
+            Instr::Copy {
+                src: Operand::Var(temp),
+                ..
+            },
+            Some(previous),
+        ) if previous.dest() == Some(*temp) && function.vars[*temp].name.is_none() => previous,
+        _ => instr,
+    }
+}
+
+/// Returns the induction variable `var` is a copy of, if it is only written once by
+/// copying one inside the loop.
+fn copied_induction(function: &Function, lp: &Loop, var: VarId) -> Option<VarId> {
+    if function.params.contains(&var) {
+        return None;
+    }
+    let mut source = None;
+    for (block, instrs) in function.blocks.iter().enumerate().map(|(id, block)| (id, &block.instrs)) {
+        for instr in instrs.iter().filter(|instr| instr.dest() == Some(var)) {
+            match instr {
+                Instr::Copy {
+                    src: Operand::Var(src),
+                    ..
+                } if lp.contains(block) && source.is_none() => source = Some(*src),
+                _ => return None,
+            }
+        }
+    }
+    source.filter(|&src| induction_step(function, lp, src).is_some())
+}
+
+/// Returns the position of the only instruction writing `var` in `blocks`.
+fn find_definition(function: &Function, blocks: impl Iterator<Item = BlockId>, var: VarId) -> Option<(BlockId, usize)> {
+    blocks.into_iter().find_map(|block| {
+        function.blocks[block]
+            .instrs
+            .iter()
+            .position(|instr| instr.dest() == Some(var))
+            .map(|index| (block, index))
+    })
+}
+
+fn scaled_var(function: &mut Function, var: VarId) -> VarId {
+    let ty = function.vars[var].ty.clone();
+    let name = match &function.vars[var].name {
+        Some(name) => format!("{}.scaled", name),
+        None => format!("{}.scaled", var),
+    };
+    function.new_var(Some(&name), ty)
+}
+
+/// Replaces multiplications of induction variables by constants with additions.
+///
+/// # Returns
+/// The number of multiplications replaced.
+fn reduce_strength(function: &mut Function, lp: &Loop, preheader: BlockId) -> usize {
+    // Multiplications `dest = var * factor` with `var` an induction variable or a copy of one.
+    let mut candidates: Vec<(VarId, VarId, f64)> = Vec::new();
+    for &block in lp.blocks.iter() {
+        for instr in function.blocks[block].instrs.iter() {
+            let (dest, var, factor) = match instr {
+                Instr::Binary {
+                    dest,
+                    op: BinaryOp::Mul,
+                    left: Operand::Var(var),
+                    right: Operand::Number(factor),
+                }
+                | Instr::Binary {
+                    dest,
+                    op: BinaryOp::Mul,
+                    left: Operand::Number(factor),
+                    right: Operand::Var(var),
+                } => (*dest, *var, *factor),
+                _ => continue,
+            };
+            if integer(factor) && factor > 0.0 {
+                candidates.push((dest, var, factor));
+            }
+        }
+    }
+
+    // Variables holding `var * factor`, keyed by `(var, factor)`.
+    let mut scaled: HashMap<(VarId, u64), VarId> = HashMap::new();
+    let mut reduced = 0;
+    for (dest, var, factor) in candidates {
+        let key = (var, factor.to_bits());
+        let replacement = match scaled.get(&key) {
+            Some(&replacement) => replacement,
+            None => {
+                let (induction, copy) = match induction_step(function, lp, var) {
+                    Some(_) => (var, None),
+                    None => match copied_induction(function, lp, var) {
+                        Some(induction) => (induction, Some(var)),
+                        None => continue,
+                    },
+                };
+                let induction_scaled = match scaled.get(&(induction, factor.to_bits())) {
+                    Some(&existing) => existing,
+                    None => {
+                        let step = induction_step(function, lp, induction).unwrap();
+                        let induction_scaled = scaled_var(function, induction);
+                        function.blocks[preheader].instrs.push(Instr::Binary {
+                            dest: induction_scaled,
+                            op: BinaryOp::Mul,
+                            left: Operand::Var(induction),
+                            right: Operand::Number(factor),
+                        });
+                        let (block, index) = find_definition(function, lp.blocks.iter().copied(), induction).unwrap();
+                        function.blocks[block].instrs.insert(
+                            index + 1,
+                            Instr::Binary {
+                                dest: induction_scaled,
+                                op: BinaryOp::Add,
+                                left: Operand::Var(induction_scaled),
+                                right: Operand::Number(step * factor),
+                            },
+                        );
+                        scaled.insert((induction, factor.to_bits()), induction_scaled);
+                        induction_scaled
+                    }
+                };
+                match copy {
+                    // A copy of the induction variable gets its own scaled copy, taken
+                    // at the same point, so it keeps matching the copy.
+                    Some(copy) => {
+                        let copy_scaled = scaled_var(function, copy);
+                        let (block, index) = find_definition(function, lp.blocks.iter().copied(), copy).unwrap();
+                        function.blocks[block].instrs.insert(
+                            index + 1,
+                            Instr::Copy {
+                                dest: copy_scaled,
+                                src: Operand::Var(induction_scaled),
+                            },
+                        );
+                        scaled.insert(key, copy_scaled);
+                        copy_scaled
+                    }
+                    None => induction_scaled,
+                }
+            }
+        };
+
+        if let Some((block, index)) = find_definition(function, lp.blocks.iter().copied(), dest) {
+            function.blocks[block].instrs[index] = Instr::Copy {
+                dest,
+                src: Operand::Var(replacement),
+            };
+            reduced += 1;
+        }
+    }
+    reduced
+}
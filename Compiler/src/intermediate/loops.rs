@@ -0,0 +1,112 @@
+//! Natural-loop detection on the control-flow graph of a [`Function`].
+//!
+//! A back edge is an edge `tail -> header` where `header` dominates `tail`. The natural
+//! loop of a header is the header plus every block that reaches one of its back edges
+//! without going through the header. Loops sharing a header are merged.
+
+use std::collections::BTreeSet;
+
+use super::ir::{BlockId, Function, Terminator};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Loop {
+    pub header: BlockId,
+    /// Every block of the loop, header included.
+    pub blocks: BTreeSet<BlockId>,
+}
+
+impl Loop {
+    pub fn contains(&self, block: BlockId) -> bool {
+        self.blocks.contains(&block)
+    }
+}
+
+/// Computes, for every block, whether each block dominates it.
+///
+/// # Returns
+/// `dominators[b][d]` is true when every path from the entry to `b` goes through `d`.
+/// Unreachable blocks are dominated by every block.
+pub fn dominators(function: &Function) -> Vec<Vec<bool>> {
+    let count = function.blocks.len();
+    let preds = function.predecessors();
+    let order = function.reachable_blocks();
+    let mut dominators = vec![vec![true; count]; count];
+    dominators[function.entry] = vec![false; count];
+    dominators[function.entry][function.entry] = true;
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &block in order.iter().filter(|&&block| block != function.entry) {
+            let mut set = vec![true; count];
+            for &pred in preds[block].iter() {
+                for (d, dominated) in set.iter_mut().enumerate() {
+                    *dominated &= dominators[pred][d];
+                }
+            }
+            set[block] = true;
+            if set != dominators[block] {
+                dominators[block] = set;
+                changed = true;
+            }
+        }
+    }
+    dominators
+}
+
+/// Finds the natural loops of `function`, innermost (smallest) first.
+pub fn find_loops(function: &Function) -> Vec<Loop> {
+    let dominators = dominators(function);
+    let preds = function.predecessors();
+    let mut loops: Vec<Loop> = Vec::new();
+
+    for tail in function.reachable_blocks() {
+        for header in function.blocks[tail].terminator.successors() {
+            if !dominators[tail][header] {
+                continue;
+            }
+            let mut blocks = BTreeSet::from([header]);
+            let mut pending = vec![tail];
+            while let Some(block) = pending.pop() {
+                if blocks.insert(block) {
+                    pending.extend(preds[block].iter().copied());
+                }
+            }
+            match loops.iter_mut().find(|l| l.header == header) {
+                Some(existing) => existing.blocks.extend(blocks),
+                None => loops.push(Loop { header, blocks }),
+            }
+        }
+    }
+
+    loops.sort_by_key(|l| (l.blocks.len(), l.header));
+    loops
+}
+
+/// Returns a block that runs right before entering `lp` and only then, creating one
+/// if the header has no such predecessor.
+///
+/// Blocks added here belong to every loop enclosing `lp`, so loops found before the
+/// call must be computed again.
+pub fn ensure_preheader(function: &mut Function, lp: &Loop) -> BlockId {
+    let outside: Vec<BlockId> = function.predecessors()[lp.header]
+        .iter()
+        .copied()
+        .filter(|pred| !lp.contains(*pred))
+        .collect();
+    if let [pred] = outside[..] {
+        if function.blocks[pred].terminator == Terminator::Jump(lp.header) {
+            return pred;
+        }
+    }
+
+    let preheader = function.new_block();
+    function.blocks[preheader].terminator = Terminator::Jump(lp.header);
+    for pred in outside {
+        function.blocks[pred].terminator.retarget(lp.header, preheader);
+    }
+    if function.entry == lp.header {
+        function.entry = preheader;
+    }
+    preheader
+}
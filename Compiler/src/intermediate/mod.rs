@@ -1 +1,5 @@
-// ...implementación del generador de código intermedio...
+pub mod escape;
+pub mod ir;
+pub mod ir_generator;
+pub mod loop_optimizations;
+pub mod loops;
@@ -0,0 +1,8 @@
+//! A tree-walking interpreter that runs programs straight from the AST.
+//!
+//! It defines the reference semantics of the language: every backend must print
+//! what the interpreter prints for the same program.
+
+pub mod runtime_error;
+pub mod tree_walker;
+pub mod value;
@@ -0,0 +1,117 @@
+//! Errors that stop a program run by the interpreter.
+
+use std::fmt;
+
+use crate::tokens::Span;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RuntimeError {
+    UndefinedVariable {
+        name: String,
+        span: Span,
+    },
+    UndefinedFunction {
+        name: String,
+        span: Span,
+    },
+    UndefinedType {
+        name: String,
+        span: Span,
+    },
+    UndefinedMethod {
+        type_name: String,
+        name: String,
+        span: Span,
+    },
+    UndefinedAttribute {
+        type_name: String,
+        name: String,
+        span: Span,
+    },
+    TypeMismatch {
+        expected: String,
+        found: String,
+        span: Span,
+    },
+    ArgumentCountMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+        span: Span,
+    },
+    InvalidAssignmentTarget {
+        span: Span,
+    },
+    StackOverflow {
+        span: Span,
+    },
+    /// Writing the output of `print` failed.
+    Output {
+        message: String,
+        span: Span,
+    },
+}
+
+impl RuntimeError {
+    /// Returns the region of the source that was being evaluated.
+    pub fn span(&self) -> Span {
+        match self {
+            RuntimeError::UndefinedVariable { span, .. }
+            | RuntimeError::UndefinedFunction { span, .. }
+            | RuntimeError::UndefinedType { span, .. }
+            | RuntimeError::UndefinedMethod { span, .. }
+            | RuntimeError::UndefinedAttribute { span, .. }
+            | RuntimeError::TypeMismatch { span, .. }
+            | RuntimeError::ArgumentCountMismatch { span, .. }
+            | RuntimeError::InvalidAssignmentTarget { span }
+            | RuntimeError::StackOverflow { span }
+            | RuntimeError::Output { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuntimeError::UndefinedVariable { name, .. } => {
+                write!(f, "variable `{}` is not defined", name)
+            }
+            RuntimeError::UndefinedFunction { name, .. } => {
+                write!(f, "function `{}` is not defined", name)
+            }
+            RuntimeError::UndefinedType { name, .. } => write!(f, "type `{}` is not defined", name),
+            RuntimeError::UndefinedMethod {
+                type_name, name, ..
+            } => {
+                write!(f, "type `{}` has no method `{}`", type_name, name)
+            }
+            RuntimeError::UndefinedAttribute {
+                type_name, name, ..
+            } => {
+                write!(f, "type `{}` has no attribute `{}`", type_name, name)
+            }
+            RuntimeError::TypeMismatch {
+                expected, found, ..
+            } => write!(
+                f,
+                "expected a value of type `{}`, found `{}`",
+                expected, found
+            ),
+            RuntimeError::ArgumentCountMismatch {
+                name,
+                expected,
+                found,
+                ..
+            } => write!(
+                f,
+                "`{}` expects {} arguments, but {} were given",
+                name, expected, found
+            ),
+            RuntimeError::InvalidAssignmentTarget { .. } => {
+                write!(f, "only variables and attributes can be assigned")
+            }
+            RuntimeError::StackOverflow { .. } => write!(f, "too many nested calls"),
+            RuntimeError::Output { message, .. } => write!(f, "cannot write output: {}", message),
+        }
+    }
+}
@@ -0,0 +1,734 @@
+//! `Interpreter` implements the [`Visitor`] trait to evaluate a program directly on
+//! its AST.
+//!
+//! Variables live in a stack of scopes: `let` and each iteration of a `for` push one,
+//! and calls replace the whole stack, so a function only sees its parameters. Objects
+//! keep their attributes by name and methods are looked up from the dynamic type of
+//! the receiver up through its ancestors.
+//!
+//! Calls in tail position, as marked by [`TailCallVisitor`], do not nest: the call
+//! returns to the function that runs it, which then runs the callee in its place, so
+//! tail recursion, mutual or not, runs in constant native stack space like it does in
+//! the compiled code.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+use std::rc::Rc;
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::BlockNode;
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::{FunctionCallNode, TailCallKind};
+use crate::ast_nodes::function_def::FunctionDefNode;
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::LetInNode;
+use crate::ast_nodes::literals::{
+    BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode,
+};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::{Program, Statement};
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+use crate::builtins;
+use crate::random::{Random, DEFAULT_SEED};
+use crate::semantic::tail_calls::TailCallVisitor;
+use crate::tokens::{OperatorToken, Span};
+use crate::types_tree::tree_node::TypeNode;
+use crate::types_tree::type_tree::{BOOLEAN, NUMBER, OBJECT};
+use crate::visitor::accept::Accept;
+use crate::visitor::visitor_trait::Visitor;
+
+use super::runtime_error::RuntimeError;
+use super::value::{Instance, Value};
+
+/// Calls nested deeper than this stop the program before the native stack overflows.
+pub const MAX_CALL_DEPTH: usize = 10_000;
+
+/// Stack size of the thread [`run`] evaluates on, enough for [`MAX_CALL_DEPTH`] calls
+/// in a debug build.
+pub const STACK_SIZE: usize = 256 << 20;
+
+type Scope = HashMap<String, Value>;
+
+/// A call in tail position, run once the call it ends has unwound.
+enum TailCall {
+    /// The enclosing function again, with these arguments.
+    Recursive(Vec<Value>),
+    /// Another function or a method, with its parameters bound, and the name of the
+    /// global function it is, if any.
+    General {
+        def: Box<FunctionDefNode>,
+        scope: Scope,
+        function: Option<String>,
+    },
+}
+
+/// Returns the default value for the static type of a node, if it has one.
+fn default_for(node_type: &Option<TypeNode>) -> Value {
+    node_type
+        .as_ref()
+        .map_or(Value::Null, |node| Value::default_of(&node.type_name))
+}
+
+/// Runs a program that passed the semantic phase, writing what it prints to `out`.
+///
+/// The program is evaluated on a new thread with a [`STACK_SIZE`] stack, so deep
+/// recursion ends with [`RuntimeError::StackOverflow`] instead of aborting.
+pub fn run<W: Write + Send>(program: &mut Program, out: &mut W) -> Result<(), RuntimeError> {
+    run_with_seed(program, DEFAULT_SEED, out)
+}
+
+/// Like [`run`], with `seed` for the numbers `rand()` returns.
+pub fn run_with_seed<W: Write + Send>(
+    program: &mut Program,
+    seed: u64,
+    out: &mut W,
+) -> Result<(), RuntimeError> {
+    std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .name("interpreter".to_string())
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, || {
+                Interpreter::new(out)
+                    .with_seed(seed)
+                    .run(program)
+                    .map(|_| ())
+            })
+            .expect("interpreter thread starts")
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+/// A visitor that runs a program, writing what it prints to `out`.
+///
+/// It evaluates on the calling thread; see [`run`] to get room for deep recursion.
+pub struct Interpreter<W: Write> {
+    out: W,
+    functions: HashMap<String, FunctionDefNode>,
+    types: HashMap<String, TypeDefNode>,
+    /// Variables in scope, innermost last.
+    scopes: Vec<Scope>,
+    /// Name of the global function being run, `None` inside methods and top-level
+    /// expressions.
+    current_function: Option<String>,
+    /// The tail call waiting for the current call to unwind.
+    tail_call: Option<TailCall>,
+    depth: usize,
+    random: Random,
+}
+
+impl<W: Write> Interpreter<W> {
+    pub fn new(out: W) -> Self {
+        Interpreter {
+            out,
+            functions: HashMap::new(),
+            types: HashMap::new(),
+            scopes: vec![Scope::new()],
+            current_function: None,
+            tail_call: None,
+            depth: 0,
+            random: Random::default(),
+        }
+    }
+
+    /// Seeds the numbers `rand()` returns.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.random = Random::new(seed);
+        self
+    }
+
+    /// Returns the writer the program printed to.
+    pub fn into_output(self) -> W {
+        self.out
+    }
+
+    /// Runs a program that passed the semantic phase.
+    ///
+    /// # Arguments
+    /// * `node` - The root `Program` node. Its tail calls are marked again before
+    ///   running.
+    ///
+    /// # Returns
+    /// The value of the last top-level expression, or the error that stopped the
+    /// program.
+    pub fn run(&mut self, node: &mut Program) -> Result<Value, RuntimeError> {
+        TailCallVisitor::new().mark_program(node);
+        for statement in node.statements.iter() {
+            match statement {
+                Statement::StatementFunctionDef(def) => {
+                    self.functions.insert(def.name.clone(), (**def).clone());
+                }
+                Statement::StatementTypeDef(def) => {
+                    self.types.insert(def.identifier.clone(), (**def).clone());
+                }
+                Statement::StatementExpression(_) => {}
+            }
+        }
+
+        let mut result = Value::Null;
+        for statement in node.statements.iter_mut() {
+            if let Statement::StatementExpression(expr) = statement {
+                result = expr.accept(self)?;
+            }
+        }
+        Ok(result)
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Value> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn number(&self, value: Value, span: Span) -> Result<f64, RuntimeError> {
+        match value {
+            Value::Number(value) => Ok(value),
+            other => Err(RuntimeError::TypeMismatch {
+                expected: NUMBER.to_string(),
+                found: other.type_name(),
+                span,
+            }),
+        }
+    }
+
+    /// Calls the builtin `node` names, whose arguments are `args`.
+    fn call_builtin(
+        &mut self,
+        node: &FunctionCallNode,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let builtin = builtins::function(&node.function_name).unwrap();
+        if args.len() != builtin.params.len() {
+            return Err(RuntimeError::ArgumentCountMismatch {
+                name: node.function_name.clone(),
+                expected: builtin.params.len(),
+                found: args.len(),
+                span: node.span,
+            });
+        }
+        let numbers = args
+            .into_iter()
+            .zip(node.arguments.iter())
+            .map(|(value, arg)| self.number(value, arg.span()))
+            .collect::<Result<Vec<f64>, RuntimeError>>()?;
+        Ok(Value::Number(builtin.call(&numbers, &mut self.random)))
+    }
+
+    fn boolean(&self, value: Value, span: Span) -> Result<bool, RuntimeError> {
+        match value {
+            Value::Boolean(value) => Ok(value),
+            other => Err(RuntimeError::TypeMismatch {
+                expected: BOOLEAN.to_string(),
+                found: other.type_name(),
+                span,
+            }),
+        }
+    }
+
+    fn instance(&self, value: Value, span: Span) -> Result<Rc<RefCell<Instance>>, RuntimeError> {
+        match value {
+            Value::Object(instance) => Ok(instance),
+            other => Err(RuntimeError::TypeMismatch {
+                expected: OBJECT.to_string(),
+                found: other.type_name(),
+                span,
+            }),
+        }
+    }
+
+    fn evaluate_all(&mut self, exprs: &mut [Expression]) -> Result<Vec<Value>, RuntimeError> {
+        exprs.iter_mut().map(|expr| expr.accept(self)).collect()
+    }
+
+    /// Runs `body` with only `scope` visible, then the function or method each tail
+    /// call it ends in names, in its place.
+    fn call(
+        &mut self,
+        mut def: FunctionDefNode,
+        scope: Scope,
+        function: Option<String>,
+        span: Span,
+    ) -> Result<Value, RuntimeError> {
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::StackOverflow { span });
+        }
+        self.depth += 1;
+        let scopes = std::mem::replace(&mut self.scopes, vec![scope]);
+        let caller = std::mem::replace(&mut self.current_function, function);
+
+        let result = loop {
+            let value = def.body.accept(self);
+            match self.tail_call.take() {
+                Some(TailCall::Recursive(args)) if value.is_ok() => {
+                    let scope = def.params.iter().map(|param| param.name.clone()).zip(args);
+                    self.scopes = vec![scope.collect()];
+                }
+                Some(TailCall::General {
+                    def: callee,
+                    scope,
+                    function,
+                }) if value.is_ok() => {
+                    def = *callee;
+                    self.scopes = vec![scope];
+                    self.current_function = function;
+                }
+                _ => break value,
+            }
+        };
+
+        self.scopes = scopes;
+        self.current_function = caller;
+        self.depth -= 1;
+        result
+    }
+
+    /// Calls `def`, or leaves it to the call being run when the call is in its tail
+    /// position.
+    fn call_in(
+        &mut self,
+        def: FunctionDefNode,
+        scope: Scope,
+        function: Option<String>,
+        tail: bool,
+        span: Span,
+    ) -> Result<Value, RuntimeError> {
+        if tail && self.depth > 0 {
+            self.tail_call = Some(TailCall::General {
+                def: Box::new(def),
+                scope,
+                function,
+            });
+            return Ok(Value::Null);
+        }
+        self.call(def, scope, function, span)
+    }
+
+    fn bind(
+        &self,
+        def: &FunctionDefNode,
+        name: &str,
+        args: Vec<Value>,
+        span: Span,
+    ) -> Result<Scope, RuntimeError> {
+        if def.params.len() != args.len() {
+            return Err(RuntimeError::ArgumentCountMismatch {
+                name: name.to_string(),
+                expected: def.params.len(),
+                found: args.len(),
+                span,
+            });
+        }
+        Ok(def
+            .params
+            .iter()
+            .map(|param| param.name.clone())
+            .zip(args)
+            .collect())
+    }
+
+    /// Finds `method` in `type_name` or its closest ancestor declaring it.
+    fn find_method(&self, type_name: &str, method: &str) -> Option<FunctionDefNode> {
+        let mut current = self.types.get(type_name);
+        while let Some(def) = current {
+            for member in def.members.iter() {
+                if let TypeMember::Method(def) = member {
+                    if def.name == method {
+                        return Some((**def).clone());
+                    }
+                }
+            }
+            current = def
+                .parent
+                .as_ref()
+                .and_then(|parent| self.types.get(parent));
+        }
+        None
+    }
+
+    /// Runs the constructor of `type_name` and of its ancestors on `instance`.
+    fn initialize(
+        &mut self,
+        type_name: &str,
+        instance: &Rc<RefCell<Instance>>,
+        args: Vec<Value>,
+        span: Span,
+    ) -> Result<(), RuntimeError> {
+        let mut def = match self.types.get(type_name) {
+            Some(def) => def.clone(),
+            None if type_name == OBJECT => return Ok(()),
+            None => {
+                return Err(RuntimeError::UndefinedType {
+                    name: type_name.to_string(),
+                    span,
+                })
+            }
+        };
+        let parent = def.parent.clone().unwrap_or_else(|| OBJECT.to_string());
+
+        // A type without parameters or parent arguments takes those of its parent.
+        if def.params.is_empty() && def.parent_args.is_empty() && parent != OBJECT {
+            self.initialize(&parent, instance, args, span)?;
+            return self.initialize_attributes(&mut def, instance, Scope::new());
+        }
+        if def.params.len() != args.len() {
+            return Err(RuntimeError::ArgumentCountMismatch {
+                name: type_name.to_string(),
+                expected: def.params.len(),
+                found: args.len(),
+                span,
+            });
+        }
+        let scope: Scope = def
+            .params
+            .iter()
+            .map(|param| param.name.clone())
+            .zip(args)
+            .collect();
+
+        let scopes = std::mem::replace(&mut self.scopes, vec![scope.clone()]);
+        let parent_args = self.evaluate_all(&mut def.parent_args);
+        self.scopes = scopes;
+        self.initialize(&parent, instance, parent_args?, span)?;
+        self.initialize_attributes(&mut def, instance, scope)
+    }
+
+    /// Evaluates the attribute initializers of `def` with `scope` visible.
+    fn initialize_attributes(
+        &mut self,
+        def: &mut TypeDefNode,
+        instance: &Rc<RefCell<Instance>>,
+        scope: Scope,
+    ) -> Result<(), RuntimeError> {
+        let scopes = std::mem::replace(&mut self.scopes, vec![scope]);
+        let mut result = Ok(());
+        for member in def.members.iter_mut() {
+            if let TypeMember::Property(assignment) = member {
+                match assignment.expression.accept(self) {
+                    Ok(value) => {
+                        let name = assignment.identifier.clone();
+                        instance.borrow_mut().fields.insert(name, value);
+                    }
+                    Err(error) => {
+                        result = Err(error);
+                        break;
+                    }
+                }
+            }
+        }
+        self.scopes = scopes;
+        result
+    }
+}
+
+impl<W: Write> Visitor<Result<Value, RuntimeError>> for Interpreter<W> {
+    fn visit_function_def(&mut self, node: &mut FunctionDefNode) -> Result<Value, RuntimeError> {
+        self.functions.insert(node.name.clone(), node.clone());
+        Ok(Value::Null)
+    }
+
+    fn visit_literal_number(
+        &mut self,
+        node: &mut NumberLiteralNode,
+    ) -> Result<Value, RuntimeError> {
+        Ok(Value::Number(node.value))
+    }
+
+    fn visit_literal_boolean(
+        &mut self,
+        node: &mut BooleanLiteralNode,
+    ) -> Result<Value, RuntimeError> {
+        Ok(Value::Boolean(node.value))
+    }
+
+    fn visit_literal_string(
+        &mut self,
+        node: &mut StringLiteralNode,
+    ) -> Result<Value, RuntimeError> {
+        Ok(Value::Str(node.value.clone()))
+    }
+
+    fn visit_identifier(&mut self, node: &mut IdentifierNode) -> Result<Value, RuntimeError> {
+        self.lookup(&node.value)
+            .cloned()
+            .or_else(|| builtins::constant(&node.value).map(Value::Number))
+            .ok_or_else(|| RuntimeError::UndefinedVariable {
+                name: node.value.clone(),
+                span: node.span,
+            })
+    }
+
+    fn visit_function_call(&mut self, node: &mut FunctionCallNode) -> Result<Value, RuntimeError> {
+        let args = self.evaluate_all(&mut node.arguments)?;
+        let recursive = node.tail_call == Some(TailCallKind::Recursive)
+            && self.current_function.as_deref() == Some(node.function_name.as_str());
+        if recursive {
+            self.tail_call = Some(TailCall::Recursive(args));
+            return Ok(Value::Null);
+        }
+        let def = match self.functions.get(&node.function_name) {
+            Some(def) => def.clone(),
+            None if builtins::function(&node.function_name).is_some() => {
+                return self.call_builtin(node, args);
+            }
+            None => {
+                return Err(RuntimeError::UndefinedFunction {
+                    name: node.function_name.clone(),
+                    span: node.span,
+                })
+            }
+        };
+        let scope = self.bind(&def, &node.function_name, args, node.span)?;
+        let function = Some(node.function_name.clone());
+        self.call_in(def, scope, function, node.tail_call.is_some(), node.span)
+    }
+
+    fn visit_while_loop(&mut self, node: &mut WhileNode) -> Result<Value, RuntimeError> {
+        let mut result = default_for(&node.node_type);
+        loop {
+            let condition = node.condition.accept(self)?;
+            if !self.boolean(condition, node.condition.span())? {
+                return Ok(result);
+            }
+            result = node.body.accept(self)?;
+        }
+    }
+
+    fn visit_for_loop(&mut self, node: &mut ForNode) -> Result<Value, RuntimeError> {
+        let start = node.start.accept(self)?;
+        let start = self.number(start, node.start.span())?;
+        let end = node.end.accept(self)?;
+        let end = self.number(end, node.end.span())?;
+
+        let mut result = default_for(&node.node_type);
+        let mut counter = start;
+        while counter < end {
+            // A copy of the counter per iteration, as `ForNode` documents.
+            let mut scope = Scope::new();
+            scope.insert(node.variable.clone(), Value::Number(counter));
+            self.scopes.push(scope);
+            let value = node.body.accept(self);
+            self.scopes.pop();
+            result = value?;
+            counter += 1.0;
+        }
+        Ok(result)
+    }
+
+    fn visit_code_block(&mut self, node: &mut BlockNode) -> Result<Value, RuntimeError> {
+        let mut result = Value::Null;
+        for expr in node.expression_list.expressions.iter_mut() {
+            result = expr.accept(self)?;
+        }
+        Ok(result)
+    }
+
+    fn visit_binary_op(&mut self, node: &mut BinaryOpNode) -> Result<Value, RuntimeError> {
+        // Both operands are evaluated, left to right, before the operator is applied.
+        let left = node.left.accept(self)?;
+        let right = node.right.accept(self)?;
+        let (left_span, right_span) = (node.left.span(), node.right.span());
+        let value = match node.operator {
+            OperatorToken::EQ => Value::Boolean(left == right),
+            OperatorToken::NEQ => Value::Boolean(left != right),
+            OperatorToken::CONCAT => Value::Str(format!("{}{}", left, right)),
+            OperatorToken::SCONCAT => Value::Str(format!("{} {}", left, right)),
+            OperatorToken::AND | OperatorToken::OR => {
+                let left = self.boolean(left, left_span)?;
+                let right = self.boolean(right, right_span)?;
+                Value::Boolean(if node.operator == OperatorToken::AND {
+                    left && right
+                } else {
+                    left || right
+                })
+            }
+            ref operator => {
+                let left = self.number(left, left_span)?;
+                let right = self.number(right, right_span)?;
+                match operator {
+                    OperatorToken::PLUS => Value::Number(left + right),
+                    OperatorToken::MINUS => Value::Number(left - right),
+                    OperatorToken::MUL => Value::Number(left * right),
+                    OperatorToken::DIV => Value::Number(left / right),
+                    OperatorToken::MOD => Value::Number(left % right),
+                    OperatorToken::POW => Value::Number(left.powf(right)),
+                    OperatorToken::LT => Value::Boolean(left < right),
+                    OperatorToken::LTE => Value::Boolean(left <= right),
+                    OperatorToken::GT => Value::Boolean(left > right),
+                    OperatorToken::GTE => Value::Boolean(left >= right),
+                    operator => unreachable!("`{}` is not a binary operator", operator),
+                }
+            }
+        };
+        Ok(value)
+    }
+
+    fn visit_unary_op(&mut self, node: &mut UnaryOpNode) -> Result<Value, RuntimeError> {
+        let operand = node.operand.accept(self)?;
+        let span = node.operand.span();
+        match node.operator {
+            OperatorToken::NOT => Ok(Value::Boolean(!self.boolean(operand, span)?)),
+            _ => Ok(Value::Number(-self.number(operand, span)?)),
+        }
+    }
+
+    fn visit_if_else(&mut self, node: &mut IfElseNode) -> Result<Value, RuntimeError> {
+        let condition = node.condition.accept(self)?;
+        if self.boolean(condition, node.condition.span())? {
+            return node.if_expression.accept(self);
+        }
+        for (condition, body) in node.elifs.iter_mut() {
+            let taken = match condition {
+                Some(condition) => {
+                    let value = condition.accept(self)?;
+                    self.boolean(value, condition.span())?
+                }
+                None => true,
+            };
+            if taken {
+                return body.accept(self);
+            }
+        }
+        Ok(default_for(&node.node_type))
+    }
+
+    fn visit_let_in(&mut self, node: &mut LetInNode) -> Result<Value, RuntimeError> {
+        // Each variable is visible from the next declaration on.
+        let count = node.assignments.len();
+        let mut result = Ok(Value::Null);
+        let mut pushed = 0;
+        for assignment in node.assignments.iter_mut() {
+            match assignment.expression.accept(self) {
+                Ok(value) => {
+                    let mut scope = Scope::new();
+                    scope.insert(assignment.identifier.clone(), value);
+                    self.scopes.push(scope);
+                    pushed += 1;
+                }
+                Err(error) => {
+                    result = Err(error);
+                    break;
+                }
+            }
+        }
+        if pushed == count {
+            result = node.body.accept(self);
+        }
+        let len = self.scopes.len() - pushed;
+        self.scopes.truncate(len);
+        result
+    }
+
+    fn visit_destructive_assign(
+        &mut self,
+        node: &mut DestructiveAssignNode,
+    ) -> Result<Value, RuntimeError> {
+        match node.identifier.as_mut() {
+            Expression::Identifier(identifier) => {
+                let value = node.expression.accept(self)?;
+                let slot = self
+                    .scopes
+                    .iter_mut()
+                    .rev()
+                    .find_map(|scope| scope.get_mut(&identifier.value));
+                match slot {
+                    Some(slot) => {
+                        *slot = value.clone();
+                        Ok(value)
+                    }
+                    None => Err(RuntimeError::UndefinedVariable {
+                        name: identifier.value.clone(),
+                        span: identifier.span,
+                    }),
+                }
+            }
+            Expression::TypePropAccess(access) => {
+                let object = access.object.accept(self)?;
+                let instance = self.instance(object, access.object.span())?;
+                let value = node.expression.accept(self)?;
+                instance
+                    .borrow_mut()
+                    .fields
+                    .insert((*access.member).clone(), value.clone());
+                Ok(value)
+            }
+            _ => Err(RuntimeError::InvalidAssignmentTarget { span: node.span }),
+        }
+    }
+
+    fn visit_type_def(&mut self, node: &mut TypeDefNode) -> Result<Value, RuntimeError> {
+        self.types.insert(node.identifier.clone(), node.clone());
+        Ok(Value::Null)
+    }
+
+    fn visit_type_instance(&mut self, node: &mut TypeInstanceNode) -> Result<Value, RuntimeError> {
+        let args = self.evaluate_all(&mut node.arguments)?;
+        let instance = Rc::new(RefCell::new(Instance {
+            type_name: node.type_name.clone(),
+            fields: HashMap::new(),
+        }));
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::StackOverflow { span: node.span });
+        }
+        self.depth += 1;
+        let result = self.initialize(&node.type_name, &instance, args, node.span);
+        self.depth -= 1;
+        result?;
+        Ok(Value::Object(instance))
+    }
+
+    fn visit_type_function_access(
+        &mut self,
+        node: &mut TypeFunctionAccessNode,
+    ) -> Result<Value, RuntimeError> {
+        let receiver = node.object.accept(self)?;
+        let args = self.evaluate_all(&mut node.member.arguments)?;
+        let type_name = receiver.type_name();
+        let name = &node.member.function_name;
+        let def = match self.find_method(&type_name, name) {
+            Some(def) => def,
+            None => {
+                return Err(RuntimeError::UndefinedMethod {
+                    type_name,
+                    name: name.clone(),
+                    span: node.span,
+                })
+            }
+        };
+        let mut scope = self.bind(&def, name, args, node.span)?;
+        scope.insert("self".to_string(), receiver);
+        let tail = node.member.tail_call.is_some();
+        self.call_in(def, scope, None, tail, node.span)
+    }
+
+    fn visit_type_prop_access(
+        &mut self,
+        node: &mut TypePropAccessNode,
+    ) -> Result<Value, RuntimeError> {
+        let object = node.object.accept(self)?;
+        let instance = self.instance(object, node.object.span())?;
+        let instance = instance.borrow();
+        instance
+            .fields
+            .get(node.member.as_str())
+            .cloned()
+            .ok_or_else(|| RuntimeError::UndefinedAttribute {
+                type_name: instance.type_name.clone(),
+                name: (*node.member).clone(),
+                span: node.span,
+            })
+    }
+
+    fn visit_print(&mut self, node: &mut PrintNode) -> Result<Value, RuntimeError> {
+        let value = node.expression.accept(self)?;
+        writeln!(self.out, "{}", value).map_err(|error| RuntimeError::Output {
+            message: error.to_string(),
+            span: node.span,
+        })?;
+        Ok(value)
+    }
+}
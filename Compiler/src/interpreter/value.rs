@@ -0,0 +1,80 @@
+//! Values the interpreter computes.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::number_format::format_number;
+use crate::types_tree::type_tree::{BOOLEAN, NUMBER, OBJECT, STRING};
+
+/// An instance of a user-defined type.
+#[derive(Debug, PartialEq)]
+pub struct Instance {
+    pub type_name: String,
+    /// Attributes of the type and of all its ancestors.
+    pub fields: HashMap<String, Value>,
+}
+
+/// A runtime value. Objects are shared, so assigning an attribute through one
+/// reference is visible through every other.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Null,
+    Number(f64),
+    Boolean(bool),
+    Str(String),
+    Object(Rc<RefCell<Instance>>),
+}
+
+impl Value {
+    /// Returns the default value of `type_name`: the value of a loop that never
+    /// runs, or of an `if` without `else` whose conditions all fail.
+    pub fn default_of(type_name: &str) -> Value {
+        match type_name {
+            NUMBER => Value::Number(0.0),
+            BOOLEAN => Value::Boolean(false),
+            STRING => Value::Str(String::new()),
+            _ => Value::Null,
+        }
+    }
+
+    /// Returns the name of the dynamic type of the value.
+    pub fn type_name(&self) -> String {
+        match self {
+            Value::Null => OBJECT.to_string(),
+            Value::Number(_) => NUMBER.to_string(),
+            Value::Boolean(_) => BOOLEAN.to_string(),
+            Value::Str(_) => STRING.to_string(),
+            Value::Object(instance) => instance.borrow().type_name.clone(),
+        }
+    }
+}
+
+/// Compares two values the way `==` does: numbers, booleans and strings by value,
+/// objects by identity, and values of different types are never equal.
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Number(left), Value::Number(right)) => left == right,
+            (Value::Boolean(left), Value::Boolean(right)) => left == right,
+            (Value::Str(left), Value::Str(right)) => left == right,
+            (Value::Object(left), Value::Object(right)) => Rc::ptr_eq(left, right),
+            _ => false,
+        }
+    }
+}
+
+/// Writes the text `print` and `@` produce for the value.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Number(value) => write!(f, "{}", format_number(*value)),
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::Str(value) => write!(f, "{}", value),
+            Value::Object(instance) => write!(f, "<{}>", instance.borrow().type_name),
+        }
+    }
+}
@@ -0,0 +1,501 @@
+//! Conversions between the boxed AST and the arena.
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::{BlockNode, ExpressionList};
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::FunctionCallNode;
+use crate::ast_nodes::function_def::{FunctionDefNode, FunctionParams};
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::{Assignment, LetInNode};
+use crate::ast_nodes::literals::{
+    BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode,
+};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::{Program, Statement};
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+use crate::tokens::Span;
+use crate::types_tree::tree_node::TypeNode;
+
+use super::{Ast, Node, NodeId};
+
+impl Ast {
+    /// Moves `program` into an arena, with its static types and backend annotations
+    /// in the side tables.
+    pub fn from_program(program: &Program) -> Ast {
+        let mut ast = Ast {
+            program_span: program.span,
+            ..Ast::default()
+        };
+        for statement in program.statements.iter() {
+            let id = match statement {
+                Statement::StatementExpression(expression) => ast.expression(expression),
+                Statement::StatementFunctionDef(function) => ast.function(function),
+                Statement::StatementTypeDef(type_def) => ast.type_def(type_def),
+            };
+            ast.statements.push(id);
+        }
+        ast
+    }
+
+    /// Builds the boxed program back, with the types and annotations of the side tables.
+    pub fn to_program(&self) -> Program {
+        let statements = self
+            .statements
+            .iter()
+            .map(|&id| match self.node(id) {
+                Node::Function { .. } => Statement::new_function_def(self.raise_function(id)),
+                Node::Type { .. } => Statement::new_type_def(self.raise_type_def(id)),
+                _ => Statement::new_expression(self.raise(id)),
+            })
+            .collect();
+        Program::new(statements, self.program_span)
+    }
+
+    /// Adds a node with the static type `node_type`.
+    fn typed(&mut self, node: Node, span: Span, node_type: &Option<TypeNode>) -> NodeId {
+        let id = self.alloc(node, span);
+        if let Some(node_type) = node_type {
+            self.types.insert(id, node_type.clone());
+        }
+        id
+    }
+
+    fn expressions(&mut self, expressions: &[Expression]) -> Vec<NodeId> {
+        expressions
+            .iter()
+            .map(|expression| self.expression(expression))
+            .collect()
+    }
+
+    fn params(&mut self, params: &[FunctionParams]) -> Vec<NodeId> {
+        params
+            .iter()
+            .map(|param| {
+                let node = Node::Param {
+                    name: param.name.clone(),
+                    annotation: param.signature.clone(),
+                };
+                self.alloc(node, param.span)
+            })
+            .collect()
+    }
+
+    fn call(&mut self, call: &FunctionCallNode) -> NodeId {
+        let arguments = self.expressions(&call.arguments);
+        let node = Node::Call {
+            function: call.function_name.clone(),
+            arguments,
+        };
+        let id = self.typed(node, call.span, &call.node_type);
+        if let Some(kind) = call.tail_call {
+            self.tail_calls.insert(id, kind);
+        }
+        id
+    }
+
+    fn binding(&mut self, assignment: &Assignment) -> NodeId {
+        let value = self.expression(&assignment.expression);
+        let node = Node::Binding {
+            name: assignment.identifier.clone(),
+            value,
+        };
+        self.typed(node, assignment.span, &assignment.node_type)
+    }
+
+    fn function(&mut self, function: &FunctionDefNode) -> NodeId {
+        let params = self.params(&function.params);
+        let body = self.expression(&function.body);
+        let node = Node::Function {
+            name: function.name.clone(),
+            params,
+            return_type: function.return_type.clone(),
+            body,
+        };
+        self.typed(node, function.span, &function.node_type)
+    }
+
+    fn type_def(&mut self, type_def: &TypeDefNode) -> NodeId {
+        let params = self.params(&type_def.params);
+        let parent_args = self.expressions(&type_def.parent_args);
+        let members = type_def
+            .members
+            .iter()
+            .map(|member| match member {
+                TypeMember::Property(assignment) => self.binding(assignment),
+                TypeMember::Method(method) => self.function(method),
+            })
+            .collect();
+        let node = Node::Type {
+            name: type_def.identifier.clone(),
+            params,
+            parent: type_def.parent.clone(),
+            parent_args,
+            members,
+        };
+        self.typed(node, type_def.span, &type_def.node_type)
+    }
+
+    fn expression(&mut self, expression: &Expression) -> NodeId {
+        match expression {
+            Expression::Number(node) => {
+                self.typed(Node::Number(node.value), node.span, &node.node_type)
+            }
+            Expression::Boolean(node) => {
+                self.typed(Node::Boolean(node.value), node.span, &node.node_type)
+            }
+            Expression::Str(node) => {
+                self.typed(Node::Str(node.value.clone()), node.span, &node.node_type)
+            }
+            Expression::Identifier(node) => self.typed(
+                Node::Identifier(node.value.clone()),
+                node.span,
+                &node.node_type,
+            ),
+            Expression::FunctionCall(node) => self.call(node),
+            Expression::WhileLoop(node) => {
+                let condition = self.expression(&node.condition);
+                let body = self.expression(&node.body);
+                self.typed(Node::While { condition, body }, node.span, &node.node_type)
+            }
+            Expression::ForLoop(node) => {
+                let start = self.expression(&node.start);
+                let end = self.expression(&node.end);
+                let body = self.expression(&node.body);
+                let lowered = Node::For {
+                    variable: node.variable.clone(),
+                    start,
+                    end,
+                    body,
+                };
+                self.typed(lowered, node.span, &node.node_type)
+            }
+            Expression::CodeBlock(node) => {
+                let expressions = self.expressions(&node.expression_list.expressions);
+                self.typed(Node::Block(expressions), node.span, &node.node_type)
+            }
+            Expression::BinaryOp(node) => {
+                let left = self.expression(&node.left);
+                let right = self.expression(&node.right);
+                let lowered = Node::Binary {
+                    operator: node.operator.clone(),
+                    left,
+                    right,
+                };
+                self.typed(lowered, node.span, &node.node_type)
+            }
+            Expression::UnaryOp(node) => {
+                let operand = self.expression(&node.operand);
+                let lowered = Node::Unary {
+                    operator: node.operator.clone(),
+                    operand,
+                };
+                self.typed(lowered, node.span, &node.node_type)
+            }
+            Expression::IfElse(node) => {
+                let condition = self.expression(&node.condition);
+                let then = self.expression(&node.if_expression);
+                let elifs = node
+                    .elifs
+                    .iter()
+                    .map(|(condition, body)| {
+                        let condition = condition
+                            .as_ref()
+                            .map(|condition| self.expression(condition));
+                        (condition, self.expression(body))
+                    })
+                    .collect();
+                let lowered = Node::If {
+                    condition,
+                    then,
+                    elifs,
+                };
+                self.typed(lowered, node.span, &node.node_type)
+            }
+            Expression::LetIn(node) => {
+                let bindings = node
+                    .assignments
+                    .iter()
+                    .map(|assignment| self.binding(assignment))
+                    .collect();
+                let body = self.expression(&node.body);
+                self.typed(Node::Let { bindings, body }, node.span, &node.node_type)
+            }
+            Expression::DestructiveAssign(node) => {
+                let target = self.expression(&node.identifier);
+                let value = self.expression(&node.expression);
+                self.typed(Node::Assign { target, value }, node.span, &node.node_type)
+            }
+            Expression::TypeInstance(node) => {
+                let arguments = self.expressions(&node.arguments);
+                let lowered = Node::New {
+                    type_name: node.type_name.clone(),
+                    arguments,
+                };
+                self.typed(lowered, node.span, &node.node_type)
+            }
+            Expression::TypeFunctionAccess(node) => {
+                let receiver = self.expression(&node.object);
+                let call = self.call(&node.member);
+                let id = self.typed(
+                    Node::MethodCall { receiver, call },
+                    node.span,
+                    &node.node_type,
+                );
+                if let Some(owner) = &node.direct_call {
+                    self.direct_calls.insert(id, owner.clone());
+                }
+                id
+            }
+            Expression::TypePropAccess(node) => {
+                let receiver = self.expression(&node.object);
+                let lowered = Node::Attribute {
+                    receiver,
+                    name: node.member.to_string(),
+                };
+                self.typed(lowered, node.span, &node.node_type)
+            }
+            Expression::Print(node) => {
+                let expression = self.expression(&node.expression);
+                self.typed(Node::Print(expression), node.span, &node.node_type)
+            }
+        }
+    }
+
+    fn node_type(&self, id: NodeId) -> Option<TypeNode> {
+        self.types.get(id).cloned()
+    }
+
+    fn raise_all(&self, ids: &[NodeId]) -> Vec<Expression> {
+        ids.iter().map(|&id| self.raise(id)).collect()
+    }
+
+    fn raise_params(&self, ids: &[NodeId]) -> Vec<FunctionParams> {
+        ids.iter()
+            .map(|&id| match self.node(id) {
+                Node::Param { name, annotation } => {
+                    FunctionParams::new(name.clone(), annotation.clone(), self.span(id))
+                }
+                node => panic!("expected a parameter, found {:?}", node),
+            })
+            .collect()
+    }
+
+    fn raise_call(&self, id: NodeId) -> FunctionCallNode {
+        match self.node(id) {
+            Node::Call {
+                function,
+                arguments,
+            } => FunctionCallNode {
+                function_name: function.clone(),
+                arguments: self.raise_all(arguments),
+                tail_call: self.tail_calls.get(id).copied(),
+                node_type: self.node_type(id),
+                span: self.span(id),
+            },
+            node => panic!("expected a call, found {:?}", node),
+        }
+    }
+
+    fn raise_binding(&self, id: NodeId) -> Assignment {
+        match self.node(id) {
+            Node::Binding { name, value } => Assignment {
+                identifier: name.clone(),
+                expression: Box::new(self.raise(*value)),
+                node_type: self.node_type(id),
+                span: self.span(id),
+            },
+            node => panic!("expected a binding, found {:?}", node),
+        }
+    }
+
+    fn raise_function(&self, id: NodeId) -> FunctionDefNode {
+        match self.node(id) {
+            Node::Function {
+                name,
+                params,
+                return_type,
+                body,
+            } => FunctionDefNode {
+                name: name.clone(),
+                params: self.raise_params(params),
+                return_type: return_type.clone(),
+                body: self.raise(*body),
+                node_type: self.node_type(id),
+                span: self.span(id),
+            },
+            node => panic!("expected a function, found {:?}", node),
+        }
+    }
+
+    fn raise_type_def(&self, id: NodeId) -> TypeDefNode {
+        match self.node(id) {
+            Node::Type {
+                name,
+                params,
+                parent,
+                parent_args,
+                members,
+            } => TypeDefNode {
+                identifier: name.clone(),
+                params: self.raise_params(params),
+                parent: parent.clone(),
+                parent_args: self.raise_all(parent_args),
+                members: members
+                    .iter()
+                    .map(|&member| match self.node(member) {
+                        Node::Function { .. } => {
+                            TypeMember::new_method(self.raise_function(member))
+                        }
+                        _ => TypeMember::new_property(self.raise_binding(member)),
+                    })
+                    .collect(),
+                node_type: self.node_type(id),
+                span: self.span(id),
+            },
+            node => panic!("expected a type, found {:?}", node),
+        }
+    }
+
+    /// Builds the expression `id` back.
+    fn raise(&self, id: NodeId) -> Expression {
+        let node_type = self.node_type(id);
+        let span = self.span(id);
+        let boxed = |id: NodeId| Box::new(self.raise(id));
+        match self.node(id) {
+            Node::Number(value) => Expression::Number(NumberLiteralNode {
+                value: *value,
+                node_type,
+                span,
+            }),
+            Node::Boolean(value) => Expression::Boolean(BooleanLiteralNode {
+                value: *value,
+                node_type,
+                span,
+            }),
+            Node::Str(value) => Expression::Str(StringLiteralNode {
+                value: value.clone(),
+                node_type,
+                span,
+            }),
+            Node::Identifier(value) => Expression::Identifier(IdentifierNode {
+                value: value.clone(),
+                node_type,
+                span,
+            }),
+            Node::Call { .. } => Expression::FunctionCall(self.raise_call(id)),
+            Node::While { condition, body } => Expression::WhileLoop(WhileNode {
+                condition: boxed(*condition),
+                body: boxed(*body),
+                node_type,
+                span,
+            }),
+            Node::For {
+                variable,
+                start,
+                end,
+                body,
+            } => Expression::ForLoop(ForNode {
+                variable: variable.clone(),
+                start: boxed(*start),
+                end: boxed(*end),
+                body: boxed(*body),
+                node_type,
+                span,
+            }),
+            Node::Block(expressions) => Expression::CodeBlock(BlockNode {
+                expression_list: Box::new(ExpressionList::new(self.raise_all(expressions))),
+                node_type,
+                span,
+            }),
+            Node::Binary {
+                operator,
+                left,
+                right,
+            } => Expression::BinaryOp(BinaryOpNode {
+                left: boxed(*left),
+                operator: operator.clone(),
+                right: boxed(*right),
+                node_type,
+                span,
+            }),
+            Node::Unary { operator, operand } => Expression::UnaryOp(UnaryOpNode {
+                operator: operator.clone(),
+                operand: boxed(*operand),
+                node_type,
+                span,
+            }),
+            Node::If {
+                condition,
+                then,
+                elifs,
+            } => Expression::IfElse(IfElseNode {
+                condition: boxed(*condition),
+                if_expression: boxed(*then),
+                elifs: elifs
+                    .iter()
+                    .map(|(condition, body)| {
+                        (
+                            condition.map(|condition| self.raise(condition)),
+                            self.raise(*body),
+                        )
+                    })
+                    .collect(),
+                node_type,
+                span,
+            }),
+            Node::Let { bindings, body } => Expression::LetIn(LetInNode {
+                assignments: bindings.iter().map(|&id| self.raise_binding(id)).collect(),
+                body: boxed(*body),
+                node_type,
+                span,
+            }),
+            Node::Assign { target, value } => {
+                Expression::DestructiveAssign(DestructiveAssignNode {
+                    identifier: boxed(*target),
+                    expression: boxed(*value),
+                    node_type,
+                    span,
+                })
+            }
+            Node::New {
+                type_name,
+                arguments,
+            } => Expression::TypeInstance(TypeInstanceNode {
+                type_name: type_name.clone(),
+                arguments: self.raise_all(arguments),
+                node_type,
+                span,
+            }),
+            Node::MethodCall { receiver, call } => {
+                Expression::TypeFunctionAccess(TypeFunctionAccessNode {
+                    object: boxed(*receiver),
+                    member: Box::new(self.raise_call(*call)),
+                    direct_call: self.direct_calls.get(id).cloned(),
+                    node_type,
+                    span,
+                })
+            }
+            Node::Attribute { receiver, name } => Expression::TypePropAccess(TypePropAccessNode {
+                object: boxed(*receiver),
+                member: Box::new(name.clone()),
+                node_type,
+                span,
+            }),
+            Node::Print(expression) => Expression::Print(PrintNode {
+                expression: boxed(*expression),
+                node_type,
+                span,
+            }),
+            node @ (Node::Binding { .. }
+            | Node::Param { .. }
+            | Node::Function { .. }
+            | Node::Type { .. }) => panic!("expected an expression, found {:?}", node),
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! An arena-allocated AST.
+//!
+//! Every node of a program lives in one vector and is named by its [`NodeId`], and
+//! nodes refer to their children by id instead of owning them. What the boxed
+//! [`crate::ast_nodes`] store inline lives in side tables indexed by id instead:
+//! [`Ast::span`] for spans, [`Ast::types`] for the static types of the semantic phase
+//! and [`Ast::tail_calls`] and [`Ast::direct_calls`] for the annotations of the
+//! backends. An analysis attaches its own results the same way, in a [`SideTable`] of
+//! its own, without touching the tree; [`resolve`] does so for the binding every name
+//! refers to.
+//!
+//! [`Ast::from_program`] builds the arena from a boxed program and [`Ast::to_program`]
+//! builds it back, so passes can move between both. The language server builds it
+//! from every checked document and finds what a variable refers to with [`resolve`].
+
+mod lower;
+mod resolve;
+
+use std::ops::Index;
+
+use crate::ast_nodes::function_call::TailCallKind;
+use crate::tokens::{OperatorToken, Span};
+use crate::types_tree::tree_node::TypeNode;
+
+pub use resolve::resolve;
+
+/// The index of a node in its [`Ast`].
+///
+/// Children are allocated before their parents, so the id of a node is greater than
+/// the ids of all the nodes below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A node of the arena. Names and literal values are stored in the node, everything
+/// computed about it in the side tables of its [`Ast`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Number(f64),
+    Boolean(bool),
+    Str(String),
+    Identifier(String),
+    /// A call to a global function, or the call part of a [`Node::MethodCall`].
+    Call {
+        function: String,
+        arguments: Vec<NodeId>,
+    },
+    While {
+        condition: NodeId,
+        body: NodeId,
+    },
+    For {
+        variable: String,
+        start: NodeId,
+        end: NodeId,
+        body: NodeId,
+    },
+    Block(Vec<NodeId>),
+    Binary {
+        operator: OperatorToken,
+        left: NodeId,
+        right: NodeId,
+    },
+    Unary {
+        operator: OperatorToken,
+        operand: NodeId,
+    },
+    /// An `if` with its `elif` branches, then its `else` branch with no condition.
+    If {
+        condition: NodeId,
+        then: NodeId,
+        elifs: Vec<(Option<NodeId>, NodeId)>,
+    },
+    /// A `let` whose bindings are [`Node::Binding`] nodes.
+    Let {
+        bindings: Vec<NodeId>,
+        body: NodeId,
+    },
+    /// A binding of a `let` or a property of a type.
+    Binding {
+        name: String,
+        value: NodeId,
+    },
+    Assign {
+        target: NodeId,
+        value: NodeId,
+    },
+    New {
+        type_name: String,
+        arguments: Vec<NodeId>,
+    },
+    /// A method call, whose name and arguments are in the [`Node::Call`] `call`.
+    MethodCall {
+        receiver: NodeId,
+        call: NodeId,
+    },
+    Attribute {
+        receiver: NodeId,
+        name: String,
+    },
+    Print(NodeId),
+    /// A parameter of a function, a method or a type.
+    Param {
+        name: String,
+        annotation: String,
+    },
+    /// A global function or a method, whose parameters are [`Node::Param`] nodes.
+    Function {
+        name: String,
+        params: Vec<NodeId>,
+        return_type: String,
+        body: NodeId,
+    },
+    /// A type, whose members are [`Node::Binding`] properties and [`Node::Function`]
+    /// methods.
+    Type {
+        name: String,
+        params: Vec<NodeId>,
+        parent: Option<String>,
+        parent_args: Vec<NodeId>,
+        members: Vec<NodeId>,
+    },
+}
+
+impl Node {
+    /// Returns the children of the node, in the order the walking visitors of
+    /// [`crate::visitor`] visit them, parameters included.
+    pub fn children(&self) -> Vec<NodeId> {
+        match self {
+            Node::Number(_) | Node::Boolean(_) | Node::Str(_) | Node::Identifier(_) => Vec::new(),
+            Node::Param { .. } => Vec::new(),
+            Node::Call { arguments, .. } | Node::New { arguments, .. } => arguments.clone(),
+            Node::While { condition, body } => vec![*condition, *body],
+            Node::For {
+                start, end, body, ..
+            } => vec![*start, *end, *body],
+            Node::Block(expressions) => expressions.clone(),
+            Node::Binary { left, right, .. } => vec![*left, *right],
+            Node::Unary { operand, .. } => vec![*operand],
+            Node::If {
+                condition,
+                then,
+                elifs,
+            } => {
+                let mut children = vec![*condition, *then];
+                for (condition, body) in elifs {
+                    children.extend(condition);
+                    children.push(*body);
+                }
+                children
+            }
+            Node::Let { bindings, body } => {
+                let mut children = bindings.clone();
+                children.push(*body);
+                children
+            }
+            Node::Binding { value, .. } => vec![*value],
+            Node::Assign { target, value } => vec![*target, *value],
+            Node::MethodCall { receiver, call } => vec![*receiver, *call],
+            Node::Attribute { receiver, .. } => vec![*receiver],
+            Node::Print(expression) => vec![*expression],
+            Node::Function { params, body, .. } => {
+                let mut children = params.clone();
+                children.push(*body);
+                children
+            }
+            Node::Type {
+                params,
+                parent_args,
+                members,
+                ..
+            } => params
+                .iter()
+                .chain(parent_args)
+                .chain(members)
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+/// Data about some nodes of an [`Ast`], indexed by [`NodeId`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SideTable<T> {
+    values: Vec<Option<T>>,
+}
+
+impl<T> Default for SideTable<T> {
+    fn default() -> Self {
+        SideTable { values: Vec::new() }
+    }
+}
+
+impl<T> SideTable<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&T> {
+        self.values.get(id.index()).and_then(Option::as_ref)
+    }
+
+    /// Stores `value` for `id`, returning the value it had.
+    pub fn insert(&mut self, id: NodeId, value: T) -> Option<T> {
+        if self.values.len() <= id.index() {
+            self.values.resize_with(id.index() + 1, || None);
+        }
+        self.values[id.index()].replace(value)
+    }
+
+    pub fn remove(&mut self, id: NodeId) -> Option<T> {
+        self.values.get_mut(id.index()).and_then(Option::take)
+    }
+
+    /// Returns the nodes that have a value, in the order of their ids.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &T)> {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(|(index, value)| Some((NodeId(index as u32), value.as_ref()?)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.iter().filter(|value| value.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A program as an arena of nodes.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Ast {
+    nodes: Vec<Node>,
+    spans: Vec<Span>,
+    statements: Vec<NodeId>,
+    program_span: Span,
+    /// The static type of every node the semantic phase typed.
+    pub types: SideTable<TypeNode>,
+    /// How calls in tail position are compiled, by [`Node::Call`].
+    pub tail_calls: SideTable<TailCallKind>,
+    /// The type whose method is called directly, by devirtualized [`Node::MethodCall`].
+    pub direct_calls: SideTable<String>,
+}
+
+impl Ast {
+    /// Adds `node` and returns its id.
+    pub fn alloc(&mut self, node: Node, span: Span) -> NodeId {
+        let id = NodeId(self.nodes.len() as u32);
+        self.nodes.push(node);
+        self.spans.push(span);
+        id
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.index()]
+    }
+
+    pub fn span(&self, id: NodeId) -> Span {
+        self.spans[id.index()]
+    }
+
+    /// The top-level functions, types and expressions, in source order.
+    pub fn statements(&self) -> &[NodeId] {
+        &self.statements
+    }
+
+    /// The span of the whole program.
+    pub fn program_span(&self) -> Span {
+        self.program_span
+    }
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns every node with its id, children before their parents.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (NodeId(index as u32), node))
+    }
+}
+
+impl Index<NodeId> for Ast {
+    type Output = Node;
+
+    fn index(&self, id: NodeId) -> &Node {
+        self.node(id)
+    }
+}
@@ -0,0 +1,150 @@
+//! Name resolution over the arena.
+
+use std::collections::HashMap;
+
+use super::{Ast, Node, NodeId, SideTable};
+
+/// Finds the node every name of `ast` refers to.
+///
+/// Variables map to the [`Node::Binding`], [`Node::Param`] or [`Node::For`] that binds
+/// them, `self` to the [`Node::Type`] of the method, and calls to the global
+/// [`Node::Function`] they call. Names of builtins, of methods and of attributes, and
+/// names with no binding in scope, are left out.
+pub fn resolve(ast: &Ast) -> SideTable<NodeId> {
+    let functions = ast
+        .statements()
+        .iter()
+        .filter_map(|&id| match ast.node(id) {
+            Node::Function { name, .. } => Some((name.as_str(), id)),
+            _ => None,
+        })
+        .collect();
+    let mut resolver = Resolver {
+        ast,
+        functions,
+        scopes: Vec::new(),
+        current_type: None,
+        resolved: SideTable::new(),
+    };
+    for &id in ast.statements() {
+        resolver.node(id);
+    }
+    resolver.resolved
+}
+
+struct Resolver<'a> {
+    ast: &'a Ast,
+    functions: HashMap<&'a str, NodeId>,
+    /// Names in scope, innermost last, with the node that binds them.
+    scopes: Vec<(&'a str, NodeId)>,
+    /// The type whose members are being resolved, what `self` refers to.
+    current_type: Option<NodeId>,
+    resolved: SideTable<NodeId>,
+}
+
+impl<'a> Resolver<'a> {
+    fn bind_params(&mut self, params: &[NodeId]) {
+        for &param in params {
+            if let Node::Param { name, .. } = self.ast.node(param) {
+                self.scopes.push((name, param));
+            }
+        }
+    }
+
+    fn node(&mut self, id: NodeId) {
+        let ast = self.ast;
+        match ast.node(id) {
+            Node::Identifier(name) => {
+                let target = if name == "self" {
+                    self.current_type
+                } else {
+                    self.scopes
+                        .iter()
+                        .rev()
+                        .find(|(bound, _)| bound == name)
+                        .map(|&(_, binding)| binding)
+                };
+                if let Some(target) = target {
+                    self.resolved.insert(id, target);
+                }
+            }
+            Node::Call {
+                function,
+                arguments,
+            } => {
+                if let Some(&function) = self.functions.get(function.as_str()) {
+                    self.resolved.insert(id, function);
+                }
+                for &argument in arguments {
+                    self.node(argument);
+                }
+            }
+            Node::MethodCall { receiver, call } => {
+                self.node(*receiver);
+                // The call names a method, so only its arguments are resolved.
+                for child in ast.node(*call).children() {
+                    self.node(child);
+                }
+            }
+            Node::For {
+                variable,
+                start,
+                end,
+                body,
+            } => {
+                self.node(*start);
+                self.node(*end);
+                self.scopes.push((variable, id));
+                self.node(*body);
+                self.scopes.pop();
+            }
+            Node::Let { bindings, body } => {
+                let depth = self.scopes.len();
+                for &binding in bindings {
+                    if let Node::Binding { name, value } = ast.node(binding) {
+                        self.node(*value);
+                        self.scopes.push((name, binding));
+                    }
+                }
+                self.node(*body);
+                self.scopes.truncate(depth);
+            }
+            Node::Function { params, body, .. } => {
+                let depth = self.scopes.len();
+                self.bind_params(params);
+                self.node(*body);
+                self.scopes.truncate(depth);
+            }
+            Node::Type {
+                params,
+                parent_args,
+                members,
+                ..
+            } => {
+                let depth = self.scopes.len();
+                self.current_type = Some(id);
+                self.bind_params(params);
+                for &argument in parent_args {
+                    self.node(argument);
+                }
+                for &member in members {
+                    if let Node::Function { .. } = ast.node(member) {
+                        // Methods see the attributes through `self`, not the parameters.
+                        let scopes = std::mem::take(&mut self.scopes);
+                        self.node(member);
+                        self.scopes = scopes;
+                    } else {
+                        self.node(member);
+                    }
+                }
+                self.current_type = None;
+                self.scopes.truncate(depth);
+            }
+            node => {
+                for child in node.children() {
+                    self.node(child);
+                }
+            }
+        }
+    }
+}
@@ -1,14 +1,16 @@
+use serde::{Deserialize, Serialize};
 use super::expression::Expression;
 use crate::{
     tokens::{OperatorToken, Span},
     types_tree::tree_node::TypeNode,
 };
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BinaryOpNode {
     pub left: Box<Expression>,
     pub operator: OperatorToken,
     pub right: Box<Expression>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
     pub span: Span,
 }
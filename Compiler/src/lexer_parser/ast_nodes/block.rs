@@ -1,8 +1,10 @@
+use serde::{Deserialize, Serialize};
 use crate::types_tree::tree_node::TypeNode;
+use crate::tokens::Span;
 
 use super::expression::Expression;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ExpressionList {
     pub expressions: Box<Vec<Expression>>,
 }
@@ -15,17 +17,21 @@ impl ExpressionList {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BlockNode {
     pub expression_list: Box<ExpressionList>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
+    /// From the `{` to the `}`.
+    pub span: Span,
 }
 
 impl BlockNode {
-    pub fn new(expression_list: ExpressionList) -> Self {
+    pub fn new(expression_list: ExpressionList, span: Span) -> Self {
         BlockNode {
             expression_list: Box::new(expression_list),
             node_type: None,
+            span,
         }
     }
     pub fn set_type(&mut self, node_type: TypeNode) {
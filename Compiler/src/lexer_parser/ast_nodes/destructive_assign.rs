@@ -1,11 +1,13 @@
+use serde::{Deserialize, Serialize};
 use crate::{tokens::Span, types_tree::tree_node::TypeNode};
 
 use super::expression::Expression;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct DestructiveAssignNode {
     pub identifier: Box<Expression>,
     pub expression: Box<Expression>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
     pub span: Span,
 }
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use super::binary_op::BinaryOpNode;
 use super::block::{BlockNode, ExpressionList};
 use super::destructive_assign::DestructiveAssignNode;
@@ -12,10 +13,12 @@ use crate::ast_nodes::print::PrintNode;
 use crate::ast_nodes::type_instance::TypeInstanceNode;
 use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
 use crate::tokens::{OperatorToken, Span};
+use crate::types_tree::tree_node::TypeNode;
 use crate::visitor::accept::Accept;
 use crate::visitor::visitor_trait::Visitor;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
 pub enum Expression {
     Number(NumberLiteralNode),
     Boolean(BooleanLiteralNode),
@@ -71,8 +74,8 @@ impl Expression {
         Expression::ForLoop(ForNode::new(variable, start, end, body, span))
     }
 
-    pub fn new_code_block(expression_list: ExpressionList) -> Self {
-        Expression::CodeBlock(BlockNode::new(expression_list))
+    pub fn new_code_block(expression_list: ExpressionList, span: Span) -> Self {
+        Expression::CodeBlock(BlockNode::new(expression_list, span))
     }
 
     pub fn new_binary_op(
@@ -134,7 +137,7 @@ impl Expression {
             Expression::FunctionCall(f) => f.span,
             Expression::WhileLoop(w) => w.span,
             Expression::ForLoop(f) => f.span,
-            Expression::CodeBlock(b) => b.expression_list.expressions[0].span(),
+            Expression::CodeBlock(b) => b.span,
             Expression::BinaryOp(b) => b.span,
             Expression::UnaryOp(u) => u.span,
             Expression::IfElse(i) => i.span,
@@ -146,6 +149,53 @@ impl Expression {
             Expression::Print(p) => p.span,
         }
     }
+
+    /// Sets the span of the node, as the parser does to cover the parentheses around it.
+    pub fn set_span(&mut self, span: Span) {
+        let target = match self {
+            Expression::Number(n) => &mut n.span,
+            Expression::Boolean(b) => &mut b.span,
+            Expression::Str(s) => &mut s.span,
+            Expression::Identifier(i) => &mut i.span,
+            Expression::FunctionCall(f) => &mut f.span,
+            Expression::WhileLoop(w) => &mut w.span,
+            Expression::ForLoop(f) => &mut f.span,
+            Expression::CodeBlock(b) => &mut b.span,
+            Expression::BinaryOp(b) => &mut b.span,
+            Expression::UnaryOp(u) => &mut u.span,
+            Expression::IfElse(i) => &mut i.span,
+            Expression::LetIn(l) => &mut l.span,
+            Expression::DestructiveAssign(d) => &mut d.span,
+            Expression::TypeInstance(t) => &mut t.span,
+            Expression::TypeFunctionAccess(t) => &mut t.span,
+            Expression::TypePropAccess(t) => &mut t.span,
+            Expression::Print(p) => &mut p.span,
+        };
+        *target = span;
+    }
+
+    /// Returns the static type computed by the semantic phase, if it already ran.
+    pub fn node_type(&self) -> Option<&TypeNode> {
+        match self {
+            Expression::Number(n) => n.node_type.as_ref(),
+            Expression::Boolean(b) => b.node_type.as_ref(),
+            Expression::Str(s) => s.node_type.as_ref(),
+            Expression::Identifier(i) => i.node_type.as_ref(),
+            Expression::FunctionCall(f) => f.node_type.as_ref(),
+            Expression::WhileLoop(w) => w.node_type.as_ref(),
+            Expression::ForLoop(f) => f.node_type.as_ref(),
+            Expression::CodeBlock(b) => b.node_type.as_ref(),
+            Expression::BinaryOp(b) => b.node_type.as_ref(),
+            Expression::UnaryOp(u) => u.node_type.as_ref(),
+            Expression::IfElse(i) => i.node_type.as_ref(),
+            Expression::LetIn(l) => l.node_type.as_ref(),
+            Expression::DestructiveAssign(d) => d.node_type.as_ref(),
+            Expression::TypeInstance(t) => t.node_type.as_ref(),
+            Expression::TypeFunctionAccess(t) => t.node_type.as_ref(),
+            Expression::TypePropAccess(t) => t.node_type.as_ref(),
+            Expression::Print(p) => p.node_type.as_ref(),
+        }
+    }
 }
 
 impl Accept for Expression {
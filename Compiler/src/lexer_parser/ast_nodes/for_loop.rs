@@ -1,11 +1,18 @@
+use serde::{Deserialize, Serialize};
 use crate::{ast_nodes::expression::Expression, tokens::Span, types_tree::tree_node::TypeNode};
 
-#[derive(Debug, PartialEq, Clone)]
+/// `for (variable in range(start, end)) body`.
+///
+/// The bounds are evaluated once, and each iteration gets its own copy of the loop
+/// variable, so assigning it does not change the number of iterations. Every backend
+/// that runs `for` loops without desugaring them follows these rules.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ForNode {
     pub variable: String,
     pub start: Box<Expression>,
     pub end: Box<Expression>,
     pub body: Box<Expression>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
     pub span: Span,
 }
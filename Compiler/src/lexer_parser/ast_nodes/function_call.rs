@@ -1,11 +1,24 @@
+use serde::{Deserialize, Serialize};
 use crate::{tokens::Span, types_tree::tree_node::TypeNode};
 
 use super::expression::Expression;
 
-#[derive(Debug, PartialEq, Clone)]
+/// How a call found in tail position can be compiled.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum TailCallKind {
+    /// The call targets the enclosing function, so it can become a jump back to its start.
+    Recursive,
+    /// The call targets another function or method, so it can reuse the caller's frame.
+    General,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct FunctionCallNode {
     pub function_name: String,
     pub arguments: Vec<Expression>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tail_call: Option<TailCallKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
     pub span: Span,
 }
@@ -15,6 +28,7 @@ impl FunctionCallNode {
         FunctionCallNode {
             function_name,
             arguments,
+            tail_call: None,
             node_type: None,
             span,
         }
@@ -22,4 +36,7 @@ impl FunctionCallNode {
     pub fn set_type(&mut self, node_type: TypeNode) {
         self.node_type = Some(node_type);
     }
+    pub fn set_tail_call(&mut self, tail_call: Option<TailCallKind>) {
+        self.tail_call = tail_call;
+    }
 }
@@ -1,8 +1,9 @@
+use serde::{Deserialize, Serialize};
 use crate::{tokens::Span, types_tree::tree_node::TypeNode};
 
 use super::expression::Expression;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct FunctionParams {
     pub name: String,
     pub signature: String,
@@ -19,12 +20,13 @@ impl FunctionParams {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct FunctionDefNode {
     pub name: String,
     pub params: Vec<FunctionParams>,
     pub return_type: String,
     pub body: Expression,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
     pub span: Span,
 }
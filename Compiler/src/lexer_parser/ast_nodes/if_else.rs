@@ -1,12 +1,14 @@
+use serde::{Deserialize, Serialize};
 use crate::{tokens::Span, types_tree::tree_node::TypeNode};
 
 use super::expression::Expression;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct IfElseNode {
     pub condition: Box<Expression>,
     pub if_expression: Box<Expression>,
     pub elifs: Vec<(Option<Expression>, Expression)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
     pub span: Span,
 }
@@ -1,12 +1,14 @@
+use serde::{Deserialize, Serialize};
 use crate::{tokens::Span, types_tree::tree_node::TypeNode};
 
 use super::expression::Expression;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Assignment {
     //TODO Add optional Signature Assignment
     pub identifier: String,
     pub expression: Box<Expression>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
     pub span: Span,
 }
@@ -25,10 +27,11 @@ impl Assignment {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct LetInNode {
     pub assignments: Vec<Assignment>,
     pub body: Box<Expression>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
     pub span: Span,
 }
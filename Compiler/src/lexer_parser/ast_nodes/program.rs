@@ -1,18 +1,59 @@
+use serde::{Deserialize, Serialize};
 use crate::visitor::accept::Accept;
 use super::function_def::FunctionDefNode;
 use super::expression::Expression;
 use crate::visitor::visitor_trait::Visitor;
 use super::type_def::TypeDefNode;
+use crate::tokens::Span;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Program{
     pub statements: Vec<Statement>,
+    /// From the first token of the program to its last.
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq)]
+impl Program {
+    pub fn new(statements: Vec<Statement>, span: Span) -> Self {
+        Program { statements, span }
+    }
+
+    /// Returns the global expression the program runs, which the grammar puts after
+    /// the declarations.
+    pub fn entry(&self) -> Option<&Expression> {
+        match self.statements.last() {
+            Some(Statement::StatementExpression(expression)) => Some(expression),
+            _ => None,
+        }
+    }
+
+    /// Returns the function and type definitions, in source order.
+    pub fn declarations(&self) -> impl Iterator<Item = &Statement> {
+        self.statements
+            .iter()
+            .filter(|statement| !matches!(statement, Statement::StatementExpression(_)))
+    }
+
+    /// Writes the program as JSON, with the span of every node and its static type
+    /// when the semantic phase already set it.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("an AST always serializes")
+    }
+
+    /// Reads a program written by [`Program::to_json`].
+    pub fn from_json(json: &str) -> serde_json::Result<Program> {
+        serde_json::from_str(json)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "node")]
 pub enum Statement {
+    #[serde(rename = "Expression")]
     StatementExpression(Box<Expression>),
+    #[serde(rename = "FunctionDef")]
     StatementFunctionDef(Box<FunctionDefNode>),
+    #[serde(rename = "TypeDef")]
     StatementTypeDef(Box<TypeDefNode>),
 }
 
@@ -28,6 +69,15 @@ impl Statement {
     pub fn new_type_def(type_def: TypeDefNode) -> Self {
         Statement::StatementTypeDef(Box::new(type_def))
     }
+
+    /// The span of the expression or definition, without the `;` that ends it.
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::StatementExpression(expression) => expression.span(),
+            Statement::StatementFunctionDef(function) => function.span,
+            Statement::StatementTypeDef(type_def) => type_def.span,
+        }
+    }
 }
 
 impl Accept for Statement {
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use crate::{
     ast_nodes::{
         expression::Expression,
@@ -24,10 +25,11 @@ impl TypeInherits {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "node")]
 pub enum TypeMember {
     Property(Assignment),
-    Method(FunctionDefNode),
+    Method(Box<FunctionDefNode>),
 }
 
 impl TypeMember {
@@ -36,17 +38,18 @@ impl TypeMember {
     }
 
     pub fn new_method(method: FunctionDefNode) -> Self {
-        TypeMember::Method(method)
+        TypeMember::Method(Box::new(method))
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct TypeDefNode {
     pub identifier: String,
     pub params: Vec<FunctionParams>,
     pub parent: Option<String>,
     pub parent_args: Vec<Expression>,
     pub members: Vec<TypeMember>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
     pub span: Span,
 }
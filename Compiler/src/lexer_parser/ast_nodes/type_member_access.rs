@@ -1,13 +1,15 @@
+use serde::{Deserialize, Serialize};
 use crate::{
     ast_nodes::{expression::Expression, function_call::FunctionCallNode},
     tokens::Span,
     types_tree::tree_node::TypeNode,
 };
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct TypePropAccessNode {
     pub object: Box<Expression>,
     pub member: Box<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
     pub span: Span,
 }
@@ -26,10 +28,15 @@ impl TypePropAccessNode {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct TypeFunctionAccessNode {
     pub object: Box<Expression>,
     pub member: Box<FunctionCallNode>,
+    /// Type whose implementation of the method is called directly, set when the exact
+    /// type of the receiver is statically known and no dynamic dispatch is needed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direct_call: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
     pub span: Span,
 }
@@ -39,6 +46,7 @@ impl TypeFunctionAccessNode {
         TypeFunctionAccessNode {
             object: Box::new(object),
             member: Box::new(member),
+            direct_call: None,
             node_type: None,
             span,
         }
@@ -46,4 +54,7 @@ impl TypeFunctionAccessNode {
     pub fn set_type(&mut self, node_type: TypeNode) {
         self.node_type = Some(node_type);
     }
+    pub fn set_direct_call(&mut self, direct_call: Option<String>) {
+        self.direct_call = direct_call;
+    }
 }
@@ -1,10 +1,12 @@
+use serde::{Deserialize, Serialize};
 use super::expression::Expression;
 use crate::{tokens::{OperatorToken, Span}, types_tree::tree_node::TypeNode};
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct UnaryOpNode {
     pub operator: OperatorToken,
     pub operand: Box<Expression>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
     pub span: Span,
 }
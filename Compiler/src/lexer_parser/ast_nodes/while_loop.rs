@@ -1,9 +1,11 @@
+use serde::{Deserialize, Serialize};
 use crate::{ast_nodes::expression::Expression, tokens::Span, types_tree::tree_node::TypeNode};
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct WhileNode {
     pub condition: Box<Expression>,
     pub body: Box<Expression>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_type: Option<TypeNode>,
     pub span: Span,
 }
@@ -0,0 +1,198 @@
+//! The kinds of tokens and nodes of the concrete syntax tree.
+
+/// What a token or node of the CST is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    // Trivia.
+    Whitespace,
+    /// A `//` comment, up to the end of the line.
+    Comment,
+
+    // Tokens with text of their own.
+    Ident,
+    Number,
+    Str,
+    /// A character no token starts with, or a string without its closing quote.
+    Unknown,
+
+    // Keywords.
+    FunctionKw,
+    TypeKw,
+    InheritsKw,
+    NewKw,
+    PrintKw,
+    TrueKw,
+    FalseKw,
+    LetKw,
+    InKw,
+    IfKw,
+    ElifKw,
+    ElseKw,
+    WhileKw,
+    ForKw,
+    RangeKw,
+
+    // Punctuation and operators.
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Comma,
+    Semicolon,
+    Colon,
+    Arrow,
+    Assign,
+    ColonAssign,
+    Dot,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Caret,
+    Bang,
+    Amp,
+    Pipe,
+    EqEq,
+    NotEq,
+    Less,
+    Greater,
+    LessEq,
+    GreaterEq,
+    At,
+    AtAt,
+    StarStar,
+
+    /// The end of the input. Never stored in a tree.
+    Eof,
+
+    // Nodes.
+    Program,
+    FunctionDef,
+    TypeDef,
+    ParamList,
+    Param,
+    Inherits,
+    Property,
+    Method,
+    ArgList,
+    Block,
+    LetIn,
+    Binding,
+    IfExpr,
+    ElifBranch,
+    ElseBranch,
+    WhileExpr,
+    ForExpr,
+    CallExpr,
+    NewExpr,
+    MethodCallExpr,
+    FieldExpr,
+    BinaryExpr,
+    UnaryExpr,
+    ParenExpr,
+    Literal,
+    NameRef,
+    PrintExpr,
+    AssignExpr,
+    /// Tokens the parser skipped to recover from an error.
+    Error,
+}
+
+use crate::operators::{Fixity, Operator, OPERATORS};
+
+use SyntaxKind::*;
+
+const KEYWORDS: [(&str, SyntaxKind); 15] = [
+    ("function", FunctionKw),
+    ("type", TypeKw),
+    ("inherits", InheritsKw),
+    ("new", NewKw),
+    ("print", PrintKw),
+    ("true", TrueKw),
+    ("false", FalseKw),
+    ("let", LetKw),
+    ("in", InKw),
+    ("if", IfKw),
+    ("elif", ElifKw),
+    ("else", ElseKw),
+    ("while", WhileKw),
+    ("for", ForKw),
+    ("range", RangeKw),
+];
+
+macro_rules! punctuation {
+    ($(
+        $spelling:literal => $operator:ident, $level:ident,
+        $fixity:ident$(($associativity:ident))?, $kind:ident;
+    )*) => {
+        /// Punctuation, then the operators of [`OPERATORS`], whose spellings may start
+        /// with one another: the lexer takes the longest that matches.
+        pub(crate) const PUNCTUATION: &[(&str, SyntaxKind)] = &[
+            ("=>", Arrow),
+            ("(", LParen),
+            (")", RParen),
+            ("{", LBrace),
+            ("}", RBrace),
+            (",", Comma),
+            (";", Semicolon),
+            (":", Colon),
+            ("=", Assign),
+            (".", Dot),
+            $(($spelling, $kind),)*
+        ];
+    };
+}
+
+operator_table!(punctuation);
+
+impl SyntaxKind {
+    /// Returns the keyword spelled `text`, if it is one.
+    pub fn keyword(text: &str) -> Option<SyntaxKind> {
+        KEYWORDS
+            .iter()
+            .find(|(keyword, _)| *keyword == text)
+            .map(|(_, kind)| *kind)
+    }
+
+    /// Returns the entry of [`OPERATORS`] for the infix operator spelled as this kind.
+    pub fn infix_operator(self) -> Option<&'static Operator> {
+        self.operator(|fixity| fixity != Fixity::Prefix)
+    }
+
+    /// Returns the entry of [`OPERATORS`] for the prefix operator spelled as this kind.
+    pub fn prefix_operator(self) -> Option<&'static Operator> {
+        self.operator(|fixity| fixity == Fixity::Prefix)
+    }
+
+    fn operator(self, wanted: fn(Fixity) -> bool) -> Option<&'static Operator> {
+        let (spelling, _) = PUNCTUATION.iter().find(|(_, kind)| *kind == self)?;
+        OPERATORS
+            .iter()
+            .find(|entry| entry.spelling == *spelling && wanted(entry.fixity))
+    }
+
+    /// Whether tokens of this kind carry no meaning: whitespace and comments.
+    pub fn is_trivia(self) -> bool {
+        matches!(self, Whitespace | Comment)
+    }
+
+    /// Describes the kind in error messages: the spelling of keywords and
+    /// punctuation, or a name for everything else.
+    pub fn describe(self) -> String {
+        if let Some((keyword, _)) = KEYWORDS.iter().find(|(_, kind)| *kind == self) {
+            return format!("`{}`", keyword);
+        }
+        if let Some((symbol, _)) = PUNCTUATION.iter().find(|(_, kind)| *kind == self) {
+            return format!("`{}`", symbol);
+        }
+        match self {
+            Ident => "identifier",
+            Number => "number",
+            Str => "string",
+            Eof => "end of input",
+            _ => "token",
+        }
+        .to_string()
+    }
+}
@@ -0,0 +1,77 @@
+//! The lossless tokenizer of the CST.
+//!
+//! Unlike [`crate::lexer::Lexer`], it keeps whitespace and comments as tokens and turns
+//! characters no token starts with into [`SyntaxKind::Unknown`] tokens instead of
+//! failing, so the text of the tokens always adds up to the whole source.
+
+use super::kind::{SyntaxKind, PUNCTUATION};
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Returns the length in bytes of the token at the start of `text` and its kind.
+fn next_token(text: &str) -> (SyntaxKind, usize) {
+    let first = text.chars().next().expect("no text left to tokenize");
+    let length_while = |skip: usize, accept: fn(char) -> bool| {
+        skip + text[skip..]
+            .find(|c: char| !accept(c))
+            .unwrap_or(text.len() - skip)
+    };
+
+    if first.is_whitespace() {
+        return (SyntaxKind::Whitespace, length_while(0, char::is_whitespace));
+    }
+    if text.starts_with("//") {
+        let end = text.find('\n').unwrap_or(text.len());
+        return (SyntaxKind::Comment, end);
+    }
+    if first.is_ascii_alphabetic() {
+        let length = length_while(0, is_identifier_char);
+        let kind = SyntaxKind::keyword(&text[..length]).unwrap_or(SyntaxKind::Ident);
+        return (kind, length);
+    }
+    if first.is_ascii_digit() {
+        let integer = length_while(0, |c| c.is_ascii_digit());
+        let rest = &text[integer..];
+        if rest.starts_with('.') && rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            return (
+                SyntaxKind::Number,
+                length_while(integer + 1, |c| c.is_ascii_digit()),
+            );
+        }
+        return (SyntaxKind::Number, integer);
+    }
+    if first == '"' {
+        let mut escaped = false;
+        for (index, c) in text.char_indices().skip(1) {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => return (SyntaxKind::Str, index + 1),
+                _ => {}
+            }
+        }
+        return (SyntaxKind::Unknown, text.len());
+    }
+    if let Some((symbol, kind)) = PUNCTUATION
+        .iter()
+        .filter(|(symbol, _)| text.starts_with(symbol))
+        .max_by_key(|(symbol, _)| symbol.len())
+    {
+        return (*kind, symbol.len());
+    }
+    (SyntaxKind::Unknown, first.len_utf8())
+}
+
+/// Splits `source` into tokens, trivia included.
+pub fn tokenize(source: &str) -> Vec<(SyntaxKind, &str)> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while !rest.is_empty() {
+        let (kind, length) = next_token(rest);
+        tokens.push((kind, &rest[..length]));
+        rest = &rest[length..];
+    }
+    tokens
+}
@@ -0,0 +1,253 @@
+//! Lowering of the CST to the AST in [`crate::ast_nodes`].
+//!
+//! The AST keeps no trivia and no parentheses, and its spans are the spans of the CST
+//! nodes, with a parenthesized expression spanning its parentheses, so a program lowers to exactly what `ProgramParser` builds from the same
+//! source. Lowering only runs on trees without errors, where every part a view reads
+//! is there; a missing one makes the whole lowering give `None`.
+
+use crate::ast_nodes::block::ExpressionList;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::function_call::FunctionCallNode;
+use crate::ast_nodes::function_def::{FunctionDefNode, FunctionParams};
+use crate::ast_nodes::let_in::Assignment;
+use crate::ast_nodes::program::{Program as AstProgram, Statement};
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::tokens::Span;
+
+use super::kind::SyntaxKind;
+use super::red::{SyntaxNode, SyntaxToken};
+use super::views::*;
+
+pub(super) fn program(program: &Program) -> Option<AstProgram> {
+    let statements = program
+        .statements()
+        .iter()
+        .map(statement)
+        .collect::<Option<_>>()?;
+    let mut tokens = program
+        .syntax()
+        .descendant_tokens()
+        .into_iter()
+        .filter(|token| !token.kind().is_trivia());
+    let span = match tokens.next() {
+        Some(first) => {
+            let first = first.span();
+            let last = tokens.last().map_or(first, |token| token.span());
+            Span::new(first.start, last.end)
+        }
+        None => Span::default(),
+    };
+    Some(AstProgram::new(statements, span))
+}
+
+fn statement(statement: &Stmt) -> Option<Statement> {
+    match statement {
+        Stmt::FunctionDef(def) => Some(Statement::new_function_def(function(
+            def.name()?,
+            def.params(),
+            def.return_type()?,
+            def.body()?,
+            def.syntax(),
+        )?)),
+        Stmt::TypeDef(def) => Some(Statement::new_type_def(type_def(def)?)),
+        Stmt::Expr(expr) => Some(Statement::new_expression(expression(expr)?)),
+    }
+}
+
+fn name(token: SyntaxToken) -> String {
+    token.text().to_string()
+}
+
+fn function(
+    name_token: SyntaxToken,
+    params: Option<ParamList>,
+    return_type: SyntaxToken,
+    body: Expr,
+    node: &SyntaxNode,
+) -> Option<FunctionDefNode> {
+    Some(FunctionDefNode::new_expr(
+        name(name_token),
+        parameters(params)?,
+        name(return_type),
+        expression(&body)?,
+        node.span(),
+    ))
+}
+
+fn parameters(params: Option<ParamList>) -> Option<Vec<FunctionParams>> {
+    let Some(params) = params else {
+        return Some(Vec::new());
+    };
+    params
+        .params()
+        .into_iter()
+        .map(|param| {
+            let name_token = param.name()?;
+            Some(FunctionParams::new(
+                name_token.text().to_string(),
+                name(param.type_name()?),
+                name_token.span(),
+            ))
+        })
+        .collect()
+}
+
+fn arguments(args: Option<ArgList>) -> Option<Vec<Expression>> {
+    args?.args().iter().map(expression).collect()
+}
+
+fn type_def(def: &TypeDef) -> Option<TypeDefNode> {
+    let (parent, parent_args) = match def.inherits() {
+        Some(inherits) => {
+            let args = match inherits.args() {
+                Some(args) => arguments(Some(args))?,
+                None => Vec::new(),
+            };
+            (Some(name(inherits.parent()?)), args)
+        }
+        None => (None, Vec::new()),
+    };
+    let members = def
+        .members()
+        .iter()
+        .map(|member| match member {
+            Member::Property(property) => Some(TypeMember::new_property(Assignment::new(
+                name(property.name()?),
+                expression(&property.value()?)?,
+                property.syntax().span(),
+            ))),
+            Member::Method(method) => Some(TypeMember::new_method(function(
+                method.name()?,
+                method.params(),
+                method.return_type()?,
+                method.body()?,
+                method.syntax(),
+            )?)),
+        })
+        .collect::<Option<_>>()?;
+    Some(TypeDefNode::new(
+        name(def.name()?),
+        parameters(def.params())?,
+        parent,
+        parent_args,
+        members,
+        def.syntax().span(),
+    ))
+}
+
+fn expression(expr: &Expr) -> Option<Expression> {
+    let span = expr.syntax().span();
+    let lowered = match expr {
+        Expr::Block(block) => Expression::new_code_block(
+            ExpressionList::new(
+                block
+                    .expressions()
+                    .iter()
+                    .map(expression)
+                    .collect::<Option<_>>()?,
+            ),
+            span,
+        ),
+        Expr::LetIn(let_in) => {
+            let assignments = let_in
+                .bindings()
+                .iter()
+                .map(|binding| {
+                    Some(Assignment::new(
+                        name(binding.name()?),
+                        expression(&binding.value()?)?,
+                        binding.syntax().span(),
+                    ))
+                })
+                .collect::<Option<_>>()?;
+            Expression::new_let_in(assignments, expression(&let_in.body()?)?, span)
+        }
+        Expr::If(if_expr) => {
+            let mut branches = Vec::new();
+            for elif in if_expr.elif_branches() {
+                branches.push((
+                    Some(expression(&elif.condition()?)?),
+                    expression(&elif.body()?)?,
+                ));
+            }
+            if let Some(else_branch) = if_expr.else_branch() {
+                branches.push((None, expression(&else_branch.body()?)?));
+            }
+            Expression::new_if_else(
+                expression(&if_expr.condition()?)?,
+                expression(&if_expr.then_branch()?)?,
+                branches,
+                span,
+            )
+        }
+        Expr::While(while_expr) => Expression::new_while_loop(
+            expression(&while_expr.condition()?)?,
+            expression(&while_expr.body()?)?,
+            span,
+        ),
+        Expr::For(for_expr) => Expression::new_for_loop(
+            name(for_expr.variable()?),
+            expression(&for_expr.start()?)?,
+            expression(&for_expr.end()?)?,
+            expression(&for_expr.body()?)?,
+            span,
+        ),
+        Expr::Call(call) => {
+            Expression::new_function_call(name(call.name()?), arguments(call.args())?, span)
+        }
+        Expr::New(new) => {
+            Expression::new_type_instance(name(new.type_name()?), arguments(new.args())?, span)
+        }
+        Expr::MethodCall(call) => {
+            let name_token = call.name()?;
+            let member_span = Span::new(name_token.span().start, span.end);
+            Expression::new_type_function_access(
+                expression(&call.receiver()?)?,
+                FunctionCallNode::new(name(name_token), arguments(call.args())?, member_span),
+                span,
+            )
+        }
+        Expr::Field(field) => Expression::new_type_prop_access(
+            expression(&field.receiver()?)?,
+            name(field.name()?),
+            span,
+        ),
+        Expr::Binary(binary) => Expression::new_binary_op(
+            expression(&binary.lhs()?)?,
+            binary.operator()?.kind().infix_operator()?.operator.clone(),
+            expression(&binary.rhs()?)?,
+            span,
+        ),
+        Expr::Unary(unary) => Expression::new_unary_op(
+            unary.operator()?.kind().prefix_operator()?.operator.clone(),
+            expression(&unary.operand()?)?,
+            span,
+        ),
+        Expr::Paren(paren) => {
+            let mut inner = expression(&paren.expr()?)?;
+            inner.set_span(span);
+            inner
+        }
+        Expr::Literal(literal) => {
+            let token = literal.token()?;
+            let text = token.text();
+            match token.kind() {
+                SyntaxKind::Number => Expression::new_number(text.to_string(), span),
+                SyntaxKind::Str => {
+                    Expression::new_string(text[1..text.len() - 1].to_string(), span)
+                }
+                SyntaxKind::TrueKw => Expression::new_boolean(true, span),
+                SyntaxKind::FalseKw => Expression::new_boolean(false, span),
+                _ => return None,
+            }
+        }
+        Expr::Name(name_ref) => Expression::new_identifier(name(name_ref.name()?), span),
+        Expr::Print(print) => Expression::new_print(expression(&print.expr()?)?, span),
+        Expr::Assign(assign) => Expression::new_destructive_assign(
+            expression(&assign.target()?)?,
+            expression(&assign.value()?)?,
+            span,
+        ),
+    };
+    Some(lowered)
+}
@@ -0,0 +1,71 @@
+//! A lossless concrete syntax tree.
+//!
+//! The tree keeps every token of the source, whitespace and comments included, so
+//! [`SyntaxNode::text`] of the root gives back the source byte for byte. It has two
+//! layers: the [`green`] tree holds the kinds, text and structure, and the [`red`] tree
+//! adds positions and parents on demand. [`views`] puts typed accessors on top of the
+//! red nodes, and [`Parse::to_ast`] lowers the tree to the [`crate::ast_nodes`] the
+//! rest of the compiler works with.
+//!
+//! The parser recovers from errors, so tools that need the layout of a broken program,
+//! such as a formatter keeping comments or an editor, still get a tree of all of it.
+
+pub mod green;
+pub mod kind;
+pub mod lexer;
+mod lower;
+mod parser;
+pub mod red;
+pub mod views;
+
+use std::rc::Rc;
+
+use crate::ast_nodes::program::Program;
+use crate::diagnostics::{Diagnostic, Phase};
+
+use green::GreenNode;
+use parser::Parser;
+use red::SyntaxNode;
+
+/// The tree of a source and the errors found building it.
+#[derive(Debug, Clone)]
+pub struct Parse {
+    green: Rc<GreenNode>,
+    pub errors: Vec<Diagnostic>,
+}
+
+/// Parses `source` into a CST. Never fails: errors are in [`Parse::errors`].
+pub fn parse(source: &str) -> Parse {
+    let (green, errors) = Parser::new(lexer::tokenize(source)).parse_program();
+    Parse { green, errors }
+}
+
+impl Parse {
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.green
+    }
+
+    /// The root of the red tree.
+    pub fn syntax(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone())
+    }
+
+    pub fn program(&self) -> views::Program {
+        views::Program::cast(self.syntax()).expect("the root of a CST is a program")
+    }
+
+    /// Lowers the tree to the AST, the same `ProgramParser` builds from the source, or
+    /// returns the errors of the source.
+    pub fn to_ast(&self) -> Result<Program, Vec<Diagnostic>> {
+        if !self.errors.is_empty() {
+            return Err(self.errors.clone());
+        }
+        lower::program(&self.program()).ok_or_else(|| {
+            vec![Diagnostic::new(
+                Phase::Syntax,
+                "the syntax tree is incomplete",
+                None,
+            )]
+        })
+    }
+}
@@ -0,0 +1,619 @@
+//! A recursive descent parser building the green tree.
+//!
+//! It accepts the same language as the LALRPOP grammar in `parser.lalrpop`, rule for
+//! rule except for binary operators, which it reads by precedence climbing over
+//! [`crate::operators::OPERATORS`], but keeps every token. Trivia goes to the innermost node open when the next
+//! meaningful token is added, and nodes are started only after the trivia before them,
+//! so a node spans exactly the tokens of its construct.
+//!
+//! Errors do not stop the parser: a missing token is reported and left out, and a
+//! token that cannot start what is expected is wrapped in an [`SyntaxKind::Error`]
+//! node, so even a broken program gives a tree with all of its text.
+
+use crate::diagnostics::{Diagnostic, Phase};
+use crate::operators::{self, Associativity};
+use crate::tokens::Span;
+
+use super::green::{Checkpoint, GreenBuilder, GreenNode};
+use super::kind::SyntaxKind::{self, *};
+
+use std::rc::Rc;
+
+/// Tokens an expression cannot start with and that recovery leaves to the enclosing
+/// construct.
+const RECOVERY: [SyntaxKind; 5] = [Semicolon, RBrace, RParen, Comma, Eof];
+
+/// Kinds of the expressions the grammar calls primary, which may be receivers of `.`
+/// and targets of `:=`.
+const PRIMARY: [SyntaxKind; 9] = [
+    CallExpr,
+    NewExpr,
+    MethodCallExpr,
+    FieldExpr,
+    Literal,
+    NameRef,
+    ParenExpr,
+    Block,
+    PrintExpr,
+];
+
+pub(super) struct Parser<'a> {
+    tokens: Vec<(SyntaxKind, &'a str)>,
+    /// Byte offset of every token.
+    offsets: Vec<usize>,
+    /// Index of the next token to add to the tree, trivia included.
+    position: usize,
+    builder: GreenBuilder,
+    errors: Vec<Diagnostic>,
+}
+
+impl<'a> Parser<'a> {
+    pub(super) fn new(tokens: Vec<(SyntaxKind, &'a str)>) -> Self {
+        let mut offset = 0;
+        let offsets = tokens
+            .iter()
+            .map(|(_, text)| {
+                let start = offset;
+                offset += text.len();
+                start
+            })
+            .collect();
+        Parser {
+            tokens,
+            offsets,
+            position: 0,
+            builder: GreenBuilder::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Parses a program and returns its tree and the errors found.
+    pub(super) fn parse_program(mut self) -> (Rc<GreenNode>, Vec<Diagnostic>) {
+        for (index, (kind, text)) in self.tokens.iter().enumerate() {
+            if *kind == Unknown {
+                let start = self.offsets[index];
+                let message = if text.starts_with('"') {
+                    "unterminated string".to_string()
+                } else {
+                    format!("unexpected character `{}`", text)
+                };
+                self.errors.push(Diagnostic::new(
+                    Phase::Lexical,
+                    message,
+                    Some(Span::new(start, start + text.len())),
+                ));
+            }
+        }
+
+        self.builder.start_node(Program);
+        self.program();
+        self.flush_trivia();
+        self.builder.finish_node();
+        (self.builder.finish(), self.errors)
+    }
+
+    // Looking at tokens.
+
+    /// Index of the `n`-th meaningful token from the position.
+    fn nth_index(&self, n: usize) -> Option<usize> {
+        (self.position..self.tokens.len())
+            .filter(|index| !self.tokens[*index].0.is_trivia())
+            .nth(n)
+    }
+
+    fn nth(&self, n: usize) -> SyntaxKind {
+        self.nth_index(n).map_or(Eof, |index| self.tokens[index].0)
+    }
+
+    fn current(&self) -> SyntaxKind {
+        self.nth(0)
+    }
+
+    fn at(&self, kind: SyntaxKind) -> bool {
+        self.current() == kind
+    }
+
+    fn current_span(&self) -> Span {
+        match self.nth_index(0) {
+            Some(index) => {
+                let start = self.offsets[index];
+                Span::new(start, start + self.tokens[index].1.len())
+            }
+            None => {
+                let end = self.offsets.last().map_or(0, |start| {
+                    start + self.tokens.last().map_or(0, |(_, text)| text.len())
+                });
+                Span::new(end, end)
+            }
+        }
+    }
+
+    // Building the tree.
+
+    fn flush_trivia(&mut self) {
+        while let Some((kind, text)) = self.tokens.get(self.position) {
+            if !kind.is_trivia() {
+                break;
+            }
+            self.builder.token(*kind, text);
+            self.position += 1;
+        }
+    }
+
+    /// Adds the current token to the tree, after the trivia before it.
+    fn bump(&mut self) {
+        self.flush_trivia();
+        if let Some((kind, text)) = self.tokens.get(self.position) {
+            self.builder.token(*kind, text);
+            self.position += 1;
+        }
+    }
+
+    fn start(&mut self, kind: SyntaxKind) {
+        self.flush_trivia();
+        self.builder.start_node(kind);
+    }
+
+    fn checkpoint(&mut self) -> Checkpoint {
+        self.flush_trivia();
+        self.builder.checkpoint()
+    }
+
+    fn start_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        self.builder.start_node_at(checkpoint, kind);
+    }
+
+    fn finish(&mut self) {
+        self.builder.finish_node();
+    }
+
+    // Errors.
+
+    fn error(&mut self, expected: &str) {
+        let span = self.current_span();
+        if self
+            .errors
+            .last()
+            .is_some_and(|last| last.span == Some(span))
+        {
+            return;
+        }
+        let found = match self.nth_index(0) {
+            Some(index) => format!("`{}`", self.tokens[index].1),
+            None => "end of input".to_string(),
+        };
+        self.errors.push(Diagnostic::new(
+            Phase::Syntax,
+            format!("expected {}, found {}", expected, found),
+            Some(span),
+        ));
+    }
+
+    /// Adds the current token if it is of `kind`, or reports it missing.
+    fn expect(&mut self, kind: SyntaxKind) -> bool {
+        if self.at(kind) {
+            self.bump();
+            true
+        } else {
+            self.error(&kind.describe());
+            false
+        }
+    }
+
+    /// Wraps the current token in an error node, unless an enclosing construct can
+    /// use it.
+    fn recover(&mut self) {
+        if !RECOVERY.contains(&self.current()) {
+            self.start(Error);
+            self.bump();
+            self.finish();
+        }
+    }
+
+    // Declarations.
+
+    /// `(Statement ";")* Statement?`
+    fn program(&mut self) {
+        while !self.at(Eof) {
+            let before = self.position;
+            self.statement();
+            if !self.at(Eof) {
+                self.expect(Semicolon);
+            }
+            if self.position == before {
+                self.start(Error);
+                self.bump();
+                self.finish();
+            }
+        }
+    }
+
+    fn statement(&mut self) {
+        match self.current() {
+            FunctionKw => self.function_def(),
+            TypeKw => self.type_def(),
+            _ => {
+                self.expr();
+            }
+        }
+    }
+
+    /// `"function" Ident ParamList ":" Ident FunctionBody`
+    fn function_def(&mut self) {
+        self.start(FunctionDef);
+        self.bump();
+        self.expect(Ident);
+        self.signature();
+        self.finish();
+    }
+
+    /// The parameters, return type and body of a function or method.
+    fn signature(&mut self) {
+        self.param_list();
+        self.expect(Colon);
+        self.expect(Ident);
+        if self.at(Arrow) {
+            self.bump();
+            self.expr();
+        } else if self.at(LBrace) {
+            self.block();
+        } else {
+            self.error("`=>` or `{`");
+        }
+    }
+
+    /// `"(" (Param ("," Param)*)? ")"`
+    fn param_list(&mut self) {
+        self.start(ParamList);
+        self.expect(LParen);
+        if !self.at(RParen) {
+            self.param();
+            while self.at(Comma) {
+                self.bump();
+                self.param();
+            }
+        }
+        self.expect(RParen);
+        self.finish();
+    }
+
+    /// `Ident ":" Ident`
+    fn param(&mut self) {
+        self.start(Param);
+        self.expect(Ident);
+        self.expect(Colon);
+        self.expect(Ident);
+        self.finish();
+    }
+
+    /// `"type" Ident ParamList? Inherits? "{" (Member ";")* Member? "}"`
+    fn type_def(&mut self) {
+        self.start(TypeDef);
+        self.bump();
+        self.expect(Ident);
+        if self.at(LParen) {
+            self.param_list();
+        }
+        if self.at(InheritsKw) {
+            self.start(Inherits);
+            self.bump();
+            self.expect(Ident);
+            if self.at(LParen) {
+                self.arg_list();
+            }
+            self.finish();
+        }
+        if self.expect(LBrace) {
+            while !self.at(RBrace) && !self.at(Eof) {
+                let before = self.position;
+                self.member();
+                if self.at(Semicolon) {
+                    self.bump();
+                } else if self.position == before {
+                    self.recover();
+                    if self.position == before {
+                        break;
+                    }
+                } else {
+                    break;
+                }
+            }
+            self.expect(RBrace);
+        }
+        self.finish();
+    }
+
+    /// `Ident "=" Expr` or `Ident ParamList ":" Ident FunctionBody`
+    fn member(&mut self) {
+        match (self.current(), self.nth(1)) {
+            (Ident, Assign) => {
+                self.start(Property);
+                self.bump();
+                self.bump();
+                self.expr();
+                self.finish();
+            }
+            (Ident, LParen) => {
+                self.start(Method);
+                self.bump();
+                self.signature();
+                self.finish();
+            }
+            _ => self.error("an attribute or a method"),
+        }
+    }
+
+    // Expressions. Each returns the kind of the outermost node it built, or `None` if
+    // there was no expression.
+
+    /// `PrimaryExpr ":=" Expr` or a binary expression.
+    fn expr(&mut self) -> Option<SyntaxKind> {
+        let checkpoint = self.checkpoint();
+        // `:=` is parsed here, the other operators by `binary`.
+        let kind = self.binary(operators::ASSIGN + 1);
+        if !self.at(ColonAssign) {
+            return kind;
+        }
+        if !kind.is_some_and(|kind| PRIMARY.contains(&kind)) {
+            self.error("an expression before `:=` that can be assigned");
+        }
+        self.start_at(checkpoint, AssignExpr);
+        self.bump();
+        self.expr();
+        self.finish();
+        Some(AssignExpr)
+    }
+
+    /// The binary expressions whose operators bind at least as tightly as
+    /// `precedence`, by precedence climbing over the operator table.
+    fn binary(&mut self, precedence: u8) -> Option<SyntaxKind> {
+        let checkpoint = self.checkpoint();
+        let mut kind = self.unary();
+        while let Some(operator) = self
+            .current()
+            .infix_operator()
+            .filter(|operator| operator.precedence >= precedence)
+        {
+            let next = match operator.associativity() {
+                Some(Associativity::Right) => operator.precedence,
+                _ => operator.precedence + 1,
+            };
+            self.start_at(checkpoint, BinaryExpr);
+            self.bump();
+            self.binary(next);
+            self.finish();
+            kind = Some(BinaryExpr);
+        }
+        kind
+    }
+
+    fn unary(&mut self) -> Option<SyntaxKind> {
+        if self.current().prefix_operator().is_none() {
+            return self.composite();
+        }
+        self.start(UnaryExpr);
+        self.bump();
+        self.unary();
+        self.finish();
+        Some(UnaryExpr)
+    }
+
+    fn composite(&mut self) -> Option<SyntaxKind> {
+        match self.current() {
+            LetKw => self.let_in(),
+            IfKw => self.if_expr(),
+            WhileKw => self.while_expr(),
+            ForKw => self.for_expr(),
+            _ => self.primary(),
+        }
+    }
+
+    /// `"let" Binding ("," Binding)* "in" CompositeExpr`
+    fn let_in(&mut self) -> Option<SyntaxKind> {
+        self.start(LetIn);
+        self.bump();
+        self.binding();
+        while self.at(Comma) {
+            self.bump();
+            self.binding();
+        }
+        self.expect(InKw);
+        self.composite();
+        self.finish();
+        Some(LetIn)
+    }
+
+    /// `Ident "=" Expr`
+    fn binding(&mut self) {
+        self.start(Binding);
+        self.expect(Ident);
+        self.expect(Assign);
+        self.expr();
+        self.finish();
+    }
+
+    /// `"(" Expr ")"`
+    fn condition(&mut self) {
+        self.expect(LParen);
+        self.expr();
+        self.expect(RParen);
+    }
+
+    /// `"if" "(" Expr ")" Block ("elif" "(" Expr ")" Block)* ("else" Block)?`, where an
+    /// `elif` needs an `else` after it.
+    fn if_expr(&mut self) -> Option<SyntaxKind> {
+        self.start(IfExpr);
+        self.bump();
+        self.condition();
+        self.branch_body();
+        let mut elif = false;
+        while self.at(ElifKw) {
+            self.start(ElifBranch);
+            self.bump();
+            self.condition();
+            self.branch_body();
+            self.finish();
+            elif = true;
+        }
+        if self.at(ElseKw) {
+            self.start(ElseBranch);
+            self.bump();
+            self.branch_body();
+            self.finish();
+        } else if elif {
+            self.error("`else` or `elif`");
+        }
+        self.finish();
+        Some(IfExpr)
+    }
+
+    fn branch_body(&mut self) {
+        if self.at(LBrace) {
+            self.block();
+        } else {
+            self.error("`{`");
+        }
+    }
+
+    /// `"while" "(" Expr ")" CompositeExpr`
+    fn while_expr(&mut self) -> Option<SyntaxKind> {
+        self.start(WhileExpr);
+        self.bump();
+        self.condition();
+        self.composite();
+        self.finish();
+        Some(WhileExpr)
+    }
+
+    /// `"for" "(" Ident "in" "range" "(" Expr "," Expr ")" ")" CompositeExpr`
+    fn for_expr(&mut self) -> Option<SyntaxKind> {
+        self.start(ForExpr);
+        self.bump();
+        self.expect(LParen);
+        self.expect(Ident);
+        self.expect(InKw);
+        self.expect(RangeKw);
+        self.expect(LParen);
+        self.expr();
+        self.expect(Comma);
+        self.expr();
+        self.expect(RParen);
+        self.expect(RParen);
+        self.composite();
+        self.finish();
+        Some(ForExpr)
+    }
+
+    /// An atom followed by any number of `.name` and `.name(args)`.
+    fn primary(&mut self) -> Option<SyntaxKind> {
+        let checkpoint = self.checkpoint();
+        let mut kind = self.atom()?;
+        while self.at(Dot) {
+            if self.nth(1) == Ident && self.nth(2) == LParen {
+                self.start_at(checkpoint, MethodCallExpr);
+                self.bump();
+                self.bump();
+                self.arg_list();
+                kind = MethodCallExpr;
+            } else {
+                self.start_at(checkpoint, FieldExpr);
+                self.bump();
+                self.expect(Ident);
+                kind = FieldExpr;
+            }
+            self.finish();
+        }
+        Some(kind)
+    }
+
+    fn atom(&mut self) -> Option<SyntaxKind> {
+        let kind = match self.current() {
+            Ident if self.nth(1) == LParen => {
+                self.start(CallExpr);
+                self.bump();
+                self.arg_list();
+                CallExpr
+            }
+            Ident => {
+                self.start(NameRef);
+                self.bump();
+                NameRef
+            }
+            NewKw => {
+                self.start(NewExpr);
+                self.bump();
+                self.expect(Ident);
+                if self.at(LParen) {
+                    self.arg_list();
+                } else {
+                    self.error("`(`");
+                }
+                NewExpr
+            }
+            Number | Str | TrueKw | FalseKw => {
+                self.start(Literal);
+                self.bump();
+                Literal
+            }
+            LParen => {
+                self.start(ParenExpr);
+                self.bump();
+                self.expr();
+                self.expect(RParen);
+                ParenExpr
+            }
+            LBrace => {
+                self.block();
+                return Some(Block);
+            }
+            PrintKw => {
+                self.start(PrintExpr);
+                self.bump();
+                self.condition();
+                PrintExpr
+            }
+            _ => {
+                self.error("an expression");
+                self.recover();
+                return None;
+            }
+        };
+        self.finish();
+        Some(kind)
+    }
+
+    /// `"{" (Expr ";")* Expr? "}"`
+    fn block(&mut self) {
+        self.start(Block);
+        self.bump();
+        while !self.at(RBrace) && !self.at(Eof) {
+            let before = self.position;
+            self.expr();
+            if self.at(Semicolon) {
+                self.bump();
+            } else if self.position == before {
+                break;
+            } else if !self.at(RBrace) {
+                self.error("`;` or `}`");
+                break;
+            }
+        }
+        self.expect(RBrace);
+        self.finish();
+    }
+
+    /// `"(" (Expr ("," Expr)*)? ")"`
+    fn arg_list(&mut self) {
+        self.start(ArgList);
+        self.expect(LParen);
+        if !self.at(RParen) {
+            self.expr();
+            while self.at(Comma) {
+                self.bump();
+                self.expr();
+            }
+        }
+        self.expect(RParen);
+        self.finish();
+    }
+}
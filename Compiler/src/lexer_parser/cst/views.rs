@@ -0,0 +1,508 @@
+//! Typed views of the CST.
+//!
+//! Each view wraps a [`SyntaxNode`] of one kind and reads the parts of its construct
+//! from the children of the node. Parts are optional because a tree with errors may
+//! lack them. The views own no data, so they are as cheap to create and clone as the
+//! node itself.
+
+use super::kind::SyntaxKind;
+use super::red::{SyntaxNode, SyntaxToken};
+
+macro_rules! views {
+    ($($(#[$doc:meta])* $name:ident,)*) => {
+        $(
+            $(#[$doc])*
+            #[derive(Debug, Clone, PartialEq, Eq)]
+            pub struct $name(SyntaxNode);
+
+            impl $name {
+                pub fn cast(node: SyntaxNode) -> Option<Self> {
+                    (node.kind() == SyntaxKind::$name).then_some($name(node))
+                }
+
+                pub fn syntax(&self) -> &SyntaxNode {
+                    &self.0
+                }
+            }
+        )*
+    };
+}
+
+views! {
+    /// The whole source.
+    Program,
+    /// `function name(params): Type => body` or `function name(params): Type { ... }`.
+    FunctionDef,
+    /// `type Name(params) inherits Parent(args) { members }`.
+    TypeDef,
+    ParamList,
+    /// `name: Type`.
+    Param,
+    /// `inherits Parent(args)`.
+    Inherits,
+    /// `name = value` in a type.
+    Property,
+    /// `name(params): Type => body` in a type.
+    Method,
+    ArgList,
+    /// `{ expr; ... }`.
+    Block,
+    /// `let bindings in body`.
+    LetIn,
+    /// `name = value` in a `let`.
+    Binding,
+    /// `if (condition) { ... }` with its `elif` and `else` branches.
+    IfExpr,
+    ElifBranch,
+    ElseBranch,
+    WhileExpr,
+    /// `for (name in range(start, end)) body`.
+    ForExpr,
+    /// `name(args)`.
+    CallExpr,
+    /// `new Name(args)`.
+    NewExpr,
+    /// `object.name(args)`.
+    MethodCallExpr,
+    /// `object.name`.
+    FieldExpr,
+    BinaryExpr,
+    UnaryExpr,
+    ParenExpr,
+    /// A number, string or boolean.
+    Literal,
+    /// A variable.
+    NameRef,
+    PrintExpr,
+    /// `target := value`.
+    AssignExpr,
+}
+
+/// A statement of the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stmt {
+    FunctionDef(FunctionDef),
+    TypeDef(TypeDef),
+    Expr(Expr),
+}
+
+/// A member of a type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Member {
+    Property(Property),
+    Method(Method),
+}
+
+/// Any expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Block(Block),
+    LetIn(LetIn),
+    If(IfExpr),
+    While(WhileExpr),
+    For(ForExpr),
+    Call(CallExpr),
+    New(NewExpr),
+    MethodCall(MethodCallExpr),
+    Field(FieldExpr),
+    Binary(BinaryExpr),
+    Unary(UnaryExpr),
+    Paren(ParenExpr),
+    Literal(Literal),
+    Name(NameRef),
+    Print(PrintExpr),
+    Assign(AssignExpr),
+}
+
+/// The `n`-th token of `kind` directly below `node`.
+fn token(node: &SyntaxNode, kind: SyntaxKind, n: usize) -> Option<SyntaxToken> {
+    node.tokens().filter(|token| token.kind() == kind).nth(n)
+}
+
+/// The first token directly below `node` that is not trivia.
+fn first_token(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.tokens().find(|token| !token.kind().is_trivia())
+}
+
+fn child<T>(node: &SyntaxNode, cast: fn(SyntaxNode) -> Option<T>) -> Option<T> {
+    node.children().find_map(cast)
+}
+
+fn expressions(node: &SyntaxNode) -> Vec<Expr> {
+    node.children().filter_map(Expr::cast).collect()
+}
+
+impl Stmt {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SyntaxKind::FunctionDef => FunctionDef::cast(node).map(Stmt::FunctionDef),
+            SyntaxKind::TypeDef => TypeDef::cast(node).map(Stmt::TypeDef),
+            _ => Expr::cast(node).map(Stmt::Expr),
+        }
+    }
+
+    pub fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Stmt::FunctionDef(def) => def.syntax(),
+            Stmt::TypeDef(def) => def.syntax(),
+            Stmt::Expr(expr) => expr.syntax(),
+        }
+    }
+}
+
+impl Member {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            SyntaxKind::Property => Property::cast(node).map(Member::Property),
+            SyntaxKind::Method => Method::cast(node).map(Member::Method),
+            _ => None,
+        }
+    }
+
+    pub fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Member::Property(property) => property.syntax(),
+            Member::Method(method) => method.syntax(),
+        }
+    }
+}
+
+impl Expr {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        let expr = match node.kind() {
+            SyntaxKind::Block => Expr::Block(Block(node)),
+            SyntaxKind::LetIn => Expr::LetIn(LetIn(node)),
+            SyntaxKind::IfExpr => Expr::If(IfExpr(node)),
+            SyntaxKind::WhileExpr => Expr::While(WhileExpr(node)),
+            SyntaxKind::ForExpr => Expr::For(ForExpr(node)),
+            SyntaxKind::CallExpr => Expr::Call(CallExpr(node)),
+            SyntaxKind::NewExpr => Expr::New(NewExpr(node)),
+            SyntaxKind::MethodCallExpr => Expr::MethodCall(MethodCallExpr(node)),
+            SyntaxKind::FieldExpr => Expr::Field(FieldExpr(node)),
+            SyntaxKind::BinaryExpr => Expr::Binary(BinaryExpr(node)),
+            SyntaxKind::UnaryExpr => Expr::Unary(UnaryExpr(node)),
+            SyntaxKind::ParenExpr => Expr::Paren(ParenExpr(node)),
+            SyntaxKind::Literal => Expr::Literal(Literal(node)),
+            SyntaxKind::NameRef => Expr::Name(NameRef(node)),
+            SyntaxKind::PrintExpr => Expr::Print(PrintExpr(node)),
+            SyntaxKind::AssignExpr => Expr::Assign(AssignExpr(node)),
+            _ => return None,
+        };
+        Some(expr)
+    }
+
+    pub fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Expr::Block(expr) => expr.syntax(),
+            Expr::LetIn(expr) => expr.syntax(),
+            Expr::If(expr) => expr.syntax(),
+            Expr::While(expr) => expr.syntax(),
+            Expr::For(expr) => expr.syntax(),
+            Expr::Call(expr) => expr.syntax(),
+            Expr::New(expr) => expr.syntax(),
+            Expr::MethodCall(expr) => expr.syntax(),
+            Expr::Field(expr) => expr.syntax(),
+            Expr::Binary(expr) => expr.syntax(),
+            Expr::Unary(expr) => expr.syntax(),
+            Expr::Paren(expr) => expr.syntax(),
+            Expr::Literal(expr) => expr.syntax(),
+            Expr::Name(expr) => expr.syntax(),
+            Expr::Print(expr) => expr.syntax(),
+            Expr::Assign(expr) => expr.syntax(),
+        }
+    }
+}
+
+impl Program {
+    pub fn statements(&self) -> Vec<Stmt> {
+        self.0.children().filter_map(Stmt::cast).collect()
+    }
+}
+
+impl FunctionDef {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 0)
+    }
+
+    pub fn params(&self) -> Option<ParamList> {
+        child(&self.0, ParamList::cast)
+    }
+
+    pub fn return_type(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 1)
+    }
+
+    /// The expression after `=>`, or the block.
+    pub fn body(&self) -> Option<Expr> {
+        child(&self.0, Expr::cast)
+    }
+}
+
+impl Method {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 0)
+    }
+
+    pub fn params(&self) -> Option<ParamList> {
+        child(&self.0, ParamList::cast)
+    }
+
+    pub fn return_type(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 1)
+    }
+
+    pub fn body(&self) -> Option<Expr> {
+        child(&self.0, Expr::cast)
+    }
+}
+
+impl ParamList {
+    pub fn params(&self) -> Vec<Param> {
+        self.0.children().filter_map(Param::cast).collect()
+    }
+}
+
+impl Param {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 0)
+    }
+
+    pub fn type_name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 1)
+    }
+}
+
+impl TypeDef {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 0)
+    }
+
+    pub fn params(&self) -> Option<ParamList> {
+        child(&self.0, ParamList::cast)
+    }
+
+    pub fn inherits(&self) -> Option<Inherits> {
+        child(&self.0, Inherits::cast)
+    }
+
+    pub fn members(&self) -> Vec<Member> {
+        self.0.children().filter_map(Member::cast).collect()
+    }
+}
+
+impl Inherits {
+    pub fn parent(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 0)
+    }
+
+    pub fn args(&self) -> Option<ArgList> {
+        child(&self.0, ArgList::cast)
+    }
+}
+
+impl Property {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 0)
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        child(&self.0, Expr::cast)
+    }
+}
+
+impl ArgList {
+    pub fn args(&self) -> Vec<Expr> {
+        expressions(&self.0)
+    }
+}
+
+impl Block {
+    pub fn expressions(&self) -> Vec<Expr> {
+        expressions(&self.0)
+    }
+}
+
+impl LetIn {
+    pub fn bindings(&self) -> Vec<Binding> {
+        self.0.children().filter_map(Binding::cast).collect()
+    }
+
+    pub fn body(&self) -> Option<Expr> {
+        child(&self.0, Expr::cast)
+    }
+}
+
+impl Binding {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 0)
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        child(&self.0, Expr::cast)
+    }
+}
+
+impl IfExpr {
+    pub fn condition(&self) -> Option<Expr> {
+        expressions(&self.0).into_iter().next()
+    }
+
+    pub fn then_branch(&self) -> Option<Expr> {
+        expressions(&self.0).into_iter().nth(1)
+    }
+
+    pub fn elif_branches(&self) -> Vec<ElifBranch> {
+        self.0.children().filter_map(ElifBranch::cast).collect()
+    }
+
+    pub fn else_branch(&self) -> Option<ElseBranch> {
+        child(&self.0, ElseBranch::cast)
+    }
+}
+
+impl ElifBranch {
+    pub fn condition(&self) -> Option<Expr> {
+        expressions(&self.0).into_iter().next()
+    }
+
+    pub fn body(&self) -> Option<Expr> {
+        expressions(&self.0).into_iter().nth(1)
+    }
+}
+
+impl ElseBranch {
+    pub fn body(&self) -> Option<Expr> {
+        child(&self.0, Expr::cast)
+    }
+}
+
+impl WhileExpr {
+    pub fn condition(&self) -> Option<Expr> {
+        expressions(&self.0).into_iter().next()
+    }
+
+    pub fn body(&self) -> Option<Expr> {
+        expressions(&self.0).into_iter().nth(1)
+    }
+}
+
+impl ForExpr {
+    pub fn variable(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 0)
+    }
+
+    pub fn start(&self) -> Option<Expr> {
+        expressions(&self.0).into_iter().next()
+    }
+
+    pub fn end(&self) -> Option<Expr> {
+        expressions(&self.0).into_iter().nth(1)
+    }
+
+    pub fn body(&self) -> Option<Expr> {
+        expressions(&self.0).into_iter().nth(2)
+    }
+}
+
+impl CallExpr {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 0)
+    }
+
+    pub fn args(&self) -> Option<ArgList> {
+        child(&self.0, ArgList::cast)
+    }
+}
+
+impl NewExpr {
+    pub fn type_name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 0)
+    }
+
+    pub fn args(&self) -> Option<ArgList> {
+        child(&self.0, ArgList::cast)
+    }
+}
+
+impl MethodCallExpr {
+    pub fn receiver(&self) -> Option<Expr> {
+        child(&self.0, Expr::cast)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 0)
+    }
+
+    pub fn args(&self) -> Option<ArgList> {
+        child(&self.0, ArgList::cast)
+    }
+}
+
+impl FieldExpr {
+    pub fn receiver(&self) -> Option<Expr> {
+        child(&self.0, Expr::cast)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 0)
+    }
+}
+
+impl BinaryExpr {
+    pub fn lhs(&self) -> Option<Expr> {
+        expressions(&self.0).into_iter().next()
+    }
+
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        first_token(&self.0)
+    }
+
+    pub fn rhs(&self) -> Option<Expr> {
+        expressions(&self.0).into_iter().nth(1)
+    }
+}
+
+impl UnaryExpr {
+    pub fn operator(&self) -> Option<SyntaxToken> {
+        first_token(&self.0)
+    }
+
+    pub fn operand(&self) -> Option<Expr> {
+        child(&self.0, Expr::cast)
+    }
+}
+
+impl ParenExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0, Expr::cast)
+    }
+}
+
+impl Literal {
+    /// The number, string or `true`/`false` token.
+    pub fn token(&self) -> Option<SyntaxToken> {
+        first_token(&self.0)
+    }
+}
+
+impl NameRef {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::Ident, 0)
+    }
+}
+
+impl PrintExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0, Expr::cast)
+    }
+}
+
+impl AssignExpr {
+    pub fn target(&self) -> Option<Expr> {
+        expressions(&self.0).into_iter().next()
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        expressions(&self.0).into_iter().nth(1)
+    }
+}
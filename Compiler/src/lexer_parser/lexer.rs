@@ -1,5 +1,6 @@
 use regex::Regex;
 use std::str::CharIndices;
+use super::operators;
 use super::tokens::{Token, Span, KeywordToken, OperatorToken, DelimiterToken};
 
 pub struct Lexer<'input> {
@@ -22,7 +23,7 @@ impl RegexCache {
             identifier: Regex::new(r"^[A-Za-z][A-Za-z_0-9]*").unwrap(),
             number: Regex::new(r"^[0-9]+(\.[0-9]+)?").unwrap(),
             string: Regex::new(r#"^"([^"\\]|\\.)*""#).unwrap(),
-            whitespace: Regex::new(r"^[ \t\n\r]+").unwrap(),
+            whitespace: Regex::new(r"^([ \t\n\r]+|//[^\n\r]*)+").unwrap(),
         }
     }
 }
@@ -47,7 +48,9 @@ impl<'input> Lexer<'input> {
     fn skip_whitespace(&mut self) {
         if let Some((pos, _)) = self.lookahead {
             if let Some(m) = self.regex_cache.whitespace.find(&self.text[pos..]) {
-                for _ in 0..m.end() {
+                // Comments may hold any character, so skip by byte offset.
+                let end = pos + m.end();
+                while matches!(self.lookahead, Some((next, _)) if next < end) {
                     self.bump();
                 }
             }
@@ -59,7 +62,7 @@ impl<'input> Lexer<'input> {
         
         let (start, c) = self.lookahead?;
         
-        // Verificar tokens de un solo carácter
+        // Delimitadores de un solo carácter
         let single_char_token = match c {
             '(' => Some(Token::LParen(DelimiterToken::LPAREN)),
             ')' => Some(Token::RParen(DelimiterToken::RPAREN)),
@@ -67,15 +70,7 @@ impl<'input> Lexer<'input> {
             '}' => Some(Token::RBrace(DelimiterToken::RBRACE)),
             ';' => Some(Token::Semicolon(DelimiterToken::SEMICOLON)),
             ',' => Some(Token::Comma(DelimiterToken::COMMA)),
-            ':' => Some(Token::Colon(DelimiterToken::COLON)),
             '.' => Some(Token::DotOp(OperatorToken::DOT)),
-            '+' => Some(Token::Plus(OperatorToken::PLUS)),
-            '-' => Some(Token::Minus(OperatorToken::MINUS)),
-            '*' => Some(Token::Star(OperatorToken::MUL)),
-            '/' => Some(Token::Slash(OperatorToken::DIV)),
-            '%' => Some(Token::Mod(OperatorToken::MOD)),
-            '^' => Some(Token::PowOp(OperatorToken::POW)),
-            '!' => Some(Token::Not(OperatorToken::NOT)),
             _ => None,
         };
 
@@ -84,41 +79,30 @@ impl<'input> Lexer<'input> {
             return Some((token, Span::new(start, start + c.len_utf8())));
         }
 
-        // Verificar tokens de múltiples caracteres
         let remaining_text = &self.text[start..];
-        
-        // Operadores de comparación
-        if remaining_text.starts_with("==") {
-            self.bump(); self.bump();
-            return Some((Token::Equal(OperatorToken::EQ), Span::new(start, start + 2)));
-        }
-        if remaining_text.starts_with("!=") {
-            self.bump(); self.bump();
-            return Some((Token::NotEqual(OperatorToken::NEQ), Span::new(start, start + 2)));
-        }
-        if remaining_text.starts_with(">=") {
-            self.bump(); self.bump();
-            return Some((Token::GreaterEqual(OperatorToken::GTE), Span::new(start, start + 2)));
-        }
-        if remaining_text.starts_with("<=") {
-            self.bump(); self.bump();
-            return Some((Token::LessEqual(OperatorToken::LTE), Span::new(start, start + 2)));
-        }
+
         if remaining_text.starts_with("=>") {
-            self.bump(); self.bump();
+            self.bump_n(2);
             return Some((Token::Arrow(DelimiterToken::ARROW), Span::new(start, start + 2)));
         }
-        if remaining_text.starts_with(":=") {
-            self.bump(); self.bump();
-            return Some((Token::DestructiveAssignOp(OperatorToken::DASSIGN), Span::new(start, start + 2)));
-        }
-        if remaining_text.starts_with("&&") {
-            self.bump(); self.bump();
-            return Some((Token::And(OperatorToken::AND), Span::new(start, start + 2)));
+
+        // Operadores, tomados de la tabla de operadores
+        if let Some(operator) = operators::longest_prefix(remaining_text) {
+            let length = operator.spelling.len();
+            self.bump_n(length);
+            return Some((operator.token(), Span::new(start, start + length)));
         }
-        if remaining_text.starts_with("||") {
-            self.bump(); self.bump();
-            return Some((Token::Or(OperatorToken::OR), Span::new(start, start + 2)));
+
+        // Signos que no son operadores
+        let short_token = match c {
+            ':' => Some(Token::Colon(DelimiterToken::COLON)),
+            '=' => Some(Token::Assign(OperatorToken::ASSIGN)),
+            _ => None,
+        };
+
+        if let Some(token) = short_token {
+            self.bump();
+            return Some((token, Span::new(start, start + 1)));
         }
 
         // Identificadores y palabras clave
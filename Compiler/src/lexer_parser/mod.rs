@@ -1,3 +1,7 @@
 pub mod tokens;
 pub mod lexer;
+#[macro_use]
+pub mod operators;
 pub mod ast_nodes;
+pub mod arena;
+pub mod cst;
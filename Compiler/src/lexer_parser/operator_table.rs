@@ -0,0 +1,47 @@
+// The operators of HULK, included both by `operators.rs` and by the build script,
+// which writes the operator levels of the LALRPOP grammar from them. It can only use
+// what the build script has too, so operators are named by their `OperatorToken`
+// variant, their level constant and their CST `SyntaxKind`.
+
+/// Precedence levels, from the loosest to the tightest.
+pub const ASSIGN: u8 = 1;
+pub const OR: u8 = 2;
+pub const AND: u8 = 3;
+pub const EQUALITY: u8 = 4;
+pub const COMPARISON: u8 = 5;
+pub const CONCAT: u8 = 6;
+pub const TERM: u8 = 7;
+pub const FACTOR: u8 = 8;
+pub const POWER: u8 = 9;
+pub const UNARY: u8 = 10;
+
+/// Calls `$define!` with every operator of HULK, from the loosest to the tightest, as
+/// `spelling => OPERATOR_TOKEN, LEVEL, fixity, CstKind;` entries, where `CstKind` is the
+/// `SyntaxKind` of the spelling. `-` is listed twice, as subtraction and as negation,
+/// and `^` and `**` both raise to a power.
+macro_rules! operator_table {
+    ($define:ident) => {
+        $define! {
+            ":=" => DASSIGN, ASSIGN, Infix(Right), ColonAssign;
+            "|" => OR, OR, Infix(Left), Pipe;
+            "&" => AND, AND, Infix(Left), Amp;
+            "==" => EQ, EQUALITY, Infix(Left), EqEq;
+            "!=" => NEQ, EQUALITY, Infix(Left), NotEq;
+            "<" => LT, COMPARISON, Infix(Left), Less;
+            "<=" => LTE, COMPARISON, Infix(Left), LessEq;
+            ">" => GT, COMPARISON, Infix(Left), Greater;
+            ">=" => GTE, COMPARISON, Infix(Left), GreaterEq;
+            "@" => CONCAT, CONCAT, Infix(Left), At;
+            "@@" => SCONCAT, CONCAT, Infix(Left), AtAt;
+            "+" => PLUS, TERM, Infix(Left), Plus;
+            "-" => MINUS, TERM, Infix(Left), Minus;
+            "*" => MUL, FACTOR, Infix(Left), Star;
+            "/" => DIV, FACTOR, Infix(Left), Slash;
+            "%" => MOD, FACTOR, Infix(Left), Percent;
+            "^" => POW, POWER, Infix(Right), Caret;
+            "**" => POW, POWER, Infix(Right), StarStar;
+            "!" => NOT, UNARY, Prefix, Bang;
+            "-" => NEG, UNARY, Prefix, Minus;
+        }
+    };
+}
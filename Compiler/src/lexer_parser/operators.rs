@@ -0,0 +1,117 @@
+//! The operators of HULK: how they are spelled, how tightly they bind and how they
+//! group.
+//!
+//! [`OPERATORS`] is the one table the front ends read them from. [`crate::lexer::Lexer`]
+//! and the CST lexer lex the spellings it lists, `OperatorToken` displays as them, the
+//! CST parser and the formatter take precedence and associativity from it, and the
+//! build script writes the operator levels of the LALRPOP grammar from
+//! `operator_table.rs`, the file it is defined in.
+//! `tests/operators.rs` walks it to check that they all agree.
+//!
+//! The `=` of bindings and the `.` of member access are punctuation, not operators, and
+//! are not in the table.
+
+use crate::tokens::{OperatorToken, Token};
+
+/// How operators of the same precedence group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    /// `a - b - c` is `(a - b) - c`.
+    Left,
+    /// `a ^ b ^ c` is `a ^ (b ^ c)`.
+    Right,
+}
+
+/// Where an operator goes with respect to its operands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fixity {
+    Prefix,
+    Infix(Associativity),
+}
+
+/// An entry of [`OPERATORS`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operator {
+    pub spelling: &'static str,
+    pub operator: OperatorToken,
+    pub fixity: Fixity,
+    /// How tightly the operator binds: the higher, the tighter.
+    pub precedence: u8,
+}
+
+include!("operator_table.rs");
+
+macro_rules! define_operators {
+    ($(
+        $spelling:literal => $operator:ident, $level:ident,
+        $fixity:ident$(($associativity:ident))?, $kind:ident;
+    )*) => {
+        /// Every operator of HULK, from the loosest to the tightest.
+        pub const OPERATORS: &[Operator] = &[$(
+            Operator {
+                spelling: $spelling,
+                operator: OperatorToken::$operator,
+                fixity: Fixity::$fixity$((Associativity::$associativity))?,
+                precedence: $level,
+            },
+        )*];
+    };
+}
+
+operator_table!(define_operators);
+
+impl Operator {
+    /// The token [`crate::lexer::Lexer`] reads the spelling as.
+    pub fn token(&self) -> Token {
+        let operator = self.operator.clone();
+        match self.operator {
+            OperatorToken::PLUS => Token::Plus(operator),
+            OperatorToken::MINUS | OperatorToken::NEG => Token::Minus(OperatorToken::MINUS),
+            OperatorToken::MUL => Token::Star(operator),
+            OperatorToken::DIV => Token::Slash(operator),
+            OperatorToken::MOD => Token::Mod(operator),
+            OperatorToken::POW => Token::PowOp(operator),
+            OperatorToken::NOT => Token::Not(operator),
+            OperatorToken::EQ => Token::Equal(operator),
+            OperatorToken::NEQ => Token::NotEqual(operator),
+            OperatorToken::GT => Token::Greater(operator),
+            OperatorToken::GTE => Token::GreaterEqual(operator),
+            OperatorToken::LT => Token::Less(operator),
+            OperatorToken::LTE => Token::LessEqual(operator),
+            OperatorToken::AND => Token::And(operator),
+            OperatorToken::OR => Token::Or(operator),
+            OperatorToken::DASSIGN => Token::DestructiveAssignOp(operator),
+            OperatorToken::CONCAT => Token::Concat(operator),
+            OperatorToken::SCONCAT => Token::SpacedConcat(operator),
+            OperatorToken::DOT => Token::DotOp(operator),
+            OperatorToken::ASSIGN => Token::Assign(operator),
+        }
+    }
+
+    /// The associativity of an infix operator, `None` for prefix ones.
+    pub fn associativity(&self) -> Option<Associativity> {
+        match self.fixity {
+            Fixity::Infix(associativity) => Some(associativity),
+            Fixity::Prefix => None,
+        }
+    }
+}
+
+/// Returns the operator with the longest spelling `text` starts with, so `**` is not
+/// read as two `*`. Where a spelling is both infix and prefix, the infix one.
+pub fn longest_prefix(text: &str) -> Option<&'static Operator> {
+    OPERATORS
+        .iter()
+        .filter(|entry| text.starts_with(entry.spelling))
+        .fold(None, |longest: Option<&Operator>, entry| match longest {
+            Some(longest) if longest.spelling.len() >= entry.spelling.len() => Some(longest),
+            _ => Some(entry),
+        })
+}
+
+/// Returns the infix entry of `operator`.
+pub fn infix_operator(operator: &OperatorToken) -> Option<&'static Operator> {
+    OPERATORS
+        .iter()
+        .find(|entry| entry.operator == *operator && entry.fixity != Fixity::Prefix)
+}
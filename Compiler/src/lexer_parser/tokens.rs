@@ -1,4 +1,8 @@
-#[derive(Debug, Clone, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+use crate::operators::OPERATORS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -50,7 +54,10 @@ pub enum Token {
     And(OperatorToken),
     Or(OperatorToken),
     DotOp(OperatorToken),
+    Assign(OperatorToken),
     DestructiveAssignOp(OperatorToken),
+    Concat(OperatorToken),
+    SpacedConcat(OperatorToken),
     
     // Delimiters
     LParen(DelimiterToken),
@@ -83,7 +90,7 @@ pub enum KeywordToken {
     FALSE,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum OperatorToken {
     PLUS,
     MINUS,
@@ -103,6 +110,29 @@ pub enum OperatorToken {
     DOT,
     ASSIGN,
     DASSIGN,
+    NEG,
+    CONCAT,
+    /// `@@`, which puts a space between the two strings.
+    SCONCAT,
+}
+
+/// Writes the spelling of the operator in [`OPERATORS`], or the `=` and `.` that are
+/// punctuation.
+impl std::fmt::Display for OperatorToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let symbol = match self {
+            OperatorToken::ASSIGN => "=",
+            OperatorToken::DOT => ".",
+            operator => {
+                OPERATORS
+                    .iter()
+                    .find(|entry| entry.operator == *operator)
+                    .expect("every other operator is in the operator table")
+                    .spelling
+            }
+        };
+        write!(f, "{}", symbol)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
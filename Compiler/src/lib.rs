@@ -0,0 +1,24 @@
+#[macro_use]
+extern crate lalrpop_util;
+
+lalrpop_mod!(#[allow(clippy::all)] pub parser, "/lexer_parser/parser.rs");
+
+pub mod lexer_parser;
+pub use hulk_common::{number_format, random};
+pub use lexer_parser::{arena, ast_nodes, cst, lexer, operators, tokens};
+
+pub mod builtins;
+pub mod bytecode;
+pub mod codegen;
+pub mod diagnostics;
+pub mod dot;
+pub mod driver;
+pub mod formatter;
+pub mod intermediate;
+pub mod interpreter;
+pub mod lsp;
+pub mod repl;
+pub mod semantic;
+pub mod symbol_table;
+pub mod types_tree;
+pub mod visitor;
@@ -0,0 +1,889 @@
+//! What the server knows about one document.
+//!
+//! An [`Analysis`] parses and checks the text of a document once per change. The
+//! semantic phase annotates every node it reaches with its static type even when it
+//! finds errors, so hover, go-to-definition and completion keep working on programs
+//! that do not check yet. Go-to-definition on variables resolves names over the
+//! [`crate::arena`] form of the AST. Positions are byte offsets into the text.
+
+use crate::arena::{self, Ast, Node, NodeId, SideTable};
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::BlockNode;
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::FunctionCallNode;
+use crate::ast_nodes::function_def::{FunctionDefNode, FunctionParams};
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::LetInNode;
+use crate::ast_nodes::literals::{
+    BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode,
+};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::{Program, Statement};
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+use crate::builtins;
+use crate::diagnostics::Diagnostic;
+use crate::driver;
+use crate::semantic::semantic_visitor::SemanticVisitor;
+use crate::tokens::Span;
+use crate::types_tree::tree_node::TypeNode;
+use crate::types_tree::type_tree::{TypeTree, NUMBER};
+use crate::visitor::ref_visitor::RefVisitor;
+
+/// What a completion inserts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Method,
+    Attribute,
+}
+
+/// A member offered after `.`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// The signature of a method or the type of an attribute.
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Type,
+    Method,
+    Attribute,
+}
+
+/// A declaration of the document, with the declarations nested in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub detail: String,
+    /// The whole declaration.
+    pub span: Span,
+    /// The name in the declaration.
+    pub selection: Span,
+    pub children: Vec<Symbol>,
+}
+
+/// The construct under the cursor.
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    /// A variable, a parameter or `self`.
+    Variable {
+        name: String,
+        type_name: Option<String>,
+    },
+    Function(String),
+    Type(String),
+    Method {
+        receiver: Option<String>,
+        name: String,
+    },
+    Attribute {
+        receiver: Option<String>,
+        name: String,
+    },
+    /// Any other expression, by its static type.
+    Expression(String),
+}
+
+/// A document, parsed and checked.
+pub struct Analysis {
+    source: String,
+    /// The AST, when the document parses.
+    program: Option<Program>,
+    /// The same AST as an arena, empty when the document does not parse, and the
+    /// binding every name in it refers to.
+    ast: Ast,
+    bindings: SideTable<NodeId>,
+    type_tree: TypeTree,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn covers(span: Span, offset: usize) -> bool {
+    span.start <= offset && offset <= span.end
+}
+
+/// Finds `name` as a whole word in `source[from..to]`.
+fn name_span(source: &str, name: &str, from: usize, to: usize) -> Option<Span> {
+    let to = to.min(source.len());
+    let text = source.get(from..to)?;
+    text.match_indices(name).find_map(|(index, _)| {
+        let start = from + index;
+        let end = start + name.len();
+        let before = source[..start].chars().next_back();
+        let after = source[end..].chars().next();
+        let whole =
+            !before.is_some_and(is_identifier_char) && !after.is_some_and(is_identifier_char);
+        whole.then(|| Span::new(start, end))
+    })
+}
+
+fn signature(name: &str, params: &[FunctionParams], return_type: &str) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .map(|param| format!("{}: {}", param.name, param.signature))
+        .collect();
+    format!("{}({}): {}", name, params.join(", "), return_type)
+}
+
+/// The expressions directly inside `expr`, in source order.
+fn subexpressions(expr: &Expression) -> Vec<&Expression> {
+    match expr {
+        Expression::Number(_)
+        | Expression::Boolean(_)
+        | Expression::Str(_)
+        | Expression::Identifier(_) => Vec::new(),
+        Expression::FunctionCall(node) => node.arguments.iter().collect(),
+        Expression::WhileLoop(node) => vec![&node.condition, &node.body],
+        Expression::ForLoop(node) => vec![&node.start, &node.end, &node.body],
+        Expression::CodeBlock(node) => node.expression_list.expressions.iter().collect(),
+        Expression::BinaryOp(node) => vec![&node.left, &node.right],
+        Expression::UnaryOp(node) => vec![&node.operand],
+        Expression::IfElse(node) => {
+            let mut children = vec![node.condition.as_ref(), node.if_expression.as_ref()];
+            for (condition, body) in node.elifs.iter() {
+                children.extend(condition.iter());
+                children.push(body);
+            }
+            children
+        }
+        Expression::LetIn(node) => {
+            let mut children: Vec<&Expression> = node
+                .assignments
+                .iter()
+                .map(|assignment| assignment.expression.as_ref())
+                .collect();
+            children.push(&node.body);
+            children
+        }
+        Expression::DestructiveAssign(node) => vec![&node.identifier, &node.expression],
+        Expression::TypeInstance(node) => node.arguments.iter().collect(),
+        Expression::TypeFunctionAccess(node) => {
+            let mut children = vec![node.object.as_ref()];
+            children.extend(node.member.arguments.iter());
+            children
+        }
+        Expression::TypePropAccess(node) => vec![&node.object],
+        Expression::Print(node) => vec![&node.expression],
+    }
+}
+
+/// The expressions of a program, including those inside declarations.
+fn top_expressions(program: &Program) -> Vec<&Expression> {
+    let mut expressions = Vec::new();
+    for statement in program.statements.iter() {
+        match statement {
+            Statement::StatementExpression(expr) => expressions.push(expr.as_ref()),
+            Statement::StatementFunctionDef(def) => expressions.push(&def.body),
+            Statement::StatementTypeDef(def) => {
+                expressions.extend(def.parent_args.iter());
+                for member in def.members.iter() {
+                    match member {
+                        TypeMember::Property(assignment) => {
+                            expressions.push(&assignment.expression)
+                        }
+                        TypeMember::Method(method) => expressions.push(&method.body),
+                    }
+                }
+            }
+        }
+    }
+    expressions
+}
+
+/// Whether an expression can be the receiver of `.`, so a larger expression ending at
+/// the same place, like `a + b` in `a + b.`, is not taken for it.
+fn is_primary(expr: &Expression) -> bool {
+    !matches!(
+        expr,
+        Expression::BinaryOp(_)
+            | Expression::UnaryOp(_)
+            | Expression::LetIn(_)
+            | Expression::IfElse(_)
+            | Expression::WhileLoop(_)
+            | Expression::ForLoop(_)
+            | Expression::DestructiveAssign(_)
+    )
+}
+
+/// Finds the type of the primary expression that starts first among those ending at
+/// `end`.
+fn receiver_type(expressions: Vec<&Expression>, end: usize) -> Option<String> {
+    let mut pending = expressions;
+    let mut best: Option<(usize, String)> = None;
+    while let Some(expr) = pending.pop() {
+        if !matches!(expr, Expression::CodeBlock(_)) && is_primary(expr) {
+            let span = expr.span();
+            if span.end == end && best.as_ref().is_none_or(|(start, _)| span.start < *start) {
+                if let Some(node_type) = expr.node_type() {
+                    best = Some((span.start, node_type.type_name.clone()));
+                }
+            }
+        }
+        pending.extend(subexpressions(expr));
+    }
+    best.map(|(_, type_name)| type_name)
+}
+
+/// Parses and checks `source`, keeping the AST and the type tree even when the
+/// semantic phase finds errors.
+fn check(source: &str) -> (Option<Program>, TypeTree, Vec<Diagnostic>) {
+    let mut program = match driver::parse(source) {
+        Ok(program) => program,
+        Err(diagnostics) => return (None, TypeTree::new(), diagnostics),
+    };
+    let mut semantic = SemanticVisitor::new();
+    let diagnostics = match semantic.check_program(&mut program) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.iter().map(Diagnostic::from).collect(),
+    };
+    (Some(program), semantic.type_tree, diagnostics)
+}
+
+impl Analysis {
+    pub fn new(source: String) -> Self {
+        let (program, type_tree, diagnostics) = check(&source);
+        let ast = program.as_ref().map(Ast::from_program).unwrap_or_default();
+        let bindings = arena::resolve(&ast);
+        Analysis {
+            source,
+            program,
+            ast,
+            bindings,
+            type_tree,
+            diagnostics,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn function(&self, name: &str) -> Option<&FunctionDefNode> {
+        self.program
+            .as_ref()?
+            .statements
+            .iter()
+            .find_map(|statement| match statement {
+                Statement::StatementFunctionDef(def) if def.name == name => Some(def.as_ref()),
+                _ => None,
+            })
+    }
+
+    fn type_def(&self, name: &str) -> Option<&TypeDefNode> {
+        self.program
+            .as_ref()?
+            .statements
+            .iter()
+            .find_map(|statement| match statement {
+                Statement::StatementTypeDef(def) if def.identifier == name => Some(def.as_ref()),
+                _ => None,
+            })
+    }
+
+    /// Returns the span of `name` inside the declaration at `span`.
+    fn selection(&self, name: &str, span: Span) -> Span {
+        name_span(&self.source, name, span.start, span.end).unwrap_or(span)
+    }
+
+    /// Finds the construct at `offset`.
+    fn target(&self, offset: usize) -> Option<Target> {
+        let program = self.program.as_ref()?;
+        let mut locator = Locator {
+            source: &self.source,
+            offset,
+            current_type: None,
+            target: None,
+        };
+        for statement in program.statements.iter() {
+            locator.visit_statement(statement);
+            if locator.target.is_some() {
+                break;
+            }
+        }
+        if let Some(target) = locator.target {
+            return Some(target);
+        }
+
+        // Names in type annotations have no node of their own.
+        let start = self.source[..offset]
+            .trim_end_matches(is_identifier_char)
+            .len();
+        let end = offset
+            + self.source[offset..]
+                .find(|c: char| !is_identifier_char(c))
+                .unwrap_or(self.source.len() - offset);
+        let word = &self.source[start..end];
+        if self.type_tree.get_type(word).is_some() {
+            Some(Target::Type(word.to_string()))
+        } else if self.function(word).is_some() {
+            Some(Target::Function(word.to_string()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the text shown when hovering `offset`.
+    pub fn hover(&self, offset: usize) -> Option<String> {
+        let text = match self.target(offset)? {
+            Target::Variable {
+                name, type_name, ..
+            } => format!("{}: {}", name, type_name.as_deref().unwrap_or("Object")),
+            Target::Function(name) => {
+                if let Some(def) = self.function(&name) {
+                    format!(
+                        "function {}",
+                        signature(&def.name, &def.params, &def.return_type)
+                    )
+                } else {
+                    let builtin = builtins::function(&name)?.signature();
+                    format!(
+                        "function {}",
+                        signature(&builtin.name, &builtin.params, &builtin.return_type)
+                    )
+                }
+            }
+            Target::Type(name) => match self.type_def(&name) {
+                Some(def) => {
+                    let mut text = format!("type {}", def.identifier);
+                    if !def.params.is_empty() {
+                        let params: Vec<String> = def
+                            .params
+                            .iter()
+                            .map(|param| format!("{}: {}", param.name, param.signature))
+                            .collect();
+                        text.push_str(&format!("({})", params.join(", ")));
+                    }
+                    if let Some(parent) = &def.parent {
+                        text.push_str(&format!(" inherits {}", parent));
+                    }
+                    text
+                }
+                None => format!("type {}", name),
+            },
+            Target::Method { receiver, name } => {
+                let (owner, method) = self.type_tree.find_method(receiver.as_deref()?, &name)?;
+                format!(
+                    "{}.{}",
+                    owner,
+                    signature(&method.name, &method.params, &method.return_type)
+                )
+            }
+            Target::Attribute { receiver, name } => {
+                let (owner, type_name) = self.attribute(receiver.as_deref()?, &name)?;
+                format!("{}.{}: {}", owner, name, type_name)
+            }
+            Target::Expression(type_name) => type_name,
+        };
+        Some(format!("```hulk\n{}\n```", text))
+    }
+
+    /// Returns the type declaring attribute `name` of `type_name` and its type.
+    fn attribute(&self, type_name: &str, name: &str) -> Option<(String, String)> {
+        self.type_tree
+            .ancestors(type_name)
+            .into_iter()
+            .find_map(|owner| {
+                let attribute = self
+                    .type_tree
+                    .get_type(&owner)?
+                    .get_attribute(name)?
+                    .clone();
+                Some((owner, attribute))
+            })
+    }
+
+    /// Finds the member `name` of `type_name` or of its closest ancestor declaring it.
+    fn member(&self, type_name: &str, name: &str, method: bool) -> Option<Span> {
+        self.type_tree
+            .ancestors(type_name)
+            .into_iter()
+            .find_map(|owner| {
+                self.type_def(&owner)?
+                    .members
+                    .iter()
+                    .find_map(|member| match member {
+                        TypeMember::Method(def) if method && def.name == name => {
+                            Some(self.selection(name, def.span))
+                        }
+                        TypeMember::Property(assignment)
+                            if !method && assignment.identifier == name =>
+                        {
+                            Some(self.selection(name, assignment.span))
+                        }
+                        _ => None,
+                    })
+            })
+    }
+
+    /// Returns the span of the name that binds the variable at `offset`, found by
+    /// resolving names over the arena. On a binding itself, its own name.
+    fn binding(&self, offset: usize) -> Option<Span> {
+        let name = |id: NodeId| -> Option<Span> {
+            let span = self.ast.span(id);
+            match &self.ast[id] {
+                Node::Identifier(_) | Node::Param { .. } | Node::Type { .. } => Some(span),
+                Node::Binding { name, .. } => Some(Span::new(span.start, span.start + name.len())),
+                Node::For { variable, .. } => {
+                    name_span(&self.source, variable, span.start, span.end)
+                }
+                _ => None,
+            }
+        };
+        let (id, _) = self
+            .ast
+            .iter()
+            .filter(|(_, node)| !matches!(node, Node::Type { .. }))
+            .filter_map(|(id, _)| Some((id, name(id)?)))
+            .filter(|&(_, span)| covers(span, offset))
+            .min_by_key(|&(_, span)| span.end - span.start)?;
+        match &self.ast[id] {
+            Node::Identifier(_) => name(*self.bindings.get(id)?),
+            _ => name(id),
+        }
+    }
+
+    /// Returns the span of the name that declares what is at `offset`.
+    pub fn definition(&self, offset: usize) -> Option<Span> {
+        match self.target(offset)? {
+            Target::Variable { .. } => self.binding(offset),
+            Target::Function(name) => {
+                let span = self.function(&name)?.span;
+                Some(self.selection(&name, span))
+            }
+            Target::Type(name) => {
+                let span = self.type_def(&name)?.span;
+                Some(self.selection(&name, span))
+            }
+            Target::Method { receiver, name } => self.member(&receiver?, &name, true),
+            Target::Attribute { receiver, name } => self.member(&receiver?, &name, false),
+            Target::Expression(_) => None,
+        }
+    }
+
+    /// Lists the members of the receiver when `offset` follows a `.` and, maybe, the
+    /// start of a name.
+    ///
+    /// The document does not parse while a member is being typed, so the `.` and the
+    /// start of the name are blanked out and the receiver is typed in the result.
+    pub fn completions(&self, offset: usize) -> Vec<Completion> {
+        let offset = offset.min(self.source.len());
+        let before = &self.source[..offset];
+        let name_start = before.trim_end_matches(is_identifier_char).len();
+        if !before[..name_start].ends_with('.') {
+            return Vec::new();
+        }
+        let dot = name_start - 1;
+        let mut patched = self.source.clone();
+        patched.replace_range(dot..offset, &" ".repeat(offset - dot));
+        let (Some(program), type_tree, _) = check(&patched) else {
+            return Vec::new();
+        };
+        let end = patched[..dot].trim_end().len();
+        let Some(receiver) = receiver_type(top_expressions(&program), end) else {
+            return Vec::new();
+        };
+
+        let mut completions: Vec<Completion> = Vec::new();
+        for owner in type_tree.ancestors(&receiver) {
+            let Some(node) = type_tree.get_type(&owner) else {
+                continue;
+            };
+            let members = node
+                .attributes
+                .iter()
+                .map(|(name, type_name)| Completion {
+                    label: name.clone(),
+                    kind: CompletionKind::Attribute,
+                    detail: type_name.clone(),
+                })
+                .chain(node.methods.iter().map(|method| Completion {
+                    label: method.name.clone(),
+                    kind: CompletionKind::Method,
+                    detail: signature(&method.name, &method.params, &method.return_type),
+                }));
+            for member in members {
+                if !completions.iter().any(|known| known.label == member.label) {
+                    completions.push(member);
+                }
+            }
+        }
+        completions
+    }
+
+    /// Lists the functions and types of the document, with the members of the types.
+    pub fn symbols(&self) -> Vec<Symbol> {
+        let Some(program) = &self.program else {
+            return Vec::new();
+        };
+        let function = |def: &FunctionDefNode, kind| Symbol {
+            name: def.name.clone(),
+            kind,
+            detail: signature(&def.name, &def.params, &def.return_type),
+            span: def.span,
+            selection: self.selection(&def.name, def.span),
+            children: Vec::new(),
+        };
+        program
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::StatementFunctionDef(def) => Some(function(def, SymbolKind::Function)),
+                Statement::StatementTypeDef(def) => Some(Symbol {
+                    name: def.identifier.clone(),
+                    kind: SymbolKind::Type,
+                    detail: def.parent.clone().unwrap_or_default(),
+                    span: def.span,
+                    selection: self.selection(&def.identifier, def.span),
+                    children: def
+                        .members
+                        .iter()
+                        .map(|member| match member {
+                            TypeMember::Method(method) => function(method, SymbolKind::Method),
+                            TypeMember::Property(assignment) => Symbol {
+                                name: assignment.identifier.clone(),
+                                kind: SymbolKind::Attribute,
+                                detail: assignment
+                                    .node_type
+                                    .as_ref()
+                                    .map(|node| node.type_name.clone())
+                                    .unwrap_or_default(),
+                                span: assignment.span,
+                                selection: self.selection(&assignment.identifier, assignment.span),
+                                children: Vec::new(),
+                            },
+                        })
+                        .collect(),
+                }),
+                Statement::StatementExpression(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// A visitor that finds the innermost construct at an offset.
+struct Locator<'a> {
+    source: &'a str,
+    offset: usize,
+    /// The type whose members are being visited.
+    current_type: Option<String>,
+    target: Option<Target>,
+}
+
+impl Locator<'_> {
+    fn name_covers(&self, name: &str, from: usize, to: usize) -> bool {
+        name_span(self.source, name, from, to).is_some_and(|span| covers(span, self.offset))
+    }
+
+    /// Takes `node_type` for the target when no child was closer to the offset.
+    fn fall_back(&mut self, span: Span, node_type: &Option<TypeNode>) {
+        if self.target.is_none() && covers(span, self.offset) {
+            if let Some(node_type) = node_type {
+                self.target = Some(Target::Expression(node_type.type_name.clone()));
+            }
+        }
+    }
+
+    /// Visits `expr` unless the target is already found.
+    fn visit(&mut self, expr: &Expression) {
+        if self.target.is_none() {
+            self.visit_expression(expr);
+        }
+    }
+
+    fn bind(&mut self, param: &FunctionParams) {
+        if covers(param.span, self.offset) {
+            self.target = Some(Target::Variable {
+                name: param.name.clone(),
+                type_name: Some(param.signature.clone()),
+            });
+        }
+    }
+}
+
+impl<'ast> RefVisitor<'ast> for Locator<'_> {
+    fn visit_function_def(&mut self, node: &'ast FunctionDefNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        if self.name_covers(&node.name, node.span.start, node.span.end) {
+            self.target = Some(match &self.current_type {
+                Some(type_name) => Target::Method {
+                    receiver: Some(type_name.clone()),
+                    name: node.name.clone(),
+                },
+                None => Target::Function(node.name.clone()),
+            });
+            return;
+        }
+        for param in node.params.iter() {
+            self.bind(param);
+        }
+        self.visit(&node.body);
+    }
+
+    fn visit_literal_number(&mut self, node: &'ast NumberLiteralNode) {
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_literal_boolean(&mut self, node: &'ast BooleanLiteralNode) {
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_literal_string(&mut self, node: &'ast StringLiteralNode) {
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_identifier(&mut self, node: &'ast IdentifierNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        let type_name = node.node_type.as_ref().map(|node| node.type_name.clone());
+        self.target = Some(Target::Variable {
+            name: node.value.clone(),
+            type_name,
+        });
+    }
+
+    fn visit_function_call(&mut self, node: &'ast FunctionCallNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        let name = Span::new(node.span.start, node.span.start + node.function_name.len());
+        if covers(name, self.offset) {
+            self.target = Some(Target::Function(node.function_name.clone()));
+            return;
+        }
+        for argument in node.arguments.iter() {
+            self.visit(argument);
+        }
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_while_loop(&mut self, node: &'ast WhileNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        self.visit(&node.condition);
+        self.visit(&node.body);
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_for_loop(&mut self, node: &'ast ForNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        let variable = name_span(self.source, &node.variable, node.span.start, node.span.end)
+            .unwrap_or(node.span);
+        if covers(variable, self.offset) {
+            self.target = Some(Target::Variable {
+                name: node.variable.clone(),
+                type_name: Some(NUMBER.to_string()),
+            });
+            return;
+        }
+        self.visit(&node.start);
+        self.visit(&node.end);
+        self.visit(&node.body);
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_code_block(&mut self, node: &'ast BlockNode) {
+        for expression in node.expression_list.expressions.iter() {
+            self.visit(expression);
+        }
+    }
+
+    fn visit_binary_op(&mut self, node: &'ast BinaryOpNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        self.visit(&node.left);
+        self.visit(&node.right);
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_unary_op(&mut self, node: &'ast UnaryOpNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        self.visit(&node.operand);
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_if_else(&mut self, node: &'ast IfElseNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        self.visit(&node.condition);
+        self.visit(&node.if_expression);
+        for (condition, body) in node.elifs.iter() {
+            if let Some(condition) = condition {
+                self.visit(condition);
+            }
+            self.visit(body);
+        }
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_let_in(&mut self, node: &'ast LetInNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        for assignment in node.assignments.iter() {
+            let name = Span::new(
+                assignment.span.start,
+                assignment.span.start + assignment.identifier.len(),
+            );
+            let type_name = assignment
+                .node_type
+                .as_ref()
+                .map(|node| node.type_name.clone());
+            if covers(name, self.offset) {
+                self.target = Some(Target::Variable {
+                    name: assignment.identifier.clone(),
+                    type_name,
+                });
+                break;
+            }
+            self.visit(&assignment.expression);
+        }
+        self.visit(&node.body);
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_destructive_assign(&mut self, node: &'ast DestructiveAssignNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        self.visit(&node.identifier);
+        self.visit(&node.expression);
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_type_def(&mut self, node: &'ast TypeDefNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        if self.name_covers(&node.identifier, node.span.start, node.span.end) {
+            self.target = Some(Target::Type(node.identifier.clone()));
+            return;
+        }
+        if let Some(parent) = &node.parent {
+            let body = self.source[node.span.start..node.span.end]
+                .find('{')
+                .map_or(node.span.end, |index| node.span.start + index);
+            let after_name = node.span.start + node.identifier.len();
+            if self.name_covers(parent, after_name, body) {
+                self.target = Some(Target::Type(parent.clone()));
+                return;
+            }
+        }
+
+        self.current_type = Some(node.identifier.clone());
+        for param in node.params.iter() {
+            self.bind(param);
+        }
+        for argument in node.parent_args.iter() {
+            self.visit(argument);
+        }
+        for member in node.members.iter() {
+            match member {
+                TypeMember::Property(assignment) => {
+                    let name = Span::new(
+                        assignment.span.start,
+                        assignment.span.start + assignment.identifier.len(),
+                    );
+                    if covers(name, self.offset) {
+                        self.target = Some(Target::Attribute {
+                            receiver: Some(node.identifier.clone()),
+                            name: assignment.identifier.clone(),
+                        });
+                    }
+                    self.visit(&assignment.expression);
+                }
+                TypeMember::Method(method) => {
+                    if self.target.is_none() {
+                        self.visit_function_def(method);
+                    }
+                }
+            }
+        }
+        self.current_type = None;
+    }
+
+    fn visit_type_instance(&mut self, node: &'ast TypeInstanceNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        if self.name_covers(&node.type_name, node.span.start, node.span.end) {
+            self.target = Some(Target::Type(node.type_name.clone()));
+            return;
+        }
+        for argument in node.arguments.iter() {
+            self.visit(argument);
+        }
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_type_function_access(&mut self, node: &'ast TypeFunctionAccessNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        self.visit(&node.object);
+        let receiver_end = match node.object.as_ref() {
+            Expression::CodeBlock(_) => node.span.start,
+            object => object.span().end,
+        };
+        if self.target.is_none()
+            && self.name_covers(&node.member.function_name, receiver_end, node.span.end)
+        {
+            self.target = Some(Target::Method {
+                receiver: node.object.node_type().map(|node| node.type_name.clone()),
+                name: node.member.function_name.clone(),
+            });
+        }
+        for argument in node.member.arguments.iter() {
+            self.visit(argument);
+        }
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_type_prop_access(&mut self, node: &'ast TypePropAccessNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        self.visit(&node.object);
+        let receiver_end = match node.object.as_ref() {
+            Expression::CodeBlock(_) => node.span.start,
+            object => object.span().end,
+        };
+        if self.target.is_none() && self.name_covers(&node.member, receiver_end, node.span.end) {
+            self.target = Some(Target::Attribute {
+                receiver: node.object.node_type().map(|node| node.type_name.clone()),
+                name: node.member.to_string(),
+            });
+        }
+        self.fall_back(node.span, &node.node_type);
+    }
+
+    fn visit_print(&mut self, node: &'ast PrintNode) {
+        if !covers(node.span, self.offset) {
+            return;
+        }
+        self.visit(&node.expression);
+        self.fall_back(node.span, &node.node_type);
+    }
+}
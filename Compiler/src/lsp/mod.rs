@@ -0,0 +1,10 @@
+//! A Language Server Protocol server for HULK.
+//!
+//! [`protocol`] frames JSON-RPC messages and converts positions, [`analysis`] answers
+//! questions about one document from its checked AST, and [`server`] ties them to the
+//! requests and notifications of the protocol. The `hulk-lsp` binary runs the server
+//! over standard input and output.
+
+pub mod analysis;
+pub mod protocol;
+pub mod server;
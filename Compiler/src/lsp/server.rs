@@ -0,0 +1,215 @@
+//! The requests and notifications the server answers.
+//!
+//! Documents are synchronized in full: every change sends the whole text, which is
+//! analyzed again and answered with its diagnostics. Requests the server does not know
+//! get the `MethodNotFound` error, and unknown notifications are ignored.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::diagnostics::Diagnostic;
+use crate::tokens::Span;
+
+use super::analysis::{Analysis, CompletionKind, Symbol, SymbolKind};
+use super::protocol::{read_message, write_message, LineIndex};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_REQUEST: i64 = -32600;
+
+/// The state of a session: the open documents by URI.
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Analysis>,
+    shutting_down: bool,
+}
+
+fn symbol_kind(kind: SymbolKind) -> u32 {
+    match kind {
+        SymbolKind::Function => 12,
+        SymbolKind::Type => 5,
+        SymbolKind::Method => 6,
+        SymbolKind::Attribute => 8,
+    }
+}
+
+fn completion_kind(kind: CompletionKind) -> u32 {
+    match kind {
+        CompletionKind::Method => 2,
+        CompletionKind::Attribute => 5,
+    }
+}
+
+fn diagnostic(index: &LineIndex, diagnostic: &Diagnostic) -> Value {
+    let range = index.range(diagnostic.span.unwrap_or(Span::new(0, 0)));
+    json!({
+        "range": range,
+        "severity": 1,
+        "source": "hulk",
+        "code": diagnostic.phase.to_string(),
+        "message": diagnostic.message,
+    })
+}
+
+fn symbol(index: &LineIndex, symbol: &Symbol) -> Value {
+    json!({
+        "name": symbol.name,
+        "kind": symbol_kind(symbol.kind),
+        "detail": symbol.detail,
+        "range": index.range(symbol.span),
+        "selectionRange": index.range(symbol.selection),
+        "children": symbol
+            .children
+            .iter()
+            .map(|child| self::symbol(index, child))
+            .collect::<Vec<_>>(),
+    })
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Server::default()
+    }
+
+    /// Answers the messages of `reader` on `writer` until the client sends `exit` or
+    /// the input ends.
+    pub fn run<R: BufRead, W: Write>(&mut self, mut reader: R, mut writer: W) -> io::Result<()> {
+        while let Some(message) = read_message(&mut reader)? {
+            let method = message["method"].as_str().unwrap_or_default().to_string();
+            if method == "exit" {
+                break;
+            }
+            let params = &message["params"];
+            match message.get("id") {
+                Some(id) => {
+                    let response = match self.request(&method, params) {
+                        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                        Err((code, text)) => json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "error": { "code": code, "message": text },
+                        }),
+                    };
+                    write_message(&mut writer, &response)?;
+                }
+                None => {
+                    if let Some(notification) = self.notification(&method, params) {
+                        write_message(&mut writer, &notification)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        if self.shutting_down {
+            return Err((INVALID_REQUEST, "the server is shutting down".to_string()));
+        }
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": { "triggerCharacters": ["."] },
+                    "documentSymbolProvider": true,
+                },
+                "serverInfo": { "name": "hulk-lsp", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.shutting_down = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => {
+                let Some(analysis) = self.documents.get(uri) else {
+                    return Ok(Value::Null);
+                };
+                let offset = LineIndex::new(analysis.source()).offset(&params["position"]);
+                Ok(analysis.hover(offset).map_or(
+                    Value::Null,
+                    |text| json!({ "contents": { "kind": "markdown", "value": text } }),
+                ))
+            }
+            "textDocument/definition" => {
+                let Some(analysis) = self.documents.get(uri) else {
+                    return Ok(Value::Null);
+                };
+                let offset = LineIndex::new(analysis.source()).offset(&params["position"]);
+                Ok(analysis.definition(offset).map_or(Value::Null, |span| {
+                    let index = LineIndex::new(analysis.source());
+                    json!({ "uri": uri, "range": index.range(span) })
+                }))
+            }
+            "textDocument/completion" => {
+                let Some(analysis) = self.documents.get(uri) else {
+                    return Ok(json!([]));
+                };
+                let offset = LineIndex::new(analysis.source()).offset(&params["position"]);
+                let items: Vec<Value> = analysis
+                    .completions(offset)
+                    .into_iter()
+                    .map(|completion| {
+                        json!({
+                            "label": completion.label,
+                            "kind": completion_kind(completion.kind),
+                            "detail": completion.detail,
+                        })
+                    })
+                    .collect();
+                Ok(Value::Array(items))
+            }
+            "textDocument/documentSymbol" => {
+                let Some(analysis) = self.documents.get(uri) else {
+                    return Ok(json!([]));
+                };
+                let index = LineIndex::new(analysis.source());
+                let symbols: Vec<Value> = analysis
+                    .symbols()
+                    .iter()
+                    .map(|symbol| self::symbol(&index, symbol))
+                    .collect();
+                Ok(Value::Array(symbols))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+        }
+    }
+
+    /// Handles a notification, returning the notification to send back if any.
+    fn notification(&mut self, method: &str, params: &Value) -> Option<Value> {
+        let uri = params["textDocument"]["uri"].as_str()?.to_string();
+        let text = match method {
+            "textDocument/didOpen" => params["textDocument"]["text"].as_str()?.to_string(),
+            "textDocument/didChange" => params["contentChanges"].as_array()?.last()?["text"]
+                .as_str()?
+                .to_string(),
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return Some(json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": { "uri": uri, "diagnostics": [] },
+                }));
+            }
+            _ => return None,
+        };
+
+        let analysis = Analysis::new(text);
+        let index = LineIndex::new(analysis.source());
+        let diagnostics: Vec<Value> = analysis
+            .diagnostics
+            .iter()
+            .map(|item| diagnostic(&index, item))
+            .collect();
+        let version = &params["textDocument"]["version"];
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "version": version, "diagnostics": diagnostics },
+        });
+        self.documents.insert(uri, analysis);
+        Some(notification)
+    }
+}
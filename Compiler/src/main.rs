@@ -1,33 +1,655 @@
-mod semantic;
-mod intermediate;
-mod codegen;
-mod symbol_table;
-mod lexer;
-mod parser;
-
-use lexer::lexer::{Lexer};
-use parser::parser::Parser;
-
-fn main() {
-    println!("Compilador iniciado");
-
-    let input = r#"
-        let x = 42;
-        if x >= 10 {
-            print("mayor o igual a 10");
-        } else {
-            print("menor que 10");
-        }
-        // Esto es un comentario
-    "#;
-
-    let mut lexer = Lexer::new(input);
-
-    // Mientras haya tokens, imprime el tipo, lexema, línea y columna de cada uno
-    while let Some(token) = lexer.next_token() {
-        let mut parser = Parser::new(0);
-        let ast = parser.parse(&mut lexer);
-        println!("{:#?}", ast);
-        break; // Salimos del bucle porque el parser consume los tokens
+//! `hulk`, the command-line driver of the compiler.
+//!
+//! Every command reads a program from the file it is given, or from standard input
+//! when the file is `-` or missing, and runs the phases it needs. Errors in the
+//! program are written to standard error in the format `--error-format` selects.
+//!
+//! The exit code is 0 on success, 1 when the program has errors and 2 when the command
+//! line is wrong or a file or tool fails.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+
+use compilador::ast_nodes::program::Program;
+use compilador::bytecode::chunk::Chunk;
+use compilador::bytecode::compiler::BytecodeCompiler;
+use compilador::bytecode::vm::Vm;
+use compilador::codegen::c::CGenerator;
+use compilador::codegen::linking::{c_command, clang_command, link_command, write_runtime};
+use compilador::codegen::llvm::LlvmGenerator;
+use compilador::codegen::x86::X86Generator;
+use compilador::diagnostics::{position, Diagnostic, ErrorFormat};
+use compilador::dot::ast::AstGraph;
+use compilador::dot::cfg::draw_module;
+use compilador::dot::scopes::draw_scopes;
+use compilador::driver::{self, CheckedProgram};
+use compilador::formatter::{self, DEFAULT_WIDTH};
+use compilador::intermediate::ir::Module;
+use compilador::interpreter::tree_walker;
+use compilador::random::DEFAULT_SEED;
+use compilador::repl::Repl;
+use compilador::visitor::sexp_printer::SexpPrinter;
+use compilador::visitor::tree_printer::TreePrinter;
+
+const USAGE: &str = "\
+usage: hulk <command> [options] [file]
+
+Reads the program from `file`, or from standard input when it is `-` or missing.
+
+commands:
+  lex                   print the tokens of the program
+  parse                 print the syntax tree of the program
+  check                 report the errors of the program
+  emit --stage=STAGE    print the program at STAGE: ast, typed-ast, ir, bytecode,
+                        c, llvm or asm, or draw it as a Graphviz graph with
+                        dot-ast, dot-scopes or dot-cfg
+  build [-o FILE]       compile the program into an executable
+  run                   run the program, or the bytecode in a `.hbc` file
+  fmt [--check]         print the program formatted, or with `--check` only
+                        report whether it already is
+  repl                  start an interactive session
+
+options:
+  --error-format=human|json   how errors are written (default: human)
+  --format=tree|json|sexp     how `parse` and the ast stages write the syntax
+                              tree (default: tree)
+  --seed=N                    seed of the numbers `rand()` returns (default: 0)
+  --backend=x86|llvm|c        backend `build` uses (default: x86)
+  --engine=interpreter|vm     engine `run` uses (default: interpreter)
+  --width=N                   line width `fmt` aims for (default: 80)
+  --report                    write what the optimization passes did to standard
+                              error, for `emit` and `build`
+  -o FILE                     executable `build` writes (default: the file name
+                              without extension, or `a.out`), or `.hbc` file
+                              `emit --stage=bytecode` writes instead of a listing
+  -h, --help                  print this message";
+
+/// Exit code for a program with errors.
+const PROGRAM_ERROR: u8 = 1;
+/// Exit code for a wrong command line or a failed file or tool.
+const USAGE_ERROR: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Ast,
+    TypedAst,
+    Ir,
+    Bytecode,
+    C,
+    Llvm,
+    Asm,
+    /// The typed AST as a Graphviz graph.
+    DotAst,
+    /// The scopes of the semantic phase as a Graphviz graph.
+    DotScopes,
+    /// The control-flow graph of every IR function as a Graphviz graph.
+    DotCfg,
+}
+
+/// How the syntax tree is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AstFormat {
+    /// One indented line per node, with its span.
+    Tree,
+    /// JSON that `Program::from_json` reads back.
+    Json,
+    /// One S-expression per statement, without spans.
+    Sexp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    X86,
+    Llvm,
+    C,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Engine {
+    Interpreter,
+    Vm,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Subcommand {
+    Lex,
+    Parse,
+    Check,
+    Emit(Stage),
+    Build,
+    Run,
+    Fmt,
+    Repl,
+}
+
+/// What the command line asks for.
+struct Options {
+    command: Subcommand,
+    input: Option<String>,
+    output: Option<PathBuf>,
+    error_format: ErrorFormat,
+    ast_format: AstFormat,
+    seed: u64,
+    width: usize,
+    /// Whether `fmt` only checks the formatting.
+    check: bool,
+    /// Whether `emit` and `build` write the reports of the optimization passes.
+    report: bool,
+    backend: Backend,
+    engine: Engine,
+}
+
+/// The program being compiled.
+struct Input {
+    /// The path errors are reported with.
+    name: String,
+    source: String,
+    /// The contents of a `.hbc` file, which has no source.
+    bytecode: Option<Vec<u8>>,
+}
+
+/// Why the driver stopped.
+enum Failure {
+    /// The program has errors.
+    Program(Vec<Diagnostic>),
+    /// `fmt --check` found the program is not formatted.
+    Unformatted,
+    /// The command line is wrong, or a file or tool failed.
+    Usage(String),
+}
+
+impl From<Vec<Diagnostic>> for Failure {
+    fn from(diagnostics: Vec<Diagnostic>) -> Self {
+        Failure::Program(diagnostics)
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(error: io::Error) -> Self {
+        Failure::Usage(error.to_string())
+    }
+}
+
+fn parse_value<T>(option: &str, value: &str, choices: &[(&str, T)]) -> Result<T, String>
+where
+    T: Copy,
+{
+    choices
+        .iter()
+        .find(|(name, _)| *name == value)
+        .map(|(_, choice)| *choice)
+        .ok_or_else(|| {
+            let names: Vec<&str> = choices.iter().map(|(name, _)| *name).collect();
+            format!(
+                "invalid value `{}` for `{}`, expected one of {}",
+                value,
+                option,
+                names.join(", ")
+            )
+        })
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let (command, rest) = args.split_first().ok_or("missing command")?;
+    let mut stage = None;
+    let mut ast_format = None;
+    let mut options = Options {
+        command: Subcommand::Lex,
+        input: None,
+        output: None,
+        error_format: ErrorFormat::Human,
+        ast_format: AstFormat::Tree,
+        seed: DEFAULT_SEED,
+        width: DEFAULT_WIDTH,
+        check: false,
+        report: false,
+        backend: Backend::X86,
+        engine: Engine::Interpreter,
+    };
+
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        let (option, value) = arg.split_once('=').unwrap_or((arg, ""));
+        match option {
+            "--stage" => {
+                stage = Some(parse_value(
+                    option,
+                    value,
+                    &[
+                        ("ast", Stage::Ast),
+                        ("typed-ast", Stage::TypedAst),
+                        ("ir", Stage::Ir),
+                        ("bytecode", Stage::Bytecode),
+                        ("c", Stage::C),
+                        ("llvm", Stage::Llvm),
+                        ("asm", Stage::Asm),
+                        ("dot-ast", Stage::DotAst),
+                        ("dot-scopes", Stage::DotScopes),
+                        ("dot-cfg", Stage::DotCfg),
+                    ],
+                )?)
+            }
+            "--error-format" => {
+                options.error_format = parse_value(
+                    option,
+                    value,
+                    &[("human", ErrorFormat::Human), ("json", ErrorFormat::Json)],
+                )?
+            }
+            "--format" => {
+                ast_format = Some(parse_value(
+                    option,
+                    value,
+                    &[
+                        ("tree", AstFormat::Tree),
+                        ("json", AstFormat::Json),
+                        ("sexp", AstFormat::Sexp),
+                    ],
+                )?)
+            }
+            "--backend" => {
+                options.backend = parse_value(
+                    option,
+                    value,
+                    &[
+                        ("x86", Backend::X86),
+                        ("llvm", Backend::Llvm),
+                        ("c", Backend::C),
+                    ],
+                )?
+            }
+            "--engine" => {
+                options.engine = parse_value(
+                    option,
+                    value,
+                    &[("interpreter", Engine::Interpreter), ("vm", Engine::Vm)],
+                )?
+            }
+            "--seed" => {
+                options.seed = value
+                    .parse()
+                    .map_err(|_| format!("invalid seed `{}`", value))?
+            }
+            "--width" => {
+                options.width = value
+                    .parse()
+                    .ok()
+                    .filter(|width| *width > 0)
+                    .ok_or_else(|| format!("invalid width `{}`", value))?
+            }
+            "--check" => options.check = true,
+            "--report" => options.report = true,
+            "-o" => {
+                let path = rest.next().ok_or("`-o` needs a file name")?;
+                options.output = Some(PathBuf::from(path));
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option `{}`", arg));
+            }
+            _ if options.input.is_some() => {
+                return Err(format!("unexpected argument `{}`", arg));
+            }
+            _ => options.input = Some(arg.clone()),
+        }
+    }
+
+    options.command = match command.as_str() {
+        "lex" => Subcommand::Lex,
+        "parse" => Subcommand::Parse,
+        "check" => Subcommand::Check,
+        "emit" => Subcommand::Emit(stage.ok_or("`emit` needs `--stage`")?),
+        "build" => Subcommand::Build,
+        "run" => Subcommand::Run,
+        "fmt" => Subcommand::Fmt,
+        "repl" => Subcommand::Repl,
+        _ => return Err(format!("unknown command `{}`", command)),
+    };
+    if let Some(format) = ast_format {
+        if !matches!(
+            options.command,
+            Subcommand::Parse | Subcommand::Emit(Stage::Ast | Stage::TypedAst)
+        ) {
+            return Err("`--format` only applies to `parse` and the ast stages".to_string());
+        }
+        options.ast_format = format;
+    }
+    if options.output.is_some()
+        && !matches!(
+            options.command,
+            Subcommand::Build | Subcommand::Emit(Stage::Bytecode)
+        )
+    {
+        return Err("`-o` only applies to `build` and `emit --stage=bytecode`".to_string());
+    }
+    if options.report && !matches!(options.command, Subcommand::Emit(_) | Subcommand::Build) {
+        return Err("`--report` only applies to `emit` and `build`".to_string());
+    }
+    Ok(options)
+}
+
+fn read_input(input: Option<&str>) -> Result<Input, Failure> {
+    match input {
+        None | Some("-") => {
+            let mut source = String::new();
+            io::stdin().read_to_string(&mut source)?;
+            Ok(Input {
+                name: "<stdin>".to_string(),
+                source,
+                bytecode: None,
+            })
+        }
+        Some(path) if path.ends_with(".hbc") => {
+            let bytecode = fs::read(path)
+                .map_err(|error| Failure::Usage(format!("cannot read `{}`: {}", path, error)))?;
+            Ok(Input {
+                name: path.to_string(),
+                source: String::new(),
+                bytecode: Some(bytecode),
+            })
+        }
+        Some(path) => {
+            let source = fs::read_to_string(path)
+                .map_err(|error| Failure::Usage(format!("cannot read `{}`: {}", path, error)))?;
+            Ok(Input {
+                name: path.to_string(),
+                source,
+                bytecode: None,
+            })
+        }
+    }
+}
+
+fn lex(input: &Input) -> Result<(), Failure> {
+    let (tokens, errors) = driver::tokenize(&input.source);
+    for (token, span) in tokens {
+        let (line, column) = position(&input.source, span.start);
+        let kind = format!("{:?}", token);
+        let kind = kind.split('(').next().unwrap_or_default();
+        println!(
+            "{}:{} {} {}",
+            line,
+            column,
+            kind,
+            &input.source[span.start..span.end]
+        );
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Failure::Program(errors))
+    }
+}
+
+/// Writes `program` in `format`, with the static types when `typed`.
+fn print_ast(program: &mut Program, format: AstFormat, typed: bool) {
+    match format {
+        AstFormat::Tree => print!("{}", TreePrinter::new(typed).print_program(program)),
+        AstFormat::Json => println!("{}", program.to_json()),
+        AstFormat::Sexp => print!("{}", SexpPrinter::new(typed).print_program(program)),
+    }
+}
+
+/// Analyzes `source` for the backends, writing the reports of the passes if asked to.
+fn analyze(source: &str, options: &Options) -> Result<CheckedProgram, Failure> {
+    let checked = driver::analyze(source)?;
+    if options.report {
+        for report in checked.reports.iter() {
+            eprintln!("{}", report);
+        }
+    }
+    Ok(checked)
+}
+
+/// Analyzes and lowers `source` to IR, writing the reports of the passes if asked to.
+fn lower(source: &str, options: &Options) -> Result<Module, Failure> {
+    let mut checked = driver::analyze(source)?;
+    let module = driver::lower(&mut checked);
+    if options.report {
+        for report in checked.reports.iter() {
+            eprintln!("{}", report);
+        }
+    }
+    Ok(module)
+}
+
+fn emit(input: &Input, stage: Stage, options: &Options) -> Result<(), Failure> {
+    let source = &input.source;
+    let seed = options.seed;
+    match stage {
+        Stage::Ast => print_ast(&mut driver::parse(source)?, options.ast_format, false),
+        Stage::TypedAst => {
+            let mut checked = driver::check(source)?;
+            print_ast(&mut checked.program, options.ast_format, true);
+        }
+        Stage::Ir => println!("{}", lower(source, options)?),
+        Stage::Bytecode => {
+            let CheckedProgram {
+                mut program,
+                type_tree,
+                ..
+            } = analyze(source, options)?;
+            let chunk = BytecodeCompiler::new(&type_tree).compile_program(&mut program);
+            match &options.output {
+                Some(path) => fs::write(path, chunk.to_bytes())?,
+                None => println!("{}", chunk),
+            }
+        }
+        Stage::C => print!("{}", generate_c(input, options)?),
+        Stage::Llvm => {
+            let module = lower(source, options)?;
+            print!("{}", LlvmGenerator::new(&module).with_seed(seed).generate());
+        }
+        Stage::Asm => {
+            let module = lower(source, options)?;
+            print!("{}", X86Generator::new(&module).with_seed(seed).generate());
+        }
+        Stage::DotAst => {
+            let mut checked = driver::check(source)?;
+            print!("{}", AstGraph::new().draw_program(&mut checked.program));
+        }
+        Stage::DotScopes => print!("{}", draw_scopes(&driver::check(source)?.symbol_table)),
+        Stage::DotCfg => print!("{}", draw_module(&lower(source, options)?)),
+    }
+    Ok(())
+}
+
+fn generate_c(input: &Input, options: &Options) -> Result<String, Failure> {
+    let CheckedProgram {
+        mut program,
+        type_tree,
+        ..
+    } = analyze(&input.source, options)?;
+    Ok(CGenerator::new(&type_tree, &input.name, &input.source)
+        .with_seed(options.seed)
+        .generate_program(&mut program))
+}
+
+/// Runs `command` and fails with its name if it does not succeed.
+fn execute(command: &mut Command) -> Result<(), Failure> {
+    let name = command.get_program().to_string_lossy().into_owned();
+    let status = command
+        .status()
+        .map_err(|error| Failure::Usage(format!("cannot run `{}`: {}", name, error)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Failure::Usage(format!("`{}` failed with {}", name, status)))
+    }
+}
+
+fn build(input: &Input, options: &Options) -> Result<(), Failure> {
+    let executable = match (&options.output, options.input.as_deref()) {
+        (Some(output), _) => output.clone(),
+        (None, Some(path)) if path != "-" => Path::new(path).with_extension(""),
+        _ => PathBuf::from("a.out"),
+    };
+    // The program is translated before anything is written, so its errors stop the build.
+    let (code, file_name) = match options.backend {
+        Backend::C => (generate_c(input, options)?, "program.c"),
+        Backend::Llvm => {
+            let module = lower(&input.source, options)?;
+            let ir = LlvmGenerator::new(&module).with_seed(options.seed).generate();
+            (ir, "program.ll")
+        }
+        Backend::X86 => {
+            let module = lower(&input.source, options)?;
+            let assembly = X86Generator::new(&module).with_seed(options.seed).generate();
+            (assembly, "program.s")
+        }
+    };
+    let dir = work_dir()?;
+    let result = (|| {
+        let library = write_runtime(&dir)
+            .map_err(|error| Failure::Usage(format!("cannot write the runtime: {}", error)))?;
+        let file = dir.join(file_name);
+        fs::write(&file, code)?;
+        let mut command = match options.backend {
+            Backend::C => c_command(&file, &library, &executable),
+            Backend::Llvm => clang_command(&file, &library, &executable),
+            Backend::X86 => link_command(&[&file], &library, &executable),
+        };
+        execute(&mut command)
+    })();
+    let _ = fs::remove_dir_all(dir);
+    result
+}
+
+/// Creates a fresh directory for the intermediate files of a build.
+fn work_dir() -> io::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("hulk-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn run(input: &Input, options: &Options) -> Result<(), Failure> {
+    if let Some(bytecode) = &input.bytecode {
+        let chunk = Chunk::from_bytes(bytecode)
+            .map_err(|error| Failure::Usage(format!("cannot load `{}`: {}", input.name, error)))?;
+        return run_chunk(&chunk, options);
+    }
+    match options.engine {
+        Engine::Interpreter => {
+            let mut checked = driver::check(&input.source)?;
+            tree_walker::run_with_seed(&mut checked.program, options.seed, &mut io::stdout())
+                .map_err(|error| vec![Diagnostic::from(&error)])?;
+        }
+        Engine::Vm => {
+            let CheckedProgram {
+                mut program,
+                type_tree,
+                ..
+            } = driver::analyze(&input.source)?;
+            let chunk = BytecodeCompiler::new(&type_tree).compile_program(&mut program);
+            run_chunk(&chunk, options)?;
+        }
+    }
+    Ok(())
+}
+
+fn run_chunk(chunk: &Chunk, options: &Options) -> Result<(), Failure> {
+    Vm::new(chunk, io::stdout())
+        .with_seed(options.seed)
+        .run()
+        .map_err(|error| Failure::Program(vec![Diagnostic::from(&error)]))
+}
+
+fn fmt(input: &Input, options: &Options) -> Result<(), Failure> {
+    let mut program = driver::parse(&input.source)?;
+    let formatted = formatter::format_source(&mut program, &input.source, options.width)
+        .map_err(|comment| {
+            let (line, column) = position(&input.source, comment.start);
+            Failure::Usage(format!(
+                "cannot format `{}`: the comment at {}:{} is inside a statement",
+                input.name, line, column
+            ))
+        })?;
+    if !options.check {
+        print!("{}", formatted);
+        Ok(())
+    } else if formatted == input.source {
+        Ok(())
+    } else {
+        eprintln!("hulk: `{}` is not formatted", input.name);
+        Err(Failure::Unformatted)
+    }
+}
+
+/// Runs the REPL on standard input, on a thread with room for deep recursion.
+fn repl(seed: u64) -> Result<(), Failure> {
+    std::thread::Builder::new()
+        .name("repl".to_string())
+        .stack_size(tree_walker::STACK_SIZE)
+        .spawn(move || {
+            Repl::new()
+                .with_seed(seed)
+                .run(io::stdin().lock(), io::stdout())
+        })?
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))?;
+    Ok(())
+}
+
+fn execute_command(options: &Options) -> Result<(), Failure> {
+    if options.command == Subcommand::Repl {
+        return repl(options.seed);
+    }
+    let input = read_input(options.input.as_deref())?;
+    if input.bytecode.is_some() && options.command != Subcommand::Run {
+        return Err(Failure::Usage(format!(
+            "`{}` holds bytecode, which only `run` takes",
+            input.name
+        )));
+    }
+    let result = match options.command {
+        Subcommand::Lex => lex(&input),
+        Subcommand::Parse => emit(&input, Stage::Ast, options),
+        Subcommand::Check => driver::check(&input.source)
+            .map(|_| ())
+            .map_err(Failure::from),
+        Subcommand::Emit(stage) => emit(&input, stage, options),
+        Subcommand::Build => build(&input, options),
+        Subcommand::Run => run(&input, options),
+        Subcommand::Fmt => fmt(&input, options),
+        Subcommand::Repl => unreachable!("the REPL reads no program"),
+    };
+    if let Err(Failure::Program(diagnostics)) = &result {
+        for diagnostic in diagnostics {
+            eprintln!(
+                "{}",
+                diagnostic.render(options.error_format, &input.name, &input.source)
+            );
+        }
+    }
+    result
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(USAGE_ERROR);
+    }
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("hulk: {}\n\n{}", message, USAGE);
+            return ExitCode::from(USAGE_ERROR);
+        }
+    };
+
+    match execute_command(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Program(_) | Failure::Unformatted) => ExitCode::from(PROGRAM_ERROR),
+        Err(Failure::Usage(message)) => {
+            eprintln!("hulk: {}", message);
+            ExitCode::from(USAGE_ERROR)
+        }
     }
 }
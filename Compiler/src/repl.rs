@@ -0,0 +1,348 @@
+//! An interactive read-eval-print loop.
+//!
+//! Every entry is one statement ending in `;`. Function and type definitions are kept
+//! for the rest of the session, and defining a name again replaces the old definition.
+//! Expressions are checked together with the definitions, run by the tree-walking
+//! interpreter, and their value is shown with its static type.
+//!
+//! Input that stops before the statement does, like an unclosed `{` or a missing `;`,
+//! is continued on the next line. An empty line ends the entry anyway, so a mistake
+//! never leaves the loop waiting for more.
+//!
+//! Lines starting with `:` are commands, listed by [`HELP`].
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+use lalrpop_util::ParseError;
+
+use crate::ast_nodes::program::{Program, Statement};
+use crate::diagnostics::{Diagnostic, ErrorFormat};
+use crate::driver;
+use crate::interpreter::tree_walker::Interpreter;
+use crate::interpreter::value::Value;
+use crate::parser::ReplEntryParser;
+use crate::random::DEFAULT_SEED;
+use crate::semantic::semantic_visitor::SemanticVisitor;
+use crate::tokens::Span;
+use crate::visitor::tree_printer::TreePrinter;
+
+pub const PROMPT: &str = "hulk> ";
+pub const CONTINUATION_PROMPT: &str = "  ... ";
+
+pub const HELP: &str = "\
+:type EXPR    show the static type of EXPR without running it
+:ast EXPR     show the syntax tree of EXPR
+:load FILE    run a program and keep its definitions
+:history      list the entries of the session
+:help         show this message
+:quit         leave";
+
+/// The state of a session.
+pub struct Repl {
+    /// Function and type definitions, in the order they were entered.
+    definitions: Vec<Statement>,
+    /// Every complete entry, commands included.
+    history: Vec<String>,
+    seed: u64,
+}
+
+/// Returns the name a definition binds, or `None` for expressions.
+fn defined_name(statement: &Statement) -> Option<(&'static str, &str)> {
+    match statement {
+        Statement::StatementFunctionDef(def) => Some(("function", &def.name)),
+        Statement::StatementTypeDef(def) => Some(("type", &def.identifier)),
+        Statement::StatementExpression(_) => None,
+    }
+}
+
+/// Writes a value the way it would be written in a program.
+fn show(value: &Value) -> String {
+    match value {
+        Value::Str(text) => format!("{:?}", text),
+        other => other.to_string(),
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Repl::new()
+    }
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Repl {
+            definitions: Vec::new(),
+            history: Vec::new(),
+            seed: DEFAULT_SEED,
+        }
+    }
+
+    /// Seeds the numbers `rand()` returns, again for every entry.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Reads entries from `input` until it ends or `:quit`, writing prompts, results
+    /// and errors to `out`.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut out: W) -> io::Result<()> {
+        let mut lines = input.lines();
+        let mut pending = String::new();
+        loop {
+            let prompt = if pending.is_empty() {
+                PROMPT
+            } else {
+                CONTINUATION_PROMPT
+            };
+            write!(out, "{}", prompt)?;
+            out.flush()?;
+            let Some(line) = lines.next().transpose()? else {
+                writeln!(out)?;
+                return Ok(());
+            };
+
+            if pending.is_empty() {
+                let command = line.trim();
+                if command.is_empty() {
+                    continue;
+                }
+                if command.starts_with(':') {
+                    self.history.push(command.to_string());
+                    if !self.command(command, &mut out)? {
+                        return Ok(());
+                    }
+                    continue;
+                }
+            }
+
+            let ended = !pending.is_empty() && line.trim().is_empty();
+            pending.push_str(&line);
+            pending.push('\n');
+            let entry = ReplEntryParser::new().parse(&pending).map_err(|error| {
+                let incomplete = matches!(error, ParseError::UnrecognizedEof { .. });
+                (incomplete, Diagnostic::from_parse_error(&error))
+            });
+            if matches!(entry, Err((true, _))) && !ended {
+                continue;
+            }
+            let source = std::mem::take(&mut pending);
+            self.history.push(source.trim_end().to_string());
+            match entry {
+                Ok(statement) => self.evaluate(statement, &source, &mut out)?,
+                Err((_, diagnostic)) => report(&[diagnostic], "<repl>", &source, &mut out)?,
+            }
+        }
+    }
+
+    /// Runs a command. Returns whether the session goes on.
+    fn command<W: Write>(&mut self, command: &str, out: &mut W) -> io::Result<bool> {
+        let (name, argument) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+        match name {
+            ":quit" | ":q" => return Ok(false),
+            ":help" => writeln!(out, "{}", HELP)?,
+            ":history" => {
+                for (index, entry) in self.history.iter().enumerate() {
+                    let entry = entry.replace('\n', "\n      ");
+                    writeln!(out, "{:>4}  {}", index + 1, entry)?;
+                }
+            }
+            ":type" => {
+                let source = terminated(argument);
+                if let Some(statement) = self.parse_expression(&source, out)? {
+                    match self.check(vec![statement]) {
+                        Ok(program) => {
+                            if let Some(expr) = program.entry() {
+                                let type_name = expr
+                                    .node_type()
+                                    .map_or("Object", |node| node.type_name.as_str());
+                                writeln!(out, "{}", type_name)?;
+                            }
+                        }
+                        Err(errors) => report(&errors, "<repl>", &source, out)?,
+                    }
+                }
+            }
+            ":ast" => {
+                let source = terminated(argument);
+                if let Some(statement) = self.parse_expression(&source, out)? {
+                    // Up to the `;` that ends the source.
+                    let span = Span::new(statement.span().start, source.len());
+                    let mut program = Program::new(vec![statement], span);
+                    write!(
+                        out,
+                        "{}",
+                        TreePrinter::new(false).print_program(&mut program)
+                    )?;
+                }
+            }
+            ":load" => self.load(argument, out)?,
+            _ => writeln!(out, "unknown command `{}`, try `:help`", name)?,
+        }
+        Ok(true)
+    }
+
+    /// Parses the argument of `:type` and `:ast`, which must be an expression.
+    fn parse_expression<W: Write>(
+        &self,
+        source: &str,
+        out: &mut W,
+    ) -> io::Result<Option<Statement>> {
+        match ReplEntryParser::new().parse(source) {
+            Ok(statement @ Statement::StatementExpression(_)) => Ok(Some(statement)),
+            Ok(_) => {
+                writeln!(out, "expected an expression")?;
+                Ok(None)
+            }
+            Err(error) => {
+                report(
+                    &[Diagnostic::from_parse_error(&error)],
+                    "<repl>",
+                    source,
+                    out,
+                )?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Checks `statements` after the definitions of the session, replacing the
+    /// definitions they redefine.
+    fn check(&self, statements: Vec<Statement>) -> Result<Program, Vec<Diagnostic>> {
+        let mut definitions: Vec<Statement> = self
+            .definitions
+            .iter()
+            .filter(|old| {
+                !statements.iter().any(|new| {
+                    defined_name(new).is_some() && defined_name(new) == defined_name(old)
+                })
+            })
+            .cloned()
+            .collect();
+        definitions.extend(statements);
+        // The statements come from several entries, so no source span covers them.
+        let mut program = Program::new(definitions, Span::default());
+        SemanticVisitor::new()
+            .with_optional_entry()
+            .check_program(&mut program)
+            .map_err(|errors| errors.iter().map(Diagnostic::from).collect::<Vec<_>>())?;
+        Ok(program)
+    }
+
+    /// Checks and runs `statements` after the definitions of the session. The
+    /// definitions among them are kept when everything checks.
+    fn execute<W: Write>(
+        &mut self,
+        statements: Vec<Statement>,
+        file_name: &str,
+        source: &str,
+        out: &mut W,
+    ) -> io::Result<Option<(Value, Program)>> {
+        let mut program = match self.check(statements.clone()) {
+            Ok(program) => program,
+            Err(errors) => {
+                report(&errors, file_name, source, out)?;
+                return Ok(None);
+            }
+        };
+        for statement in statements {
+            if defined_name(&statement).is_some() {
+                self.definitions
+                    .retain(|old| defined_name(old) != defined_name(&statement));
+                self.definitions.push(statement);
+            }
+        }
+
+        let mut interpreter = Interpreter::new(&mut *out).with_seed(self.seed);
+        let result = interpreter.run(&mut program);
+        match result {
+            Ok(value) => Ok(Some((value, program))),
+            Err(error) => {
+                report(&[Diagnostic::from(&error)], file_name, source, out)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn evaluate<W: Write>(
+        &mut self,
+        statement: Statement,
+        source: &str,
+        out: &mut W,
+    ) -> io::Result<()> {
+        if let Some((kind, name)) = defined_name(&statement) {
+            let message = format!("defined {} `{}`", kind, name);
+            if self
+                .execute(vec![statement], "<repl>", source, out)?
+                .is_some()
+            {
+                writeln!(out, "{}", message)?;
+            }
+            return Ok(());
+        }
+
+        if let Some((value, program)) = self.execute(vec![statement], "<repl>", source, out)? {
+            if let Some(expr) = program.entry() {
+                let type_name = expr
+                    .node_type()
+                    .map_or("Object", |node| node.type_name.as_str());
+                writeln!(out, "{} : {}", show(&value), type_name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the program in `path` and keeps its definitions.
+    fn load<W: Write>(&mut self, path: &str, out: &mut W) -> io::Result<()> {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(error) => return writeln!(out, "cannot read `{}`: {}", path, error),
+        };
+        match driver::parse(&source) {
+            Ok(program) => {
+                let count = program
+                    .statements
+                    .iter()
+                    .filter(|statement| defined_name(statement).is_some())
+                    .count();
+                if self
+                    .execute(program.statements, path, &source, out)?
+                    .is_some()
+                {
+                    writeln!(out, "loaded `{}`, {} definition(s)", path, count)?;
+                }
+                Ok(())
+            }
+            Err(diagnostics) => report(&diagnostics, path, &source, out),
+        }
+    }
+}
+
+/// Adds the `;` that ends a statement when `source` lacks it.
+fn terminated(source: &str) -> String {
+    let source = source.trim_end();
+    if source.ends_with(';') {
+        source.to_string()
+    } else {
+        format!("{};", source)
+    }
+}
+
+fn report<W: Write>(
+    diagnostics: &[Diagnostic],
+    file_name: &str,
+    source: &str,
+    out: &mut W,
+) -> io::Result<()> {
+    for diagnostic in diagnostics {
+        writeln!(
+            out,
+            "{}",
+            diagnostic.render(ErrorFormat::Human, file_name, source)
+        )?;
+    }
+    Ok(())
+}
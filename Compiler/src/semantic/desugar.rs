@@ -0,0 +1,258 @@
+//! `Desugarer` implements the [`Folder`] trait to rewrite the surface syntax of HULK
+//! into a smaller core language, so the backends have fewer forms to handle:
+//! - a `let` with several bindings becomes nested `let`s with one binding each,
+//! - the `elif` branches of an `if` become `if`s nested in its `else` branch,
+//! - `for (x in range(a, b)) body` becomes a `let` of a hidden counter around a `while`,
+//! - a function or method whose body follows `=>` gets a code block as its body,
+//! - `a @@ b` becomes `a @ " " @ b`.
+//!
+//! The `for` loop becomes
+//!
+//! ```text
+//! let _index = a in let _end = b in
+//!     while (_index < _end) let x = _index in { _index := _index + 1; body }
+//! ```
+//!
+//! which evaluates the bounds once and gives every iteration its own `x`, as the loop
+//! does. Identifiers cannot start with `_`, so the hidden names never clash with the
+//! program's.
+//!
+//! Synthetic nodes take the span of the source they stand for, so errors found in them
+//! still point at the user's code. Run after
+//! [`SemanticVisitor`](super::semantic_visitor::SemanticVisitor), the pass keeps static
+//! types: every synthetic node of a typed node gets its type too.
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::{BlockNode, ExpressionList};
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_def::FunctionDefNode;
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::{Assignment, LetInNode};
+use crate::ast_nodes::literals::{IdentifierNode, NumberLiteralNode, StringLiteralNode};
+use crate::ast_nodes::program::Program;
+use crate::ast_nodes::while_loop::WhileNode;
+use crate::tokens::{OperatorToken, Span};
+use crate::types_tree::tree_node::TypeNode;
+use crate::types_tree::type_tree::{TypeTree, BOOLEAN, NUMBER, STRING};
+use crate::visitor::folder::{
+    walk_binary_op, walk_for_loop, walk_function_def, walk_if_else, walk_let_in, Folder,
+};
+
+/// Name of the counter of a desugared `for` loop.
+const INDEX: &str = "_index";
+/// Name of the bound a desugared `for` loop stops at.
+const END: &str = "_end";
+
+/// A folder that rewrites a program into the core language.
+pub struct Desugarer<'a> {
+    type_tree: &'a TypeTree,
+}
+
+impl<'a> Desugarer<'a> {
+    pub fn new(type_tree: &'a TypeTree) -> Self {
+        Desugarer { type_tree }
+    }
+
+    /// Rewrites every function, type and expression of the program.
+    ///
+    /// # Arguments
+    /// * `node` - The root `Program` node, parsed or already annotated by the semantic
+    ///   phase.
+    pub fn desugar_program(&mut self, node: Program) -> Program {
+        self.fold_program(node)
+    }
+
+    /// Returns the type `name` for a synthetic node of a typed node, `None` otherwise.
+    fn type_if(&self, typed: bool, name: &str) -> Option<TypeNode> {
+        typed.then(|| self.type_tree.type_node(name))
+    }
+
+    fn identifier(&self, name: &str, span: Span, typed: bool) -> Expression {
+        Expression::Identifier(IdentifierNode {
+            node_type: self.type_if(typed, NUMBER),
+            ..IdentifierNode::new(name, span)
+        })
+    }
+
+    fn binding(&self, name: &str, value: Expression, typed: bool) -> Assignment {
+        let span = value.span();
+        Assignment {
+            node_type: self.type_if(typed, NUMBER),
+            ..Assignment::new(name.to_string(), value, span)
+        }
+    }
+}
+
+impl Folder for Desugarer<'_> {
+    fn fold_function_def(&mut self, node: FunctionDefNode) -> FunctionDefNode {
+        let node = walk_function_def(self, node);
+        if let Expression::CodeBlock(_) = node.body {
+            return node;
+        }
+        let span = node.body.span();
+        let node_type = node.body.node_type().cloned();
+        FunctionDefNode {
+            body: Expression::CodeBlock(BlockNode {
+                node_type,
+                ..BlockNode::new(ExpressionList::new(vec![node.body]), span)
+            }),
+            ..node
+        }
+    }
+
+    fn fold_binary_op(&mut self, node: BinaryOpNode) -> Expression {
+        let node = walk_binary_op(self, node);
+        if node.operator != OperatorToken::SCONCAT {
+            return Expression::BinaryOp(node);
+        }
+        let typed = node.node_type.is_some();
+        let (left_span, right_span) = (node.left.span(), node.right.span());
+        // The space stands between the operands, where the `@@` is.
+        let space_span = Span::new(left_span.end, right_span.start);
+        let space = Expression::Str(StringLiteralNode {
+            node_type: self.type_if(typed, STRING),
+            ..StringLiteralNode::new(" ", space_span)
+        });
+        let spaced = Expression::BinaryOp(BinaryOpNode {
+            node_type: self.type_if(typed, STRING),
+            ..BinaryOpNode::new(
+                *node.left,
+                OperatorToken::CONCAT,
+                space,
+                Span::new(left_span.start, right_span.start),
+            )
+        });
+        Expression::BinaryOp(BinaryOpNode {
+            left: Box::new(spaced),
+            operator: OperatorToken::CONCAT,
+            ..node
+        })
+    }
+
+    fn fold_let_in(&mut self, node: LetInNode) -> Expression {
+        let node = walk_let_in(self, node);
+        let mut assignments = node.assignments.into_iter();
+        let Some(first) = assignments.next() else {
+            return Expression::LetIn(LetInNode {
+                assignments: Vec::new(),
+                ..node
+            });
+        };
+        // Each binding scopes over the ones after it and the body, up to the end of the
+        // `let`.
+        let mut body = *node.body;
+        for assignment in assignments.rev() {
+            let span = Span::new(assignment.span.start, node.span.end);
+            body = Expression::LetIn(LetInNode {
+                node_type: node.node_type.clone(),
+                ..LetInNode::new(vec![assignment], body, span)
+            });
+        }
+        Expression::LetIn(LetInNode {
+            assignments: vec![first],
+            body: Box::new(body),
+            ..node
+        })
+    }
+
+    fn fold_if_else(&mut self, node: IfElseNode) -> Expression {
+        let node = walk_if_else(self, node);
+        let mut elifs = node.elifs;
+        let mut tail = match elifs.last() {
+            Some((None, _)) => vec![elifs.pop().unwrap()],
+            _ => Vec::new(),
+        };
+        // The nested `if` of an `elif` runs from its condition to the end of the chain.
+        while let Some((Some(condition), body)) = elifs.pop() {
+            let span = Span::new(condition.span().start, node.span.end);
+            let nested = IfElseNode {
+                node_type: node.node_type.clone(),
+                ..IfElseNode::new(condition, body, tail, span)
+            };
+            tail = vec![(None, Expression::IfElse(nested))];
+        }
+        Expression::IfElse(IfElseNode {
+            elifs: tail,
+            ..node
+        })
+    }
+
+    fn fold_for_loop(&mut self, node: ForNode) -> Expression {
+        let node = walk_for_loop(self, node);
+        let typed = node.node_type.is_some();
+        let (start_span, end_span) = (node.start.span(), node.end.span());
+        let range = Span::new(start_span.start, end_span.end);
+        // Every iteration binds the variable to the counter, then runs the body.
+        let iteration_span = Span::new(start_span.start, node.body.span().end);
+
+        let increment = Expression::DestructiveAssign(DestructiveAssignNode {
+            node_type: self.type_if(typed, NUMBER),
+            ..DestructiveAssignNode::new(
+                self.identifier(INDEX, start_span, typed),
+                Expression::BinaryOp(BinaryOpNode {
+                    node_type: self.type_if(typed, NUMBER),
+                    ..BinaryOpNode::new(
+                        self.identifier(INDEX, start_span, typed),
+                        OperatorToken::PLUS,
+                        Expression::Number(NumberLiteralNode {
+                            node_type: self.type_if(typed, NUMBER),
+                            ..NumberLiteralNode::new("1", start_span)
+                        }),
+                        start_span,
+                    )
+                }),
+                start_span,
+            )
+        });
+        let body = Expression::CodeBlock(BlockNode {
+            node_type: node.node_type.clone(),
+            ..BlockNode::new(
+                ExpressionList::new(vec![increment, *node.body]),
+                iteration_span,
+            )
+        });
+        let iteration = Expression::LetIn(LetInNode {
+            node_type: node.node_type.clone(),
+            ..LetInNode::new(
+                vec![self.binding(
+                    &node.variable,
+                    self.identifier(INDEX, start_span, typed),
+                    typed,
+                )],
+                body,
+                iteration_span,
+            )
+        });
+        let condition = Expression::BinaryOp(BinaryOpNode {
+            node_type: self.type_if(typed, BOOLEAN),
+            ..BinaryOpNode::new(
+                self.identifier(INDEX, start_span, typed),
+                OperatorToken::LT,
+                self.identifier(END, end_span, typed),
+                range,
+            )
+        });
+        let while_loop = Expression::WhileLoop(WhileNode {
+            node_type: node.node_type.clone(),
+            ..WhileNode::new(condition, iteration, node.span)
+        });
+        let bounded = Expression::LetIn(LetInNode {
+            node_type: node.node_type.clone(),
+            ..LetInNode::new(
+                vec![self.binding(END, *node.end, typed)],
+                while_loop,
+                node.span,
+            )
+        });
+        Expression::LetIn(LetInNode {
+            node_type: node.node_type,
+            ..LetInNode::new(
+                vec![self.binding(INDEX, *node.start, typed)],
+                bounded,
+                node.span,
+            )
+        })
+    }
+}
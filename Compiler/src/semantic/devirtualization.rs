@@ -0,0 +1,83 @@
+//! `DevirtualizationVisitor` implements the [`MutVisitor`] trait to replace dynamic
+//! dispatch with direct calls where the receiver's exact type is statically known.
+//!
+//! It must run after [`SemanticVisitor`](super::semantic_visitor::SemanticVisitor), since
+//! it reads the static types stored in `node_type`. The exact type of a receiver is
+//! known when:
+//! - the receiver is a fresh `new T(...)`, or
+//! - its static type has no subtypes, so no other implementation can be reached.
+//!
+//! Devirtualized calls get the type that implements the method in `direct_call`.
+
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::program::Program;
+use crate::ast_nodes::type_member_access::TypeFunctionAccessNode;
+use crate::types_tree::type_tree::TypeTree;
+use crate::visitor::mut_visitor::{walk_type_function_access, MutVisitor};
+
+/// A visitor that marks method calls whose target can be resolved at compile time.
+pub struct DevirtualizationVisitor<'a> {
+    type_tree: &'a TypeTree,
+    /// Number of method call sites found.
+    pub call_sites: usize,
+    /// Number of method call sites turned into direct calls.
+    pub devirtualized: usize,
+}
+
+impl<'a> DevirtualizationVisitor<'a> {
+    pub fn new(type_tree: &'a TypeTree) -> Self {
+        DevirtualizationVisitor {
+            type_tree,
+            call_sites: 0,
+            devirtualized: 0,
+        }
+    }
+
+    /// Devirtualizes every method call of a type-checked program.
+    ///
+    /// # Arguments
+    /// * `node` - The root `Program` node, already annotated by the semantic phase.
+    pub fn devirtualize_program(&mut self, node: &mut Program) {
+        self.call_sites = 0;
+        self.devirtualized = 0;
+        self.visit_program(node);
+    }
+
+    /// Returns a one-line summary of the call sites that were devirtualized.
+    pub fn report(&self) -> String {
+        format!(
+            "devirtualized {} of {} method call site(s)",
+            self.devirtualized, self.call_sites
+        )
+    }
+
+    /// Returns the exact runtime type of `object`, if it can be known statically.
+    fn exact_type(&self, object: &Expression) -> Option<String> {
+        if let Expression::TypeInstance(instance) = object {
+            return Some(instance.type_name.clone());
+        }
+        let static_type = object.node_type()?;
+        let type_name = &static_type.type_name;
+        if TypeTree::is_builtin(type_name) || self.type_tree.has_subtypes(type_name) {
+            return None;
+        }
+        Some(type_name.clone())
+    }
+}
+
+impl MutVisitor for DevirtualizationVisitor<'_> {
+    fn visit_type_function_access(&mut self, node: &mut TypeFunctionAccessNode) {
+        walk_type_function_access(self, node);
+
+        self.call_sites += 1;
+        let target = self.exact_type(&node.object).and_then(|exact| {
+            self.type_tree
+                .find_method(&exact, &node.member.function_name)
+                .map(|(owner, _)| owner)
+        });
+        if target.is_some() {
+            self.devirtualized += 1;
+        }
+        node.set_direct_call(target);
+    }
+}
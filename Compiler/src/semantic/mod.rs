@@ -1 +1,5 @@
-// ...implementación del análisis semántico...
+pub mod desugar;
+pub mod devirtualization;
+pub mod semantic_errors;
+pub mod semantic_visitor;
+pub mod tail_calls;
@@ -0,0 +1,136 @@
+//! Errors reported by the semantic phase.
+
+use std::fmt;
+
+use crate::tokens::Span;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SemanticError {
+    UndefinedVariable { name: String, span: Span },
+    UndefinedFunction { name: String, span: Span },
+    UndefinedType { name: String, span: Span },
+    UndefinedMethod { type_name: String, name: String, span: Span },
+    UndefinedAttribute { type_name: String, name: String, span: Span },
+    DuplicateFunction { name: String, span: Span },
+    DuplicateType { name: String, span: Span },
+    DuplicateMember { type_name: String, name: String, span: Span },
+    CyclicInheritance { name: String, span: Span },
+    InvalidInheritance { name: String, parent: String, span: Span },
+    InvalidOverride { type_name: String, name: String, span: Span },
+    TypeMismatch { expected: String, found: String, span: Span },
+    ArgumentCountMismatch { name: String, expected: usize, found: usize, span: Span },
+    InvalidOperand { operator: String, found: String, span: Span },
+    InvalidAssignmentTarget { span: Span },
+    MissingElse { span: Span },
+    /// Reported with the span of the construct holding the block, since blocks have none.
+    EmptyBlock { span: Span },
+    /// Reported at the end of a program that only declares functions and types.
+    MissingEntryExpression { span: Span },
+    /// A global expression after the entry expression.
+    ExtraEntryExpression { span: Span },
+    DeclarationAfterEntry { name: String, span: Span },
+}
+
+impl SemanticError {
+    /// Returns the region of the source the error refers to.
+    pub fn span(&self) -> Span {
+        match self {
+            SemanticError::UndefinedVariable { span, .. }
+            | SemanticError::UndefinedFunction { span, .. }
+            | SemanticError::UndefinedType { span, .. }
+            | SemanticError::UndefinedMethod { span, .. }
+            | SemanticError::UndefinedAttribute { span, .. }
+            | SemanticError::DuplicateFunction { span, .. }
+            | SemanticError::DuplicateType { span, .. }
+            | SemanticError::DuplicateMember { span, .. }
+            | SemanticError::CyclicInheritance { span, .. }
+            | SemanticError::InvalidInheritance { span, .. }
+            | SemanticError::InvalidOverride { span, .. }
+            | SemanticError::TypeMismatch { span, .. }
+            | SemanticError::ArgumentCountMismatch { span, .. }
+            | SemanticError::InvalidOperand { span, .. }
+            | SemanticError::InvalidAssignmentTarget { span }
+            | SemanticError::MissingElse { span }
+            | SemanticError::EmptyBlock { span }
+            | SemanticError::MissingEntryExpression { span }
+            | SemanticError::ExtraEntryExpression { span }
+            | SemanticError::DeclarationAfterEntry { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for SemanticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SemanticError::UndefinedVariable { name, .. } => {
+                write!(f, "variable `{}` is not defined", name)
+            }
+            SemanticError::UndefinedFunction { name, .. } => {
+                write!(f, "function `{}` is not defined", name)
+            }
+            SemanticError::UndefinedType { name, .. } => write!(f, "type `{}` is not defined", name),
+            SemanticError::UndefinedMethod { type_name, name, .. } => {
+                write!(f, "type `{}` has no method `{}`", type_name, name)
+            }
+            SemanticError::UndefinedAttribute { type_name, name, .. } => {
+                write!(f, "type `{}` has no attribute `{}`", type_name, name)
+            }
+            SemanticError::DuplicateFunction { name, .. } => {
+                write!(f, "function `{}` is defined more than once", name)
+            }
+            SemanticError::DuplicateType { name, .. } => {
+                write!(f, "type `{}` is defined more than once", name)
+            }
+            SemanticError::DuplicateMember { type_name, name, .. } => {
+                write!(f, "member `{}` is defined more than once in type `{}`", name, type_name)
+            }
+            SemanticError::CyclicInheritance { name, .. } => {
+                write!(f, "type `{}` inherits from itself", name)
+            }
+            SemanticError::InvalidInheritance { name, parent, .. } => {
+                write!(f, "type `{}` cannot inherit from `{}`", name, parent)
+            }
+            SemanticError::InvalidOverride { type_name, name, .. } => write!(
+                f,
+                "method `{}` of type `{}` does not match the signature it overrides",
+                name, type_name
+            ),
+            SemanticError::TypeMismatch { expected, found, .. } => {
+                write!(f, "expected type `{}`, found `{}`", expected, found)
+            }
+            SemanticError::ArgumentCountMismatch {
+                name,
+                expected,
+                found,
+                ..
+            } => write!(
+                f,
+                "`{}` expects {} argument(s), but {} were given",
+                name, expected, found
+            ),
+            SemanticError::InvalidOperand {
+                operator, found, ..
+            } => write!(f, "operator `{}` cannot be applied to `{}`", operator, found),
+            SemanticError::InvalidAssignmentTarget { .. } => {
+                write!(f, "only variables and attributes of `self` can be assigned with `:=`")
+            }
+            SemanticError::MissingElse { .. } => {
+                write!(f, "`if` expression is missing its `else` branch")
+            }
+            SemanticError::EmptyBlock { .. } => write!(f, "code block must contain an expression"),
+            SemanticError::MissingEntryExpression { .. } => write!(
+                f,
+                "program has no entry expression: an expression must follow the declarations"
+            ),
+            SemanticError::ExtraEntryExpression { .. } => write!(
+                f,
+                "program has more than one entry expression: group them in a block `{{ ... }}`"
+            ),
+            SemanticError::DeclarationAfterEntry { name, .. } => write!(
+                f,
+                "`{}` is declared after the entry expression: declarations must come first",
+                name
+            ),
+        }
+    }
+}
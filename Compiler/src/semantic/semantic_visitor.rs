@@ -0,0 +1,820 @@
+//! `SemanticVisitor` implements the [`Visitor`] trait to type-check a program.
+//!
+//! Checking runs in several passes over the program:
+//! 1. type declarations are collected into the [`TypeTree`], parents first;
+//! 2. constructor, method and global function signatures are collected;
+//! 3. attribute initializers are checked and their types recorded, parents first;
+//! 4. method bodies, function bodies and top-level expressions are checked.
+//!
+//! Every expression gets its static type through `set_type`, so later phases can read
+//! it from `node_type`. Errors are collected instead of stopping the check; an
+//! expression whose type cannot be computed gets the internal unknown type, which
+//! conforms to everything and keeps one mistake from being reported many times.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::BlockNode;
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::FunctionCallNode;
+use crate::ast_nodes::function_def::{FunctionDefNode, FunctionParams};
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::LetInNode;
+use crate::ast_nodes::literals::{BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::{Program, Statement};
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+use crate::builtins;
+use crate::symbol_table::{Symbol, SymbolTable, SymbolType};
+use crate::tokens::{OperatorToken, Span};
+use crate::types_tree::tree_node::{FunctionSignature, TypeNode};
+use crate::types_tree::type_tree::{TypeTree, BOOLEAN, NUMBER, OBJECT, STRING, UNKNOWN};
+use crate::visitor::accept::Accept;
+use crate::visitor::visitor_trait::Visitor;
+
+use super::semantic_errors::SemanticError;
+
+/// A visitor that computes the static type of every node and reports semantic errors.
+#[derive(Default)]
+pub struct SemanticVisitor {
+    pub type_tree: TypeTree,
+    pub symbol_table: SymbolTable,
+    pub functions: HashMap<String, FunctionSignature>,
+    pub errors: Vec<SemanticError>,
+    /// Type whose members are being checked, if any.
+    current_type: Option<String>,
+    /// Span of the innermost construct being checked, used for nodes without a span.
+    enclosing_span: Option<Span>,
+    /// Whether a program may lack its entry expression.
+    optional_entry: bool,
+}
+
+impl SemanticVisitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accepts programs with only declarations, as the REPL checks its definitions.
+    pub fn with_optional_entry(mut self) -> Self {
+        self.optional_entry = true;
+        self
+    }
+
+    /// Type-checks the whole program, annotating every node with its type.
+    ///
+    /// # Arguments
+    /// * `node` - The root `Program` node to check.
+    ///
+    /// # Returns
+    /// `Ok(())` if the program is well typed, or every error found otherwise.
+    pub fn check_program(&mut self, node: &mut Program) -> Result<(), Vec<SemanticError>> {
+        self.check_structure(node);
+        self.collect_types(node);
+        self.collect_signatures(node);
+
+        let type_defs = self.type_defs_by_depth(node);
+        for &index in type_defs.iter() {
+            if let Statement::StatementTypeDef(def) = &mut node.statements[index] {
+                self.check_type_attributes(def);
+            }
+        }
+        for &index in type_defs.iter() {
+            if let Statement::StatementTypeDef(def) = &mut node.statements[index] {
+                self.check_type_methods(def);
+            }
+        }
+
+        for statement in node.statements.iter_mut() {
+            match statement {
+                Statement::StatementTypeDef(_) => {}
+                Statement::StatementFunctionDef(def) => {
+                    self.visit_function_def(def);
+                }
+                Statement::StatementExpression(expr) => {
+                    self.enclosing_span = None;
+                    expr.accept(self);
+                }
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors.clone())
+        }
+    }
+
+    /// Checks the structure of programs the grammar did not build, such as the ones the
+    /// CST lowers or the REPL assembles, which may put their statements in any order.
+    fn check_structure(&mut self, program: &Program) {
+        for error in structure_errors(program) {
+            let missing_entry = matches!(error, SemanticError::MissingEntryExpression { .. });
+            if !(self.optional_entry && missing_entry) {
+                self.errors.push(error);
+            }
+        }
+    }
+
+    /// Adds every user type to the type tree, making sure parents are added first.
+    fn collect_types(&mut self, program: &Program) {
+        let mut pending: Vec<(String, Option<String>, Span)> = Vec::new();
+        for statement in program.statements.iter() {
+            if let Statement::StatementTypeDef(def) = statement {
+                let duplicated = TypeTree::is_builtin(&def.identifier)
+                    || pending.iter().any(|(name, _, _)| *name == def.identifier);
+                if duplicated {
+                    self.errors.push(SemanticError::DuplicateType {
+                        name: def.identifier.clone(),
+                        span: def.span,
+                    });
+                } else {
+                    pending.push((def.identifier.clone(), def.parent.clone(), def.span));
+                }
+            }
+        }
+
+        let declared: HashSet<String> = pending.iter().map(|(name, _, _)| name.clone()).collect();
+        while !pending.is_empty() {
+            let ready = pending.iter().position(|(_, parent, _)| match parent {
+                Some(parent) => self.type_tree.get_type(parent).is_some() || !declared.contains(parent),
+                None => true,
+            });
+            match ready {
+                Some(index) => {
+                    let (name, parent, span) = pending.remove(index);
+                    let parent = parent.unwrap_or_else(|| OBJECT.to_string());
+                    let parent = self.checked_parent(&name, parent, span);
+                    self.type_tree.add_type(&name, &parent);
+                }
+                None => {
+                    // Every remaining type waits for another remaining type: a cycle.
+                    for (name, _, span) in pending.drain(..) {
+                        self.errors.push(SemanticError::CyclicInheritance { name: name.clone(), span });
+                        self.type_tree.add_type(&name, OBJECT);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Validates the parent of `name`, falling back to `Object` when it is not usable.
+    fn checked_parent(&mut self, name: &str, parent: String, span: Span) -> String {
+        if self.type_tree.get_type(&parent).is_none() {
+            self.errors.push(SemanticError::UndefinedType { name: parent, span });
+            return OBJECT.to_string();
+        }
+        if TypeTree::is_primitive(&parent) || parent == UNKNOWN {
+            self.errors.push(SemanticError::InvalidInheritance {
+                name: name.to_string(),
+                parent,
+                span,
+            });
+            return OBJECT.to_string();
+        }
+        parent
+    }
+
+    /// Records constructor parameters, method signatures and global function signatures.
+    fn collect_signatures(&mut self, program: &Program) {
+        let mut type_defs: Vec<&TypeDefNode> = program
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::StatementTypeDef(def) => Some(def.as_ref()),
+                _ => None,
+            })
+            .collect();
+        type_defs.sort_by_key(|def| self.depth_of(&def.identifier));
+
+        let mut seen_types = HashSet::new();
+        for def in type_defs {
+            if !seen_types.insert(def.identifier.clone()) {
+                continue;
+            }
+            let mut params = self.checked_params(&def.params);
+            if def.params.is_empty() && def.parent_args.is_empty() {
+                if let Some(parent) = &self.type_tree.get_type(&def.identifier).unwrap().parent {
+                    params = self.type_tree.constructor_params(parent);
+                }
+            }
+
+            let mut methods: Vec<FunctionSignature> = Vec::new();
+            for member in def.members.iter() {
+                if let TypeMember::Method(method) = member {
+                    if methods.iter().any(|m| m.name == method.name) {
+                        self.errors.push(SemanticError::DuplicateMember {
+                            type_name: def.identifier.clone(),
+                            name: method.name.clone(),
+                            span: method.span,
+                        });
+                        continue;
+                    }
+                    methods.push(self.checked_signature(method));
+                }
+            }
+
+            let node = self.type_tree.get_type_mut(&def.identifier).unwrap();
+            node.params = params;
+            for method in methods {
+                node.add_method(method);
+            }
+        }
+
+        for statement in program.statements.iter() {
+            if let Statement::StatementFunctionDef(def) = statement {
+                if self.functions.contains_key(&def.name) {
+                    self.errors.push(SemanticError::DuplicateFunction {
+                        name: def.name.clone(),
+                        span: def.span,
+                    });
+                    continue;
+                }
+                let signature = self.checked_signature(def);
+                self.functions.insert(def.name.clone(), signature);
+                self.symbol_table
+                    .insert(Symbol::new(&def.name, SymbolType::Function, &def.return_type, def.span));
+            }
+        }
+    }
+
+    /// Builds the signature of `def`, reporting parameter and return types that do not exist.
+    fn checked_signature(&mut self, def: &FunctionDefNode) -> FunctionSignature {
+        let params = self.checked_params(&def.params);
+        let return_type = self.resolve_type(&def.return_type, def.span);
+        FunctionSignature::new(def.name.clone(), params, return_type)
+    }
+
+    fn checked_params(&mut self, params: &[FunctionParams]) -> Vec<FunctionParams> {
+        params
+            .iter()
+            .map(|param| {
+                let signature = self.resolve_type(&param.signature, param.span);
+                FunctionParams::new(param.name.clone(), signature, param.span)
+            })
+            .collect()
+    }
+
+    /// Returns `name` if it names a type, or the unknown type after reporting an error.
+    ///
+    /// Signatures are resolved when they are collected and again when their bodies are
+    /// checked, so each undefined type is only reported the first time.
+    fn resolve_type(&mut self, name: &str, span: Span) -> String {
+        if name != UNKNOWN && self.type_tree.get_type(name).is_some() {
+            return name.to_string();
+        }
+        let error = SemanticError::UndefinedType {
+            name: name.to_string(),
+            span,
+        };
+        if !self.errors.contains(&error) {
+            self.errors.push(error);
+        }
+        UNKNOWN.to_string()
+    }
+
+    fn depth_of(&self, type_name: &str) -> usize {
+        self.type_tree.get_type(type_name).map_or(0, |node| node.depth)
+    }
+
+    /// Returns the statement indexes of the type definitions, parents before children.
+    fn type_defs_by_depth(&self, program: &Program) -> Vec<usize> {
+        let mut seen = HashSet::new();
+        let mut indexes: Vec<usize> = program
+            .statements
+            .iter()
+            .enumerate()
+            .filter_map(|(i, statement)| match statement {
+                Statement::StatementTypeDef(def) if seen.insert(def.identifier.clone()) => Some(i),
+                _ => None,
+            })
+            .collect();
+        indexes.sort_by_key(|&i| match &program.statements[i] {
+            Statement::StatementTypeDef(def) => self.depth_of(&def.identifier),
+            _ => 0,
+        });
+        indexes
+    }
+
+    /// Checks the parent arguments and attribute initializers of a type and records
+    /// the attribute types in the type tree.
+    fn check_type_attributes(&mut self, node: &mut TypeDefNode) {
+        let type_name = node.identifier.clone();
+        self.enclosing_span = Some(node.span);
+        self.symbol_table.enter_scope(&format!("type {}", type_name));
+        let params = self.checked_params(&node.params);
+        self.declare_params(&params);
+
+        // A type that declares neither parameters nor parent arguments reuses the
+        // parent's constructor, so there is nothing to check in that case.
+        if !node.params.is_empty() || !node.parent_args.is_empty() {
+            let parent = self.type_tree.get_type(&type_name).unwrap().parent.clone();
+            let parent = parent.unwrap_or_else(|| OBJECT.to_string());
+            let expected = self.type_tree.constructor_params(&parent);
+            self.check_arguments(&parent, &expected, &mut node.parent_args, node.span);
+        }
+
+        let mut attributes: Vec<(String, String)> = Vec::new();
+        for member in node.members.iter_mut() {
+            if let TypeMember::Property(assignment) = member {
+                let value_type = assignment.expression.accept(self);
+                assignment.set_type(value_type.clone());
+                if attributes.iter().any(|(name, _)| *name == assignment.identifier) {
+                    self.errors.push(SemanticError::DuplicateMember {
+                        type_name: type_name.clone(),
+                        name: assignment.identifier.clone(),
+                        span: assignment.span,
+                    });
+                    continue;
+                }
+                attributes.push((assignment.identifier.clone(), value_type.type_name));
+            }
+        }
+        self.symbol_table.exit_scope();
+
+        let type_node = self.type_tree.get_type_mut(&type_name).unwrap();
+        for (name, type_name) in attributes {
+            type_node.add_attribute(name, type_name);
+        }
+        let type_node = type_node.clone();
+        node.set_type(type_node);
+    }
+
+    /// Checks the body of every method of a type against its signature.
+    fn check_type_methods(&mut self, node: &mut TypeDefNode) {
+        let type_name = node.identifier.clone();
+        let previous_type = self.current_type.replace(type_name.clone());
+        for member in node.members.iter_mut() {
+            if let TypeMember::Method(method) = member {
+                self.check_override(&type_name, method);
+                self.symbol_table
+                    .enter_scope(&format!("method {}.{}", type_name, method.name));
+                self.symbol_table
+                    .insert(Symbol::new("self", SymbolType::Variable, &type_name, node.span));
+                self.check_function_body(method);
+                self.symbol_table.exit_scope();
+            }
+        }
+        self.current_type = previous_type;
+    }
+
+    /// Reports `method` if it overrides an inherited method with a different signature.
+    fn check_override(&mut self, type_name: &str, method: &FunctionDefNode) {
+        let parent = match &self.type_tree.get_type(type_name).unwrap().parent {
+            Some(parent) => parent.clone(),
+            None => return,
+        };
+        let own = match self.type_tree.get_type(type_name).unwrap().get_method(&method.name) {
+            Some(own) => own.clone(),
+            None => return,
+        };
+        if let Some((_, inherited)) = self.type_tree.find_method(&parent, &method.name) {
+            if inherited.param_types() != own.param_types() || inherited.return_type != own.return_type {
+                self.errors.push(SemanticError::InvalidOverride {
+                    type_name: type_name.to_string(),
+                    name: method.name.clone(),
+                    span: method.span,
+                });
+            }
+        }
+    }
+
+    /// Declares the parameters of `node` and checks its body against the return type.
+    fn check_function_body(&mut self, node: &mut FunctionDefNode) -> TypeNode {
+        self.enclosing_span = Some(node.span);
+        let params = self.checked_params(&node.params);
+        let return_type = self.resolve_type(&node.return_type, node.span);
+        self.declare_params(&params);
+        let body_type = node.body.accept(self);
+        let body_span = self.span_of(&node.body);
+        self.expect_type(&body_type.type_name, &return_type, body_span);
+        let return_node = self.type_tree.type_node(&return_type);
+        node.set_type(return_node.clone());
+        return_node
+    }
+
+    fn declare_params(&mut self, params: &[FunctionParams]) {
+        for param in params {
+            self.symbol_table.insert(Symbol::new(
+                &param.name,
+                SymbolType::Parameter,
+                &param.signature,
+                param.span,
+            ));
+        }
+    }
+
+    /// Checks the count and types of `args` against `params`.
+    fn check_arguments(
+        &mut self,
+        name: &str,
+        params: &[FunctionParams],
+        args: &mut [Expression],
+        span: Span,
+    ) {
+        let arg_types: Vec<TypeNode> = args.iter_mut().map(|arg| arg.accept(self)).collect();
+        if params.len() != args.len() {
+            self.errors.push(SemanticError::ArgumentCountMismatch {
+                name: name.to_string(),
+                expected: params.len(),
+                found: args.len(),
+                span,
+            });
+            return;
+        }
+        for ((param, arg), arg_type) in params.iter().zip(args.iter()).zip(arg_types) {
+            let span = self.span_of(arg);
+            self.expect_type(&arg_type.type_name, &param.signature, span);
+        }
+    }
+
+    /// Reports a mismatch unless `found` conforms to `expected`.
+    fn expect_type(&mut self, found: &str, expected: &str, span: Span) {
+        if !self.type_tree.conforms(found, expected) {
+            self.errors.push(SemanticError::TypeMismatch {
+                expected: expected.to_string(),
+                found: found.to_string(),
+                span,
+            });
+        }
+    }
+
+    /// Reports an invalid operand unless `found` is one of `allowed`.
+    fn expect_operand(&mut self, operator: &OperatorToken, found: &str, allowed: &[&str], span: Span) {
+        if found != UNKNOWN && !allowed.contains(&found) {
+            self.errors.push(SemanticError::InvalidOperand {
+                operator: operator.to_string(),
+                found: found.to_string(),
+                span,
+            });
+        }
+    }
+
+    /// Returns the span of `expr`, falling back to the enclosing construct for empty blocks.
+    fn span_of(&self, expr: &Expression) -> Span {
+        match expr {
+            Expression::CodeBlock(block) if block.expression_list.expressions.is_empty() => {
+                self.enclosing_span.unwrap_or(Span::new(0, 0))
+            }
+            _ => expr.span(),
+        }
+    }
+
+    fn node_of(&self, type_name: &str) -> TypeNode {
+        self.type_tree.type_node(type_name)
+    }
+}
+
+impl Visitor<TypeNode> for SemanticVisitor {
+    fn visit_function_def(&mut self, node: &mut FunctionDefNode) -> TypeNode {
+        self.symbol_table.enter_scope(&format!("function {}", node.name));
+        let return_type = self.check_function_body(node);
+        self.symbol_table.exit_scope();
+        return_type
+    }
+
+    fn visit_literal_number(&mut self, node: &mut NumberLiteralNode) -> TypeNode {
+        let ty = self.node_of(NUMBER);
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_literal_boolean(&mut self, node: &mut BooleanLiteralNode) -> TypeNode {
+        let ty = self.node_of(BOOLEAN);
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_literal_string(&mut self, node: &mut StringLiteralNode) -> TypeNode {
+        let ty = self.node_of(STRING);
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_identifier(&mut self, node: &mut IdentifierNode) -> TypeNode {
+        let type_name = match self.symbol_table.lookup(&node.value) {
+            Some(symbol) if symbol.symbol_type != SymbolType::Function => symbol.type_name.clone(),
+            _ if builtins::constant(&node.value).is_some() => NUMBER.to_string(),
+            _ => {
+                self.errors.push(SemanticError::UndefinedVariable {
+                    name: node.value.clone(),
+                    span: node.span,
+                });
+                UNKNOWN.to_string()
+            }
+        };
+        let ty = self.node_of(&type_name);
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_function_call(&mut self, node: &mut FunctionCallNode) -> TypeNode {
+        let signature = self
+            .functions
+            .get(&node.function_name)
+            .cloned()
+            .or_else(|| builtins::function(&node.function_name).map(|builtin| builtin.signature()));
+        let return_type = match signature {
+            Some(signature) => {
+                self.check_arguments(&node.function_name, &signature.params, &mut node.arguments, node.span);
+                signature.return_type
+            }
+            None => {
+                for arg in node.arguments.iter_mut() {
+                    arg.accept(self);
+                }
+                self.errors.push(SemanticError::UndefinedFunction {
+                    name: node.function_name.clone(),
+                    span: node.span,
+                });
+                UNKNOWN.to_string()
+            }
+        };
+        let ty = self.node_of(&return_type);
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_while_loop(&mut self, node: &mut WhileNode) -> TypeNode {
+        self.enclosing_span = Some(node.span);
+        let condition = node.condition.accept(self);
+        let span = self.span_of(&node.condition);
+        self.expect_type(&condition.type_name, BOOLEAN, span);
+        let ty = node.body.accept(self);
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_for_loop(&mut self, node: &mut ForNode) -> TypeNode {
+        self.enclosing_span = Some(node.span);
+        let start = node.start.accept(self);
+        let span = self.span_of(&node.start);
+        self.expect_type(&start.type_name, NUMBER, span);
+        let end = node.end.accept(self);
+        let span = self.span_of(&node.end);
+        self.expect_type(&end.type_name, NUMBER, span);
+
+        self.symbol_table.enter_scope("for");
+        self.symbol_table
+            .insert(Symbol::new(&node.variable, SymbolType::Variable, NUMBER, node.span));
+        let ty = node.body.accept(self);
+        self.symbol_table.exit_scope();
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_code_block(&mut self, node: &mut BlockNode) -> TypeNode {
+        let mut ty = self.node_of(UNKNOWN);
+        for expr in node.expression_list.expressions.iter_mut() {
+            ty = expr.accept(self);
+        }
+        if node.expression_list.expressions.is_empty() {
+            if let Some(span) = self.enclosing_span {
+                self.errors.push(SemanticError::EmptyBlock { span });
+            }
+        }
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_binary_op(&mut self, node: &mut BinaryOpNode) -> TypeNode {
+        let left = node.left.accept(self).type_name;
+        let right = node.right.accept(self).type_name;
+        let (allowed, result): (&[&str], &str) = match node.operator {
+            OperatorToken::PLUS
+            | OperatorToken::MINUS
+            | OperatorToken::MUL
+            | OperatorToken::DIV
+            | OperatorToken::MOD
+            | OperatorToken::POW => (&[NUMBER], NUMBER),
+            OperatorToken::GT | OperatorToken::GTE | OperatorToken::LT | OperatorToken::LTE => {
+                (&[NUMBER], BOOLEAN)
+            }
+            OperatorToken::AND | OperatorToken::OR => (&[BOOLEAN], BOOLEAN),
+            OperatorToken::CONCAT | OperatorToken::SCONCAT => (&[STRING, NUMBER, BOOLEAN], STRING),
+            _ => (&[], BOOLEAN),
+        };
+        if !allowed.is_empty() {
+            let (left_span, right_span) = (self.span_of(&node.left), self.span_of(&node.right));
+            self.expect_operand(&node.operator, &left, allowed, left_span);
+            self.expect_operand(&node.operator, &right, allowed, right_span);
+        }
+        let ty = self.node_of(result);
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_unary_op(&mut self, node: &mut UnaryOpNode) -> TypeNode {
+        let operand = node.operand.accept(self).type_name;
+        let expected = match node.operator {
+            OperatorToken::NOT => BOOLEAN,
+            _ => NUMBER,
+        };
+        let span = self.span_of(&node.operand);
+        self.expect_operand(&node.operator, &operand, &[expected], span);
+        let ty = self.node_of(expected);
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_if_else(&mut self, node: &mut IfElseNode) -> TypeNode {
+        self.enclosing_span = Some(node.span);
+        let condition = node.condition.accept(self);
+        let span = self.span_of(&node.condition);
+        self.expect_type(&condition.type_name, BOOLEAN, span);
+        let mut result = node.if_expression.accept(self).type_name;
+
+        for (condition, body) in node.elifs.iter_mut() {
+            if let Some(cond) = condition {
+                let cond_type = cond.accept(self);
+                let span = self.span_of(cond);
+                self.expect_type(&cond_type.type_name, BOOLEAN, span);
+            }
+            self.enclosing_span = Some(node.span);
+            let body_type = body.accept(self);
+            result = self.type_tree.lowest_common_ancestor(&result, &body_type.type_name);
+        }
+        if !matches!(node.elifs.last(), Some((None, _))) {
+            self.errors.push(SemanticError::MissingElse { span: node.span });
+        }
+
+        let ty = self.node_of(&result);
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_let_in(&mut self, node: &mut LetInNode) -> TypeNode {
+        self.enclosing_span = Some(node.span);
+        self.symbol_table.enter_scope("let");
+        for assignment in node.assignments.iter_mut() {
+            let value_type = assignment.expression.accept(self);
+            // Each binding is visible to the ones after it, as in nested `let`s.
+            self.symbol_table.insert(Symbol::new(
+                &assignment.identifier,
+                SymbolType::Variable,
+                &value_type.type_name,
+                assignment.span,
+            ));
+            assignment.set_type(value_type);
+        }
+        let ty = node.body.accept(self);
+        self.symbol_table.exit_scope();
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_destructive_assign(&mut self, node: &mut DestructiveAssignNode) -> TypeNode {
+        let target_is_valid = match node.identifier.as_ref() {
+            Expression::Identifier(id) => id.value != "self",
+            Expression::TypePropAccess(access) => {
+                matches!(access.object.as_ref(), Expression::Identifier(id) if id.value == "self")
+            }
+            _ => false,
+        };
+        if !target_is_valid {
+            self.errors
+                .push(SemanticError::InvalidAssignmentTarget { span: node.span });
+        }
+
+        let target = node.identifier.accept(self);
+        let value = node.expression.accept(self);
+        if target_is_valid {
+            let span = self.span_of(&node.expression);
+            self.expect_type(&value.type_name, &target.type_name, span);
+        }
+        node.set_type(target.clone());
+        target
+    }
+
+    fn visit_type_def(&mut self, node: &mut TypeDefNode) -> TypeNode {
+        self.check_type_attributes(node);
+        self.check_type_methods(node);
+        self.node_of(&node.identifier)
+    }
+
+    fn visit_type_instance(&mut self, node: &mut TypeInstanceNode) -> TypeNode {
+        let type_name = if self.type_tree.get_type(&node.type_name).is_none()
+            || TypeTree::is_builtin(&node.type_name)
+        {
+            self.errors.push(SemanticError::UndefinedType {
+                name: node.type_name.clone(),
+                span: node.span,
+            });
+            for arg in node.arguments.iter_mut() {
+                arg.accept(self);
+            }
+            UNKNOWN.to_string()
+        } else {
+            let params = self.type_tree.constructor_params(&node.type_name);
+            self.check_arguments(&node.type_name, &params, &mut node.arguments, node.span);
+            node.type_name.clone()
+        };
+        let ty = self.node_of(&type_name);
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_type_function_access(&mut self, node: &mut TypeFunctionAccessNode) -> TypeNode {
+        let object = node.object.accept(self).type_name;
+        let member = &mut node.member;
+        let signature = self
+            .type_tree
+            .find_method(&object, &member.function_name)
+            .map(|(_, signature)| signature.clone());
+
+        let return_type = match signature {
+            Some(signature) => {
+                self.check_arguments(&member.function_name, &signature.params, &mut member.arguments, member.span);
+                signature.return_type
+            }
+            None => {
+                for arg in member.arguments.iter_mut() {
+                    arg.accept(self);
+                }
+                if object != UNKNOWN {
+                    self.errors.push(SemanticError::UndefinedMethod {
+                        type_name: object,
+                        name: member.function_name.clone(),
+                        span: member.span,
+                    });
+                }
+                UNKNOWN.to_string()
+            }
+        };
+        let ty = self.node_of(&return_type);
+        member.set_type(ty.clone());
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_type_prop_access(&mut self, node: &mut TypePropAccessNode) -> TypeNode {
+        let object = node.object.accept(self).type_name;
+        let attribute_type = match self.type_tree.find_attribute(&object, &node.member) {
+            Some(attribute_type) => attribute_type.clone(),
+            None => {
+                if object != UNKNOWN {
+                    self.errors.push(SemanticError::UndefinedAttribute {
+                        type_name: object,
+                        name: node.member.to_string(),
+                        span: node.span,
+                    });
+                }
+                UNKNOWN.to_string()
+            }
+        };
+        let ty = self.node_of(&attribute_type);
+        node.set_type(ty.clone());
+        ty
+    }
+
+    fn visit_print(&mut self, node: &mut PrintNode) -> TypeNode {
+        let ty = node.expression.accept(self);
+        node.set_type(ty.clone());
+        ty
+    }
+}
+
+/// Checks that `program` declares its functions and types first and ends with exactly
+/// one global expression, its entry point.
+///
+/// # Returns
+/// An error for every statement out of place, and for a missing entry expression.
+pub fn structure_errors(program: &Program) -> Vec<SemanticError> {
+    let mut errors = Vec::new();
+    let mut has_entry = false;
+    for statement in program.statements.iter() {
+        let error = match statement {
+            Statement::StatementExpression(_) if !has_entry => {
+                has_entry = true;
+                continue;
+            }
+            Statement::StatementExpression(expr) => {
+                SemanticError::ExtraEntryExpression { span: expr.span() }
+            }
+            Statement::StatementFunctionDef(def) if has_entry => {
+                SemanticError::DeclarationAfterEntry {
+                    name: def.name.clone(),
+                    span: def.span,
+                }
+            }
+            Statement::StatementTypeDef(def) if has_entry => {
+                SemanticError::DeclarationAfterEntry {
+                    name: def.identifier.clone(),
+                    span: def.span,
+                }
+            }
+            _ => continue,
+        };
+        errors.push(error);
+    }
+    if !has_entry {
+        let end = program.span.end;
+        errors.push(SemanticError::MissingEntryExpression { span: Span::new(end, end) });
+    }
+    errors
+}
@@ -0,0 +1,216 @@
+//! `TailCallVisitor` implements the [`Visitor`] trait to find calls in tail position.
+//!
+//! A call is in tail position when its value is the value of the enclosing function
+//! body: the body itself, the branches of an `if`/`elif`/`else`, the body of a `let`
+//! and the last expression of a code block. Every other context (conditions, operands,
+//! arguments, loop bodies, `print`, assignments) still needs the call's result and
+//! resets the tail position.
+//!
+//! Calls to the enclosing global function are marked as [`TailCallKind::Recursive`] so
+//! code generation turns them into a jump back to the start of the function. Any other
+//! call in tail position, including method calls, is marked as [`TailCallKind::General`]
+//! and is left to the backend's tail-call mechanism.
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::BlockNode;
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::{FunctionCallNode, TailCallKind};
+use crate::ast_nodes::function_def::FunctionDefNode;
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::LetInNode;
+use crate::ast_nodes::literals::{BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::Program;
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+use crate::visitor::accept::Accept;
+use crate::visitor::visitor_trait::Visitor;
+
+/// A visitor that marks every call in tail position with its [`TailCallKind`].
+///
+/// Marks from a previous run are cleared, so the analysis can be repeated after the
+/// AST has been transformed.
+#[derive(Default)]
+pub struct TailCallVisitor {
+    /// Name of the global function whose body is being visited, `None` inside methods
+    /// and top-level expressions.
+    current_function: Option<String>,
+    /// Whether the expression being visited is in tail position.
+    in_tail: bool,
+    /// Number of calls marked as [`TailCallKind::Recursive`].
+    pub recursive_calls: usize,
+    /// Number of calls marked as [`TailCallKind::General`].
+    pub general_calls: usize,
+}
+
+impl TailCallVisitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks the tail calls of every function and method of the program.
+    ///
+    /// # Arguments
+    /// * `node` - The root `Program` node to analyze.
+    pub fn mark_program(&mut self, node: &mut Program) {
+        self.recursive_calls = 0;
+        self.general_calls = 0;
+        for statement in node.statements.iter_mut() {
+            self.in_tail = false;
+            self.current_function = None;
+            statement.accept(self);
+        }
+    }
+
+    /// Visits `expr` with the given tail position, restoring the previous one afterwards.
+    fn visit_in(&mut self, expr: &mut Expression, tail: bool) {
+        let previous = std::mem::replace(&mut self.in_tail, tail);
+        expr.accept(self);
+        self.in_tail = previous;
+    }
+
+    /// Visits a function or method body, where the body itself is in tail position.
+    fn visit_body(&mut self, body: &mut Expression, function: Option<String>) {
+        let previous_function = std::mem::replace(&mut self.current_function, function);
+        self.visit_in(body, true);
+        self.current_function = previous_function;
+    }
+
+    /// Computes the kind of a call to `function_name` at the current position.
+    fn tail_kind(&self, function_name: &str) -> Option<TailCallKind> {
+        if !self.in_tail {
+            return None;
+        }
+        match &self.current_function {
+            Some(current) if current == function_name => Some(TailCallKind::Recursive),
+            _ => Some(TailCallKind::General),
+        }
+    }
+
+    fn count(&mut self, kind: Option<TailCallKind>) {
+        match kind {
+            Some(TailCallKind::Recursive) => self.recursive_calls += 1,
+            Some(TailCallKind::General) => self.general_calls += 1,
+            None => {}
+        }
+    }
+}
+
+impl Visitor<()> for TailCallVisitor {
+    fn visit_function_def(&mut self, node: &mut FunctionDefNode) {
+        self.visit_body(&mut node.body, Some(node.name.clone()));
+    }
+
+    fn visit_literal_number(&mut self, _node: &mut NumberLiteralNode) {}
+
+    fn visit_literal_boolean(&mut self, _node: &mut BooleanLiteralNode) {}
+
+    fn visit_literal_string(&mut self, _node: &mut StringLiteralNode) {}
+
+    fn visit_identifier(&mut self, _node: &mut IdentifierNode) {}
+
+    fn visit_function_call(&mut self, node: &mut FunctionCallNode) {
+        let kind = self.tail_kind(&node.function_name);
+        node.set_tail_call(kind);
+        self.count(kind);
+        for arg in node.arguments.iter_mut() {
+            self.visit_in(arg, false);
+        }
+    }
+
+    fn visit_while_loop(&mut self, node: &mut WhileNode) {
+        self.visit_in(&mut node.condition, false);
+        self.visit_in(&mut node.body, false);
+    }
+
+    fn visit_for_loop(&mut self, node: &mut ForNode) {
+        self.visit_in(&mut node.start, false);
+        self.visit_in(&mut node.end, false);
+        self.visit_in(&mut node.body, false);
+    }
+
+    fn visit_code_block(&mut self, node: &mut BlockNode) {
+        let tail = self.in_tail;
+        let expressions = node.expression_list.expressions.iter_mut();
+        let last = expressions.len().saturating_sub(1);
+        for (i, expr) in expressions.enumerate() {
+            self.visit_in(expr, tail && i == last);
+        }
+    }
+
+    fn visit_binary_op(&mut self, node: &mut BinaryOpNode) {
+        self.visit_in(&mut node.left, false);
+        self.visit_in(&mut node.right, false);
+    }
+
+    fn visit_unary_op(&mut self, node: &mut UnaryOpNode) {
+        self.visit_in(&mut node.operand, false);
+    }
+
+    fn visit_if_else(&mut self, node: &mut IfElseNode) {
+        let tail = self.in_tail;
+        self.visit_in(&mut node.condition, false);
+        self.visit_in(&mut node.if_expression, tail);
+        for (condition, body) in node.elifs.iter_mut() {
+            if let Some(cond) = condition {
+                self.visit_in(cond, false);
+            }
+            self.visit_in(body, tail);
+        }
+    }
+
+    fn visit_let_in(&mut self, node: &mut LetInNode) {
+        let tail = self.in_tail;
+        for assignment in node.assignments.iter_mut() {
+            self.visit_in(&mut assignment.expression, false);
+        }
+        self.visit_in(&mut node.body, tail);
+    }
+
+    fn visit_destructive_assign(&mut self, node: &mut DestructiveAssignNode) {
+        self.visit_in(&mut node.identifier, false);
+        self.visit_in(&mut node.expression, false);
+    }
+
+    fn visit_type_def(&mut self, node: &mut TypeDefNode) {
+        for arg in node.parent_args.iter_mut() {
+            self.visit_in(arg, false);
+        }
+        for member in node.members.iter_mut() {
+            match member {
+                TypeMember::Property(assign) => self.visit_in(&mut assign.expression, false),
+                // Methods are dispatched dynamically, so no call inside them is recursive.
+                TypeMember::Method(method) => self.visit_body(&mut method.body, None),
+            }
+        }
+    }
+
+    fn visit_type_instance(&mut self, node: &mut TypeInstanceNode) {
+        for arg in node.arguments.iter_mut() {
+            self.visit_in(arg, false);
+        }
+    }
+
+    fn visit_type_function_access(&mut self, node: &mut TypeFunctionAccessNode) {
+        let kind = self.in_tail.then_some(TailCallKind::General);
+        node.member.set_tail_call(kind);
+        self.count(kind);
+        self.visit_in(&mut node.object, false);
+        for arg in node.member.arguments.iter_mut() {
+            self.visit_in(arg, false);
+        }
+    }
+
+    fn visit_type_prop_access(&mut self, node: &mut TypePropAccessNode) {
+        self.visit_in(&mut node.object, false);
+    }
+
+    fn visit_print(&mut self, node: &mut PrintNode) {
+        self.visit_in(&mut node.expression, false);
+    }
+}
@@ -1,23 +1,107 @@
 use std::collections::HashMap;
 
-#[allow(dead_code)]
+use crate::tokens::Span;
+
+/// A tree of lexical scopes.
+///
+/// Scopes are never removed when they are exited, so once a pass is done the whole
+/// tree is still available for later phases (tooling, debug output).
 #[derive(Debug)]
 pub struct SymbolTable {
-    symbols: HashMap<String, Symbol>,
+    pub scopes: Vec<Scope>,
+    current: usize,
 }
 
-#[allow(dead_code)]
 #[derive(Debug)]
+pub struct Scope {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub symbols: HashMap<String, Symbol>,
+}
+
+#[derive(Debug, Clone)]
 pub struct Symbol {
-    name: String,
-    symbol_type: SymbolType,
-    scope: String,
+    pub name: String,
+    pub symbol_type: SymbolType,
+    pub type_name: String,
+    pub span: Span,
 }
 
-#[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SymbolType {
     Variable,
+    Parameter,
     Function,
-    // Añadir más tipos según se necesite
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SymbolTable {
+    /// Creates a table holding only the global scope.
+    pub fn new() -> Self {
+        SymbolTable {
+            scopes: vec![Scope {
+                name: "global".to_string(),
+                parent: None,
+                children: Vec::new(),
+                symbols: HashMap::new(),
+            }],
+            current: 0,
+        }
+    }
+
+    /// Opens a new scope nested in the current one.
+    pub fn enter_scope(&mut self, name: &str) {
+        let id = self.scopes.len();
+        self.scopes.push(Scope {
+            name: name.to_string(),
+            parent: Some(self.current),
+            children: Vec::new(),
+            symbols: HashMap::new(),
+        });
+        self.scopes[self.current].children.push(id);
+        self.current = id;
+    }
+
+    /// Goes back to the parent of the current scope.
+    pub fn exit_scope(&mut self) {
+        if let Some(parent) = self.scopes[self.current].parent {
+            self.current = parent;
+        }
+    }
+
+    /// Declares `symbol` in the current scope, shadowing outer declarations.
+    pub fn insert(&mut self, symbol: Symbol) {
+        self.scopes[self.current]
+            .symbols
+            .insert(symbol.name.clone(), symbol);
+    }
+
+    /// Finds the innermost declaration of `name` visible from the current scope.
+    pub fn lookup(&self, name: &str) -> Option<&Symbol> {
+        let mut scope = Some(self.current);
+        while let Some(id) = scope {
+            if let Some(symbol) = self.scopes[id].symbols.get(name) {
+                return Some(symbol);
+            }
+            scope = self.scopes[id].parent;
+        }
+        None
+    }
+}
+
+impl Symbol {
+    pub fn new(name: &str, symbol_type: SymbolType, type_name: &str, span: Span) -> Self {
+        Symbol {
+            name: name.to_string(),
+            symbol_type,
+            type_name: type_name.to_string(),
+            span,
+        }
+    }
 }
@@ -0,0 +1,2 @@
+pub mod tree_node;
+pub mod type_tree;
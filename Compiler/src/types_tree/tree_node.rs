@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use crate::ast_nodes::function_def::FunctionParams;
+
+/// The signature of a global function or a method: parameter types and return type.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FunctionSignature {
+    pub name: String,
+    pub params: Vec<FunctionParams>,
+    pub return_type: String,
+}
+
+impl FunctionSignature {
+    pub fn new(name: String, params: Vec<FunctionParams>, return_type: String) -> Self {
+        FunctionSignature {
+            name,
+            params,
+            return_type,
+        }
+    }
+
+    /// Returns the declared type name of every parameter, in order.
+    pub fn param_types(&self) -> Vec<String> {
+        self.params.iter().map(|param| param.signature.clone()).collect()
+    }
+}
+
+/// A node of the HULK type hierarchy.
+///
+/// AST nodes keep a copy of the `TypeNode` of their static type once the
+/// semantic phase has computed it.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TypeNode {
+    pub type_name: String,
+    pub depth: usize,
+    pub parent: Option<String>,
+    pub children: Vec<String>,
+    /// Constructor parameters. A type that declares neither parameters nor parent
+    /// arguments takes the parameters of its parent.
+    pub params: Vec<FunctionParams>,
+    /// Attributes declared by the type itself, as `(name, type)` in declaration order.
+    pub attributes: Vec<(String, String)>,
+    /// Methods declared by the type itself, in declaration order.
+    pub methods: Vec<FunctionSignature>,
+}
+
+impl TypeNode {
+    pub fn new(type_name: &str, parent: Option<String>, depth: usize) -> Self {
+        TypeNode {
+            type_name: type_name.to_string(),
+            depth,
+            parent,
+            children: Vec::new(),
+            params: Vec::new(),
+            attributes: Vec::new(),
+            methods: Vec::new(),
+        }
+    }
+
+    pub fn add_child(&mut self, child: String) {
+        self.children.push(child);
+    }
+
+    pub fn add_attribute(&mut self, name: String, type_name: String) {
+        self.attributes.push((name, type_name));
+    }
+
+    pub fn add_method(&mut self, method: FunctionSignature) {
+        self.methods.push(method);
+    }
+
+    pub fn get_attribute(&self, name: &str) -> Option<&String> {
+        self.attributes.iter().find(|(attr, _)| attr == name).map(|(_, ty)| ty)
+    }
+
+    pub fn get_method(&self, name: &str) -> Option<&FunctionSignature> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+}
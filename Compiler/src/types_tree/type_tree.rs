@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use crate::ast_nodes::function_def::FunctionParams;
+
+use super::tree_node::{FunctionSignature, TypeNode};
+
+/// Name of the root of the hierarchy.
+pub const OBJECT: &str = "Object";
+pub const NUMBER: &str = "Number";
+pub const STRING: &str = "String";
+pub const BOOLEAN: &str = "Boolean";
+/// Internal type given to expressions whose type could not be computed.
+///
+/// It conforms to every type, so a single error does not cascade into many.
+pub const UNKNOWN: &str = "<unknown>";
+
+/// The HULK type hierarchy, rooted at `Object`.
+#[derive(Debug, Clone)]
+pub struct TypeTree {
+    pub root: String,
+    pub nodes: HashMap<String, TypeNode>,
+}
+
+impl Default for TypeTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypeTree {
+    /// Creates a tree holding only the builtin types.
+    pub fn new() -> Self {
+        let mut tree = TypeTree {
+            root: OBJECT.to_string(),
+            nodes: HashMap::new(),
+        };
+        tree.nodes.insert(OBJECT.to_string(), TypeNode::new(OBJECT, None, 0));
+        tree.nodes.insert(UNKNOWN.to_string(), TypeNode::new(UNKNOWN, None, 0));
+        for builtin in [NUMBER, STRING, BOOLEAN] {
+            tree.add_type(builtin, OBJECT);
+        }
+        tree
+    }
+
+    /// Returns whether `name` is one of the types every program gets for free.
+    pub fn is_builtin(name: &str) -> bool {
+        matches!(name, OBJECT | NUMBER | STRING | BOOLEAN | UNKNOWN)
+    }
+
+    /// Returns whether values of `name` are plain values rather than objects.
+    pub fn is_primitive(name: &str) -> bool {
+        matches!(name, NUMBER | STRING | BOOLEAN)
+    }
+
+    /// Adds `name` as a child of `parent`, which must already be in the tree.
+    pub fn add_type(&mut self, name: &str, parent: &str) {
+        let depth = self.nodes.get(parent).map_or(0, |node| node.depth) + 1;
+        self.nodes
+            .insert(name.to_string(), TypeNode::new(name, Some(parent.to_string()), depth));
+        if let Some(parent_node) = self.nodes.get_mut(parent) {
+            parent_node.add_child(name.to_string());
+        }
+    }
+
+    pub fn get_type(&self, name: &str) -> Option<&TypeNode> {
+        self.nodes.get(name)
+    }
+
+    pub fn get_type_mut(&mut self, name: &str) -> Option<&mut TypeNode> {
+        self.nodes.get_mut(name)
+    }
+
+    /// Returns a copy of the node for `name`, or the unknown type if it does not exist.
+    pub fn type_node(&self, name: &str) -> TypeNode {
+        self.nodes
+            .get(name)
+            .or_else(|| self.nodes.get(UNKNOWN))
+            .cloned()
+            .unwrap()
+    }
+
+    /// Returns `name` followed by all its ancestors up to `Object`.
+    pub fn ancestors(&self, name: &str) -> Vec<String> {
+        let mut chain = Vec::new();
+        let mut current = self.nodes.get(name);
+        while let Some(node) = current {
+            chain.push(node.type_name.clone());
+            current = node.parent.as_ref().and_then(|parent| self.nodes.get(parent));
+        }
+        chain
+    }
+
+    /// Returns whether a value of type `child` can be used where `parent` is expected.
+    pub fn conforms(&self, child: &str, parent: &str) -> bool {
+        if child == UNKNOWN || parent == UNKNOWN {
+            return true;
+        }
+        self.ancestors(child).iter().any(|ancestor| ancestor == parent)
+    }
+
+    /// Returns the most specific type both `a` and `b` conform to.
+    pub fn lowest_common_ancestor(&self, a: &str, b: &str) -> String {
+        if a == UNKNOWN {
+            return b.to_string();
+        }
+        if b == UNKNOWN {
+            return a.to_string();
+        }
+        let b_ancestors = self.ancestors(b);
+        self.ancestors(a)
+            .into_iter()
+            .find(|ancestor| b_ancestors.contains(ancestor))
+            .unwrap_or_else(|| self.root.clone())
+    }
+
+    /// Returns whether some user type inherits from `name`.
+    pub fn has_subtypes(&self, name: &str) -> bool {
+        self.nodes
+            .get(name)
+            .is_some_and(|node| !node.children.is_empty())
+    }
+
+    /// Looks `method` up in `type_name` and its ancestors.
+    ///
+    /// # Returns
+    /// The name of the type that declares the method together with its signature.
+    pub fn find_method(&self, type_name: &str, method: &str) -> Option<(String, &FunctionSignature)> {
+        self.ancestors(type_name).into_iter().find_map(|ancestor| {
+            self.nodes[&ancestor]
+                .get_method(method)
+                .map(|signature| (ancestor.clone(), signature))
+        })
+    }
+
+    /// Looks `attribute` up in `type_name` and its ancestors and returns its type.
+    pub fn find_attribute(&self, type_name: &str, attribute: &str) -> Option<&String> {
+        self.ancestors(type_name)
+            .into_iter()
+            .find_map(|ancestor| self.nodes[&ancestor].get_attribute(attribute))
+    }
+
+    /// Returns the parameters `new type_name(...)` expects.
+    pub fn constructor_params(&self, type_name: &str) -> Vec<FunctionParams> {
+        self.nodes
+            .get(type_name)
+            .map(|node| node.params.clone())
+            .unwrap_or_default()
+    }
+}
@@ -10,8 +10,6 @@ use super::visitor_trait::Visitor;
 /// # Type Parameters
 /// - `V`: A concrete type implementing the [`Visitor`] trait.
 /// - `T`: The return type produced by the visitor operation.
-///
-
 pub trait Accept {
     /// Accepts a mutable visitor, allowing it to operate on the implementing node.
     ///
@@ -0,0 +1,370 @@
+//! Defines the `Folder` trait, which consumes the AST and rebuilds it, for passes that
+//! rewrite the tree such as desugaring.
+//!
+//! Every `fold_*` method takes a node by value and returns what replaces it. The methods
+//! of expressions return an [`Expression`], so a pass may replace a node with one of a
+//! different kind. By default they rebuild the node with the `walk_*` function of the
+//! same name, which folds its children in the order documented in [the module](super)
+//! and keeps every other field, including the span and the static type.
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::{BlockNode, ExpressionList};
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::FunctionCallNode;
+use crate::ast_nodes::function_def::FunctionDefNode;
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::{Assignment, LetInNode};
+use crate::ast_nodes::literals::{
+    BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode,
+};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::{Program, Statement};
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+
+/// A pass that rebuilds the AST, node by node.
+///
+/// Leaves have no children, so their methods default to returning them unchanged.
+pub trait Folder {
+    fn fold_program(&mut self, node: Program) -> Program {
+        walk_program(self, node)
+    }
+
+    fn fold_statement(&mut self, node: Statement) -> Statement {
+        walk_statement(self, node)
+    }
+
+    /// Dispatches to the method of the node `node` holds.
+    fn fold_expression(&mut self, node: Expression) -> Expression {
+        walk_expression(self, node)
+    }
+
+    /// Folds a global function or a method of a type.
+    fn fold_function_def(&mut self, node: FunctionDefNode) -> FunctionDefNode {
+        walk_function_def(self, node)
+    }
+
+    fn fold_type_def(&mut self, node: TypeDefNode) -> TypeDefNode {
+        walk_type_def(self, node)
+    }
+
+    /// Folds a binding of a `let` or a property of a type.
+    fn fold_assignment(&mut self, node: Assignment) -> Assignment {
+        walk_assignment(self, node)
+    }
+
+    fn fold_literal_number(&mut self, node: NumberLiteralNode) -> Expression {
+        Expression::Number(node)
+    }
+
+    fn fold_literal_boolean(&mut self, node: BooleanLiteralNode) -> Expression {
+        Expression::Boolean(node)
+    }
+
+    fn fold_literal_string(&mut self, node: StringLiteralNode) -> Expression {
+        Expression::Str(node)
+    }
+
+    fn fold_identifier(&mut self, node: IdentifierNode) -> Expression {
+        Expression::Identifier(node)
+    }
+
+    fn fold_function_call(&mut self, node: FunctionCallNode) -> Expression {
+        Expression::FunctionCall(walk_function_call(self, node))
+    }
+
+    fn fold_while_loop(&mut self, node: WhileNode) -> Expression {
+        Expression::WhileLoop(walk_while_loop(self, node))
+    }
+
+    fn fold_for_loop(&mut self, node: ForNode) -> Expression {
+        Expression::ForLoop(walk_for_loop(self, node))
+    }
+
+    fn fold_code_block(&mut self, node: BlockNode) -> Expression {
+        Expression::CodeBlock(walk_code_block(self, node))
+    }
+
+    fn fold_binary_op(&mut self, node: BinaryOpNode) -> Expression {
+        Expression::BinaryOp(walk_binary_op(self, node))
+    }
+
+    fn fold_unary_op(&mut self, node: UnaryOpNode) -> Expression {
+        Expression::UnaryOp(walk_unary_op(self, node))
+    }
+
+    fn fold_if_else(&mut self, node: IfElseNode) -> Expression {
+        Expression::IfElse(walk_if_else(self, node))
+    }
+
+    fn fold_let_in(&mut self, node: LetInNode) -> Expression {
+        Expression::LetIn(walk_let_in(self, node))
+    }
+
+    fn fold_destructive_assign(&mut self, node: DestructiveAssignNode) -> Expression {
+        Expression::DestructiveAssign(walk_destructive_assign(self, node))
+    }
+
+    fn fold_type_instance(&mut self, node: TypeInstanceNode) -> Expression {
+        Expression::TypeInstance(walk_type_instance(self, node))
+    }
+
+    fn fold_type_function_access(&mut self, node: TypeFunctionAccessNode) -> Expression {
+        Expression::TypeFunctionAccess(walk_type_function_access(self, node))
+    }
+
+    fn fold_type_prop_access(&mut self, node: TypePropAccessNode) -> Expression {
+        Expression::TypePropAccess(walk_type_prop_access(self, node))
+    }
+
+    fn fold_print(&mut self, node: PrintNode) -> Expression {
+        Expression::Print(walk_print(self, node))
+    }
+}
+
+fn fold_box<F: Folder + ?Sized>(folder: &mut F, node: Box<Expression>) -> Box<Expression> {
+    Box::new(folder.fold_expression(*node))
+}
+
+fn fold_all<F: Folder + ?Sized>(folder: &mut F, nodes: Vec<Expression>) -> Vec<Expression> {
+    nodes
+        .into_iter()
+        .map(|node| folder.fold_expression(node))
+        .collect()
+}
+
+pub fn walk_program<F: Folder + ?Sized>(folder: &mut F, node: Program) -> Program {
+    let statements = node
+        .statements
+        .into_iter()
+        .map(|statement| folder.fold_statement(statement))
+        .collect();
+    Program::new(statements, node.span)
+}
+
+pub fn walk_statement<F: Folder + ?Sized>(folder: &mut F, node: Statement) -> Statement {
+    match node {
+        Statement::StatementExpression(expression) => {
+            Statement::StatementExpression(fold_box(folder, expression))
+        }
+        Statement::StatementFunctionDef(function) => {
+            Statement::new_function_def(folder.fold_function_def(*function))
+        }
+        Statement::StatementTypeDef(type_def) => {
+            Statement::new_type_def(folder.fold_type_def(*type_def))
+        }
+    }
+}
+
+pub fn walk_expression<F: Folder + ?Sized>(folder: &mut F, node: Expression) -> Expression {
+    match node {
+        Expression::Number(node) => folder.fold_literal_number(node),
+        Expression::Boolean(node) => folder.fold_literal_boolean(node),
+        Expression::Str(node) => folder.fold_literal_string(node),
+        Expression::Identifier(node) => folder.fold_identifier(node),
+        Expression::FunctionCall(node) => folder.fold_function_call(node),
+        Expression::WhileLoop(node) => folder.fold_while_loop(node),
+        Expression::ForLoop(node) => folder.fold_for_loop(node),
+        Expression::CodeBlock(node) => folder.fold_code_block(node),
+        Expression::BinaryOp(node) => folder.fold_binary_op(node),
+        Expression::UnaryOp(node) => folder.fold_unary_op(node),
+        Expression::IfElse(node) => folder.fold_if_else(node),
+        Expression::LetIn(node) => folder.fold_let_in(node),
+        Expression::DestructiveAssign(node) => folder.fold_destructive_assign(node),
+        Expression::TypeInstance(node) => folder.fold_type_instance(node),
+        Expression::TypeFunctionAccess(node) => folder.fold_type_function_access(node),
+        Expression::TypePropAccess(node) => folder.fold_type_prop_access(node),
+        Expression::Print(node) => folder.fold_print(node),
+    }
+}
+
+pub fn walk_function_def<F: Folder + ?Sized>(
+    folder: &mut F,
+    node: FunctionDefNode,
+) -> FunctionDefNode {
+    FunctionDefNode {
+        body: folder.fold_expression(node.body),
+        ..node
+    }
+}
+
+pub fn walk_type_def<F: Folder + ?Sized>(folder: &mut F, node: TypeDefNode) -> TypeDefNode {
+    let parent_args = fold_all(folder, node.parent_args);
+    let members = node
+        .members
+        .into_iter()
+        .map(|member| match member {
+            TypeMember::Property(assignment) => {
+                TypeMember::Property(folder.fold_assignment(assignment))
+            }
+            TypeMember::Method(method) => {
+                TypeMember::Method(Box::new(folder.fold_function_def(*method)))
+            }
+        })
+        .collect();
+    TypeDefNode {
+        parent_args,
+        members,
+        ..node
+    }
+}
+
+pub fn walk_assignment<F: Folder + ?Sized>(folder: &mut F, node: Assignment) -> Assignment {
+    Assignment {
+        expression: fold_box(folder, node.expression),
+        ..node
+    }
+}
+
+pub fn walk_function_call<F: Folder + ?Sized>(
+    folder: &mut F,
+    node: FunctionCallNode,
+) -> FunctionCallNode {
+    FunctionCallNode {
+        arguments: fold_all(folder, node.arguments),
+        ..node
+    }
+}
+
+pub fn walk_while_loop<F: Folder + ?Sized>(folder: &mut F, node: WhileNode) -> WhileNode {
+    let condition = fold_box(folder, node.condition);
+    let body = fold_box(folder, node.body);
+    WhileNode {
+        condition,
+        body,
+        ..node
+    }
+}
+
+pub fn walk_for_loop<F: Folder + ?Sized>(folder: &mut F, node: ForNode) -> ForNode {
+    let start = fold_box(folder, node.start);
+    let end = fold_box(folder, node.end);
+    let body = fold_box(folder, node.body);
+    ForNode {
+        start,
+        end,
+        body,
+        ..node
+    }
+}
+
+pub fn walk_code_block<F: Folder + ?Sized>(folder: &mut F, node: BlockNode) -> BlockNode {
+    let expressions = fold_all(folder, *node.expression_list.expressions);
+    BlockNode {
+        expression_list: Box::new(ExpressionList::new(expressions)),
+        ..node
+    }
+}
+
+pub fn walk_binary_op<F: Folder + ?Sized>(folder: &mut F, node: BinaryOpNode) -> BinaryOpNode {
+    let left = fold_box(folder, node.left);
+    let right = fold_box(folder, node.right);
+    BinaryOpNode {
+        left,
+        right,
+        ..node
+    }
+}
+
+pub fn walk_unary_op<F: Folder + ?Sized>(folder: &mut F, node: UnaryOpNode) -> UnaryOpNode {
+    UnaryOpNode {
+        operand: fold_box(folder, node.operand),
+        ..node
+    }
+}
+
+pub fn walk_if_else<F: Folder + ?Sized>(folder: &mut F, node: IfElseNode) -> IfElseNode {
+    let condition = fold_box(folder, node.condition);
+    let if_expression = fold_box(folder, node.if_expression);
+    let elifs = node
+        .elifs
+        .into_iter()
+        .map(|(condition, body)| {
+            let condition = condition.map(|condition| folder.fold_expression(condition));
+            (condition, folder.fold_expression(body))
+        })
+        .collect();
+    IfElseNode {
+        condition,
+        if_expression,
+        elifs,
+        ..node
+    }
+}
+
+pub fn walk_let_in<F: Folder + ?Sized>(folder: &mut F, node: LetInNode) -> LetInNode {
+    let assignments = node
+        .assignments
+        .into_iter()
+        .map(|assignment| folder.fold_assignment(assignment))
+        .collect();
+    let body = fold_box(folder, node.body);
+    LetInNode {
+        assignments,
+        body,
+        ..node
+    }
+}
+
+pub fn walk_destructive_assign<F: Folder + ?Sized>(
+    folder: &mut F,
+    node: DestructiveAssignNode,
+) -> DestructiveAssignNode {
+    let identifier = fold_box(folder, node.identifier);
+    let expression = fold_box(folder, node.expression);
+    DestructiveAssignNode {
+        identifier,
+        expression,
+        ..node
+    }
+}
+
+pub fn walk_type_instance<F: Folder + ?Sized>(
+    folder: &mut F,
+    node: TypeInstanceNode,
+) -> TypeInstanceNode {
+    TypeInstanceNode {
+        arguments: fold_all(folder, node.arguments),
+        ..node
+    }
+}
+
+/// Folds the receiver and then the arguments. The call in `member` is not a call to a
+/// global function, so it does not go through `fold_function_call`.
+pub fn walk_type_function_access<F: Folder + ?Sized>(
+    folder: &mut F,
+    node: TypeFunctionAccessNode,
+) -> TypeFunctionAccessNode {
+    let object = fold_box(folder, node.object);
+    let member = FunctionCallNode {
+        arguments: fold_all(folder, node.member.arguments),
+        ..*node.member
+    };
+    TypeFunctionAccessNode {
+        object,
+        member: Box::new(member),
+        ..node
+    }
+}
+
+pub fn walk_type_prop_access<F: Folder + ?Sized>(
+    folder: &mut F,
+    node: TypePropAccessNode,
+) -> TypePropAccessNode {
+    TypePropAccessNode {
+        object: fold_box(folder, node.object),
+        ..node
+    }
+}
+
+pub fn walk_print<F: Folder + ?Sized>(folder: &mut F, node: PrintNode) -> PrintNode {
+    PrintNode {
+        expression: fold_box(folder, node.expression),
+        ..node
+    }
+}
@@ -1,3 +1,32 @@
+//! Traversals of the AST.
+//!
+//! - [`visitor_trait::Visitor`] asks for one method per node and returns a value from
+//!   each, for passes such as type checking and code generation that handle every node.
+//! - [`mut_visitor::MutVisitor`] and [`ref_visitor::RefVisitor`] walk the whole tree by
+//!   default, over `&mut` and `&` nodes, so a pass overrides only the nodes it cares about.
+//! - [`folder::Folder`] consumes the tree and rebuilds it, for passes that rewrite it.
+//!
+//! # Traversal order
+//!
+//! The walking traits visit a node before its children, and the children in the order
+//! they appear in the source:
+//!
+//! - program: statements; function: body; type: parent arguments, then properties and
+//!   methods as declared.
+//! - `let`: every binding, then the body; `if`: condition, then branch, then each
+//!   `elif` condition and branch, then the `else` branch.
+//! - `while`: condition, then body; `for`: start, end, then body.
+//! - call, `new` and block: arguments or expressions, left to right.
+//! - binary operator: left, then right; `:=`: target, then value; method call:
+//!   receiver, then arguments.
+//!
+//! Overriding a method and calling the matching `walk_*` function at its end instead
+//! visits the children first.
+
 pub mod visitor_trait;
 pub mod accept;
-pub mod printer_visitor;
\ No newline at end of file
+pub mod mut_visitor;
+pub mod ref_visitor;
+pub mod folder;
+pub mod tree_printer;
+pub mod sexp_printer;
@@ -0,0 +1,261 @@
+//! Defines the `MutVisitor` trait, a visitor over `&mut` nodes that walks the whole AST
+//! by default.
+//!
+//! Every `visit_*` method defaults to the `walk_*` function of the same node, which
+//! visits its children in the order documented in [the module](super). A pass overrides
+//! only the nodes it cares about and calls `walk_*` from them to keep descending.
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::BlockNode;
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::FunctionCallNode;
+use crate::ast_nodes::function_def::FunctionDefNode;
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::{Assignment, LetInNode};
+use crate::ast_nodes::literals::{
+    BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode,
+};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::{Program, Statement};
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+
+/// A visitor that may modify the nodes in place and returns nothing.
+///
+/// Leaves have no children, so their methods default to doing nothing.
+pub trait MutVisitor {
+    fn visit_program(&mut self, node: &mut Program) {
+        walk_program(self, node)
+    }
+
+    fn visit_statement(&mut self, node: &mut Statement) {
+        walk_statement(self, node)
+    }
+
+    /// Dispatches to the method of the node `node` holds.
+    fn visit_expression(&mut self, node: &mut Expression) {
+        walk_expression(self, node)
+    }
+
+    /// Visits a global function or a method of a type.
+    fn visit_function_def(&mut self, node: &mut FunctionDefNode) {
+        walk_function_def(self, node)
+    }
+
+    fn visit_type_def(&mut self, node: &mut TypeDefNode) {
+        walk_type_def(self, node)
+    }
+
+    /// Visits a binding of a `let` or a property of a type.
+    fn visit_assignment(&mut self, node: &mut Assignment) {
+        walk_assignment(self, node)
+    }
+
+    fn visit_literal_number(&mut self, _node: &mut NumberLiteralNode) {}
+
+    fn visit_literal_boolean(&mut self, _node: &mut BooleanLiteralNode) {}
+
+    fn visit_literal_string(&mut self, _node: &mut StringLiteralNode) {}
+
+    fn visit_identifier(&mut self, _node: &mut IdentifierNode) {}
+
+    fn visit_function_call(&mut self, node: &mut FunctionCallNode) {
+        walk_function_call(self, node)
+    }
+
+    fn visit_while_loop(&mut self, node: &mut WhileNode) {
+        walk_while_loop(self, node)
+    }
+
+    fn visit_for_loop(&mut self, node: &mut ForNode) {
+        walk_for_loop(self, node)
+    }
+
+    fn visit_code_block(&mut self, node: &mut BlockNode) {
+        walk_code_block(self, node)
+    }
+
+    fn visit_binary_op(&mut self, node: &mut BinaryOpNode) {
+        walk_binary_op(self, node)
+    }
+
+    fn visit_unary_op(&mut self, node: &mut UnaryOpNode) {
+        walk_unary_op(self, node)
+    }
+
+    fn visit_if_else(&mut self, node: &mut IfElseNode) {
+        walk_if_else(self, node)
+    }
+
+    fn visit_let_in(&mut self, node: &mut LetInNode) {
+        walk_let_in(self, node)
+    }
+
+    fn visit_destructive_assign(&mut self, node: &mut DestructiveAssignNode) {
+        walk_destructive_assign(self, node)
+    }
+
+    fn visit_type_instance(&mut self, node: &mut TypeInstanceNode) {
+        walk_type_instance(self, node)
+    }
+
+    fn visit_type_function_access(&mut self, node: &mut TypeFunctionAccessNode) {
+        walk_type_function_access(self, node)
+    }
+
+    fn visit_type_prop_access(&mut self, node: &mut TypePropAccessNode) {
+        walk_type_prop_access(self, node)
+    }
+
+    fn visit_print(&mut self, node: &mut PrintNode) {
+        walk_print(self, node)
+    }
+}
+
+pub fn walk_program<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut Program) {
+    for statement in node.statements.iter_mut() {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut Statement) {
+    match node {
+        Statement::StatementExpression(expression) => visitor.visit_expression(expression),
+        Statement::StatementFunctionDef(function) => visitor.visit_function_def(function),
+        Statement::StatementTypeDef(type_def) => visitor.visit_type_def(type_def),
+    }
+}
+
+pub fn walk_expression<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut Expression) {
+    match node {
+        Expression::Number(node) => visitor.visit_literal_number(node),
+        Expression::Boolean(node) => visitor.visit_literal_boolean(node),
+        Expression::Str(node) => visitor.visit_literal_string(node),
+        Expression::Identifier(node) => visitor.visit_identifier(node),
+        Expression::FunctionCall(node) => visitor.visit_function_call(node),
+        Expression::WhileLoop(node) => visitor.visit_while_loop(node),
+        Expression::ForLoop(node) => visitor.visit_for_loop(node),
+        Expression::CodeBlock(node) => visitor.visit_code_block(node),
+        Expression::BinaryOp(node) => visitor.visit_binary_op(node),
+        Expression::UnaryOp(node) => visitor.visit_unary_op(node),
+        Expression::IfElse(node) => visitor.visit_if_else(node),
+        Expression::LetIn(node) => visitor.visit_let_in(node),
+        Expression::DestructiveAssign(node) => visitor.visit_destructive_assign(node),
+        Expression::TypeInstance(node) => visitor.visit_type_instance(node),
+        Expression::TypeFunctionAccess(node) => visitor.visit_type_function_access(node),
+        Expression::TypePropAccess(node) => visitor.visit_type_prop_access(node),
+        Expression::Print(node) => visitor.visit_print(node),
+    }
+}
+
+pub fn walk_function_def<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut FunctionDefNode) {
+    visitor.visit_expression(&mut node.body);
+}
+
+pub fn walk_type_def<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut TypeDefNode) {
+    for argument in node.parent_args.iter_mut() {
+        visitor.visit_expression(argument);
+    }
+    for member in node.members.iter_mut() {
+        match member {
+            TypeMember::Property(assignment) => visitor.visit_assignment(assignment),
+            TypeMember::Method(method) => visitor.visit_function_def(method),
+        }
+    }
+}
+
+pub fn walk_assignment<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut Assignment) {
+    visitor.visit_expression(&mut node.expression);
+}
+
+pub fn walk_function_call<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut FunctionCallNode) {
+    for argument in node.arguments.iter_mut() {
+        visitor.visit_expression(argument);
+    }
+}
+
+pub fn walk_while_loop<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut WhileNode) {
+    visitor.visit_expression(&mut node.condition);
+    visitor.visit_expression(&mut node.body);
+}
+
+pub fn walk_for_loop<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut ForNode) {
+    visitor.visit_expression(&mut node.start);
+    visitor.visit_expression(&mut node.end);
+    visitor.visit_expression(&mut node.body);
+}
+
+pub fn walk_code_block<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut BlockNode) {
+    for expression in node.expression_list.expressions.iter_mut() {
+        visitor.visit_expression(expression);
+    }
+}
+
+pub fn walk_binary_op<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut BinaryOpNode) {
+    visitor.visit_expression(&mut node.left);
+    visitor.visit_expression(&mut node.right);
+}
+
+pub fn walk_unary_op<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut UnaryOpNode) {
+    visitor.visit_expression(&mut node.operand);
+}
+
+pub fn walk_if_else<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut IfElseNode) {
+    visitor.visit_expression(&mut node.condition);
+    visitor.visit_expression(&mut node.if_expression);
+    for (condition, body) in node.elifs.iter_mut() {
+        if let Some(condition) = condition {
+            visitor.visit_expression(condition);
+        }
+        visitor.visit_expression(body);
+    }
+}
+
+pub fn walk_let_in<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut LetInNode) {
+    for assignment in node.assignments.iter_mut() {
+        visitor.visit_assignment(assignment);
+    }
+    visitor.visit_expression(&mut node.body);
+}
+
+pub fn walk_destructive_assign<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    node: &mut DestructiveAssignNode,
+) {
+    visitor.visit_expression(&mut node.identifier);
+    visitor.visit_expression(&mut node.expression);
+}
+
+pub fn walk_type_instance<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut TypeInstanceNode) {
+    for argument in node.arguments.iter_mut() {
+        visitor.visit_expression(argument);
+    }
+}
+
+/// Visits the receiver and then the arguments. The call in `member` is not a call to a
+/// global function, so it does not go through `visit_function_call`.
+pub fn walk_type_function_access<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    node: &mut TypeFunctionAccessNode,
+) {
+    visitor.visit_expression(&mut node.object);
+    for argument in node.member.arguments.iter_mut() {
+        visitor.visit_expression(argument);
+    }
+}
+
+pub fn walk_type_prop_access<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    node: &mut TypePropAccessNode,
+) {
+    visitor.visit_expression(&mut node.object);
+}
+
+pub fn walk_print<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut PrintNode) {
+    visitor.visit_expression(&mut node.expression);
+}
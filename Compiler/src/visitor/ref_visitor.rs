@@ -0,0 +1,281 @@
+//! Defines the `RefVisitor` trait, a read-only visitor over `&` nodes that walks the
+//! whole AST by default.
+//!
+//! It mirrors [`MutVisitor`](super::mut_visitor::MutVisitor) for passes that only read
+//! the tree, such as printers and queries, so they do not need a mutable AST. Every
+//! `visit_*` method defaults to the `walk_*` function of the same node, which visits its
+//! children in the order documented in [the module](super). The nodes live for `'ast`,
+//! so a visitor may keep references to them.
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::BlockNode;
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::FunctionCallNode;
+use crate::ast_nodes::function_def::FunctionDefNode;
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::{Assignment, LetInNode};
+use crate::ast_nodes::literals::{
+    BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode,
+};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::{Program, Statement};
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+
+/// A visitor that reads the nodes and returns nothing.
+///
+/// Leaves have no children, so their methods default to doing nothing.
+pub trait RefVisitor<'ast> {
+    fn visit_program(&mut self, node: &'ast Program) {
+        walk_program(self, node)
+    }
+
+    fn visit_statement(&mut self, node: &'ast Statement) {
+        walk_statement(self, node)
+    }
+
+    /// Dispatches to the method of the node `node` holds.
+    fn visit_expression(&mut self, node: &'ast Expression) {
+        walk_expression(self, node)
+    }
+
+    /// Visits a global function or a method of a type.
+    fn visit_function_def(&mut self, node: &'ast FunctionDefNode) {
+        walk_function_def(self, node)
+    }
+
+    fn visit_type_def(&mut self, node: &'ast TypeDefNode) {
+        walk_type_def(self, node)
+    }
+
+    /// Visits a binding of a `let` or a property of a type.
+    fn visit_assignment(&mut self, node: &'ast Assignment) {
+        walk_assignment(self, node)
+    }
+
+    fn visit_literal_number(&mut self, _node: &'ast NumberLiteralNode) {}
+
+    fn visit_literal_boolean(&mut self, _node: &'ast BooleanLiteralNode) {}
+
+    fn visit_literal_string(&mut self, _node: &'ast StringLiteralNode) {}
+
+    fn visit_identifier(&mut self, _node: &'ast IdentifierNode) {}
+
+    fn visit_function_call(&mut self, node: &'ast FunctionCallNode) {
+        walk_function_call(self, node)
+    }
+
+    fn visit_while_loop(&mut self, node: &'ast WhileNode) {
+        walk_while_loop(self, node)
+    }
+
+    fn visit_for_loop(&mut self, node: &'ast ForNode) {
+        walk_for_loop(self, node)
+    }
+
+    fn visit_code_block(&mut self, node: &'ast BlockNode) {
+        walk_code_block(self, node)
+    }
+
+    fn visit_binary_op(&mut self, node: &'ast BinaryOpNode) {
+        walk_binary_op(self, node)
+    }
+
+    fn visit_unary_op(&mut self, node: &'ast UnaryOpNode) {
+        walk_unary_op(self, node)
+    }
+
+    fn visit_if_else(&mut self, node: &'ast IfElseNode) {
+        walk_if_else(self, node)
+    }
+
+    fn visit_let_in(&mut self, node: &'ast LetInNode) {
+        walk_let_in(self, node)
+    }
+
+    fn visit_destructive_assign(&mut self, node: &'ast DestructiveAssignNode) {
+        walk_destructive_assign(self, node)
+    }
+
+    fn visit_type_instance(&mut self, node: &'ast TypeInstanceNode) {
+        walk_type_instance(self, node)
+    }
+
+    fn visit_type_function_access(&mut self, node: &'ast TypeFunctionAccessNode) {
+        walk_type_function_access(self, node)
+    }
+
+    fn visit_type_prop_access(&mut self, node: &'ast TypePropAccessNode) {
+        walk_type_prop_access(self, node)
+    }
+
+    fn visit_print(&mut self, node: &'ast PrintNode) {
+        walk_print(self, node)
+    }
+}
+
+pub fn walk_program<'ast, V: RefVisitor<'ast> + ?Sized>(visitor: &mut V, node: &'ast Program) {
+    for statement in node.statements.iter() {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<'ast, V: RefVisitor<'ast> + ?Sized>(visitor: &mut V, node: &'ast Statement) {
+    match node {
+        Statement::StatementExpression(expression) => visitor.visit_expression(expression),
+        Statement::StatementFunctionDef(function) => visitor.visit_function_def(function),
+        Statement::StatementTypeDef(type_def) => visitor.visit_type_def(type_def),
+    }
+}
+
+pub fn walk_expression<'ast, V: RefVisitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    node: &'ast Expression,
+) {
+    match node {
+        Expression::Number(node) => visitor.visit_literal_number(node),
+        Expression::Boolean(node) => visitor.visit_literal_boolean(node),
+        Expression::Str(node) => visitor.visit_literal_string(node),
+        Expression::Identifier(node) => visitor.visit_identifier(node),
+        Expression::FunctionCall(node) => visitor.visit_function_call(node),
+        Expression::WhileLoop(node) => visitor.visit_while_loop(node),
+        Expression::ForLoop(node) => visitor.visit_for_loop(node),
+        Expression::CodeBlock(node) => visitor.visit_code_block(node),
+        Expression::BinaryOp(node) => visitor.visit_binary_op(node),
+        Expression::UnaryOp(node) => visitor.visit_unary_op(node),
+        Expression::IfElse(node) => visitor.visit_if_else(node),
+        Expression::LetIn(node) => visitor.visit_let_in(node),
+        Expression::DestructiveAssign(node) => visitor.visit_destructive_assign(node),
+        Expression::TypeInstance(node) => visitor.visit_type_instance(node),
+        Expression::TypeFunctionAccess(node) => visitor.visit_type_function_access(node),
+        Expression::TypePropAccess(node) => visitor.visit_type_prop_access(node),
+        Expression::Print(node) => visitor.visit_print(node),
+    }
+}
+
+pub fn walk_function_def<'ast, V: RefVisitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    node: &'ast FunctionDefNode,
+) {
+    visitor.visit_expression(&node.body);
+}
+
+pub fn walk_type_def<'ast, V: RefVisitor<'ast> + ?Sized>(visitor: &mut V, node: &'ast TypeDefNode) {
+    for argument in node.parent_args.iter() {
+        visitor.visit_expression(argument);
+    }
+    for member in node.members.iter() {
+        match member {
+            TypeMember::Property(assignment) => visitor.visit_assignment(assignment),
+            TypeMember::Method(method) => visitor.visit_function_def(method),
+        }
+    }
+}
+
+pub fn walk_assignment<'ast, V: RefVisitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    node: &'ast Assignment,
+) {
+    visitor.visit_expression(&node.expression);
+}
+
+pub fn walk_function_call<'ast, V: RefVisitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    node: &'ast FunctionCallNode,
+) {
+    for argument in node.arguments.iter() {
+        visitor.visit_expression(argument);
+    }
+}
+
+pub fn walk_while_loop<'ast, V: RefVisitor<'ast> + ?Sized>(visitor: &mut V, node: &'ast WhileNode) {
+    visitor.visit_expression(&node.condition);
+    visitor.visit_expression(&node.body);
+}
+
+pub fn walk_for_loop<'ast, V: RefVisitor<'ast> + ?Sized>(visitor: &mut V, node: &'ast ForNode) {
+    visitor.visit_expression(&node.start);
+    visitor.visit_expression(&node.end);
+    visitor.visit_expression(&node.body);
+}
+
+pub fn walk_code_block<'ast, V: RefVisitor<'ast> + ?Sized>(visitor: &mut V, node: &'ast BlockNode) {
+    for expression in node.expression_list.expressions.iter() {
+        visitor.visit_expression(expression);
+    }
+}
+
+pub fn walk_binary_op<'ast, V: RefVisitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    node: &'ast BinaryOpNode,
+) {
+    visitor.visit_expression(&node.left);
+    visitor.visit_expression(&node.right);
+}
+
+pub fn walk_unary_op<'ast, V: RefVisitor<'ast> + ?Sized>(visitor: &mut V, node: &'ast UnaryOpNode) {
+    visitor.visit_expression(&node.operand);
+}
+
+pub fn walk_if_else<'ast, V: RefVisitor<'ast> + ?Sized>(visitor: &mut V, node: &'ast IfElseNode) {
+    visitor.visit_expression(&node.condition);
+    visitor.visit_expression(&node.if_expression);
+    for (condition, body) in node.elifs.iter() {
+        if let Some(condition) = condition {
+            visitor.visit_expression(condition);
+        }
+        visitor.visit_expression(body);
+    }
+}
+
+pub fn walk_let_in<'ast, V: RefVisitor<'ast> + ?Sized>(visitor: &mut V, node: &'ast LetInNode) {
+    for assignment in node.assignments.iter() {
+        visitor.visit_assignment(assignment);
+    }
+    visitor.visit_expression(&node.body);
+}
+
+pub fn walk_destructive_assign<'ast, V: RefVisitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    node: &'ast DestructiveAssignNode,
+) {
+    visitor.visit_expression(&node.identifier);
+    visitor.visit_expression(&node.expression);
+}
+
+pub fn walk_type_instance<'ast, V: RefVisitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    node: &'ast TypeInstanceNode,
+) {
+    for argument in node.arguments.iter() {
+        visitor.visit_expression(argument);
+    }
+}
+
+/// Visits the receiver and then the arguments. The call in `member` is not a call to a
+/// global function, so it does not go through `visit_function_call`.
+pub fn walk_type_function_access<'ast, V: RefVisitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    node: &'ast TypeFunctionAccessNode,
+) {
+    visitor.visit_expression(&node.object);
+    for argument in node.member.arguments.iter() {
+        visitor.visit_expression(argument);
+    }
+}
+
+pub fn walk_type_prop_access<'ast, V: RefVisitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    node: &'ast TypePropAccessNode,
+) {
+    visitor.visit_expression(&node.object);
+}
+
+pub fn walk_print<'ast, V: RefVisitor<'ast> + ?Sized>(visitor: &mut V, node: &'ast PrintNode) {
+    visitor.visit_expression(&node.expression);
+}
@@ -0,0 +1,263 @@
+//! `SexpPrinter` implements the [`Visitor`] trait to dump the AST as S-expressions.
+//!
+//! Every statement takes one line, and every node is a list headed by its kind, such
+//! as `(+ (number 1) (identifier x))`. Spans are left out so that the dump of a program
+//! only changes when its structure does, which suits test snapshots. After the semantic
+//! phase it can also show the static type of every node, as `:Type` after its head.
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::BlockNode;
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::expression::Expression;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::FunctionCallNode;
+use crate::ast_nodes::function_def::{FunctionDefNode, FunctionParams};
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::{Assignment, LetInNode};
+use crate::ast_nodes::literals::{
+    BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode,
+};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::Program;
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+use crate::types_tree::tree_node::TypeNode;
+
+use super::accept::Accept;
+use super::visitor_trait::Visitor;
+
+/// A visitor that writes every node as an S-expression.
+pub struct SexpPrinter {
+    /// Whether to write the static type of every node.
+    typed: bool,
+}
+
+impl SexpPrinter {
+    /// Creates a printer. With `typed`, the static type computed by the semantic phase
+    /// follows the head of every node that has one.
+    pub fn new(typed: bool) -> Self {
+        SexpPrinter { typed }
+    }
+
+    /// Dumps the full program, one statement per line.
+    pub fn print_program(mut self, node: &mut Program) -> String {
+        let mut out = String::new();
+        for statement in node.statements.iter_mut() {
+            out.push_str(&statement.accept(&mut self));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Writes a list of `head`, the type of the node and `items`.
+    fn list(&self, head: &str, node_type: Option<&TypeNode>, items: Vec<String>) -> String {
+        let mut out = format!("({}", head);
+        if let (true, Some(node_type)) = (self.typed, node_type) {
+            out.push_str(&format!(" :{}", node_type.type_name));
+        }
+        for item in items {
+            out.push(' ');
+            out.push_str(&item);
+        }
+        out.push(')');
+        out
+    }
+
+    fn assignment(&mut self, head: &str, node: &mut Assignment) -> String {
+        let value = node.expression.accept(self);
+        self.list(
+            head,
+            node.node_type.as_ref(),
+            vec![node.identifier.clone(), value],
+        )
+    }
+
+    fn all(&mut self, expressions: &mut [Expression]) -> Vec<String> {
+        expressions
+            .iter_mut()
+            .map(|expression| expression.accept(self))
+            .collect()
+    }
+}
+
+fn params(params: &[FunctionParams]) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .map(|param| format!("({} {})", param.name, param.signature))
+        .collect();
+    format!("({})", params.join(" "))
+}
+
+impl Visitor<String> for SexpPrinter {
+    fn visit_function_def(&mut self, node: &mut FunctionDefNode) -> String {
+        let body = node.body.accept(self);
+        self.list(
+            "function",
+            node.node_type.as_ref(),
+            vec![
+                node.name.clone(),
+                params(&node.params),
+                node.return_type.clone(),
+                body,
+            ],
+        )
+    }
+
+    fn visit_literal_number(&mut self, node: &mut NumberLiteralNode) -> String {
+        self.list(
+            "number",
+            node.node_type.as_ref(),
+            vec![node.value.to_string()],
+        )
+    }
+
+    fn visit_literal_boolean(&mut self, node: &mut BooleanLiteralNode) -> String {
+        self.list(
+            "boolean",
+            node.node_type.as_ref(),
+            vec![node.value.to_string()],
+        )
+    }
+
+    fn visit_literal_string(&mut self, node: &mut StringLiteralNode) -> String {
+        // The value keeps the escapes of the source, so it quotes as it was written.
+        self.list(
+            "string",
+            node.node_type.as_ref(),
+            vec![format!("\"{}\"", node.value)],
+        )
+    }
+
+    fn visit_identifier(&mut self, node: &mut IdentifierNode) -> String {
+        self.list(
+            "identifier",
+            node.node_type.as_ref(),
+            vec![node.value.clone()],
+        )
+    }
+
+    fn visit_function_call(&mut self, node: &mut FunctionCallNode) -> String {
+        let mut items = vec![node.function_name.clone()];
+        items.extend(self.all(&mut node.arguments));
+        self.list("call", node.node_type.as_ref(), items)
+    }
+
+    fn visit_while_loop(&mut self, node: &mut WhileNode) -> String {
+        let items = vec![node.condition.accept(self), node.body.accept(self)];
+        self.list("while", node.node_type.as_ref(), items)
+    }
+
+    fn visit_for_loop(&mut self, node: &mut ForNode) -> String {
+        let items = vec![
+            node.variable.clone(),
+            node.start.accept(self),
+            node.end.accept(self),
+            node.body.accept(self),
+        ];
+        self.list("for", node.node_type.as_ref(), items)
+    }
+
+    fn visit_code_block(&mut self, node: &mut BlockNode) -> String {
+        let items = self.all(&mut node.expression_list.expressions);
+        self.list("block", node.node_type.as_ref(), items)
+    }
+
+    fn visit_binary_op(&mut self, node: &mut BinaryOpNode) -> String {
+        let items = vec![node.left.accept(self), node.right.accept(self)];
+        self.list(&node.operator.to_string(), node.node_type.as_ref(), items)
+    }
+
+    fn visit_unary_op(&mut self, node: &mut UnaryOpNode) -> String {
+        let items = vec![node.operand.accept(self)];
+        self.list(&node.operator.to_string(), node.node_type.as_ref(), items)
+    }
+
+    fn visit_if_else(&mut self, node: &mut IfElseNode) -> String {
+        let mut items = vec![node.condition.accept(self), node.if_expression.accept(self)];
+        for (condition, body) in node.elifs.iter_mut() {
+            let branch = match condition {
+                Some(condition) => {
+                    let branch = vec![condition.accept(self), body.accept(self)];
+                    self.list("elif", None, branch)
+                }
+                None => {
+                    let branch = vec![body.accept(self)];
+                    self.list("else", None, branch)
+                }
+            };
+            items.push(branch);
+        }
+        self.list("if", node.node_type.as_ref(), items)
+    }
+
+    fn visit_let_in(&mut self, node: &mut LetInNode) -> String {
+        let bindings: Vec<String> = node
+            .assignments
+            .iter_mut()
+            .map(|assignment| {
+                let value = assignment.expression.accept(self);
+                format!("({} {})", assignment.identifier, value)
+            })
+            .collect();
+        let items = vec![format!("({})", bindings.join(" ")), node.body.accept(self)];
+        self.list("let", node.node_type.as_ref(), items)
+    }
+
+    fn visit_destructive_assign(&mut self, node: &mut DestructiveAssignNode) -> String {
+        let items = vec![node.identifier.accept(self), node.expression.accept(self)];
+        self.list(":=", node.node_type.as_ref(), items)
+    }
+
+    fn visit_type_def(&mut self, node: &mut TypeDefNode) -> String {
+        let mut items = vec![node.identifier.clone(), params(&node.params)];
+        if let Some(parent) = &node.parent {
+            let mut inherits = vec![parent.clone()];
+            inherits.extend(self.all(&mut node.parent_args));
+            items.push(self.list("inherits", None, inherits));
+        }
+        for member in node.members.iter_mut() {
+            items.push(match member {
+                TypeMember::Property(assignment) => self.assignment("property", assignment),
+                TypeMember::Method(method) => {
+                    let body = method.body.accept(self);
+                    self.list(
+                        "method",
+                        method.node_type.as_ref(),
+                        vec![
+                            method.name.clone(),
+                            params(&method.params),
+                            method.return_type.clone(),
+                            body,
+                        ],
+                    )
+                }
+            });
+        }
+        self.list("type", node.node_type.as_ref(), items)
+    }
+
+    fn visit_type_instance(&mut self, node: &mut TypeInstanceNode) -> String {
+        let mut items = vec![node.type_name.clone()];
+        items.extend(self.all(&mut node.arguments));
+        self.list("new", node.node_type.as_ref(), items)
+    }
+
+    fn visit_type_function_access(&mut self, node: &mut TypeFunctionAccessNode) -> String {
+        let mut items = vec![node.object.accept(self), node.member.function_name.clone()];
+        items.extend(self.all(&mut node.member.arguments));
+        self.list("method-call", node.node_type.as_ref(), items)
+    }
+
+    fn visit_type_prop_access(&mut self, node: &mut TypePropAccessNode) -> String {
+        let items = vec![node.object.accept(self), node.member.to_string()];
+        self.list("attribute", node.node_type.as_ref(), items)
+    }
+
+    fn visit_print(&mut self, node: &mut PrintNode) -> String {
+        let items = vec![node.expression.accept(self)];
+        self.list("print", node.node_type.as_ref(), items)
+    }
+}
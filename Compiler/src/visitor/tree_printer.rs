@@ -0,0 +1,297 @@
+//! `TreePrinter` implements the [`Visitor`] trait to dump the structure of the AST.
+//!
+//! Every node takes one line, indented under its parent, with its kind, the names or
+//! values it holds and its span. After the semantic phase it can also show the static
+//! type of every node.
+
+use crate::ast_nodes::binary_op::BinaryOpNode;
+use crate::ast_nodes::block::BlockNode;
+use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
+use crate::ast_nodes::for_loop::ForNode;
+use crate::ast_nodes::function_call::FunctionCallNode;
+use crate::ast_nodes::function_def::{FunctionDefNode, FunctionParams};
+use crate::ast_nodes::if_else::IfElseNode;
+use crate::ast_nodes::let_in::{Assignment, LetInNode};
+use crate::ast_nodes::literals::{
+    BooleanLiteralNode, IdentifierNode, NumberLiteralNode, StringLiteralNode,
+};
+use crate::ast_nodes::print::PrintNode;
+use crate::ast_nodes::program::Program;
+use crate::ast_nodes::type_def::{TypeDefNode, TypeMember};
+use crate::ast_nodes::type_instance::TypeInstanceNode;
+use crate::ast_nodes::type_member_access::{TypeFunctionAccessNode, TypePropAccessNode};
+use crate::ast_nodes::unary_op::UnaryOpNode;
+use crate::ast_nodes::while_loop::WhileNode;
+use crate::tokens::Span;
+use crate::types_tree::tree_node::TypeNode;
+
+use super::accept::Accept;
+use super::visitor_trait::Visitor;
+
+/// A visitor that writes one indented line per AST node.
+pub struct TreePrinter {
+    out: String,
+    depth: usize,
+    /// Whether to write the static type of every node.
+    typed: bool,
+}
+
+impl TreePrinter {
+    /// Creates a printer. With `typed`, the static type computed by the semantic phase
+    /// follows every node that has one.
+    pub fn new(typed: bool) -> Self {
+        TreePrinter {
+            out: String::new(),
+            depth: 0,
+            typed,
+        }
+    }
+
+    /// Dumps the full program.
+    pub fn print_program(mut self, node: &mut Program) -> String {
+        self.line("Program", Some(node.span), None);
+        self.depth = 1;
+        for statement in node.statements.iter_mut() {
+            statement.accept(&mut self);
+        }
+        self.out
+    }
+
+    /// Writes the line of a node.
+    fn line(&mut self, label: &str, span: Option<Span>, node_type: Option<&TypeNode>) {
+        self.out.push_str(&"  ".repeat(self.depth));
+        self.out.push_str(label);
+        if let Some(span) = span {
+            self.out
+                .push_str(&format!(" @{}..{}", span.start, span.end));
+        }
+        if let (true, Some(node_type)) = (self.typed, node_type) {
+            self.out.push_str(&format!(" : {}", node_type.type_name));
+        }
+        self.out.push('\n');
+    }
+
+    /// Writes the line of a node and then its children, one level deeper.
+    fn node(
+        &mut self,
+        label: &str,
+        span: Option<Span>,
+        node_type: Option<&TypeNode>,
+        children: impl FnOnce(&mut Self),
+    ) {
+        self.line(label, span, node_type);
+        self.depth += 1;
+        children(self);
+        self.depth -= 1;
+    }
+
+    fn assignment(&mut self, node: &mut Assignment) {
+        let label = format!("Assignment {}", node.identifier);
+        let (span, node_type) = (node.span, node.node_type.clone());
+        self.node(&label, Some(span), node_type.as_ref(), |printer| {
+            node.expression.accept(printer);
+        });
+    }
+}
+
+fn params(params: &[FunctionParams]) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .map(|param| format!("{}: {}", param.name, param.signature))
+        .collect();
+    params.join(", ")
+}
+
+impl Visitor<()> for TreePrinter {
+    fn visit_function_def(&mut self, node: &mut FunctionDefNode) {
+        let label = format!(
+            "FunctionDef {}({}): {}",
+            node.name,
+            params(&node.params),
+            node.return_type
+        );
+        let node_type = node.node_type.clone();
+        self.node(&label, Some(node.span), node_type.as_ref(), |printer| {
+            node.body.accept(printer);
+        });
+    }
+
+    fn visit_literal_number(&mut self, node: &mut NumberLiteralNode) {
+        let label = format!("Number {}", node.value);
+        self.line(&label, Some(node.span), node.node_type.as_ref());
+    }
+
+    fn visit_literal_boolean(&mut self, node: &mut BooleanLiteralNode) {
+        let label = format!("Boolean {}", node.value);
+        self.line(&label, Some(node.span), node.node_type.as_ref());
+    }
+
+    fn visit_literal_string(&mut self, node: &mut StringLiteralNode) {
+        let label = format!("String {:?}", node.value);
+        self.line(&label, Some(node.span), node.node_type.as_ref());
+    }
+
+    fn visit_identifier(&mut self, node: &mut IdentifierNode) {
+        let label = format!("Identifier {}", node.value);
+        self.line(&label, Some(node.span), node.node_type.as_ref());
+    }
+
+    fn visit_function_call(&mut self, node: &mut FunctionCallNode) {
+        let label = format!("Call {}", node.function_name);
+        let node_type = node.node_type.clone();
+        self.node(&label, Some(node.span), node_type.as_ref(), |printer| {
+            for argument in node.arguments.iter_mut() {
+                argument.accept(printer);
+            }
+        });
+    }
+
+    fn visit_while_loop(&mut self, node: &mut WhileNode) {
+        let node_type = node.node_type.clone();
+        self.node("While", Some(node.span), node_type.as_ref(), |printer| {
+            node.condition.accept(printer);
+            node.body.accept(printer);
+        });
+    }
+
+    fn visit_for_loop(&mut self, node: &mut ForNode) {
+        let label = format!("For {}", node.variable);
+        let node_type = node.node_type.clone();
+        self.node(&label, Some(node.span), node_type.as_ref(), |printer| {
+            node.start.accept(printer);
+            node.end.accept(printer);
+            node.body.accept(printer);
+        });
+    }
+
+    fn visit_code_block(&mut self, node: &mut BlockNode) {
+        let (span, node_type) = (node.span, node.node_type.clone());
+        self.node("Block", Some(span), node_type.as_ref(), |printer| {
+            for expression in node.expression_list.expressions.iter_mut() {
+                expression.accept(printer);
+            }
+        });
+    }
+
+    fn visit_binary_op(&mut self, node: &mut BinaryOpNode) {
+        let label = format!("BinaryOp {}", node.operator);
+        let node_type = node.node_type.clone();
+        self.node(&label, Some(node.span), node_type.as_ref(), |printer| {
+            node.left.accept(printer);
+            node.right.accept(printer);
+        });
+    }
+
+    fn visit_unary_op(&mut self, node: &mut UnaryOpNode) {
+        let label = format!("UnaryOp {}", node.operator);
+        let node_type = node.node_type.clone();
+        self.node(&label, Some(node.span), node_type.as_ref(), |printer| {
+            node.operand.accept(printer);
+        });
+    }
+
+    fn visit_if_else(&mut self, node: &mut IfElseNode) {
+        let node_type = node.node_type.clone();
+        self.node("If", Some(node.span), node_type.as_ref(), |printer| {
+            node.condition.accept(printer);
+            node.if_expression.accept(printer);
+            for (condition, body) in node.elifs.iter_mut() {
+                match condition {
+                    Some(condition) => printer.node("Elif", None, None, |printer| {
+                        condition.accept(printer);
+                        body.accept(printer);
+                    }),
+                    None => printer.node("Else", None, None, |printer| body.accept(printer)),
+                }
+            }
+        });
+    }
+
+    fn visit_let_in(&mut self, node: &mut LetInNode) {
+        let node_type = node.node_type.clone();
+        self.node("LetIn", Some(node.span), node_type.as_ref(), |printer| {
+            for assignment in node.assignments.iter_mut() {
+                printer.assignment(assignment);
+            }
+            node.body.accept(printer);
+        });
+    }
+
+    fn visit_destructive_assign(&mut self, node: &mut DestructiveAssignNode) {
+        let node_type = node.node_type.clone();
+        self.node(
+            "DestructiveAssign",
+            Some(node.span),
+            node_type.as_ref(),
+            |printer| {
+                node.identifier.accept(printer);
+                node.expression.accept(printer);
+            },
+        );
+    }
+
+    fn visit_type_def(&mut self, node: &mut TypeDefNode) {
+        let mut label = format!("TypeDef {}({})", node.identifier, params(&node.params));
+        if let Some(parent) = &node.parent {
+            label.push_str(&format!(" inherits {}", parent));
+        }
+        let node_type = node.node_type.clone();
+        self.node(&label, Some(node.span), node_type.as_ref(), |printer| {
+            if !node.parent_args.is_empty() {
+                printer.node("ParentArguments", None, None, |printer| {
+                    for argument in node.parent_args.iter_mut() {
+                        argument.accept(printer);
+                    }
+                });
+            }
+            for member in node.members.iter_mut() {
+                match member {
+                    TypeMember::Property(assignment) => printer.assignment(assignment),
+                    TypeMember::Method(method) => printer.visit_function_def(method),
+                }
+            }
+        });
+    }
+
+    fn visit_type_instance(&mut self, node: &mut TypeInstanceNode) {
+        let label = format!("New {}", node.type_name);
+        let node_type = node.node_type.clone();
+        self.node(&label, Some(node.span), node_type.as_ref(), |printer| {
+            for argument in node.arguments.iter_mut() {
+                argument.accept(printer);
+            }
+        });
+    }
+
+    fn visit_type_function_access(&mut self, node: &mut TypeFunctionAccessNode) {
+        let mut label = format!("MethodCall {}", node.member.function_name);
+        if let Some(direct) = &node.direct_call {
+            label.push_str(&format!(
+                " (calls {}.{})",
+                direct, node.member.function_name
+            ));
+        }
+        let node_type = node.node_type.clone();
+        self.node(&label, Some(node.span), node_type.as_ref(), |printer| {
+            node.object.accept(printer);
+            for argument in node.member.arguments.iter_mut() {
+                argument.accept(printer);
+            }
+        });
+    }
+
+    fn visit_type_prop_access(&mut self, node: &mut TypePropAccessNode) {
+        let label = format!("Attribute {}", node.member);
+        let node_type = node.node_type.clone();
+        self.node(&label, Some(node.span), node_type.as_ref(), |printer| {
+            node.object.accept(printer);
+        });
+    }
+
+    fn visit_print(&mut self, node: &mut PrintNode) {
+        let node_type = node.node_type.clone();
+        self.node("Print", Some(node.span), node_type.as_ref(), |printer| {
+            node.expression.accept(printer);
+        });
+    }
+}
@@ -3,6 +3,9 @@
 //! The `Visitor<T>` trait allows different operations (e.g., code generation, pretty-printing,
 //! semantic checking) to be performed on AST nodes by implementing a specific `visit_*` method
 //! for each node type.
+//!
+//! Passes that only care about some nodes can use the walking traits of the sibling
+//! modules instead, which visit every child by default.
 
 use crate::ast_nodes::binary_op::BinaryOpNode;
 use crate::ast_nodes::destructive_assign::DestructiveAssignNode;
@@ -0,0 +1,116 @@
+//! Checks the arena AST: that it holds the same program as the boxed AST, and the
+//! name resolution over it.
+
+use std::fs;
+use std::path::Path;
+
+use compilador::arena::{self, Ast, Node, NodeId, SideTable};
+use compilador::driver;
+use compilador::parser::ProgramParser;
+
+#[test]
+fn programs_round_trip_through_the_arena() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "hulk") {
+            continue;
+        }
+        let source = fs::read_to_string(&path).unwrap();
+        let parsed = ProgramParser::new().parse(&source).unwrap();
+        let ast = Ast::from_program(&parsed);
+        assert!(ast.types.is_empty());
+        assert_eq!(ast.to_program(), parsed, "{}", path.display());
+
+        // Typed programs keep their types and backend annotations in the side tables.
+        let analyzed = driver::analyze(&source).unwrap().program;
+        let ast = Ast::from_program(&analyzed);
+        assert!(!ast.types.is_empty());
+        assert_eq!(ast.to_program(), analyzed, "{}", path.display());
+
+        for (id, node) in ast.iter() {
+            for child in node.children() {
+                assert!(
+                    child < id,
+                    "{}: {:?} under {:?}",
+                    path.display(),
+                    child,
+                    node
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn analyses_attach_data_in_side_tables() {
+    let program = ProgramParser::new()
+        .parse("let x = 1 in { print(-x); x; };")
+        .unwrap();
+    let ast = Ast::from_program(&program);
+
+    // The depth of every node, computed from the root down.
+    let mut depths = SideTable::new();
+    let mut pending: Vec<(NodeId, usize)> = ast.statements().iter().map(|&id| (id, 0)).collect();
+    while let Some((id, depth)) = pending.pop() {
+        depths.insert(id, depth);
+        pending.extend(
+            ast[id]
+                .children()
+                .into_iter()
+                .map(|child| (child, depth + 1)),
+        );
+    }
+    assert_eq!(depths.len(), ast.len());
+    let deepest = depths.iter().max_by_key(|&(_, depth)| *depth).unwrap();
+    assert_eq!(ast[deepest.0], Node::Identifier("x".to_string()));
+    assert_eq!(*deepest.1, 4);
+    assert_eq!(ast.span(deepest.0).start, 22);
+}
+
+#[test]
+fn names_resolve_to_their_bindings() {
+    let source = "type P(a: Number) { x = a; get(): Number => self.x; };\n\
+                  function f(n: Number): Number => let m = n in m + g(n);\n\
+                  function g(n: Number): Number => n;\n\
+                  for (i in range(0, 2)) let n = i in print(f(n) + sqrt(n));";
+    let program = ProgramParser::new().parse(source).unwrap();
+    let ast = Ast::from_program(&program);
+    let resolved = arena::resolve(&ast);
+
+    let mut names: Vec<(String, String)> = resolved
+        .iter()
+        .map(|(id, &target)| {
+            let name = match &ast[id] {
+                Node::Identifier(name) => name.clone(),
+                Node::Call { function, .. } => format!("{}()", function),
+                node => panic!("{:?} resolved", node),
+            };
+            let target = match &ast[target] {
+                Node::Param { name, .. } => format!("parameter {}", name),
+                Node::Binding { name, .. } => format!("binding {}", name),
+                Node::For { variable, .. } => format!("loop {}", variable),
+                Node::Function { name, .. } => format!("function {}", name),
+                Node::Type { name, .. } => format!("type {}", name),
+                node => panic!("resolved to {:?}", node),
+            };
+            (name, target)
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(
+        names,
+        [
+            ("a", "parameter a"),
+            ("f()", "function f"),
+            ("g()", "function g"),
+            ("i", "loop i"),
+            ("m", "binding m"),
+            ("n", "binding n"),
+            ("n", "parameter n"),
+            ("self", "type P"),
+        ]
+        .map(|(name, target)| (name.to_string(), target.to_string()))
+    );
+}
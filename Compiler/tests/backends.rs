@@ -0,0 +1,269 @@
+//! Compiles every program in `tests/programs` with each native backend, runs it and
+//! compares its output with the `.out` file next to it. The `.out` files are what the
+//! tree-walking interpreter, the reference semantics, prints.
+//!
+//! The native backends link the runtime library the compiler embeds; the LLVM one is
+//! checked when `clang` is installed. The executables also run with the garbage
+//! collector in stress mode.
+//! The bytecode virtual machine runs in process.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+use compilador::ast_nodes::program::Program;
+use compilador::bytecode::chunk::Chunk;
+use compilador::bytecode::compiler::BytecodeCompiler;
+use compilador::bytecode::vm::Vm;
+use compilador::codegen::c::CGenerator;
+use compilador::codegen::linking::{c_command, clang_command, link_command, write_runtime};
+use compilador::codegen::llvm::LlvmGenerator;
+use compilador::codegen::x86::X86Generator;
+use compilador::driver;
+use compilador::intermediate::escape::EscapeAnalysis;
+use compilador::intermediate::ir::Module;
+use compilador::intermediate::ir_generator::IrGenerator;
+use compilador::intermediate::loop_optimizations::LoopOptimizer;
+use compilador::interpreter::tree_walker;
+use compilador::types_tree::type_tree::TypeTree;
+
+/// Parses, checks, desugars and annotates a program for the backends.
+fn analyze(source: &str) -> (Program, TypeTree) {
+    match driver::analyze(source) {
+        Ok(checked) => (checked.program, checked.type_tree),
+        Err(errors) => panic!(
+            "test program has errors: {:?}",
+            errors.iter().map(|e| e.message.clone()).collect::<Vec<_>>()
+        ),
+    }
+}
+
+fn compile(source: &str) -> Module {
+    let (mut program, type_tree) = analyze(source);
+    let mut module = IrGenerator::new(&type_tree).generate_program(&mut program);
+    EscapeAnalysis::new().analyze_module(&mut module);
+    LoopOptimizer::new().optimize_module(&mut module);
+    module
+}
+
+fn programs() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let mut programs: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "hulk"))
+        .collect();
+    programs.sort();
+    programs
+}
+
+fn work_dir(backend: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(backend);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Returns the runtime library, writing it the first time.
+fn runtime_library() -> &'static Path {
+    static LIBRARY: OnceLock<PathBuf> = OnceLock::new();
+    LIBRARY.get_or_init(|| write_runtime(&work_dir("runtime")).expect("runtime library is written"))
+}
+
+fn available(tool: &str) -> bool {
+    Command::new(tool)
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Runs `command`, checks that it succeeds and returns its standard output and error.
+fn run_with_errors(command: &mut Command) -> (String, String) {
+    let output = command.output().unwrap();
+    let errors = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(output.status.success(), "{:?} failed:\n{}", command, errors);
+    (String::from_utf8(output.stdout).unwrap(), errors)
+}
+
+fn run(command: &mut Command) -> String {
+    run_with_errors(command).0
+}
+
+/// Runs every test program built by `build`, which gets the path of the program, the
+/// working directory and the program name, and returns the path of the executable.
+/// With `collected`, the executable also runs with a collection at every safepoint.
+fn check_backend(backend: &str, collected: bool, build: impl Fn(&Path, &Path, &str) -> PathBuf) {
+    let dir = work_dir(backend);
+    for path in programs() {
+        let name = path.file_stem().unwrap().to_str().unwrap();
+        let executable = build(&path, &dir, name);
+        let expected = fs::read_to_string(path.with_extension("out")).unwrap();
+        assert_eq!(
+            run(&mut Command::new(&executable)),
+            expected,
+            "{} with {}",
+            name,
+            backend
+        );
+        if collected {
+            assert_eq!(
+                run(Command::new(&executable).env("HULK_GC_STRESS", "1")),
+                expected,
+                "{} with {} collecting at every safepoint",
+                name,
+                backend
+            );
+        }
+    }
+}
+
+/// Builds the program at the path in the directory, with the name, and returns the
+/// path of the executable.
+type Build = fn(&Path, &Path, &str) -> PathBuf;
+
+/// Builds the program at `path` with the x86-64 backend, in `dir`.
+fn build_x86(path: &Path, dir: &Path, name: &str) -> PathBuf {
+    let module = compile(&fs::read_to_string(path).unwrap());
+    let assembly = dir.join(format!("{}.s", name));
+    fs::write(&assembly, X86Generator::new(&module).generate()).unwrap();
+    let executable = dir.join(name);
+    run(&mut link_command(
+        &[&assembly],
+        runtime_library(),
+        &executable,
+    ));
+    executable
+}
+
+/// Builds the program at `path` with the C backend, in `dir`.
+fn build_c(path: &Path, dir: &Path, name: &str) -> PathBuf {
+    let source = fs::read_to_string(path).unwrap();
+    let (mut program, type_tree) = analyze(&source);
+    let file_name = path.to_str().unwrap();
+    let c = CGenerator::new(&type_tree, file_name, &source).generate_program(&mut program);
+    let c_file = dir.join(format!("{}.c", name));
+    fs::write(&c_file, c).unwrap();
+    let executable = dir.join(name);
+    run(&mut c_command(&c_file, runtime_library(), &executable));
+    executable
+}
+
+#[test]
+fn interpreter_matches_expected_output() {
+    for path in programs() {
+        let name = path.file_stem().unwrap().to_str().unwrap();
+        let (mut program, _) = analyze(&fs::read_to_string(&path).unwrap());
+        let mut output = Vec::new();
+        if let Err(error) = tree_walker::run(&mut program, &mut output) {
+            panic!("{} failed: {}", name, error);
+        }
+        let output = String::from_utf8(output).unwrap();
+        let expected = fs::read_to_string(path.with_extension("out")).unwrap();
+        assert_eq!(output, expected, "{} with the interpreter", name);
+    }
+}
+
+#[test]
+fn x86_backend_matches_expected_output() {
+    check_backend("x86", true, build_x86);
+}
+
+#[test]
+fn c_backend_matches_expected_output() {
+    check_backend("c", true, build_c);
+}
+
+#[test]
+fn llvm_backend_matches_expected_output() {
+    if !available("clang") {
+        eprintln!("clang not found, skipping the LLVM backend");
+        return;
+    }
+    check_backend("llvm", true, |path, dir, name| {
+        let module = compile(&fs::read_to_string(path).unwrap());
+        let ir = dir.join(format!("{}.ll", name));
+        fs::write(&ir, LlvmGenerator::new(&module).generate()).unwrap();
+        let executable = dir.join(name);
+        run(&mut clang_command(&ir, runtime_library(), &executable));
+        executable
+    });
+}
+
+#[test]
+fn bytecode_vm_matches_expected_output() {
+    for path in programs() {
+        let name = path.file_stem().unwrap().to_str().unwrap();
+        let (mut program, type_tree) = analyze(&fs::read_to_string(&path).unwrap());
+        let chunk = BytecodeCompiler::new(&type_tree).compile_program(&mut program);
+        let decoded = Chunk::from_bytes(&chunk.to_bytes()).expect("bytecode decodes");
+        assert_eq!(decoded, chunk, "{} round trip", name);
+
+        let mut output = Vec::new();
+        if let Err(error) = Vm::new(&decoded, &mut output).run() {
+            panic!("{} failed: {}", name, error);
+        }
+        let expected = fs::read_to_string(path.with_extension("out")).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            expected,
+            "{} with the VM",
+            name
+        );
+    }
+}
+
+#[test]
+fn garbage_collector_reports_its_statistics() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs/garbage.hulk");
+    let dir = work_dir("gc");
+    for (backend, build) in [("x86", build_x86 as Build), ("c", build_c)] {
+        let executable = build(&path, &dir, &format!("garbage_{}", backend));
+        let (_, errors) = run_with_errors(Command::new(executable).env("HULK_GC_STATS", "1"));
+        let numbers: Vec<usize> = errors
+            .trim()
+            .trim_start_matches("gc: ")
+            .split(", ")
+            .map(|part| part.split(' ').next().unwrap().parse().unwrap())
+            .collect();
+        let [collections, allocated, freed, live, peak] = numbers[..] else {
+            panic!("unexpected statistics with {}: {}", backend, errors);
+        };
+        assert!(collections > 0, "{}: {}", backend, errors);
+        assert_eq!(allocated - freed, live, "{}: {}", backend, errors);
+        assert!(peak < allocated / 2, "{}: {}", backend, errors);
+    }
+}
+
+#[test]
+fn native_backends_follow_the_seed() {
+    let source = "{ print(rand()); print(rand() + rand()); }";
+    let (mut program, type_tree) = analyze(source);
+    let mut expected = Vec::new();
+    tree_walker::run_with_seed(&mut program, 42, &mut expected).unwrap();
+    let expected = String::from_utf8(expected).unwrap();
+
+    let dir = work_dir("seed");
+    let module = compile(source);
+    let assembly = dir.join("seed.s");
+    fs::write(
+        &assembly,
+        X86Generator::new(&module).with_seed(42).generate(),
+    )
+    .unwrap();
+    let executable = dir.join("seed_x86");
+    run(&mut link_command(
+        &[&assembly],
+        runtime_library(),
+        &executable,
+    ));
+    assert_eq!(run(&mut Command::new(&executable)), expected, "x86");
+
+    let c = CGenerator::new(&type_tree, "seed.hulk", source)
+        .with_seed(42)
+        .generate_program(&mut program);
+    let c_file = dir.join("seed.c");
+    fs::write(&c_file, c).unwrap();
+    let executable = dir.join("seed_c");
+    run(&mut c_command(&c_file, runtime_library(), &executable));
+    assert_eq!(run(&mut Command::new(&executable)), expected, "C");
+}
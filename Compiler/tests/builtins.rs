@@ -0,0 +1,90 @@
+//! Checks the builtin functions and constants: their signatures in the semantic phase,
+//! programs hiding them, and `rand()` following the seed in the in-process engines.
+
+use compilador::bytecode::compiler::BytecodeCompiler;
+use compilador::bytecode::vm::Vm;
+use compilador::interpreter::tree_walker;
+use compilador::parser::ProgramParser;
+use compilador::semantic::semantic_errors::SemanticError;
+use compilador::semantic::semantic_visitor::SemanticVisitor;
+
+fn check(source: &str) -> Result<SemanticVisitor, Vec<SemanticError>> {
+    let mut program = ProgramParser::new()
+        .parse(source)
+        .expect("test program parses");
+    let mut semantic = SemanticVisitor::new();
+    semantic.check_program(&mut program)?;
+    Ok(semantic)
+}
+
+/// Runs `source` with the interpreter and with the virtual machine, checks that they
+/// print the same and returns it.
+fn run(source: &str, seed: u64) -> String {
+    let mut program = ProgramParser::new()
+        .parse(source)
+        .expect("test program parses");
+    let mut semantic = SemanticVisitor::new();
+    if let Err(errors) = semantic.check_program(&mut program) {
+        panic!(
+            "test program has errors: {:?}",
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>()
+        );
+    }
+
+    let mut interpreted = Vec::new();
+    tree_walker::run_with_seed(&mut program, seed, &mut interpreted).unwrap();
+    let chunk = BytecodeCompiler::new(&semantic.type_tree).compile_program(&mut program);
+    let mut executed = Vec::new();
+    Vm::new(&chunk, &mut executed)
+        .with_seed(seed)
+        .run()
+        .unwrap();
+
+    assert_eq!(interpreted, executed);
+    String::from_utf8(interpreted).unwrap()
+}
+
+#[test]
+fn builtins_are_checked_like_functions() {
+    let errors = check(r#"{ print(sqrt("four")); print(log(8)); print(PI @ ""); }"#)
+        .err()
+        .expect("the program has errors");
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(matches!(errors[0], SemanticError::TypeMismatch { .. }));
+    assert!(matches!(
+        errors[1],
+        SemanticError::ArgumentCountMismatch {
+            expected: 2,
+            found: 1,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn program_definitions_hide_builtins() {
+    let output = run(
+        r#"
+        function sqrt(x: String): String => "root of " @ x;
+        {
+            print(sqrt("nine"));
+            let E = "e" in print(E);
+            print(E > 2.7);
+        }
+    "#,
+        0,
+    );
+    assert_eq!(output, "root of nine\ne\ntrue\n");
+}
+
+#[test]
+fn rand_follows_the_seed() {
+    let source = "{ print(rand()); print(rand()); }";
+    let first = run(source, 42);
+    assert_eq!(run(source, 42), first);
+    assert_ne!(run(source, 7), first);
+
+    let numbers: Vec<f64> = first.lines().map(|line| line.parse().unwrap()).collect();
+    assert_ne!(numbers[0], numbers[1]);
+    assert!(numbers.iter().all(|number| (0.0..1.0).contains(number)));
+}
@@ -0,0 +1,99 @@
+//! Checks that damaged `.hbc` files are rejected when they are read and that the VM
+//! reports, instead of crashing on, slots that do not fit the object at hand.
+
+use compilador::bytecode::chunk::{Chunk, Op};
+use compilador::bytecode::compiler::BytecodeCompiler;
+use compilador::bytecode::serialize::DecodeError;
+use compilador::bytecode::vm::Vm;
+use compilador::driver;
+
+const SOURCE: &str = "\
+    type Point(x: Number) { x = x; get(): Number => self.x; };
+    type Empty { };
+    function twice(n: Number): Number => n * 2;
+    print(twice(new Point(3).get()) @ \"!\");";
+
+fn compile() -> Chunk {
+    let checked = driver::analyze(SOURCE).unwrap();
+    let mut program = checked.program;
+    BytecodeCompiler::new(&checked.type_tree).compile_program(&mut program)
+}
+
+/// Replaces the first `original` instruction with `replacement`, encodes the chunk and
+/// decodes it again.
+fn decode_with(original: Op, replacement: Op) -> Result<Chunk, DecodeError> {
+    let mut chunk = compile();
+    let op = chunk
+        .functions
+        .iter_mut()
+        .flat_map(|function| function.code.iter_mut())
+        .find(|op| **op == original)
+        .unwrap();
+    *op = replacement;
+    Chunk::from_bytes(&chunk.to_bytes())
+}
+
+#[test]
+fn compiled_chunks_decode() {
+    let chunk = compile();
+    assert_eq!(Chunk::from_bytes(&chunk.to_bytes()), Ok(chunk));
+}
+
+#[test]
+fn truncated_and_foreign_files_are_rejected() {
+    let bytes = compile().to_bytes();
+    assert_eq!(
+        Chunk::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(DecodeError::UnexpectedEnd)
+    );
+    assert_eq!(Chunk::from_bytes(b"ELF"), Err(DecodeError::BadMagic));
+}
+
+#[test]
+fn references_to_missing_entries_are_rejected() {
+    let cases = [
+        (Op::Constant(1), Op::Constant(1000), "constant 1000"),
+        (Op::Call(5, 1), Op::Call(1000, 1), "function 1000"),
+        (Op::New(0), Op::New(1000), "class 1000"),
+        (Op::Call(5, 1), Op::Jump(1000), "instruction 1000"),
+    ];
+    for (original, replacement, reference) in cases {
+        match decode_with(original, replacement) {
+            Err(DecodeError::InvalidReference(message)) => {
+                assert!(message.contains(reference), "{}", message)
+            }
+            other => panic!("{} was accepted: {:?}", reference, other.map(|_| ())),
+        }
+    }
+
+    let mut chunk = compile();
+    chunk.classes[0].vtable[0].1 = 1000;
+    assert!(matches!(
+        Chunk::from_bytes(&chunk.to_bytes()),
+        Err(DecodeError::InvalidReference(_))
+    ));
+}
+
+#[test]
+fn slots_of_another_class_are_runtime_errors() {
+    // `Point` has a field and a method that `Empty` lacks; both slots are valid for
+    // some class, so only the VM can tell they do not fit.
+    let chunk = compile();
+    let empty = chunk
+        .classes
+        .iter()
+        .position(|class| class.name == "Empty")
+        .unwrap() as u32;
+    for op in [Op::GetField(0), Op::InvokeVirtual(0, 0)] {
+        let mut chunk = chunk.clone();
+        let entry = chunk.entry as usize;
+        chunk.functions[entry].code = vec![Op::New(empty), op, Op::Return];
+        let chunk = Chunk::from_bytes(&chunk.to_bytes()).unwrap();
+        let error = Vm::new(&chunk, Vec::new()).run().unwrap_err();
+        assert!(
+            error.message.starts_with("`Empty` has no"),
+            "{}",
+            error.message
+        );
+    }
+}
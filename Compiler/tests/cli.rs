@@ -0,0 +1,289 @@
+//! Runs the `hulk` binary the way users do and checks its output and exit codes.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+fn hulk(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_hulk"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+fn program(name: &str) -> String {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/programs")
+        .join(name)
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn run_prints_what_the_program_prints() {
+    let path = program("features.hulk");
+    let expected = fs::read_to_string(program("features.out")).unwrap();
+    for engine in ["--engine=interpreter", "--engine=vm"] {
+        let output = hulk(&["run", engine, &path], "");
+        assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+        assert_eq!(stdout(&output), expected, "{}", engine);
+    }
+}
+
+#[test]
+fn programs_are_read_from_standard_input() {
+    let output = hulk(&["run"], "print(1 + 2);");
+    assert_eq!(stdout(&output), "3\n");
+    let output = hulk(&["run", "-"], "print(\"a\" @ \"b\");");
+    assert_eq!(stdout(&output), "ab\n");
+}
+
+#[test]
+fn errors_are_reported_for_people() {
+    let output = hulk(&["check"], "let x = 1 in\nprint(y);");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        "<stdin>:2:7: semantic error: variable `y` is not defined\n  |\n2 | print(y);\n  |       ^\n"
+    );
+}
+
+#[test]
+fn errors_are_reported_as_json() {
+    let output = hulk(&["check", "--error-format=json"], "print(1 +);");
+    assert_eq!(output.status.code(), Some(1));
+    let error = stderr(&output);
+    assert!(
+        error
+            .starts_with("{\"file\":\"<stdin>\",\"phase\":\"syntax\",\"message\":\"unexpected `)`"),
+        "{}",
+        error
+    );
+    assert!(
+        error.ends_with("\"line\":1,\"column\":10,\"start\":9,\"end\":10}\n"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn statements_out_of_order_are_syntax_errors() {
+    let source = "print(1);\nfunction f(): Number => 2;";
+    let output = hulk(&["check"], source);
+    assert_eq!(
+        stderr(&output),
+        "<stdin>:2:1: syntax error: `f` is declared after the entry expression: \
+         declarations must come first\n  |\n2 | function f(): Number => 2;\n  | ^^^^^^^^^^^^^^^^^^^^^^^^^\n"
+    );
+    let output = hulk(&["check", "--error-format=json"], source);
+    let error = stderr(&output);
+    assert!(error.contains("\"phase\":\"syntax\""), "{}", error);
+    assert!(error.contains("\"start\":10,\"end\":35}"), "{}", error);
+}
+
+#[test]
+fn emit_shows_each_stage() {
+    let source = "print(1 + 2);";
+    let ast = stdout(&hulk(&["emit", "--stage=ast"], source));
+    assert_eq!(
+        ast,
+        "Program @0..13\n  Print @0..12\n    BinaryOp + @6..11\n      Number 1 @6..7\n      Number 2 @10..11\n"
+    );
+    let typed = stdout(&hulk(&["emit", "--stage=typed-ast"], source));
+    assert!(typed.contains("BinaryOp + @6..11 : Number"), "{}", typed);
+    let sexp = stdout(&hulk(&["emit", "--stage=ast", "--format=sexp"], source));
+    assert_eq!(sexp, "(print (+ (number 1) (number 2)))\n");
+    let json = stdout(&hulk(&["emit", "--stage=ast", "--format=json"], source));
+    assert!(json.contains("\"kind\": \"BinaryOp\""), "{}", json);
+    let typed = stdout(&hulk(&["emit", "--stage=typed-ast", "--format=json"], source));
+    assert!(typed.contains("\"node_type\""), "{}", typed);
+    for stage in [
+        "ir",
+        "bytecode",
+        "c",
+        "llvm",
+        "asm",
+        "dot-ast",
+        "dot-scopes",
+        "dot-cfg",
+    ] {
+        let output = hulk(&["emit", &format!("--stage={}", stage)], source);
+        assert_eq!(output.status.code(), Some(0), "{}", stage);
+        assert!(!output.stdout.is_empty(), "{}", stage);
+    }
+}
+
+#[test]
+fn report_shows_what_the_optimization_passes_did() {
+    let source = "type Point(x: Number) { x = x; get(): Number => self.x; }; \
+                  let p = new Point(3), i = 0, s = 0 in { \
+                  while (i < 5) { s := s + p.get() + i * 2; i := i + 1; }; print(s); };";
+    let output = hulk(&["emit", "--stage=ir", "--report"], source);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let report = stderr(&output);
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines[0], "devirtualized 1 of 1 method call site(s)");
+    assert_eq!(
+        lines[1],
+        "escape analysis: 2 allocation(s), 0 on the stack, 0 scalar-replaced, 2 on the heap"
+    );
+    assert_eq!(
+        lines.last().unwrap(),
+        &"found 1 loop(s), hoisted 0 invariant instruction(s), strength-reduced 1 multiplication(s)"
+    );
+
+    let output = hulk(&["emit", "--stage=c", "--report"], source);
+    assert_eq!(stderr(&output), "devirtualized 1 of 1 method call site(s)\n");
+    let output = hulk(&["emit", "--stage=ir"], source);
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn lex_prints_one_token_per_line() {
+    let output = hulk(&["lex"], "let x = 10 in\n  x <= 3;");
+    assert_eq!(
+        stdout(&output),
+        "1:1 Let let\n1:5 Identifier x\n1:7 Assign =\n1:9 Num 10\n1:12 In in\n\
+         2:3 Identifier x\n2:5 LessEqual <=\n2:8 Num 3\n2:9 Semicolon ;\n"
+    );
+    let output = hulk(&["lex"], "x // ñ y\n;");
+    assert_eq!(stdout(&output), "1:1 Identifier x\n2:1 Semicolon ;\n");
+    let output = hulk(&["lex"], "x # y");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("unexpected character `#`"));
+}
+
+#[test]
+fn build_writes_an_executable() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cli");
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("hello.hulk");
+    fs::write(&source, "print(\"hello\");").unwrap();
+    let executable = dir.join("hello");
+    let output = hulk(
+        &[
+            "build",
+            "--backend=c",
+            source.to_str().unwrap(),
+            "-o",
+            executable.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let run = Command::new(&executable).output().unwrap();
+    assert_eq!(String::from_utf8(run.stdout).unwrap(), "hello\n");
+}
+
+#[test]
+fn bytecode_files_are_written_and_run() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cli");
+    fs::create_dir_all(&dir).unwrap();
+    let bytecode = dir.join("features.hbc");
+    let bytecode = bytecode.to_str().unwrap();
+    let output = hulk(
+        &[
+            "emit",
+            "--stage=bytecode",
+            &program("features.hulk"),
+            "-o",
+            bytecode,
+        ],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+
+    let output = hulk(&["run", bytecode], "");
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let expected = fs::read_to_string(program("features.out")).unwrap();
+    assert_eq!(stdout(&output), expected);
+
+    let damaged = dir.join("damaged.hbc");
+    let bytes = fs::read(bytecode).unwrap();
+    fs::write(&damaged, &bytes[..bytes.len() / 2]).unwrap();
+    let output = hulk(&["run", damaged.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("cannot load"), "{}", stderr(&output));
+    let output = hulk(&["check", bytecode], "");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn wrong_command_lines_exit_with_two() {
+    for args in [
+        &["frobnicate"][..],
+        &["emit"],
+        &["emit", "--stage=tokens"],
+        &["run", "--error-format=xml"],
+        &["parse", "--format=xml"],
+        &["emit", "--stage=ir", "--format=json"],
+        &["run", "--report"],
+        &["run", "-o", "out"],
+        &["emit", "--stage=ir", "-o", "out"],
+        &["run", "missing.hulk"],
+    ] {
+        let output = hulk(args, "");
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(stderr(&output).starts_with("hulk: "), "{:?}", args);
+    }
+}
+
+#[test]
+fn repl_reads_standard_input() {
+    let output = hulk(&["repl"], "function f(): Number => 4;\nf() + 1;\n");
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(stdout(&output).contains("5 : Number"), "{}", stdout(&output));
+}
+
+#[test]
+fn fmt_check_reports_unformatted_programs() {
+    let output = hulk(&["fmt"], "print(1+2) ;");
+    assert_eq!(stdout(&output), "print(1 + 2);\n");
+    let output = hulk(&["fmt", "--check"], "print(1 + 2);\n");
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let output = hulk(&["fmt", "--check"], "print(1+2) ;");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), "hulk: `<stdin>` is not formatted\n");
+    let output = hulk(&["fmt", "--width=10"], "print(100 + 200);");
+    assert_eq!(stdout(&output), "print(\n    100 +\n        200\n);\n");
+}
+
+#[test]
+fn fmt_keeps_comments_between_statements() {
+    let source = "// square\nfunction sq(x: Number): Number => x*x; // once\n\nprint(sq(3));\n// end\n";
+    let output = hulk(&["fmt"], source);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "// square\nfunction sq(x: Number): Number => x * x; // once\n\nprint(sq(3));\n// end\n"
+    );
+    let output = hulk(&["fmt"], "print(1 + // one\n2);");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        stderr(&output),
+        "hulk: cannot format `<stdin>`: the comment at 1:11 is inside a statement\n"
+    );
+    let output = hulk(&["run"], "// comments are skipped\nprint(1); // here too\n");
+    assert_eq!(stdout(&output), "1\n");
+}
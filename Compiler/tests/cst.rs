@@ -0,0 +1,224 @@
+//! Checks that the CST keeps the whole source and lowers to the AST `ProgramParser`
+//! builds.
+
+use std::fs;
+use std::path::Path;
+
+use compilador::cst::kind::SyntaxKind;
+use compilador::cst::views::{Expr, Member, Stmt};
+use compilador::cst::{self, red::SyntaxElement};
+use compilador::parser::ProgramParser;
+use compilador::tokens::Span;
+
+const SNIPPETS: &[&str] = &[
+    "print(1 + 2 * 3 ^ 2 ^ 2);",
+    "let a = 1, b = \"s\\\"tr\" in { a := (b @ a); -a; !true };",
+    "if (x < 1 & y >= 2 | !z) { 1 } elif (x != 2) { 2; } else { };",
+    "while (i <= 10) { i := i + 1 };",
+    "for (i in range(0, (10))) { print(i % 3); };",
+    "function f(a: Number, b: String): Number => a / b; f(1, 2);",
+    "function g(): Object { 1; 2 }; g();",
+    "type A { x = 1; f(): Number => self.x }; new A().f();",
+    "type B(a: Number) inherits A(a, 2) { g(n: Number): Object { new A().f().x; } ; }; 1",
+    "type C inherits A { }; new C();",
+    "(new B(1)).x := 3.25",
+    "{};",
+];
+
+fn programs() -> Vec<(String, String)> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let mut programs = Vec::new();
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "hulk")
+        {
+            let source = fs::read_to_string(&path).unwrap();
+            programs.push((path.display().to_string(), source));
+        }
+    }
+    programs
+}
+
+#[test]
+fn the_tree_reconstructs_the_source() {
+    let mut sources: Vec<String> = programs().into_iter().map(|(_, source)| source).collect();
+    sources.extend(SNIPPETS.iter().map(|snippet| snippet.to_string()));
+    sources.extend(
+        [
+            "",
+            "  // only a comment\n",
+            "let x = 1 in\n  // keep me\n  x ; // and me",
+            "print(1 +);  type { f( ; $ \"open",
+            "function (): => ;; }}} ))",
+            "print(\"ñandú\") ~ 1;",
+        ]
+        .map(String::from),
+    );
+    for source in sources {
+        let parse = cst::parse(&source);
+        let root = parse.syntax();
+        assert_eq!(root.text(), source);
+        assert_eq!(root.span().end, source.len());
+        let tokens: String = root
+            .descendant_tokens()
+            .iter()
+            .map(|token| token.text().to_string())
+            .collect();
+        assert_eq!(tokens, source);
+    }
+}
+
+#[test]
+fn lowering_matches_the_parser() {
+    let mut sources = programs();
+    sources.extend(
+        SNIPPETS
+            .iter()
+            .map(|snippet| (snippet.to_string(), snippet.to_string())),
+    );
+    for (name, source) in sources {
+        let expected = ProgramParser::new()
+            .parse(&source)
+            .unwrap_or_else(|error| panic!("{}: {}", name, error));
+        let parse = cst::parse(&source);
+        assert_eq!(parse.errors, vec![], "{}", name);
+        assert_eq!(parse.to_ast().unwrap(), expected, "{}", name);
+    }
+}
+
+#[test]
+fn comments_and_whitespace_are_kept_as_trivia() {
+    let source = "// the answer\nprint( 42 ) ; // done\n";
+    let parse = cst::parse(source);
+    let tokens = parse.syntax().descendant_tokens();
+    let trivia: Vec<&str> = tokens
+        .iter()
+        .filter(|token| token.kind().is_trivia())
+        .map(|token| token.text())
+        .collect();
+    assert_eq!(
+        trivia,
+        ["// the answer", "\n", " ", " ", " ", " ", "// done", "\n"]
+    );
+
+    // Nodes start at their first token: the leading comment belongs to the program.
+    let statement = &parse.program().statements()[0];
+    assert_eq!(statement.syntax().text(), "print( 42 )");
+    // `ProgramParser` skips the same comments.
+    assert_eq!(
+        parse.to_ast().unwrap(),
+        ProgramParser::new().parse(source).unwrap()
+    );
+}
+
+#[test]
+fn errors_are_reported_and_the_text_is_kept() {
+    let source = "let x = in x; print(1 $ 2); type A { 3 };";
+    let parse = cst::parse(source);
+    assert_eq!(parse.syntax().text(), source);
+    let messages: Vec<String> = parse
+        .errors
+        .iter()
+        .map(|error| error.message.clone())
+        .collect();
+    assert!(
+        messages.contains(&"expected an expression, found `in`".to_string()),
+        "{:?}",
+        messages
+    );
+    assert!(
+        messages.contains(&"unexpected character `$`".to_string()),
+        "{:?}",
+        messages
+    );
+    assert!(parse.to_ast().is_err());
+    assert!(parse
+        .syntax()
+        .descendants()
+        .iter()
+        .any(|node| node.kind() == SyntaxKind::Error));
+    assert!(!cst::parse(";").errors.is_empty());
+}
+
+#[test]
+fn sources_the_parser_rejects_have_errors() {
+    for source in [
+        "while (i <= 10) i := i + 1;",
+        "if (a) { 1 } elif (b) { 2 };",
+        "1 + 2 := 3;",
+        "print(1) print(2)",
+        "print(1);;",
+        "type A { x = 1;; };",
+        "for (i in range(0)) 1;",
+        "new A;",
+        "let in 1;",
+    ] {
+        assert!(ProgramParser::new().parse(source).is_err(), "{}", source);
+        assert!(!cst::parse(source).errors.is_empty(), "{}", source);
+    }
+}
+
+#[test]
+fn typed_views_read_the_parts_of_each_construct() {
+    let source =
+        "type Point(x: Number) inherits Base(x) { x = x; norm(): Number => self.x ^ 2 };\n\
+                  function f(a: Number): Number { a + 1 };\n\
+                  for (i in range(0, 3)) print(f(i));";
+    let parse = cst::parse(source);
+    assert_eq!(parse.errors, vec![]);
+    let statements = parse.program().statements();
+    assert_eq!(statements.len(), 3);
+
+    let Stmt::TypeDef(point) = &statements[0] else {
+        panic!("expected a type");
+    };
+    assert_eq!(point.name().unwrap().text(), "Point");
+    let params = point.params().unwrap().params();
+    assert_eq!(params[0].name().unwrap().text(), "x");
+    assert_eq!(params[0].type_name().unwrap().text(), "Number");
+    let inherits = point.inherits().unwrap();
+    assert_eq!(inherits.parent().unwrap().text(), "Base");
+    assert_eq!(inherits.args().unwrap().args().len(), 1);
+    let members = point.members();
+    let Member::Method(norm) = &members[1] else {
+        panic!("expected a method");
+    };
+    assert_eq!(norm.name().unwrap().text(), "norm");
+    let Some(Expr::Binary(power)) = norm.body() else {
+        panic!("expected a binary expression");
+    };
+    assert_eq!(power.operator().unwrap().kind(), SyntaxKind::Caret);
+    assert!(matches!(power.lhs(), Some(Expr::Field(_))));
+
+    let Stmt::FunctionDef(f) = &statements[1] else {
+        panic!("expected a function");
+    };
+    assert_eq!(f.name().unwrap().text(), "f");
+    assert_eq!(f.return_type().unwrap().text(), "Number");
+    let Some(Expr::Block(body)) = f.body() else {
+        panic!("expected a block");
+    };
+    assert_eq!(body.expressions().len(), 1);
+
+    let Stmt::Expr(Expr::For(for_loop)) = &statements[2] else {
+        panic!("expected a for loop");
+    };
+    assert_eq!(for_loop.variable().unwrap().text(), "i");
+    assert_eq!(for_loop.end().unwrap().syntax().text(), "3");
+    assert_eq!(for_loop.body().unwrap().syntax().text(), "print(f(i))");
+
+    let root = parse.syntax();
+    let start = source.find("a + 1").unwrap();
+    let sum = root.covering_node(Span::new(start, start + 5));
+    assert_eq!(sum.kind(), SyntaxKind::BinaryExpr);
+    assert!(sum
+        .ancestors()
+        .any(|node| node.kind() == SyntaxKind::FunctionDef));
+    let SyntaxElement::Node(first) = &root.children_with_tokens()[0] else {
+        panic!("expected a node");
+    };
+    assert_eq!(first.kind(), SyntaxKind::TypeDef);
+    assert_eq!(first.tokens().next().unwrap().kind(), SyntaxKind::TypeKw);
+}
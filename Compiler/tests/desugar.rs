@@ -0,0 +1,100 @@
+//! Checks the desugaring pass: the core forms it produces, the spans of the nodes it
+//! makes up and that desugared programs behave like the originals.
+
+use std::fs;
+use std::path::Path;
+
+use compilador::arena::Ast;
+use compilador::driver;
+use compilador::interpreter::tree_walker;
+use compilador::parser::ProgramParser;
+use compilador::semantic::desugar::Desugarer;
+use compilador::types_tree::type_tree::TypeTree;
+use compilador::visitor::sexp_printer::SexpPrinter;
+
+fn programs() -> Vec<(String, String)> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let mut programs = Vec::new();
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "hulk")
+        {
+            let source = fs::read_to_string(&path).unwrap();
+            programs.push((path.display().to_string(), source));
+        }
+    }
+    programs
+}
+
+#[test]
+fn surface_forms_become_core_forms() {
+    let source = "\
+function f(x: Number): Number => x + 1;
+let a = 1, b = a + 1 in
+    if (a > b) { 1; } elif (a == b) { 2; } else { for (i in range(a, b)) { print(i); }; };";
+    let program = ProgramParser::new().parse(source).unwrap();
+    let type_tree = TypeTree::new();
+    let mut desugared = Desugarer::new(&type_tree).desugar_program(program);
+    let expected = "\
+(function f ((x Number)) Number (block (+ (identifier x) (number 1))))
+(let ((a (number 1))) (let ((b (+ (identifier a) (number 1)))) \
+(if (> (identifier a) (identifier b)) (block (number 1)) \
+(else (if (== (identifier a) (identifier b)) (block (number 2)) \
+(else (block (let ((_index (identifier a))) (let ((_end (identifier b))) \
+(while (< (identifier _index) (identifier _end)) (let ((i (identifier _index))) \
+(block (:= (identifier _index) (+ (identifier _index) (number 1))) \
+(block (print (identifier i)))))))))))))))
+";
+    assert_eq!(
+        SexpPrinter::new(false).print_program(&mut desugared),
+        expected
+    );
+}
+
+#[test]
+fn synthetic_nodes_lie_inside_the_source_they_replace() {
+    for (name, source) in programs() {
+        let checked = driver::analyze(&source).unwrap();
+        let ast = Ast::from_program(&checked.program);
+        let program = ast.program_span();
+        for (id, node) in ast.iter() {
+            let outer = ast.span(id);
+            assert!(
+                outer.start <= outer.end && outer.end <= program.end,
+                "{}",
+                name
+            );
+            for child in node.children() {
+                let inner = ast.span(child);
+                assert!(
+                    outer.start <= inner.start && inner.end <= outer.end,
+                    "{}: {:?} at {:?} is not inside {:?} at {:?}",
+                    name,
+                    ast[child],
+                    inner,
+                    node,
+                    outer
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn desugared_programs_print_what_the_originals_print() {
+    for (name, source) in programs() {
+        let mut original = driver::check(&source).unwrap().program;
+        let mut desugared = driver::analyze(&source).unwrap().program;
+        let (mut expected, mut actual) = (Vec::new(), Vec::new());
+        tree_walker::run(&mut original, &mut expected).unwrap();
+        tree_walker::run(&mut desugared, &mut actual).unwrap();
+        assert_eq!(
+            String::from_utf8(actual).unwrap(),
+            String::from_utf8(expected).unwrap(),
+            "{}",
+            name
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Checks the Graphviz graphs of the AST, the scopes and the control-flow graphs.
+
+use compilador::dot::ast::AstGraph;
+use compilador::dot::cfg::draw_module;
+use compilador::dot::scopes::draw_scopes;
+use compilador::driver;
+
+const SOURCE: &str =
+    "function fact(n: Number): Number => if (n > 1) { n * fact(n - 1) } else { 1 };\n\
+                      let s = \"a\\\"b\" in for (i in range(0, 3)) print(s @ fact(i));";
+
+/// Returns the lines of `graph` that declare nodes or edges.
+fn statements(graph: &str) -> Vec<&str> {
+    graph
+        .lines()
+        .map(str::trim)
+        .filter(|line| {
+            line.strip_prefix('n')
+                .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        })
+        .collect()
+}
+
+#[test]
+fn the_ast_graph_has_a_labeled_node_per_ast_node() {
+    let mut program = driver::check(SOURCE).unwrap().program;
+    let graph = AstGraph::new().draw_program(&mut program);
+    assert!(graph.starts_with("digraph \"ast\" {\n"), "{}", graph);
+    assert!(graph.ends_with("}\n"));
+    assert!(graph.contains("[label=\"FunctionDef\\lfact(n: Number): Number\\l: Number\\l\"]"));
+    assert!(graph.contains("[label=\"BinaryOp\\l>\\l: Boolean\\l\"]"));
+    assert!(graph.contains("[label=\"String\\l\\\"a\\\\\\\"b\\\"\\l: String\\l\"]"));
+    assert!(graph.contains("[label=\"condition\"]"));
+    assert!(graph.contains("[label=\"else\"]"));
+
+    // A tree: every node but the root has exactly one incoming edge.
+    let lines = statements(&graph);
+    let nodes = lines.iter().filter(|line| !line.contains("->")).count();
+    let edges = lines.iter().filter(|line| line.contains("->")).count();
+    assert_eq!(edges, nodes - 1);
+}
+
+#[test]
+fn the_scope_graph_nests_scopes_with_their_symbols() {
+    let table = driver::check(SOURCE).unwrap().symbol_table;
+    let graph = draw_scopes(&table);
+    assert!(graph.contains("n0 [label=\"global\\lfact: Number (function)\\l\"];"));
+    assert!(graph.contains("[label=\"function fact\\ln: Number (parameter)\\l\"];"));
+    assert!(graph.contains("[label=\"let\\ls: String (variable)\\l\"];"));
+    assert!(graph.contains("[label=\"for\\li: Number (variable)\\l\"];"));
+    let edges = statements(&graph)
+        .into_iter()
+        .filter(|line| line.contains("->"))
+        .count();
+    assert_eq!(edges, table.scopes.len() - 1);
+}
+
+#[test]
+fn the_cfg_graph_has_a_cluster_per_function() {
+    let module = driver::lower(&mut driver::analyze(SOURCE).unwrap());
+    let graph = draw_module(&module);
+    let clusters = graph
+        .lines()
+        .filter(|line| line.trim().starts_with("subgraph cluster_"))
+        .count();
+    assert_eq!(clusters, module.functions.len());
+    assert!(
+        graph.contains("label=\"fact(%n.0: Number): Number\";"),
+        "{}",
+        graph
+    );
+    assert!(graph.contains("[label=\"true\"]"));
+    assert!(graph.contains("[label=\"false\"]"));
+    let blocks: usize = module
+        .functions
+        .iter()
+        .map(|function| function.blocks.len())
+        .sum();
+    let nodes = statements(&graph)
+        .into_iter()
+        .filter(|line| line.contains("[label=\"bb"))
+        .count();
+    assert_eq!(nodes, blocks);
+}
@@ -0,0 +1,179 @@
+//! Runs escape analysis on small hand-written IR functions and checks where each
+//! allocation is placed and how the function is rewritten.
+
+use compilador::intermediate::escape::{EscapeAnalysis, EscapeReason, Placement};
+use compilador::intermediate::ir::{
+    BinaryOp, Function, Instr, IrType, Module, Operand, Terminator, TypeLayout, VarId,
+};
+
+fn point() -> IrType {
+    IrType::Object("Point".to_string())
+}
+
+/// Builds a function whose entry block allocates a `Point` into `%p`, followed by what
+/// `body` adds to it.
+fn function(body: impl FnOnce(&mut Function, VarId)) -> Function {
+    let mut function = Function::new("f", IrType::Number);
+    let entry = function.new_block();
+    function.entry = entry;
+    let object = function.new_var(Some("p"), point());
+    function.blocks[entry].instrs.push(Instr::Alloc {
+        dest: object,
+        type_name: "Point".to_string(),
+        stack: false,
+    });
+    body(&mut function, object);
+    function
+}
+
+fn set_x(object: VarId, value: Operand) -> Instr {
+    Instr::SetField {
+        object: Operand::Var(object),
+        type_name: "Point".to_string(),
+        field: "x".to_string(),
+        value,
+    }
+}
+
+/// Runs the analysis on `function` alone and returns its only allocation's placement.
+fn analyze(function: Function) -> (Placement, Function) {
+    let mut module = Module {
+        types: vec![TypeLayout {
+            name: "Point".to_string(),
+            parent: None,
+            fields: vec![("x".to_string(), IrType::Number)],
+            methods: vec![],
+        }],
+        functions: vec![function],
+    };
+    let mut analysis = EscapeAnalysis::new();
+    analysis.analyze_module(&mut module);
+    assert_eq!(analysis.allocations.len(), 1, "{}", analysis.report());
+    let placement = analysis.allocations[0].placement.clone();
+    (placement, module.functions.pop().unwrap())
+}
+
+fn instrs(function: &Function) -> impl Iterator<Item = &Instr> {
+    function.blocks.iter().flat_map(|block| block.instrs.iter())
+}
+
+#[test]
+fn objects_only_used_for_their_fields_become_scalars() {
+    let (placement, function) = analyze(function(|function, p| {
+        let x = function.new_var(None, IrType::Number);
+        let block = &mut function.blocks[0];
+        block.instrs.push(set_x(p, Operand::Number(3.0)));
+        block.instrs.push(Instr::GetField {
+            dest: x,
+            object: Operand::Var(p),
+            type_name: "Point".to_string(),
+            field: "x".to_string(),
+        });
+        block.terminator = Terminator::Return(Operand::Var(x));
+    }));
+    assert_eq!(placement, Placement::Scalars);
+    assert!(
+        instrs(&function).all(|instr| matches!(instr, Instr::Copy { .. })),
+        "{}",
+        function
+    );
+    assert!(function
+        .vars
+        .iter()
+        .any(|var| var.name.as_deref() == Some("p.x")));
+}
+
+#[test]
+fn objects_used_as_values_that_do_not_escape_go_on_the_stack() {
+    let (placement, function) = analyze(function(|function, p| {
+        let block = &mut function.blocks[0];
+        block.instrs.push(set_x(p, Operand::Number(3.0)));
+        block.instrs.push(Instr::Print {
+            value: Operand::Var(p),
+        });
+        block.terminator = Terminator::Return(Operand::Number(0.0));
+    }));
+    assert_eq!(placement, Placement::Stack);
+    assert!(
+        instrs(&function).any(|instr| matches!(instr, Instr::Alloc { stack: true, .. })),
+        "{}",
+        function
+    );
+}
+
+#[test]
+fn returned_objects_stay_on_the_heap_through_copies() {
+    let (placement, function) = analyze(function(|function, p| {
+        function.return_type = point();
+        let q = function.new_var(Some("q"), point());
+        let block = &mut function.blocks[0];
+        block.instrs.push(Instr::Copy {
+            dest: q,
+            src: Operand::Var(p),
+        });
+        block.terminator = Terminator::Return(Operand::Var(q));
+    }));
+    assert_eq!(placement, Placement::Heap(EscapeReason::Returned));
+    assert!(
+        instrs(&function).any(|instr| matches!(instr, Instr::Alloc { stack: false, .. })),
+        "{}",
+        function
+    );
+}
+
+#[test]
+fn objects_passed_to_calls_stay_on_the_heap() {
+    let (placement, _) = analyze(function(|function, p| {
+        let result = function.new_var(None, IrType::Number);
+        let block = &mut function.blocks[0];
+        block.instrs.push(Instr::CallMethod {
+            dest: result,
+            receiver: Operand::Var(p),
+            static_type: "Point".to_string(),
+            method: "norm".to_string(),
+            args: vec![],
+            tail: false,
+        });
+        block.terminator = Terminator::Return(Operand::Var(result));
+    }));
+    assert_eq!(placement, Placement::Heap(EscapeReason::PassedToCall));
+}
+
+#[test]
+fn objects_allocated_in_loops_stay_on_the_heap() {
+    // entry: jump body; body: p = new Point; p.x = 1; if (c) jump body else exit
+    let mut function = Function::new("f", IrType::Number);
+    let entry = function.new_block();
+    let body = function.new_block();
+    let exit = function.new_block();
+    let p = function.new_var(Some("p"), point());
+    let c = function.new_var(Some("c"), IrType::Boolean);
+    function.blocks[entry].instrs.push(Instr::Copy {
+        dest: c,
+        src: Operand::Boolean(true),
+    });
+    function.blocks[entry].terminator = Terminator::Jump(body);
+    function.blocks[body].instrs = vec![
+        Instr::Alloc {
+            dest: p,
+            type_name: "Point".to_string(),
+            stack: false,
+        },
+        set_x(p, Operand::Number(1.0)),
+        Instr::Binary {
+            dest: c,
+            op: BinaryOp::Eq,
+            left: Operand::Var(c),
+            right: Operand::Boolean(false),
+        },
+    ];
+    function.blocks[body].terminator = Terminator::Branch {
+        cond: Operand::Var(c),
+        then_block: body,
+        else_block: exit,
+    };
+    function.blocks[exit].terminator = Terminator::Return(Operand::Number(0.0));
+
+    let (placement, _) = analyze(function);
+    assert_eq!(placement, Placement::Heap(EscapeReason::InLoop));
+}
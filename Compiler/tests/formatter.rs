@@ -0,0 +1,112 @@
+//! Checks that `hulk fmt` keeps the AST, is idempotent and lays out every construct.
+
+use std::fs;
+use std::path::Path;
+
+use compilador::formatter::{format_program, DEFAULT_WIDTH};
+use compilador::parser::ProgramParser;
+use compilador::visitor::tree_printer::TreePrinter;
+use regex::Regex;
+
+fn format(source: &str, width: usize) -> String {
+    let mut program = ProgramParser::new().parse(source).unwrap();
+    format_program(&mut program, width)
+}
+
+/// Dumps the AST of `source` without spans, which formatting moves.
+fn tree(source: &str) -> String {
+    let mut program = ProgramParser::new()
+        .parse(source)
+        .unwrap_or_else(|error| panic!("{}\n{}", error, source));
+    let tree = TreePrinter::new(false).print_program(&mut program);
+    Regex::new(r" @\d+\.\.\d+")
+        .unwrap()
+        .replace_all(&tree, "")
+        .into_owned()
+}
+
+#[test]
+fn formatting_keeps_the_ast_and_is_idempotent() {
+    let programs = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    for entry in fs::read_dir(programs).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "hulk") {
+            continue;
+        }
+        let source = fs::read_to_string(&path).unwrap();
+        for width in [DEFAULT_WIDTH, 40, 1] {
+            let formatted = format(&source, width);
+            assert_eq!(tree(&formatted), tree(&source), "{}", path.display());
+            assert_eq!(format(&formatted, width), formatted, "{}", path.display());
+        }
+    }
+}
+
+#[test]
+fn parentheses_follow_precedence() {
+    for (source, expected) in [
+        ("print(- x);", "print(-x);\n"),
+        ("(1 + 2) * 3;", "(1 + 2) * 3;\n"),
+        ("1 + (2 * 3);", "1 + 2 * 3;\n"),
+        ("a - (b - c);", "a - (b - c);\n"),
+        ("(a - b) - c;", "a - b - c;\n"),
+        ("2 ^ (3 ^ 4);", "2 ^ 3 ^ 4;\n"),
+        ("(2 ^ 3) ^ 4;", "(2 ^ 3) ^ 4;\n"),
+        ("-(2 ^ 2);", "-(2 ^ 2);\n"),
+        ("-(-x);", "-(-x);\n"),
+        ("- -x;", "-(-x);\n"),
+        ("!(!b);", "!(!b);\n"),
+        ("-(!b);", "-!b;\n"),
+        ("!(a & b) | c;", "!(a & b) | c;\n"),
+        ("\"n = \" @ (3 < 4);", "\"n = \" @ (3 < 4);\n"),
+        ("(let x = 1 in x).y;", "(let x = 1 in x).y;\n"),
+        ("let x = 1 in (x + 1);", "let x = 1 in (x + 1);\n"),
+        ("x := (y := 2);", "x := y := 2;\n"),
+    ] {
+        let formatted = format(source, DEFAULT_WIDTH);
+        assert_eq!(formatted, expected, "{}", source);
+        assert_eq!(tree(&formatted), tree(source), "{}", source);
+        assert_eq!(format(&formatted, DEFAULT_WIDTH), formatted, "{}", source);
+    }
+}
+
+#[test]
+fn blocks_are_indented_and_end_every_expression() {
+    let source = "while (x > 0) { print(x); x := x - 1 };";
+    assert_eq!(
+        format(source, DEFAULT_WIDTH),
+        "while (x > 0) {\n    print(x);\n    x := x - 1;\n};\n"
+    );
+    assert_eq!(format("{ 1 };", DEFAULT_WIDTH), "{ 1; };\n");
+    assert_eq!(format("{};", DEFAULT_WIDTH), "{};\n");
+}
+
+#[test]
+fn elif_and_else_follow_the_closing_brace() {
+    let source = "if (a) { 1 } elif (b) { 2 } else { 3 };";
+    assert_eq!(
+        format(source, DEFAULT_WIDTH),
+        "if (a) { 1; } elif (b) { 2; } else { 3; };\n"
+    );
+    assert_eq!(
+        format(source, 20),
+        "if (a) {\n    1;\n} elif (b) {\n    2;\n} else {\n    3;\n};\n"
+    );
+}
+
+#[test]
+fn lines_break_at_the_configured_width() {
+    let source = "function add(first: Number, second: Number): Number => first + second;";
+    let program = format!("{}\n\nadd(1, 2);", source);
+    assert_eq!(format(&program, DEFAULT_WIDTH), format!("{}\n", program));
+    assert_eq!(
+        format(&program, 40),
+        "function add(\n    first: Number,\n    second: Number\n\
+         ): Number => first + second;\n\nadd(1, 2);\n"
+    );
+    let source = "type P(x: Number) inherits Q(x) { x = x; get(): Number { self.x } }; 1;";
+    assert_eq!(
+        format(source, DEFAULT_WIDTH),
+        "type P(x: Number) inherits Q(x) {\n    x = x;\n    get(): Number { self.x; };\n};\n\n1;\n"
+    );
+}
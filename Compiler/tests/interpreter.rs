@@ -0,0 +1,83 @@
+//! Runs small programs with the tree-walking interpreter and checks what they print
+//! and the runtime errors that stop them.
+
+use compilador::interpreter::tree_walker;
+use compilador::interpreter::runtime_error::RuntimeError;
+use compilador::parser::ProgramParser;
+use compilador::semantic::semantic_visitor::SemanticVisitor;
+
+fn run(source: &str) -> Result<String, RuntimeError> {
+    let mut program = ProgramParser::new()
+        .parse(source)
+        .expect("test program parses");
+    if let Err(errors) = SemanticVisitor::new().check_program(&mut program) {
+        panic!(
+            "test program has errors: {:?}",
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>()
+        );
+    }
+    let mut output = Vec::new();
+    tree_walker::run(&mut program, &mut output)?;
+    Ok(String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn methods_dispatch_on_the_dynamic_type() {
+    let output = run(r#"
+        type Shape { area(): Number => 0; name(): String => "shape " @ self.area(); };
+        type Square(side: Number) inherits Shape { side = side; area(): Number => self.side ^ 2; };
+        function describe(s: Shape): String => s.name();
+        { print(describe(new Shape())); print(describe(new Square(3))); }
+    "#);
+    assert_eq!(output.unwrap(), "shape 0\nshape 9\n");
+}
+
+#[test]
+fn constructors_run_from_the_root_type() {
+    let output = run(r#"
+        type A(x: Number) { x = x; double = x * 2; };
+        type B(y: Number) inherits A(y + 1) { y = y; };
+        type C inherits B { z = 5; };
+        let c = new C(1) in print(c.x @ " " @ c.double @ " " @ c.y);
+    "#);
+    assert_eq!(output.unwrap(), "2 4 1\n");
+}
+
+#[test]
+fn assignments_update_the_innermost_variable_and_shared_objects() {
+    let output = run(r#"
+        type Box(v: Number) { v = v; set(v: Number): Number => self.v := v; };
+        {
+            let a = 1 in { let a = 2 in { a := 3; }; print(a); };
+            let b = new Box(1), c = b in { c.set(7); print(b.v); };
+        }
+    "#);
+    assert_eq!(output.unwrap(), "1\n7\n");
+}
+
+#[test]
+fn tail_recursion_does_not_nest() {
+    let output = run(r#"
+        function count(n: Number): Number => if (n == 0) { 0 } else { count(n - 1) };
+        print(count(100000));
+    "#);
+    assert_eq!(output.unwrap(), "0\n");
+}
+
+#[test]
+fn recursion_nests_up_to_the_call_limit() {
+    let output = run(r#"
+        function depth(n: Number): Number => if (n == 0) { 0 } else { 1 + depth(n - 1) };
+        print(depth(9000));
+    "#);
+    assert_eq!(output.unwrap(), "9000\n");
+}
+
+#[test]
+fn deep_recursion_is_reported() {
+    let output = run(r#"
+        function depth(n: Number): Number => if (n == 0) { 0 } else { 1 + depth(n - 1) };
+        print(depth(100000));
+    "#);
+    assert!(matches!(output, Err(RuntimeError::StackOverflow { .. })));
+}
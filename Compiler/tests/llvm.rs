@@ -0,0 +1,90 @@
+//! Checks the text of the LLVM IR backend: the structs and vtables it lays out for
+//! types and the calls it emits, without needing LLVM installed.
+
+use compilador::codegen::llvm::LlvmGenerator;
+use compilador::driver;
+use regex::Regex;
+
+const ANIMALS: &str = "\
+    type Animal(name: String) { name = name; speak(): String => \"...\"; };
+    type Dog inherits Animal { speak(): String => \"woof\"; };
+    function speak(a: Animal): String => a.speak();
+    function twice(n: Number): Number => n * 2;
+    { print(speak(new Dog(\"rex\"))); print(twice(21)); print(true); };";
+
+fn emit(source: &str) -> String {
+    let mut checked = driver::analyze(source).unwrap();
+    let module = driver::lower(&mut checked);
+    LlvmGenerator::new(&module).generate()
+}
+
+/// Returns the line of `ir` that starts with `prefix`.
+fn line<'a>(ir: &'a str, prefix: &str) -> &'a str {
+    ir.lines()
+        .find(|line| line.starts_with(prefix))
+        .unwrap_or_else(|| panic!("no line starts with {}\n{}", prefix, ir))
+}
+
+#[test]
+fn types_become_structs_with_a_vtable_pointer() {
+    let ir = emit(ANIMALS);
+    assert_eq!(line(&ir, "%Animal = "), "%Animal = type { ptr, ptr }");
+    assert_eq!(line(&ir, "%Dog = "), "%Dog = type { ptr, ptr }");
+    // Name, parent, pointer map and one slot per method.
+    assert_eq!(
+        line(&ir, "%Dog.vtable = "),
+        "%Dog.vtable = type { ptr, ptr, ptr, ptr }"
+    );
+    assert!(line(&ir, "@Animal.pointers = ").contains("[2 x i64] [i64 1, "));
+}
+
+#[test]
+fn vtables_point_to_the_parent_and_the_overriding_methods() {
+    let ir = emit(ANIMALS);
+    assert!(line(&ir, "@Animal.vtable = ")
+        .ends_with("ptr @hulk_object_vtable, ptr @Animal.pointers, ptr @hulk.Animal.speak }"));
+    assert!(line(&ir, "@Dog.vtable = ")
+        .ends_with("ptr @Animal.vtable, ptr @Dog.pointers, ptr @hulk.Dog.speak }"));
+    assert!(ir.contains("c\"Dog\\00\""), "{}", ir);
+
+    // `a` may be any animal, so `speak` is loaded from the fourth slot of its vtable.
+    let virtual_call =
+        Regex::new(r"getelementptr %Animal\.vtable, ptr %t\d+, i32 0, i32 3\n").unwrap();
+    assert!(virtual_call.is_match(&ir), "{}", ir);
+    assert!(ir.contains("call ptr @hulk_alloc_object(ptr @Dog.vtable, "));
+}
+
+#[test]
+fn print_calls_the_runtime_function_of_the_static_type() {
+    let ir = emit(ANIMALS);
+    let prints: Vec<&str> = Regex::new(r"call void @(hulk_print_\w+)\((\w+) ")
+        .unwrap()
+        .captures_iter(&ir)
+        .map(|captures| captures.get(1).unwrap().as_str())
+        .collect();
+    assert_eq!(
+        prints,
+        ["hulk_print_string", "hulk_print_number", "hulk_print_bool"]
+    );
+    assert!(ir.contains("call void @hulk_print_bool(i1 true)"));
+}
+
+#[test]
+fn every_called_function_is_defined_or_declared() {
+    let ir = emit(ANIMALS);
+    let calls: Vec<&str> = Regex::new(r"call \w+ (@[\w.]+)\(")
+        .unwrap()
+        .captures_iter(&ir)
+        .map(|captures| captures.get(1).unwrap().as_str())
+        .collect();
+    assert!(calls.contains(&"@hulk.twice"), "{:?}", calls);
+    for function in calls {
+        let defined = Regex::new(&format!(
+            r"(?m)^(define|declare) \w+ {}\(",
+            regex::escape(function)
+        ))
+        .unwrap();
+        assert!(defined.is_match(&ir), "{} is never defined", function);
+    }
+    assert!(ir.contains("define i32 @main() {"));
+}
@@ -0,0 +1,127 @@
+//! Runs the loop optimizations on the IR of small programs and checks what they move
+//! out of loops and what they rewrite inside them.
+
+use compilador::driver;
+use compilador::intermediate::ir::{BinaryOp, Function, Instr, Module};
+use compilador::intermediate::ir_generator::IrGenerator;
+use compilador::intermediate::loop_optimizations::LoopOptimizer;
+use compilador::intermediate::loops::find_loops;
+use compilador::visitor::tree_printer::TreePrinter;
+
+/// Lowers `source` to IR and optimizes its loops.
+fn optimize(source: &str) -> (Module, LoopOptimizer) {
+    let mut checked =
+        driver::analyze(source).unwrap_or_else(|errors| panic!("{}: {:?}", source, errors));
+    let mut module = IrGenerator::new(&checked.type_tree).generate_program(&mut checked.program);
+    let mut optimizer = LoopOptimizer::new();
+    optimizer.optimize_module(&mut module);
+    (module, optimizer)
+}
+
+/// The instructions of `function` that run on every iteration of some loop.
+fn loop_instrs(function: &Function) -> Vec<&Instr> {
+    let loops = find_loops(function);
+    function
+        .blocks
+        .iter()
+        .enumerate()
+        .filter(|(block, _)| loops.iter().any(|lp| lp.contains(*block)))
+        .flat_map(|(_, block)| block.instrs.iter())
+        .collect()
+}
+
+fn multiplications(function: &Function) -> usize {
+    loop_instrs(function)
+        .into_iter()
+        .filter(|instr| {
+            matches!(
+                instr,
+                Instr::Binary {
+                    op: BinaryOp::Mul,
+                    ..
+                }
+            )
+        })
+        .count()
+}
+
+#[test]
+fn for_loops_strength_reduce_their_index() {
+    let source = "let s = 0 in { for (i in range(0, 10)) { s := s + i * 3; }; print(s); };";
+    let (module, optimizer) = optimize(source);
+    let main = module.get_function("main").unwrap();
+    assert_eq!(optimizer.reduced, 1, "{}", main);
+    assert_eq!(multiplications(main), 0, "{}", main);
+}
+
+#[test]
+fn desugared_for_loops_are_strength_reduced() {
+    // The desugarer turns both loops into `while` loops over counters that are
+    // incremented through a temporary.
+    let source = "let s = 0 in { for (i in range(0, 10)) { for (j in range(1, 4)) \
+                  { s := s + j * 4 + i * 2; }; }; print(s); };";
+    let mut checked = driver::analyze(source).unwrap();
+    let tree = TreePrinter::new(false).print_program(&mut checked.program);
+    assert!(!tree.contains("For "), "{}", tree);
+    let (module, optimizer) = optimize(source);
+    let main = module.get_function("main").unwrap();
+    assert!(optimizer.reduced > 0, "{}", main);
+}
+
+/// The functions called on every iteration of some loop.
+fn loop_calls(function: &Function) -> Vec<&str> {
+    loop_instrs(function)
+        .into_iter()
+        .filter_map(|instr| match instr {
+            Instr::Call { function, .. } => Some(function.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn while_loops_strength_reduce_integer_induction_variables() {
+    let source =
+        "let i = 0, s = 0 in { while (i < 10) { s := s + i * 4; i := i + 1; }; print(s); };";
+    let (module, optimizer) = optimize(source);
+    let main = module.get_function("main").unwrap();
+    assert_eq!((optimizer.loops, optimizer.reduced), (1, 1), "{}", main);
+    assert_eq!(multiplications(main), 0, "{}", main);
+}
+
+#[test]
+fn fractional_steps_and_factors_are_not_reduced() {
+    for source in [
+        "let i = 0, s = 0 in { while (i < 10) { s := s + i * 4; i := i + 0.5; }; print(s); };",
+        "let i = 0, s = 0 in { while (i < 10) { s := s + i * 0.5; i := i + 1; }; print(s); };",
+    ] {
+        let (module, optimizer) = optimize(source);
+        let main = module.get_function("main").unwrap();
+        assert_eq!(optimizer.reduced, 0, "{}", main);
+        assert_eq!(multiplications(main), 1, "{}", main);
+    }
+}
+
+#[test]
+fn invariants_move_to_the_preheader() {
+    let source = "let a = 3, i = 0, s = 0 in { \
+                  while (i < 10) { s := s + sqrt(a) + (a * 7 + 2); i := i + 1; }; \
+                  print(s); };";
+    let (module, optimizer) = optimize(source);
+    let main = module.get_function("main").unwrap();
+    assert_eq!(optimizer.hoisted, 3, "{}", main);
+    assert_eq!(multiplications(main), 0, "{}", main);
+    assert_eq!(loop_calls(main), Vec::<&str>::new(), "{}", main);
+}
+
+#[test]
+fn impure_calls_and_redefined_builtins_stay_in_the_loop() {
+    let source = "function sqrt(x: Number): Number => { print(x); x; }; \
+                  let a = 3, i = 0, s = 0 in { \
+                  while (i < 10) { s := s + sqrt(a) + rand() + a * 7; i := i + 1; }; \
+                  print(s); };";
+    let (module, optimizer) = optimize(source);
+    let main = module.get_function("main").unwrap();
+    assert_eq!(optimizer.hoisted, 1, "{}", main);
+    assert_eq!(loop_calls(main), vec!["sqrt", "rand"], "{}", main);
+}
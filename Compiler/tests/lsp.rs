@@ -0,0 +1,234 @@
+//! Scripted sessions with the language server, the way an editor drives it.
+
+use std::io::{BufReader, Write};
+use std::process::{Command, Stdio};
+
+use compilador::lsp::protocol::{read_message, write_message};
+use compilador::lsp::server::Server;
+use serde_json::{json, Value};
+
+const URI: &str = "file:///test.hulk";
+
+const PROGRAM: &str = "\
+type Point(x: Number, y: Number) {
+    x = x;
+    y = y;
+    norm(): Number => sqrt(self.x ^ 2 + self.y ^ 2);
+};
+
+type Point3(x: Number, y: Number, z: Number) inherits Point(x, y) {
+    z = z;
+};
+
+function twice(n: Number): Number => 2 * n;
+
+let p = new Point3(1, 2, 3) in print(twice(p.norm()));
+";
+
+/// Runs a session made of `messages`, returning what the server wrote.
+fn session(messages: Vec<Value>) -> Vec<Value> {
+    let mut input = Vec::new();
+    for message in messages.iter() {
+        write_message(&mut input, message).unwrap();
+    }
+    let mut output = Vec::new();
+    Server::new().run(input.as_slice(), &mut output).unwrap();
+    let mut reader = BufReader::new(output.as_slice());
+    let mut replies = Vec::new();
+    while let Some(message) = read_message(&mut reader).unwrap() {
+        replies.push(message);
+    }
+    replies
+}
+
+fn open(text: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": { "uri": URI, "languageId": "hulk", "version": 1, "text": text },
+        },
+    })
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn at(line: u64, character: u64) -> Value {
+    json!({ "textDocument": { "uri": URI }, "position": { "line": line, "character": character } })
+}
+
+/// Returns the result of the request with `id`.
+fn result(replies: &[Value], id: u64) -> Value {
+    replies
+        .iter()
+        .find(|reply| reply["id"] == json!(id))
+        .unwrap_or_else(|| panic!("no reply to {}", id))["result"]
+        .clone()
+}
+
+#[test]
+fn changes_publish_diagnostics() {
+    let change = json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": URI, "version": 2 },
+            "contentChanges": [{ "text": "print(1 + );" }],
+        },
+    });
+    let replies = session(vec![open("let x = 1 in\nprint(y);"), change]);
+    assert_eq!(replies.len(), 2);
+
+    let semantic = &replies[0]["params"];
+    assert_eq!(replies[0]["method"], "textDocument/publishDiagnostics");
+    assert_eq!(semantic["version"], 1);
+    assert_eq!(
+        semantic["diagnostics"][0]["message"],
+        "variable `y` is not defined"
+    );
+    assert_eq!(
+        semantic["diagnostics"][0]["range"],
+        json!({ "start": { "line": 1, "character": 6 }, "end": { "line": 1, "character": 7 } })
+    );
+
+    let syntax = &replies[1]["params"];
+    assert_eq!(syntax["version"], 2);
+    assert_eq!(syntax["diagnostics"][0]["code"], "syntax");
+    assert_eq!(syntax["diagnostics"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn hover_shows_inferred_types() {
+    let replies = session(vec![
+        open(PROGRAM),
+        request(1, "textDocument/hover", at(12, 4)),
+        request(2, "textDocument/hover", at(12, 47)),
+        request(3, "textDocument/hover", at(12, 40)),
+        request(4, "textDocument/hover", at(3, 23)),
+    ]);
+    let hover = |id| result(&replies, id)["contents"]["value"].clone();
+    assert_eq!(hover(1), "```hulk\np: Point3\n```");
+    assert_eq!(hover(2), "```hulk\nPoint.norm(): Number\n```");
+    assert_eq!(hover(3), "```hulk\nfunction twice(n: Number): Number\n```");
+    assert_eq!(hover(4), "```hulk\nfunction sqrt(x: Number): Number\n```");
+}
+
+#[test]
+fn definitions_of_functions_types_methods_and_variables() {
+    let replies = session(vec![
+        open(PROGRAM),
+        request(1, "textDocument/definition", at(12, 40)),
+        request(2, "textDocument/definition", at(12, 14)),
+        request(3, "textDocument/definition", at(12, 47)),
+        request(4, "textDocument/definition", at(12, 43)),
+        request(5, "textDocument/definition", at(6, 55)),
+        request(6, "textDocument/definition", at(6, 60)),
+        request(7, "textDocument/definition", at(3, 27)),
+    ]);
+    let line = |id| {
+        let range = result(&replies, id)["range"].clone();
+        (
+            range["start"]["line"].clone(),
+            range["start"]["character"].clone(),
+        )
+    };
+    assert_eq!(line(1), (json!(10), json!(9)));
+    assert_eq!(line(2), (json!(6), json!(5)));
+    assert_eq!(line(3), (json!(3), json!(4)));
+    assert_eq!(line(4), (json!(12), json!(4)));
+    assert_eq!(line(5), (json!(0), json!(5)));
+    assert_eq!(line(6), (json!(6), json!(12)));
+    assert_eq!(line(7), (json!(0), json!(0)));
+}
+
+#[test]
+fn completion_lists_the_members_of_the_receiver() {
+    let text = PROGRAM.replace("p.norm()", "p.");
+    let replies = session(vec![
+        open(&text),
+        request(1, "textDocument/completion", at(12, 45)),
+    ]);
+    let labels: Vec<Value> = result(&replies, 1)
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].clone())
+        .collect();
+    assert_eq!(
+        labels,
+        vec![json!("z"), json!("x"), json!("y"), json!("norm")]
+    );
+}
+
+#[test]
+fn document_symbols_list_functions_and_types() {
+    let replies = session(vec![
+        open(PROGRAM),
+        request(
+            1,
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": URI } }),
+        ),
+    ]);
+    let symbols = result(&replies, 1);
+    let names: Vec<(Value, Value)> = symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| (symbol["name"].clone(), symbol["kind"].clone()))
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            (json!("Point"), json!(5)),
+            (json!("Point3"), json!(5)),
+            (json!("twice"), json!(12)),
+        ]
+    );
+    let members: Vec<Value> = symbols[0]["children"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|member| member["name"].clone())
+        .collect();
+    assert_eq!(members, vec![json!("x"), json!("y"), json!("norm")]);
+}
+
+#[test]
+fn unknown_requests_and_shutdown() {
+    let replies = session(vec![
+        request(1, "initialize", json!({})),
+        request(2, "workspace/frobnicate", json!({})),
+        request(3, "shutdown", Value::Null),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+        request(4, "initialize", json!({})),
+    ]);
+    assert_eq!(replies.len(), 3);
+    assert_eq!(
+        result(&replies, 1)["capabilities"]["completionProvider"]["triggerCharacters"],
+        json!(["."])
+    );
+    assert_eq!(replies[1]["error"]["code"], -32601);
+    assert_eq!(replies[2]["result"], Value::Null);
+}
+
+#[test]
+fn the_binary_speaks_over_standard_io() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_hulk-lsp"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    write_message(&mut stdin, &request(1, "initialize", json!({}))).unwrap();
+    write_message(&mut stdin, &json!({ "jsonrpc": "2.0", "method": "exit" })).unwrap();
+    stdin.flush().unwrap();
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let mut reader = BufReader::new(output.stdout.as_slice());
+    let reply = read_message(&mut reader).unwrap().unwrap();
+    assert_eq!(reply["result"]["serverInfo"]["name"], "hulk-lsp");
+}
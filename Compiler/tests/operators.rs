@@ -0,0 +1,148 @@
+//! Walks the operator table and checks that the lexer, both parsers and the formatter
+//! agree with it on every operator.
+
+use compilador::ast_nodes::program::Program;
+use compilador::cst;
+use compilador::driver;
+use compilador::formatter::{format_program, DEFAULT_WIDTH};
+use compilador::interpreter::tree_walker;
+use compilador::operators::{Associativity, Fixity, Operator, OPERATORS};
+use compilador::parser::ProgramParser;
+use compilador::tokens::Token;
+use compilador::visitor::sexp_printer::SexpPrinter;
+
+fn infix() -> impl Iterator<Item = &'static Operator> {
+    OPERATORS
+        .iter()
+        .filter(|entry| entry.fixity != Fixity::Prefix)
+}
+
+fn prefix() -> impl Iterator<Item = &'static Operator> {
+    OPERATORS
+        .iter()
+        .filter(|entry| entry.fixity == Fixity::Prefix)
+}
+
+/// Parses `source` with `ProgramParser` and the CST, which must agree.
+fn parse(source: &str) -> Program {
+    let program = ProgramParser::new()
+        .parse(source)
+        .unwrap_or_else(|error| panic!("{}: {}", source, error));
+    let parse = cst::parse(source);
+    assert_eq!(parse.errors, vec![], "{}", source);
+    assert_eq!(parse.to_ast().unwrap(), program, "{}", source);
+    program
+}
+
+/// The shape of `source` without spans, which parentheses widen.
+fn shape(source: &str) -> String {
+    SexpPrinter::new(false).print_program(&mut parse(source))
+}
+
+/// Checks that `source` groups like `grouped` and that its formatting does too.
+fn assert_groups(source: &str, grouped: &str) {
+    let expected = shape(grouped);
+    assert_eq!(shape(source), expected, "{} should be {}", source, grouped);
+    let formatted = format_program(&mut parse(source), DEFAULT_WIDTH);
+    assert_eq!(
+        shape(&formatted),
+        expected,
+        "{} formats as {}",
+        source,
+        formatted
+    );
+}
+
+#[test]
+fn every_spelling_lexes_to_one_token() {
+    for entry in OPERATORS {
+        let source = match entry.fixity {
+            Fixity::Prefix => format!("{}a", entry.spelling),
+            Fixity::Infix(_) => format!("a{}b", entry.spelling),
+        };
+        let (tokens, errors) = driver::tokenize(&source);
+        assert!(errors.is_empty(), "{}", source);
+        let operator = match entry.fixity {
+            Fixity::Prefix => &tokens[0],
+            Fixity::Infix(_) => &tokens[1],
+        };
+        assert_eq!(operator.0, entry.token(), "{}", source);
+        assert_eq!(&source[operator.1.start..operator.1.end], entry.spelling);
+
+        let tokens = cst::lexer::tokenize(&source);
+        let (kind, text) = match entry.fixity {
+            Fixity::Prefix => (tokens[0].0.prefix_operator(), tokens[0].1),
+            Fixity::Infix(_) => (tokens[1].0.infix_operator(), tokens[1].1),
+        };
+        assert_eq!((kind, text), (Some(entry), entry.spelling), "{}", source);
+
+        // `^` and `**` are the same operator, which displays as the first spelling.
+        let first = OPERATORS
+            .iter()
+            .find(|other| other.operator == entry.operator)
+            .unwrap();
+        assert_eq!(entry.operator.to_string(), first.spelling, "{}", source);
+    }
+
+    // `&&` and `||` are not HULK: they lex as two operators and do not parse.
+    let (tokens, _) = driver::tokenize("a && b || c");
+    let ands = tokens
+        .iter()
+        .filter(|(token, _)| matches!(token, Token::And(_)))
+        .count();
+    assert_eq!(ands, 2);
+    for source in ["a && b;", "a || b;"] {
+        assert!(ProgramParser::new().parse(source).is_err(), "{}", source);
+        assert!(!cst::parse(source).errors.is_empty(), "{}", source);
+    }
+}
+
+#[test]
+fn every_pair_of_infix_operators_groups_by_the_table() {
+    for first in infix() {
+        for second in infix() {
+            // Only a name or a member may be assigned, so `:=` cannot follow another
+            // operator.
+            if second.spelling == ":=" && first.spelling != ":=" {
+                continue;
+            }
+            let (a, b) = (first.spelling, second.spelling);
+            let left_first = first.precedence > second.precedence
+                || (first.precedence == second.precedence
+                    && first.associativity() == Some(Associativity::Left));
+            let grouped = if left_first {
+                format!("(x {} y) {} z;", a, b)
+            } else {
+                format!("x {} (y {} z);", a, b)
+            };
+            assert_groups(&format!("x {} y {} z;", a, b), &grouped);
+        }
+    }
+}
+
+#[test]
+fn prefix_operators_bind_tighter_than_every_infix_operator() {
+    for unary in prefix() {
+        for binary in infix().filter(|entry| entry.spelling != ":=") {
+            let (p, o) = (unary.spelling, binary.spelling);
+            assert_groups(&format!("{}x {} y;", p, o), &format!("({}x) {} y;", p, o));
+            assert_groups(&format!("x {} {}y;", o, p), &format!("x {} ({}y);", o, p));
+        }
+        assert_groups(
+            &format!("{}{}x;", unary.spelling, unary.spelling),
+            &format!("{}({}x);", unary.spelling, unary.spelling),
+        );
+    }
+}
+
+#[test]
+fn spaced_concatenation_and_double_star_evaluate() {
+    let source = "print(\"a\" @@ 1 + 2 @@ 2 ** 3 ** 2 @ \"!\");";
+    let mut checked = driver::check(source).unwrap().program;
+    let mut desugared = driver::analyze(source).unwrap().program;
+    for program in [&mut checked, &mut desugared] {
+        let mut out = Vec::new();
+        tree_walker::run(program, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "a 3 512!\n");
+    }
+}
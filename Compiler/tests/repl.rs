@@ -0,0 +1,108 @@
+//! Drives REPL sessions from scripted input.
+
+use std::fs;
+use std::path::Path;
+
+use compilador::repl::{Repl, CONTINUATION_PROMPT, PROMPT};
+
+/// Runs a session on `input` and returns what it wrote, without the prompts.
+fn session(input: &str) -> String {
+    let mut out = Vec::new();
+    Repl::new().run(input.as_bytes(), &mut out).unwrap();
+    String::from_utf8(out)
+        .unwrap()
+        .replace(PROMPT, "")
+        .replace(CONTINUATION_PROMPT, "")
+}
+
+#[test]
+fn definitions_persist_and_expressions_show_value_and_type() {
+    let output = session(
+        "function double(x: Number): Number => x * 2;\n\
+         type Box(value: String) { value = value; get(): String => self.value; };\n\
+         double(21);\n\
+         new Box(\"hi\").get() @ \"!\";\n\
+         double(1) > 1;\n",
+    );
+    assert_eq!(
+        output,
+        "defined function `double`\ndefined type `Box`\n42 : Number\n\"hi!\" : String\ntrue : Boolean\n\n"
+    );
+}
+
+#[test]
+fn incomplete_input_asks_for_more_lines() {
+    let mut out = Vec::new();
+    Repl::new()
+        .run("{\n  print(1);\n  2;\n}\n;\n3\n;\n".as_bytes(), &mut out)
+        .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(out.matches(CONTINUATION_PROMPT).count(), 5, "{}", out);
+    assert!(out.contains("1\n2 : Number\n"), "{}", out);
+    assert!(out.contains("3 : Number\n"), "{}", out);
+
+    let output = session("let x = 1 in\n\nprint(2);\n");
+    assert!(
+        output.contains("syntax error: unexpected end of input"),
+        "{}",
+        output
+    );
+    assert!(output.contains("2\n2 : Number"), "{}", output);
+}
+
+#[test]
+fn errors_keep_the_session_going() {
+    let output = session(
+        "function f(x: Number): Number => x + 1;\n\
+         f(\"a\");\n\
+         function f(x: Number): String => \"redefined\";\n\
+         f(1);\n",
+    );
+    assert!(
+        output.contains("semantic error: expected type `Number`, found `String`"),
+        "{}",
+        output
+    );
+    assert!(output.ends_with("\"redefined\" : String\n\n"), "{}", output);
+}
+
+#[test]
+fn commands_inspect_expressions_and_the_session() {
+    let output = session(
+        "function f(x: Number): Number => x;\n\
+         :type f(1) @ \"a\"\n\
+         :ast -1\n\
+         :history\n\
+         :quit\n\
+         f(1);\n",
+    );
+    assert_eq!(
+        output,
+        "defined function `f`\nString\nProgram @0..3\n  UnaryOp - @0..2\n    Number 1 @1..2\n\
+         \x20  1  function f(x: Number): Number => x;\n\
+         \x20  2  :type f(1) @ \"a\"\n\
+         \x20  3  :ast -1\n\
+         \x20  4  :history\n"
+    );
+}
+
+#[test]
+fn load_runs_a_program_and_keeps_its_definitions() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("repl");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("square.hulk");
+    fs::write(
+        &path,
+        "function square(x: Number): Number => x * x;\nprint(square(3));\n",
+    )
+    .unwrap();
+
+    let output = session(&format!(":load {}\nsquare(4);\n", path.display()));
+    assert_eq!(
+        output,
+        format!(
+            "9\nloaded `{}`, 1 definition(s)\n16 : Number\n\n",
+            path.display()
+        )
+    );
+}
@@ -0,0 +1,125 @@
+//! Checks the semantic phase: the errors it reports, the type tree it builds, and the
+//! method calls the devirtualization pass resolves with it.
+
+use compilador::ast_nodes::type_member_access::TypeFunctionAccessNode;
+use compilador::driver;
+use compilador::semantic::devirtualization::DevirtualizationVisitor;
+use compilador::visitor::ref_visitor::{self, RefVisitor};
+
+/// Returns the message and the text of every error of `source`.
+fn errors(source: &str) -> Vec<(String, &str)> {
+    let Err(diagnostics) = driver::check(source) else {
+        panic!("{} has no errors", source);
+    };
+    diagnostics
+        .into_iter()
+        .map(|diagnostic| {
+            let span = diagnostic.span.unwrap();
+            (diagnostic.message, &source[span.start..span.end])
+        })
+        .collect()
+}
+
+fn error(message: &str, text: &'static str) -> (String, &'static str) {
+    (message.to_string(), text)
+}
+
+const ANIMALS: &str = "\
+    type Animal { speak(): String => \"...\"; name(): String => \"animal\"; };
+    type Dog inherits Animal { speak(): String => \"woof\"; };
+    type Point { x(): Number => 1; };
+    function f(a: Animal, p: Point): String =>
+        a.speak() @ p.x() @ new Dog().name() @ new Dog().speak();
+    print(f(new Dog(), new Point()));";
+
+/// Records the target every method call is resolved to, by method name.
+#[derive(Default)]
+struct Targets(Vec<(String, Option<String>)>);
+
+impl<'ast> RefVisitor<'ast> for Targets {
+    fn visit_type_function_access(&mut self, node: &'ast TypeFunctionAccessNode) {
+        self.0
+            .push((node.member.function_name.clone(), node.direct_call.clone()));
+        ref_visitor::walk_type_function_access(self, node);
+    }
+}
+
+#[test]
+fn undefined_types_in_signatures_are_reported_once() {
+    let source = "function f(x: Foo): Bar => x; type A(y: Baz) { m(z: Foo): Number => 1; }; f(1);";
+    assert_eq!(
+        errors(source),
+        vec![
+            error("type `Baz` is not defined", "y"),
+            error("type `Foo` is not defined", "z"),
+            error("type `Foo` is not defined", "x"),
+            error("type `Bar` is not defined", "function f(x: Foo): Bar => x"),
+        ]
+    );
+}
+
+#[test]
+fn calls_overrides_and_inheritance_are_checked() {
+    let source = "type A { m(x: Number): Number => x; }; \
+                  type B inherits A { m(x: String): Number => 1; }; \
+                  type C inherits Number { }; \
+                  function f(x: Number): Number => x; \
+                  { f(1, 2); g(); };";
+    let messages: Vec<String> = errors(source)
+        .into_iter()
+        .map(|(message, _)| message)
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            "type `C` cannot inherit from `Number`",
+            "method `m` of type `B` does not match the signature it overrides",
+            "`f` expects 1 argument(s), but 2 were given",
+            "function `g` is not defined",
+        ]
+    );
+}
+
+#[test]
+fn the_type_tree_follows_inheritance() {
+    let tree = driver::check(ANIMALS).unwrap().type_tree;
+    assert!(tree.conforms("Dog", "Animal"));
+    assert!(tree.conforms("Dog", "Object"));
+    assert!(!tree.conforms("Animal", "Dog"));
+    assert_eq!(tree.lowest_common_ancestor("Dog", "Animal"), "Animal");
+    assert_eq!(tree.lowest_common_ancestor("Dog", "Point"), "Object");
+    assert!(tree.has_subtypes("Animal"));
+    assert!(!tree.has_subtypes("Dog"));
+    assert_eq!(tree.find_method("Dog", "speak").unwrap().0, "Dog");
+    assert_eq!(tree.find_method("Dog", "name").unwrap().0, "Animal");
+    assert!(tree.find_method("Point", "speak").is_none());
+}
+
+#[test]
+fn calls_on_exact_types_are_devirtualized() {
+    let mut checked = driver::check(ANIMALS).unwrap();
+    let mut devirtualization = DevirtualizationVisitor::new(&checked.type_tree);
+    devirtualization.devirtualize_program(&mut checked.program);
+    assert_eq!(devirtualization.call_sites, 4);
+    assert_eq!(devirtualization.devirtualized, 3);
+    assert_eq!(
+        devirtualization.report(),
+        "devirtualized 3 of 4 method call site(s)"
+    );
+
+    let mut targets = Targets::default();
+    targets.visit_program(&checked.program);
+    let target = |method: &str, owner: Option<&str>| (method.to_string(), owner.map(String::from));
+    assert_eq!(
+        targets.0,
+        vec![
+            // `Animal` has subtypes, so `a` may be any of them.
+            target("speak", None),
+            // `Point` has none.
+            target("x", Some("Point")),
+            // A fresh object has an exact type, and the method may be inherited.
+            target("name", Some("Animal")),
+            target("speak", Some("Dog")),
+        ]
+    );
+}
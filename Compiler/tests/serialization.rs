@@ -0,0 +1,108 @@
+//! Checks that the AST survives a round trip through JSON and that the S-expression
+//! dump shows its structure.
+
+use std::fs;
+use std::path::Path;
+
+use compilador::ast_nodes::program::Program;
+use compilador::driver;
+use compilador::parser::ProgramParser;
+use compilador::visitor::sexp_printer::SexpPrinter;
+use serde_json::Value;
+
+fn programs() -> Vec<(String, String)> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let mut programs = Vec::new();
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "hulk")
+        {
+            let source = fs::read_to_string(&path).unwrap();
+            programs.push((path.display().to_string(), source));
+        }
+    }
+    programs
+}
+
+#[test]
+fn json_round_trips_parsed_and_typed_programs() {
+    for (name, source) in programs() {
+        let parsed = ProgramParser::new().parse(&source).unwrap();
+        assert_eq!(
+            Program::from_json(&parsed.to_json()).unwrap(),
+            parsed,
+            "{}",
+            name
+        );
+
+        // Typed programs also carry the static types and backend annotations.
+        let analyzed = driver::analyze(&source).unwrap().program;
+        assert_eq!(
+            Program::from_json(&analyzed.to_json()).unwrap(),
+            analyzed,
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn json_has_kinds_spans_and_types_when_present() {
+    let source = "function f(x: Number): Number => -x; { f(2) @ \"s\"; };";
+    let parsed = ProgramParser::new().parse(source).unwrap();
+    let json: Value = serde_json::from_str(&parsed.to_json()).unwrap();
+    let function = &json["statements"][0];
+    assert_eq!(function["kind"], "FunctionDef");
+    assert_eq!(function["node"]["name"], "f");
+    assert_eq!(function["node"]["params"][0]["signature"], "Number");
+    let negation = &function["node"]["body"];
+    assert_eq!(negation["kind"], "UnaryOp");
+    assert_eq!(negation["operator"], "NEG");
+    assert_eq!(negation["span"]["start"], 33);
+    assert_eq!(negation["span"]["end"], 35);
+    assert!(negation.get("node_type").is_none());
+    let block = &json["statements"][1]["node"];
+    assert_eq!(block["kind"], "CodeBlock");
+    assert_eq!(
+        block["expression_list"]["expressions"][0]["right"]["value"],
+        "s"
+    );
+
+    let checked = driver::check(source).unwrap().program;
+    let json: Value = serde_json::from_str(&checked.to_json()).unwrap();
+    assert_eq!(
+        json["statements"][0]["node"]["body"]["node_type"]["type_name"],
+        "Number"
+    );
+
+    assert!(Program::from_json("{\"statements\": [{\"kind\": \"Loop\"}]}").is_err());
+}
+
+#[test]
+fn sexp_shows_every_construct() {
+    let source = "type A(x: Number) inherits B(x, 1) { y = x; m(): Object => self.y.z(1); };\n\
+                  function f(a: Number): Boolean { a := a ^ 2; !(a >= 1) };\n\
+                  let n = new A(1), s = \"q\\\"\" in for (i in range(0, 2)) \
+                  while (false) if (true) { print(s); } elif (n.y == 1) { f(i) } else { };";
+    let mut program = ProgramParser::new().parse(source).unwrap();
+    assert_eq!(
+        SexpPrinter::new(false).print_program(&mut program),
+        "(type A ((x Number)) (inherits B (identifier x) (number 1)) \
+         (property y (identifier x)) \
+         (method m () Object (method-call (attribute (identifier self) y) z (number 1))))\n\
+         (function f ((a Number)) Boolean (block (:= (identifier a) (^ (identifier a) (number 2))) \
+         (! (>= (identifier a) (number 1)))))\n\
+         (let ((n (new A (number 1))) (s (string \"q\\\"\"))) (for i (number 0) (number 2) \
+         (while (boolean false) (if (boolean true) (block (print (identifier s))) \
+         (elif (== (attribute (identifier n) y) (number 1)) (block (call f (identifier i)))) \
+         (else (block))))))\n"
+    );
+
+    let mut checked = driver::check("print(1 + 2);").unwrap().program;
+    assert_eq!(
+        SexpPrinter::new(true).print_program(&mut checked),
+        "(print :Number (+ :Number (number :Number 1) (number :Number 2)))\n"
+    );
+}
@@ -0,0 +1,109 @@
+//! Checks that every node spans exactly its part of the source.
+
+use std::fs;
+use std::path::Path;
+
+use compilador::arena::{Ast, Node, NodeId};
+use compilador::ast_nodes::expression::Expression;
+use compilador::ast_nodes::program::Statement;
+use compilador::cst;
+use compilador::parser::ProgramParser;
+
+/// Checks that the span of every node of `ast` lies inside the span of its parent.
+fn assert_nested(ast: &Ast, source: &str, name: &str) {
+    let program = ast.program_span();
+    let inside = |outer: compilador::tokens::Span, id: NodeId| {
+        let inner = ast.span(id);
+        assert!(
+            outer.start <= inner.start && inner.end <= outer.end,
+            "{}: {:?} at {:?} ({:?}) is not inside {:?}",
+            name,
+            ast[id],
+            inner,
+            &source[inner.start..inner.end],
+            outer
+        );
+    };
+    for &statement in ast.statements() {
+        inside(program, statement);
+    }
+    for (id, node) in ast.iter() {
+        for child in node.children() {
+            inside(ast.span(id), child);
+        }
+    }
+}
+
+#[test]
+fn child_spans_lie_inside_their_parents() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "hulk") {
+            continue;
+        }
+        let source = fs::read_to_string(&path).unwrap();
+        let name = path.display().to_string();
+        let program = ProgramParser::new().parse(&source).unwrap();
+        assert_eq!(source[..program.span.end].trim_start(), source.trim());
+        let ast = Ast::from_program(&program);
+        assert_nested(&ast, &source, &name);
+        for (id, node) in ast.iter() {
+            let text = &source[ast.span(id).start..ast.span(id).end];
+            if let Node::Block(_) = node {
+                assert!(text.starts_with('{') && text.ends_with('}'), "{}", text);
+            }
+        }
+    }
+}
+
+#[test]
+fn parentheses_and_empty_blocks_have_spans() {
+    let source = "  (1 + 2) * {} ;";
+    let program = ProgramParser::new().parse(source).unwrap();
+    assert_eq!(program.span.start, 2);
+    assert_eq!(program.span.end, source.len());
+    let Statement::StatementExpression(expression) = &program.statements[0] else {
+        panic!("expected an expression");
+    };
+    assert_eq!(program.statements[0].span(), expression.span());
+    let Expression::BinaryOp(product) = expression.as_ref() else {
+        panic!("expected a product");
+    };
+    assert_eq!(
+        &source[product.span.start..product.span.end],
+        "(1 + 2) * {}"
+    );
+    assert_eq!(
+        &source[product.left.span().start..product.left.span().end],
+        "(1 + 2)"
+    );
+    assert_eq!(
+        &source[product.right.span().start..product.right.span().end],
+        "{}"
+    );
+
+    // The CST lowers to the same spans.
+    assert_eq!(cst::parse(source).to_ast().unwrap(), program);
+}
+
+#[test]
+fn method_calls_span_their_name_and_arguments() {
+    let source = "type A { m(x: Number): Number => x; }; (new A()).m(1);";
+    let program = ProgramParser::new().parse(source).unwrap();
+    let Statement::StatementExpression(expression) = &program.statements[1] else {
+        panic!("expected an expression");
+    };
+    let Expression::TypeFunctionAccess(call) = expression.as_ref() else {
+        panic!("expected a method call");
+    };
+    assert_eq!(&source[call.span.start..call.span.end], "(new A()).m(1)");
+    assert_eq!(
+        &source[call.member.span.start..call.member.span.end],
+        "m(1)"
+    );
+    assert_eq!(
+        &source[call.object.span().start..call.object.span().end],
+        "(new A())"
+    );
+}
@@ -0,0 +1,81 @@
+//! Checks the structure of a program: declarations first, then one entry expression
+//! with an optional `;` after it.
+
+use compilador::ast_nodes::expression::Expression;
+use compilador::cst;
+use compilador::diagnostics::Phase;
+use compilador::driver;
+use compilador::parser::ProgramParser;
+
+/// Returns the message and the text of every error of `source`, which are all syntax
+/// errors.
+fn errors(source: &str) -> Vec<(String, &str)> {
+    let Err(diagnostics) = driver::check(source) else {
+        panic!("{} has no errors", source);
+    };
+    diagnostics
+        .into_iter()
+        .map(|diagnostic| {
+            assert_eq!(diagnostic.phase, Phase::Syntax, "{}", diagnostic.message);
+            let span = diagnostic.span.unwrap();
+            (diagnostic.message, &source[span.start..span.end])
+        })
+        .collect()
+}
+
+#[test]
+fn the_entry_expression_follows_the_declarations() {
+    for source in [
+        "function f(): Number => 1; type A { }; print(f())",
+        "function f(): Number => 1; type A { }; print(f());",
+    ] {
+        let checked = driver::check(source).unwrap();
+        let program = &checked.program;
+        assert_eq!(program.declarations().count(), 2);
+        assert!(matches!(program.entry(), Some(Expression::Print(_))));
+        assert_eq!(&source[program.span.start..program.span.end], source);
+    }
+    assert!(driver::parse("print(1);;").is_err());
+}
+
+#[test]
+fn a_program_without_entry_expression_is_rejected() {
+    let source = "function f(): Number => 1;";
+    assert!(ProgramParser::new().parse(source).is_err());
+    assert!(cst::parse(source).to_ast().unwrap().entry().is_none());
+    assert_eq!(
+        errors(source),
+        [(
+            "program has no entry expression: an expression must follow the declarations"
+                .to_string(),
+            ""
+        )]
+    );
+    assert_eq!(errors("").len(), 1);
+}
+
+#[test]
+fn declarations_and_expressions_after_the_entry_are_rejected() {
+    let source = "print(1); function f(): Number => 2; print(f()); type A { };";
+    assert!(ProgramParser::new().parse(source).is_err());
+    assert_eq!(
+        errors(source),
+        [
+            (
+                "`f` is declared after the entry expression: declarations must come first"
+                    .to_string(),
+                "function f(): Number => 2"
+            ),
+            (
+                "program has more than one entry expression: group them in a block `{ ... }`"
+                    .to_string(),
+                "print(f())"
+            ),
+            (
+                "`A` is declared after the entry expression: declarations must come first"
+                    .to_string(),
+                "type A { }"
+            ),
+        ]
+    );
+}
@@ -0,0 +1,151 @@
+//! Checks which calls `TailCallVisitor` marks and how, and that the engines that run
+//! the AST in process follow chains of tail calls in constant stack.
+
+use compilador::ast_nodes::function_call::{FunctionCallNode, TailCallKind};
+use compilador::ast_nodes::program::Program;
+use compilador::ast_nodes::type_member_access::TypeFunctionAccessNode;
+use compilador::bytecode::compiler::BytecodeCompiler;
+use compilador::bytecode::vm::Vm;
+use compilador::driver;
+use compilador::interpreter::tree_walker;
+use compilador::parser::ProgramParser;
+use compilador::semantic::tail_calls::TailCallVisitor;
+use compilador::visitor::ref_visitor::{self, RefVisitor};
+
+/// Records the mark of every call, by the name of the function or method called.
+#[derive(Default)]
+struct Marks(Vec<(String, Option<TailCallKind>)>);
+
+impl<'ast> RefVisitor<'ast> for Marks {
+    fn visit_function_call(&mut self, node: &'ast FunctionCallNode) {
+        self.0.push((node.function_name.clone(), node.tail_call));
+        ref_visitor::walk_function_call(self, node);
+    }
+
+    fn visit_type_function_access(&mut self, node: &'ast TypeFunctionAccessNode) {
+        let member = &node.member;
+        self.0
+            .push((format!(".{}", member.function_name), member.tail_call));
+        ref_visitor::walk_type_function_access(self, node);
+    }
+}
+
+fn parse(source: &str) -> Program {
+    ProgramParser::new()
+        .parse(source)
+        .unwrap_or_else(|error| panic!("{}: {}", source, error))
+}
+
+/// Marks the tail calls of `source` and returns the marks in source order.
+fn marks(source: &str) -> Vec<(String, Option<TailCallKind>)> {
+    let mut program = parse(source);
+    TailCallVisitor::new().mark_program(&mut program);
+    let mut marks = Marks::default();
+    marks.visit_program(&program);
+    marks.0
+}
+
+fn mark(name: &str, kind: Option<TailCallKind>) -> (String, Option<TailCallKind>) {
+    (name.to_string(), kind)
+}
+
+const GENERAL: Option<TailCallKind> = Some(TailCallKind::General);
+const RECURSIVE: Option<TailCallKind> = Some(TailCallKind::Recursive);
+
+#[test]
+fn branches_let_bodies_and_last_block_expressions_are_tail_positions() {
+    let source = "function f(n: Number): Number => \
+                  if (p(n)) { a(n); } elif (q(n)) { b(n); } else { let x = c(n) in { d(x); e(x); }; }; \
+                  1;";
+    assert_eq!(
+        marks(source),
+        vec![
+            mark("p", None),
+            mark("a", GENERAL),
+            mark("q", None),
+            mark("b", GENERAL),
+            mark("c", None),
+            mark("d", None),
+            mark("e", GENERAL),
+        ]
+    );
+}
+
+#[test]
+fn operands_arguments_loops_and_top_level_calls_are_not() {
+    let source = "function f(n: Number): Number => { \
+                  while (p(n)) q(n); \
+                  print(r(n)); \
+                  s(n) + t(u(n)); \
+                  }; \
+                  v(1);";
+    let marked: Vec<_> = marks(source)
+        .into_iter()
+        .filter(|(_, kind)| kind.is_some())
+        .collect();
+    assert_eq!(marked, vec![]);
+}
+
+#[test]
+fn only_calls_to_the_enclosing_function_are_recursive() {
+    let source = "function f(n: Number): Number => if (n == 0) { g(f(n)); } else { f(n - 1); }; \
+                  function g(n: Number): Number => f(n); \
+                  type A { f(n: Number): Number => self.f(n); g(n: Number): Number => f(n); }; \
+                  f(1);";
+    assert_eq!(
+        marks(source),
+        vec![
+            mark("g", GENERAL),
+            mark("f", None),
+            mark("f", RECURSIVE),
+            mark("f", GENERAL),
+            mark(".f", GENERAL),
+            mark("f", GENERAL),
+            mark("f", None),
+        ]
+    );
+}
+
+#[test]
+fn counters_match_the_marks_and_restart_on_every_run() {
+    let source = "function f(n: Number): Number => if (n == 0) { g(n); } else { f(n - 1); }; \
+                  function g(n: Number): Number => { h(n); g(n); }; \
+                  function h(n: Number): Number => n; \
+                  type A { m(): Number => self.m(); }; \
+                  f(1);";
+    let mut program = parse(source);
+    let mut visitor = TailCallVisitor::new();
+    for _ in 0..2 {
+        visitor.mark_program(&mut program);
+        assert_eq!(visitor.recursive_calls, 2);
+        assert_eq!(visitor.general_calls, 2);
+    }
+}
+
+/// Mutual recursion deeper than any engine allows nested calls.
+const DEEP_RECURSION: &str = "\
+    type Parity {
+        even(n: Number): Boolean => if (n == 0) { true; } else { self.odd(n - 1); };
+        odd(n: Number): Boolean => if (n == 0) { false; } else { self.even(n - 1); };
+    };
+    function even(n: Number): Boolean => if (n == 0) { true; } else { odd(n - 1); };
+    function odd(n: Number): Boolean => if (n == 0) { false; } else { even(n - 1); };
+    { print(even(200001)); print(new Parity().odd(200001)); };";
+
+#[test]
+fn the_interpreter_runs_general_tail_calls_in_constant_stack() {
+    let mut program = driver::check(DEEP_RECURSION).unwrap().program;
+    let mut output = Vec::new();
+    tree_walker::run(&mut program, &mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "false\ntrue\n");
+}
+
+#[test]
+fn the_vm_runs_general_tail_calls_in_constant_stack() {
+    let checked = driver::analyze(DEEP_RECURSION).unwrap();
+    let mut program = checked.program;
+    let chunk = BytecodeCompiler::new(&checked.type_tree).compile_program(&mut program);
+    let mut output = Vec::new();
+    Vm::new(&chunk, &mut output).run().unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "false\ntrue\n");
+}
@@ -0,0 +1,180 @@
+//! Checks the walking visitors and the folder: the order they visit nodes in, and that
+//! folding without changes rebuilds the same tree.
+
+use std::fs;
+use std::path::Path;
+
+use compilador::ast_nodes::binary_op::BinaryOpNode;
+use compilador::ast_nodes::expression::Expression;
+use compilador::ast_nodes::function_def::FunctionDefNode;
+use compilador::ast_nodes::let_in::Assignment;
+use compilador::ast_nodes::literals::IdentifierNode;
+use compilador::ast_nodes::type_def::TypeDefNode;
+use compilador::driver;
+use compilador::parser::ProgramParser;
+use compilador::tokens::OperatorToken;
+use compilador::visitor::folder::{self, Folder};
+use compilador::visitor::mut_visitor::MutVisitor;
+use compilador::visitor::ref_visitor::{self, RefVisitor};
+use compilador::visitor::sexp_printer::SexpPrinter;
+
+/// Records every node it reaches, by kind and name.
+#[derive(Default)]
+struct Recorder<'ast> {
+    visited: Vec<String>,
+    identifiers: Vec<&'ast str>,
+}
+
+impl<'ast> RefVisitor<'ast> for Recorder<'ast> {
+    fn visit_expression(&mut self, node: &'ast Expression) {
+        let json = serde_json::to_value(node).unwrap();
+        self.visited
+            .push(json["kind"].as_str().unwrap().to_string());
+        ref_visitor::walk_expression(self, node);
+    }
+
+    fn visit_function_def(&mut self, node: &'ast FunctionDefNode) {
+        self.visited.push(format!("function {}", node.name));
+        ref_visitor::walk_function_def(self, node);
+    }
+
+    fn visit_type_def(&mut self, node: &'ast TypeDefNode) {
+        self.visited.push(format!("type {}", node.identifier));
+        ref_visitor::walk_type_def(self, node);
+    }
+
+    fn visit_assignment(&mut self, node: &'ast Assignment) {
+        self.visited.push(format!("binding {}", node.identifier));
+        ref_visitor::walk_assignment(self, node);
+    }
+
+    fn visit_identifier(&mut self, node: &'ast IdentifierNode) {
+        self.identifiers.push(&node.value);
+    }
+}
+
+#[test]
+fn walking_visits_nodes_before_their_children_in_source_order() {
+    let source = "type P(a: Number) inherits Q(a) { x = a; m(): Number => self.x; };\n\
+                  function f(n: Number): Number => if (n > 0) { n } elif (n < 0) { -n } else { 0 };\n\
+                  let y = new P(1) in for (i in range(0, y.m())) print(f(i));";
+    let program = ProgramParser::new().parse(source).unwrap();
+    let mut recorder = Recorder::default();
+    recorder.visit_program(&program);
+    assert_eq!(
+        recorder.visited,
+        [
+            "type P",
+            "Identifier",
+            "binding x",
+            "Identifier",
+            "function m",
+            "TypePropAccess",
+            "Identifier",
+            "function f",
+            "IfElse",
+            "BinaryOp",
+            "Identifier",
+            "Number",
+            "CodeBlock",
+            "Identifier",
+            "BinaryOp",
+            "Identifier",
+            "Number",
+            "CodeBlock",
+            "UnaryOp",
+            "Identifier",
+            "CodeBlock",
+            "Number",
+            "LetIn",
+            "binding y",
+            "TypeInstance",
+            "Number",
+            "ForLoop",
+            "Number",
+            "TypeFunctionAccess",
+            "Identifier",
+            "Print",
+            "FunctionCall",
+            "Identifier",
+        ]
+    );
+    assert_eq!(
+        recorder.identifiers,
+        ["a", "a", "self", "n", "n", "n", "n", "y", "i"]
+    );
+}
+
+/// Renames every identifier `from` to `to`.
+struct Rename {
+    from: &'static str,
+    to: &'static str,
+}
+
+impl MutVisitor for Rename {
+    fn visit_identifier(&mut self, node: &mut IdentifierNode) {
+        if node.value == self.from {
+            node.value = self.to.to_string();
+        }
+    }
+}
+
+#[test]
+fn mutable_walking_reaches_every_identifier() {
+    let mut program = ProgramParser::new()
+        .parse("let x = 1 in { x := x + 1; print(x); };")
+        .unwrap();
+    Rename { from: "x", to: "y" }.visit_program(&mut program);
+    assert_eq!(
+        SexpPrinter::new(false).print_program(&mut program),
+        "(let ((x (number 1))) (block (:= (identifier y) (+ (identifier y) (number 1))) (print (identifier y))))\n"
+    );
+}
+
+/// Does not change anything.
+struct Identity;
+
+impl Folder for Identity {}
+
+/// Adds up additions of two number literals.
+struct FoldSums;
+
+impl Folder for FoldSums {
+    fn fold_binary_op(&mut self, node: BinaryOpNode) -> Expression {
+        let node = folder::walk_binary_op(self, node);
+        match (&node.operator, node.left.as_ref(), node.right.as_ref()) {
+            (OperatorToken::PLUS, Expression::Number(left), Expression::Number(right)) => {
+                Expression::new_number((left.value + right.value).to_string(), node.span)
+            }
+            _ => Expression::BinaryOp(node),
+        }
+    }
+}
+
+#[test]
+fn folding_rebuilds_the_tree() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "hulk") {
+            continue;
+        }
+        let source = fs::read_to_string(&path).unwrap();
+        let typed = driver::check(&source).unwrap().program;
+        assert_eq!(
+            Identity.fold_program(typed.clone()),
+            typed,
+            "{}",
+            path.display()
+        );
+    }
+
+    let program = ProgramParser::new()
+        .parse("print(1 + 2 + x + (3 + 4));")
+        .unwrap();
+    let mut folded = FoldSums.fold_program(program);
+    assert_eq!(
+        SexpPrinter::new(false).print_program(&mut folded),
+        "(print (+ (+ (number 3) (identifier x)) (number 7)))\n"
+    );
+}